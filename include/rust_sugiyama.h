@@ -0,0 +1,129 @@
+/*
+ * C interface of rust-sugiyama, available with the `ffi` feature.
+ *
+ * Build the library with
+ *   cargo rustc --release --features ffi --crate-type cdylib
+ * or `--crate-type staticlib` and link against it.
+ */
+#ifndef RUST_SUGIYAMA_H
+#define RUST_SUGIYAMA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* status codes */
+#define SUGIYAMA_OK 0
+#define SUGIYAMA_NULL_POINTER -1
+#define SUGIYAMA_UNKNOWN_VERTEX -2
+#define SUGIYAMA_INVALID_CONFIG -3
+#define SUGIYAMA_UNKNOWN_COMPONENT -4
+
+/* values of SugiyamaConfig.ranking_type */
+#define SUGIYAMA_RANKING_ORIGINAL 0
+#define SUGIYAMA_RANKING_MINIMIZE 1
+#define SUGIYAMA_RANKING_UP 2
+#define SUGIYAMA_RANKING_DOWN 3
+#define SUGIYAMA_RANKING_MIN_WIDTH 4
+/* only valid if the library is built with the lp feature */
+#define SUGIYAMA_RANKING_OPTIMAL 5
+
+/* values of SugiyamaConfig.c_minimization */
+#define SUGIYAMA_CROSSING_BARYCENTER 0
+#define SUGIYAMA_CROSSING_MEDIAN 1
+#define SUGIYAMA_CROSSING_BEST 2
+/* only valid if the library is built with the evolutionary feature */
+#define SUGIYAMA_CROSSING_EVOLUTIONARY 3
+
+/* values of SugiyamaConfig.alignment */
+#define SUGIYAMA_ALIGNMENT_BALANCED 0
+#define SUGIYAMA_ALIGNMENT_UP_LEFT 1
+#define SUGIYAMA_ALIGNMENT_UP_RIGHT 2
+#define SUGIYAMA_ALIGNMENT_DOWN_LEFT 3
+#define SUGIYAMA_ALIGNMENT_DOWN_RIGHT 4
+
+typedef struct SugiyamaGraph SugiyamaGraph;
+typedef struct SugiyamaLayout SugiyamaLayout;
+
+typedef struct SugiyamaConfig {
+    uint32_t minimum_length;
+    double vertex_spacing;
+    bool dummy_vertices;
+    double dummy_size;
+    uint32_t ranking_type;
+    uint32_t c_minimization;
+    bool transpose;
+    /* 0 means no limit for all of the following fields */
+    uint64_t time_budget_ms;
+    size_t max_ranking_iterations;
+    size_t max_sweeps;
+    size_t max_transpose_iterations;
+    /* seed for random choices during crossing minimization, 0 means no seed */
+    uint64_t seed;
+    /* additional runs of crossing minimization, 0 means a single run */
+    size_t restarts;
+    bool multilevel;
+    bool transitive_reduction;
+    bool condense_components;
+    bool contract_chains;
+    bool commit_graph;
+    bool radial;
+    /* ratio of the width to the height of the layout, 0 means no ratio */
+    double aspect_ratio;
+    /* maximum number of vertices in a rank, 0 means no limit */
+    size_t max_layer_width;
+    bool promote_vertices;
+    /* minimum spacing between ranks, 0 means the vertex spacing */
+    double rank_spacing;
+    /* border around each component, 0 means components keep their coordinates */
+    size_t padding;
+    /* emit coordinates with the y-axis pointing down, like SVG */
+    bool y_down;
+    bool mirror;
+    /* step of the grid x-coordinates are snapped to, 0 means no grid */
+    size_t grid;
+    bool snap_y;
+    /* steps each unit is divided into during the layout, 0 means a single step */
+    size_t subdivisions;
+    bool horizontal_compaction;
+    /* one of the SUGIYAMA_ALIGNMENT_* constants */
+    uint32_t alignment;
+    bool center_parents;
+    bool minimize_area;
+    bool minimize_bends;
+    /* sweeps without fewer crossings before crossing reduction stops, 0 means 1 */
+    size_t patience;
+    bool shuffle_transpose;
+    bool stable_ties;
+    bool community_ordering;
+} SugiyamaConfig;
+
+int32_t sugiyama_config_default(SugiyamaConfig *config);
+
+SugiyamaGraph *sugiyama_graph_new(void);
+void sugiyama_graph_free(SugiyamaGraph *graph);
+/* returns UINT32_MAX if graph is NULL */
+uint32_t sugiyama_graph_add_vertex(SugiyamaGraph *graph);
+int32_t sugiyama_graph_add_edge(SugiyamaGraph *graph, uint32_t tail, uint32_t head);
+int32_t sugiyama_graph_set_config(SugiyamaGraph *graph, const SugiyamaConfig *config);
+
+/* returns NULL if graph is NULL */
+SugiyamaLayout *sugiyama_layout(const SugiyamaGraph *graph);
+void sugiyama_layout_free(SugiyamaLayout *layout);
+size_t sugiyama_layout_component_count(const SugiyamaLayout *layout);
+/* width and height may be NULL */
+int32_t sugiyama_layout_component_size(const SugiyamaLayout *layout, size_t component,
+                                       size_t *width, size_t *height);
+/* x, y and component may be NULL */
+int32_t sugiyama_layout_coordinates(const SugiyamaLayout *layout, uint32_t vertex,
+                                    int64_t *x, int64_t *y, size_t *component);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUST_SUGIYAMA_H */
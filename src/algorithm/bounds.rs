@@ -0,0 +1,75 @@
+//! Bounding box of a finished layout.
+//!
+//! The box contains the boxes of all vertices, including their margins, the
+//! routes of all edges and the boxes of clusters, strongly connected
+//! components, vertices spanning several ranks, lanes and ranks. With a
+//! padding, the box is extended by it on every side, and the layout is moved
+//! so the top left corner of the box is at the origin, so the drawing lies in
+//! `0..=width` horizontally and `-height..=0` vertically.
+use std::collections::HashMap;
+
+use log::info;
+
+use super::{routing, transform};
+use crate::{Bounds, Geometry, Layout};
+
+type Coordinates = (isize, isize);
+
+/// Calculates the bounding box of the layout, given the width and height of
+/// the vertices by their id, and applies the padding, if there is one.
+pub(super) fn apply(
+    layout: &mut Layout,
+    geometry: &mut Geometry,
+    sizes: &HashMap<usize, Coordinates>,
+    padding: Option<usize>,
+) {
+    let mut boxes = layout
+        .0
+        .iter()
+        .map(|(id, (x, y))| {
+            let (w, h) = sizes.get(id).copied().unwrap_or((0, 0));
+            ((x - w / 2, y - h / 2), (x + (w + 1) / 2, y + (h + 1) / 2))
+        })
+        .collect::<Vec<_>>();
+    boxes.extend(
+        routing::routes(geometry)
+            .into_iter()
+            .flat_map(|points| points.iter().map(|p| (*p, *p))),
+    );
+    boxes.extend(geometry.clusters.iter().map(|c| (c.min, c.max)));
+    boxes.extend(geometry.condensed.iter().map(|c| (c.min, c.max)));
+    boxes.extend(geometry.spans.iter().map(|s| (s.min, s.max)));
+    let Some(((mut min_x, mut min_y), (mut max_x, mut max_y))) =
+        boxes.into_iter().reduce(|(min, max), (a, b)| {
+            (
+                (min.0.min(a.0), min.1.min(a.1)),
+                (max.0.max(b.0), max.1.max(b.1)),
+            )
+        })
+    else {
+        return;
+    };
+    // lanes and ranks only extend the box in one direction
+    for lane in &geometry.lanes {
+        (min_x, max_x) = (min_x.min(lane.min), max_x.max(lane.max));
+    }
+    for rank in &geometry.ranks {
+        (min_y, max_y) = (min_y.min(rank.min), max_y.max(rank.max));
+    }
+
+    geometry.bounds = Bounds {
+        min: (min_x, min_y),
+        max: (max_x, max_y),
+    };
+    let Some(padding) = padding else {
+        return;
+    };
+    let padding = padding as isize;
+    let (dx, dy) = (padding - min_x, -padding - max_y);
+    info!(target: "layouting", "Moving layout by ({dx}, {dy}) for a padding of {padding}");
+    transform::map(layout, geometry, |(x, y)| (x + dx, y + dy));
+    geometry.bounds = Bounds {
+        min: (0, min_y + dy - padding),
+        max: (max_x + dx + padding, 0),
+    };
+}
@@ -0,0 +1,200 @@
+//! Contraction of chains of vertices.
+//!
+//! A chain is a path of vertices with exactly one incoming and one outgoing
+//! edge, like the statements of a pipeline or the nodes of a parse tree with a
+//! single child. After ranking, each chain is replaced by a single edge from
+//! the vertex before the chain to the vertex after it, which gets a dummy
+//! vertex in each rank it spans, so the vertices of a chain are ordered as one
+//! unit during crossing reduction. Once the coordinates are known, the
+//! vertices of the chain are placed on the route of that edge again.
+use std::collections::HashSet;
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::{Incoming, Outgoing};
+
+use super::{Edge, Vertex};
+use crate::{EdgeRoute, Geometry, Layout};
+
+type Coordinates = (isize, isize);
+
+struct Chain {
+    /// Id of the edge which replaces the chain.
+    edge: usize,
+    /// Ranks of the vertices before and after the chain.
+    ranks: (i32, i32),
+    /// Vertices of the chain, from top to bottom.
+    vertices: Vec<Vertex>,
+    /// Edges of the chain, from top to bottom.
+    edges: Vec<Edge>,
+}
+
+/// Chains which were replaced by a single edge.
+#[derive(Default)]
+pub(super) struct Chains {
+    chains: Vec<Chain>,
+}
+
+impl Chains {
+    /// Replaces each chain of `graph` by a single edge. Vertices in `fixed`
+    /// and vertices which are constrained in any other way are not part of
+    /// any chain.
+    pub(super) fn contract(
+        graph: &mut StableDiGraph<Vertex, Edge>,
+        fixed: &HashSet<NodeIndex>,
+    ) -> Self {
+        let is_inner = |v: NodeIndex| {
+            let w = &graph[v];
+            let mut edges = graph
+                .edges_directed(v, Incoming)
+                .chain(graph.edges_directed(v, Outgoing));
+            !fixed.contains(&v)
+                && !w.is_dummy
+                && w.hyperedge.is_none()
+                && w.cluster.is_none()
+                && w.lane.is_none()
+                && w.pin.is_none()
+                && w.prev_x.is_none()
+                && w.start_x.is_none()
+                && w.extent() == (0, 0)
+                && graph.edges_directed(v, Incoming).count() == 1
+                && graph.edges_directed(v, Outgoing).count() == 1
+                && edges.all(|e| {
+                    let e = e.weight();
+                    !e.is_vertical && e.priority == 0 && e.ports == (None, None)
+                })
+        };
+        let inner = graph
+            .node_indices()
+            .filter(|v| is_inner(*v))
+            .collect::<HashSet<_>>();
+
+        let mut chains = Self::default();
+        let first_id = graph.edge_weights().map(|e| e.id + 1).max().unwrap_or(0);
+        let mut starts = inner
+            .iter()
+            .copied()
+            .filter(|v| {
+                let tail = graph.neighbors_directed(*v, Incoming).next().unwrap();
+                !inner.contains(&tail)
+            })
+            .collect::<Vec<_>>();
+        starts.sort();
+        for (id, start) in (first_id..).zip(starts) {
+            let tail = graph.neighbors_directed(start, Incoming).next().unwrap();
+            let mut vertices = Vec::new();
+            let mut edges = vec![graph.edges_directed(start, Incoming).next().unwrap().id()];
+            let mut head = start;
+            while inner.contains(&head) {
+                vertices.push(head);
+                let e = graph.edges_directed(head, Outgoing).next().unwrap();
+                edges.push(e.id());
+                head = e.target();
+            }
+            chains.chains.push(Chain {
+                edge: id,
+                ranks: (graph[tail].rank, graph[head].rank),
+                vertices: vertices.iter().map(|v| graph[*v]).collect(),
+                edges: edges.iter().map(|e| graph[*e]).collect(),
+            });
+            for v in vertices {
+                graph.remove_node(v);
+            }
+            graph.add_edge(
+                tail,
+                head,
+                Edge {
+                    id,
+                    ..Default::default()
+                },
+            );
+        }
+        info!(target: "layouting",
+            "Contracted {} chains of {} vertices",
+            chains.chains.len(),
+            inner.len());
+        chains
+    }
+
+    /// Returns the number of vertices in all chains.
+    pub(super) fn len(&self) -> usize {
+        self.chains.iter().map(|c| c.vertices.len()).sum()
+    }
+
+    /// Returns the vertices of all chains.
+    pub(super) fn vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.chains.iter().flat_map(|c| c.vertices.iter())
+    }
+
+    /// Places the vertices of each chain on the route of the edge which
+    /// replaced it, and splits the route into the routes of the edges of the
+    /// chain.
+    pub(super) fn expand(&self, layout: &mut Layout, geometry: &mut Geometry) {
+        if self.chains.is_empty() {
+            return;
+        }
+        for chain in &self.chains {
+            let Some(r) = geometry.edges.iter().position(|r| r.edge == chain.edge) else {
+                continue;
+            };
+            let route = geometry.edges.swap_remove(r).points;
+            let (top, bottom) = chain.ranks;
+            let n = route.len();
+            let mut positions = vec![route[0]];
+            for v in &chain.vertices {
+                let position = if n as i32 == bottom - top + 1 {
+                    // the route has a point in each rank
+                    route[(v.rank - top) as usize]
+                } else {
+                    let (y1, y2) = (route[0].1, route[n - 1].1);
+                    let y = y1 + (y2 - y1) * (v.rank - top) as isize / (bottom - top) as isize;
+                    point_at(&route, y)
+                };
+                layout.0.push((v.id, position));
+                positions.push(position);
+            }
+            positions.push(route[n - 1]);
+
+            for (e, w) in chain.edges.iter().zip(positions.windows(2)) {
+                let mut points = vec![w[0]];
+                points.extend(route.iter().filter(|(_, y)| *y < w[0].1 && *y > w[1].1));
+                points.push(w[1]);
+                if e.is_reversed {
+                    points.reverse();
+                }
+                geometry.edges.push(EdgeRoute { edge: e.id, points });
+            }
+        }
+        layout.0.sort_by_key(|(id, _)| *id);
+        geometry.edges.sort_by_key(|route| route.edge);
+    }
+}
+
+/// Returns the point of `route` at the y-coordinate `y`, where routes go from
+/// top to bottom.
+fn point_at(route: &[Coordinates], y: isize) -> Coordinates {
+    for w in route.windows(2) {
+        let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+        if y <= y1 && y >= y2 {
+            if y1 == y2 {
+                return (x1, y);
+            }
+            return (x1 + (x2 - x1) * (y1 - y) / (y1 - y2), y);
+        }
+    }
+    route[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::point_at;
+
+    #[test]
+    fn points_between_route_points_are_interpolated() {
+        let route = [(0, 0), (10, -10), (10, -30)];
+        assert_eq!(point_at(&route, 0), (0, 0));
+        assert_eq!(point_at(&route, -5), (5, -5));
+        assert_eq!(point_at(&route, -20), (10, -20));
+    }
+}
@@ -0,0 +1,176 @@
+//! Lane layout of commit graphs.
+//!
+//! Instead of ranks with several vertices, each vertex gets a row of its own,
+//! in topological order, and vertices and edges are assigned to as few
+//! columns (lanes) as possible, like the history graph of a version control
+//! system. The first outgoing edge of a vertex (its first parent) continues
+//! in the lane of the vertex, so chains of first parents are drawn in a
+//! straight line. Other edges get a lane of their own, or share the lane of
+//! an edge to the same head. A lane is free again once the vertex its edges
+//! lead to is placed.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use log::info;
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::{Incoming, Outgoing};
+
+use super::{finish_layout, Edge, Vertex};
+use crate::{Geometry, Layout};
+
+/// Assigns each vertex of `graph` a row and a lane, and calculates the
+/// coordinates and routes of the layout. Overrides the ranks of all vertices.
+pub(super) fn calculate_coordinates(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    vertex_spacing: usize,
+    rank_spacing: usize,
+) -> (Layout, Geometry) {
+    let order = topological_order(graph);
+    let mut lanes = Vec::<Option<(NodeIndex, bool)>>::new();
+    let mut vertex_lanes = HashMap::new();
+    let mut edge_lanes = HashMap::<EdgeIndex, usize>::new();
+    let free = |lanes: &mut Vec<Option<(NodeIndex, bool)>>| {
+        lanes.iter().position(Option::is_none).unwrap_or_else(|| {
+            lanes.push(None);
+            lanes.len() - 1
+        })
+    };
+    for (row, v) in order.iter().enumerate() {
+        graph[*v].rank = row as i32;
+        // a vertex continues the lane of an edge for which it is the first parent
+        let incoming = (0..lanes.len())
+            .filter(|l| lanes[*l].is_some_and(|(head, _)| head == *v))
+            .collect::<Vec<_>>();
+        let lane = incoming
+            .iter()
+            .find(|l| lanes[**l].is_some_and(|(_, first)| first))
+            .or(incoming.first())
+            .copied()
+            .unwrap_or_else(|| free(&mut lanes));
+        incoming.iter().for_each(|l| lanes[*l] = None);
+        vertex_lanes.insert(*v, lane);
+
+        let mut edges = graph
+            .edges_directed(*v, Outgoing)
+            .map(|e| (e.weight().id, e.id(), e.target()))
+            .collect::<Vec<_>>();
+        edges.sort();
+        for (i, (_, e, head)) in edges.into_iter().enumerate() {
+            let l = if i == 0 {
+                lane
+            } else {
+                lanes
+                    .iter()
+                    .position(|l| l.is_some_and(|(h, _)| h == head))
+                    .unwrap_or_else(|| free(&mut lanes))
+            };
+            let first = i == 0 || lanes[l].is_some_and(|(_, first)| first);
+            lanes[l] = Some((head, first));
+            edge_lanes.insert(e, l);
+        }
+    }
+    info!(target: "layouting", "Placed {} vertices in {} lanes", order.len(), lanes.len());
+
+    let lane_width = vertex_spacing as isize
+        + graph
+            .node_weights()
+            .map(|v| v.extent().0)
+            .max()
+            .unwrap_or(0);
+    let x_coordinates = vertex_lanes
+        .iter()
+        .map(|(v, lane)| (*v, *lane as isize * lane_width))
+        .collect();
+    let (layout, mut geometry) = finish_layout(
+        graph,
+        x_coordinates,
+        &[],
+        Vec::new(),
+        rank_spacing,
+        lanes.len(),
+        order.len(),
+    );
+
+    // edges in a lane other than the one of their tail or head leave the tail
+    // and enter the head diagonally, within half the rank spacing
+    let half = rank_spacing as isize / 2;
+    let bends = edge_lanes
+        .into_iter()
+        .filter_map(|(e, lane)| {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            let is_hyperedge = graph[tail].hyperedge.is_some() || graph[head].hyperedge.is_some();
+            (!is_hyperedge).then_some((graph[e].id, (graph[e], lane as isize * lane_width)))
+        })
+        .collect::<HashMap<_, _>>();
+    for route in &mut geometry.edges {
+        let Some((edge, x)) = bends.get(&route.edge) else {
+            continue;
+        };
+        let n = route.points.len();
+        let (mut tail, mut head) = (route.points[0], route.points[n - 1]);
+        if edge.is_reversed {
+            (tail, head) = (head, tail);
+        }
+        let mut points = vec![tail];
+        if *x != tail.0 {
+            points.push((*x, tail.1 - half));
+        }
+        if *x != head.0 {
+            points.push((*x, head.1 + half));
+        }
+        points.push(head);
+        points.dedup();
+        if edge.is_reversed {
+            points.reverse();
+        }
+        route.points = points;
+    }
+    (layout, geometry)
+}
+
+/// Orders the vertices of `graph` topologically, preferring vertices with a
+/// lower id.
+fn topological_order(graph: &StableDiGraph<Vertex, Edge>) -> Vec<NodeIndex> {
+    let mut in_degrees = graph
+        .node_indices()
+        .map(|v| (v, graph.edges_directed(v, Incoming).count()))
+        .collect::<HashMap<_, _>>();
+    let mut queue = in_degrees
+        .iter()
+        .filter(|(_, d)| **d == 0)
+        .map(|(v, _)| Reverse((graph[*v].id, *v)))
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(graph.node_count());
+    while let Some(Reverse((_, v))) = queue.pop() {
+        order.push(v);
+        for head in graph.neighbors_directed(v, Outgoing) {
+            let d = in_degrees.get_mut(&head).unwrap();
+            *d -= 1;
+            if *d == 0 {
+                queue.push(Reverse((graph[head].id, head)));
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::topological_order;
+    use crate::algorithm::{init_graph, Edge, Vertex};
+
+    #[test]
+    fn vertices_with_lower_ids_come_first() {
+        let mut graph =
+            StableDiGraph::<Vertex, Edge>::from_edges([(0, 3), (3, 4), (0, 1), (1, 4), (2, 4)]);
+        init_graph(&mut graph);
+        let order = topological_order(&graph)
+            .into_iter()
+            .map(|v| graph[v].id)
+            .collect::<Vec<_>>();
+        assert_eq!(order, [0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,290 @@
+//! Condensation of strongly connected components.
+//!
+//! Each strongly connected component with more than one vertex is laid out on
+//! its own, and replaced by a single vertex with the size of its layout, so
+//! the cycles within a component don't affect the ranks and the order of the
+//! rest of the graph. Once the condensed graph is laid out, the vertices and
+//! routes of each component are moved into the box of the vertex standing in
+//! for it, and edges between a component and the rest of the graph end at
+//! their vertex inside the box.
+//!
+//! Hyperedges are not part of any component. Components are laid out without
+//! the order constraints, clusters, lanes and pins of their vertices.
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use log::info;
+use petgraph::algo::tarjan_scc;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use super::{build_layout, Constraints, Edge, Vertex};
+use crate::configure::Config;
+use crate::progress::{Cancelled, Monitor};
+use crate::{CondensedBox, EdgeRoute, Geometry, Layout, LayoutStats};
+
+type Coordinates = (isize, isize);
+
+/// The layout of a strongly connected component.
+struct Component {
+    /// Id of the vertex which stands in for the component.
+    id: usize,
+    /// Coordinates of the vertices, relative to the center of the component.
+    coordinates: Vec<(usize, Coordinates)>,
+    /// Routes of the edges within the component, relative to its center, and
+    /// the edges left out by transitive reduction.
+    geometry: Geometry,
+    size: (isize, isize),
+}
+
+/// Strongly connected components which were replaced by a single vertex.
+#[derive(Default)]
+pub(super) struct Condensation {
+    components: Vec<Component>,
+    /// Vertices of all components.
+    vertices: Vec<Vertex>,
+    /// Ids of the vertices in a component which edges leaving or entering it
+    /// are attached to, by the id of the edge and as tail and head.
+    endpoints: HashMap<usize, (Option<usize>, Option<usize>)>,
+    /// Statistics of the layouts of all components.
+    pub(super) stats: LayoutStats,
+}
+
+impl Condensation {
+    /// Lays out each strongly connected component of `graph` with more than
+    /// one vertex, and replaces it by a single vertex of the same size.
+    pub(super) fn new(
+        graph: &mut StableDiGraph<Vertex, Edge>,
+        config: Config,
+        monitor: Monitor,
+        deadline: Option<Instant>,
+    ) -> Result<Self, Cancelled> {
+        // keeps the indices, so the components refer to vertices of `graph`
+        let without_hyperedges =
+            graph.filter_map(|_, v| v.hyperedge.is_none().then_some(()), |_, _| Some(()));
+        let mut components = tarjan_scc(&without_hyperedges)
+            .into_iter()
+            .filter(|c| c.len() > 1)
+            .collect::<Vec<_>>();
+        components.iter_mut().for_each(|c| c.sort());
+        components.sort();
+        info!(target: "layouting", "Condensing {} strongly connected components", components.len());
+
+        let mut condensation = Self::default();
+        let first_id = graph.node_weights().map(|v| v.id + 1).max().unwrap_or(0);
+        let padding = config.spacing() as isize / 4;
+        for (id, members) in (first_id..).zip(components) {
+            let members = members.into_iter().collect::<HashSet<_>>();
+            let component = graph.filter_map(
+                |v, w| {
+                    members.contains(&v).then_some(Vertex {
+                        pin: None,
+                        is_frozen: false,
+                        rank_constraint: None,
+                        cluster: None,
+                        lane: None,
+                        ..*w
+                    })
+                },
+                |_, e| Some(*e),
+            );
+            condensation
+                .vertices
+                .extend(component.node_weights().copied());
+            let sizes = component
+                .node_weights()
+                .map(|v| (v.id, v.extent()))
+                .collect::<HashMap<_, _>>();
+            let config = Config {
+                condense_components: false,
+                padding: None,
+                y_down: false,
+                mirror: false,
+                grid: None,
+                ..config
+            };
+            let ((coordinates, _, _), stats, geometry) = build_layout(
+                component,
+                config,
+                &Constraints::default(),
+                monitor.silent(),
+                deadline,
+            )?;
+            condensation.stats.crossings += stats.crossings;
+            condensation.stats.reversed_edges += stats.reversed_edges;
+            condensation.stats.dummy_vertices += stats.dummy_vertices;
+            condensation.stats.bends += stats.bends;
+
+            // the box contains all vertices and routes of the component
+            let corners = coordinates.iter().flat_map(|(id, (x, y))| {
+                let (w, h) = sizes[id];
+                [(x - w / 2, y - h / 2), (x + (w + 1) / 2, y + (h + 1) / 2)]
+            });
+            let points = geometry
+                .edges
+                .iter()
+                .flat_map(|route| route.points.iter().copied());
+            let (mut min, mut max) = ((isize::MAX, isize::MAX), (isize::MIN, isize::MIN));
+            for (x, y) in corners.chain(points) {
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            }
+            let center = ((min.0 + max.0) / 2, (min.1 + max.1) / 2);
+            let size = (max.0 - min.0 + 2 * padding, max.1 - min.1 + 2 * padding);
+
+            let vertex = graph.add_node(Vertex {
+                id,
+                size,
+                ..Default::default()
+            });
+            graph[vertex].root = vertex;
+            graph[vertex].align = vertex;
+            graph[vertex].sink = vertex;
+            condensation.redirect_edges(graph, &members, vertex);
+            members.iter().for_each(|v| {
+                graph.remove_node(*v);
+            });
+
+            let mut geometry = geometry;
+            translate(&mut geometry.edges, (-center.0, -center.1));
+            condensation.components.push(Component {
+                id,
+                coordinates: coordinates
+                    .into_iter()
+                    .map(|(id, (x, y))| (id, (x - center.0, y - center.1)))
+                    .collect(),
+                geometry,
+                size,
+            });
+        }
+        Ok(condensation)
+    }
+
+    /// Attaches the edges between `members` and the rest of the graph to `vertex`.
+    fn redirect_edges(
+        &mut self,
+        graph: &mut StableDiGraph<Vertex, Edge>,
+        members: &HashSet<NodeIndex>,
+        vertex: NodeIndex,
+    ) {
+        let edges = graph
+            .edge_references()
+            .filter(|e| members.contains(&e.source()) != members.contains(&e.target()))
+            .map(|e| e.id())
+            .collect::<Vec<_>>();
+        for e in edges {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            let weight = graph.remove_edge(e).unwrap();
+            let endpoints = self.endpoints.entry(weight.id).or_default();
+            if members.contains(&tail) {
+                endpoints.0 = Some(graph[tail].id);
+                graph.add_edge(vertex, head, weight);
+            } else {
+                endpoints.1 = Some(graph[head].id);
+                graph.add_edge(tail, vertex, weight);
+            }
+        }
+    }
+
+    /// Returns the ids of the vertices the tail and the head of the edge
+    /// `edge` from `tail` to `head` are attached to.
+    pub(super) fn endpoints(&self, edge: usize, tail: usize, head: usize) -> (usize, usize) {
+        match self.endpoints.get(&edge) {
+            Some((t, h)) => (t.unwrap_or(tail), h.unwrap_or(head)),
+            None => (tail, head),
+        }
+    }
+
+    /// Returns the vertices of all components.
+    pub(super) fn vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices.iter()
+    }
+
+    /// Replaces the vertex of each component by the vertices of the component
+    /// and adds the routes of its edges. Edges leaving or entering a
+    /// component end at their vertex inside of it.
+    pub(super) fn expand(&self, layout: &mut Layout, geometry: &mut Geometry) {
+        if self.components.is_empty() {
+            return;
+        }
+        let centers = layout.0.iter().copied().collect::<HashMap<_, _>>();
+        layout
+            .0
+            .retain(|(id, _)| self.components.iter().all(|c| c.id != *id));
+        for c in &self.components {
+            let (x, y) = centers[&c.id];
+            let mut vertices = c
+                .coordinates
+                .iter()
+                .map(|(id, (dx, dy))| (*id, (x + dx, y + dy)))
+                .collect::<Vec<_>>();
+            layout.0.extend_from_slice(&vertices);
+            let mut edges = c.geometry.edges.clone();
+            translate(&mut edges, (x, y));
+            geometry.edges.extend(edges);
+            geometry
+                .transitive_edges
+                .extend_from_slice(&c.geometry.transitive_edges);
+            vertices.sort();
+            geometry.condensed.push(CondensedBox {
+                vertices: vertices.into_iter().map(|(id, _)| id).collect(),
+                min: (x - c.size.0 / 2, y - c.size.1 / 2),
+                max: (x + (c.size.0 + 1) / 2, y + (c.size.1 + 1) / 2),
+            });
+        }
+        let coordinates = layout.0.iter().copied().collect::<HashMap<_, _>>();
+        for route in &mut geometry.edges {
+            let Some((tail, head)) = self.endpoints.get(&route.edge) else {
+                continue;
+            };
+            let n = route.points.len();
+            if let Some(tail) = tail {
+                route.points[0] = coordinates[tail];
+            }
+            if let Some(head) = head {
+                route.points[n - 1] = coordinates[head];
+            }
+        }
+        layout.0.sort_by_key(|(id, _)| *id);
+        geometry.edges.sort_by_key(|route| route.edge);
+        geometry.transitive_edges.sort();
+        geometry.condensed.sort_by_key(|c| c.vertices[0]);
+    }
+}
+
+/// Moves all points of `routes` by `offset`.
+fn translate(routes: &mut [EdgeRoute], (dx, dy): Coordinates) {
+    for (x, y) in routes.iter_mut().flat_map(|route| route.points.iter_mut()) {
+        *x += dx;
+        *y += dy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::Condensation;
+    use crate::algorithm::{init_graph, Edge, Vertex};
+    use crate::configure::Config;
+    use crate::progress::Hooks;
+
+    #[test]
+    fn components_are_replaced_by_one_vertex() {
+        let mut graph =
+            StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (1, 0), (1, 2), (2, 3), (3, 2)]);
+        init_graph(&mut graph);
+        let hooks = Hooks::default();
+        let condensation =
+            Condensation::new(&mut graph, Config::default(), hooks.monitor(100.), None).unwrap();
+        // one vertex for each component, connected by the edge between them
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.edge_count(), 1);
+        let ids = graph.node_weights().map(|v| v.id).collect::<Vec<_>>();
+        assert_eq!(condensation.endpoints(2, ids[0], ids[1]), (1, 2));
+        assert_eq!(condensation.endpoints(0, 7, 8), (7, 8));
+        assert!(graph.node_weights().all(|v| v.size.0 > 0 && v.size.1 > 0));
+        assert_eq!(condensation.vertices().count(), 4);
+        assert_eq!(condensation.stats.reversed_edges, 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! Graphviz dumps of the intermediate graph, to debug the phases of the
+//! algorithm.
+//!
+//! Input vertices are labeled with their id, dummy vertices and the junctions
+//! of hyperedges are drawn as points. Reversed edges point into the direction
+//! of the input edge and are dashed, edges which don't constrain the ranking
+//! are dotted. If the vertices are ranked, each rank is drawn as a row and, if
+//! they are ordered as well, the vertices of a row are kept in their order.
+use std::fmt::Write;
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use super::{Edge, Vertex};
+
+/// Returns the graph in the dot format, with the vertices of each rank in
+/// `layers`, which is empty if the graph isn't ranked yet.
+pub(super) fn to_dot(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    ordered: bool,
+) -> String {
+    let mut dot = String::from("digraph {\n");
+    for v in graph.node_indices() {
+        let vertex = &graph[v];
+        let _ = if vertex.is_dummy || vertex.hyperedge.is_some() {
+            writeln!(dot, "  {} [shape=point];", v.index())
+        } else if let Some(span_of) = vertex.span_of {
+            writeln!(dot, "  {} [label=\"{span_of}'\"];", v.index())
+        } else {
+            writeln!(dot, "  {} [label=\"{}\"];", v.index(), vertex.id)
+        };
+    }
+    for edge in graph.edge_references() {
+        let (tail, head) = (edge.source().index(), edge.target().index());
+        let weight = edge.weight();
+        let _ = match (weight.is_reversed, weight.is_constraining) {
+            (false, true) => writeln!(dot, "  {tail} -> {head};"),
+            (false, false) => writeln!(dot, "  {tail} -> {head} [style=dotted];"),
+            (true, _) => writeln!(dot, "  {tail} -> {head} [dir=back, style=dashed];"),
+        };
+    }
+    for (rank, layer) in layers.iter().enumerate() {
+        let _ = write!(
+            dot,
+            "  {{ rank=same; r{rank} [shape=plaintext, label=\"{rank}\"];"
+        );
+        for v in layer {
+            let _ = write!(dot, " {};", v.index());
+        }
+        dot.push_str(" }\n");
+        // invisible edges keep the vertices of each rank in order
+        if ordered {
+            let mut left = format!("r{rank}");
+            for v in layer {
+                let _ = writeln!(dot, "  {left} -> {} [style=invis];", v.index());
+                left = v.index().to_string();
+            }
+        }
+    }
+    // stack the rank labels, so the ranks are drawn in order
+    for rank in 1..layers.len() {
+        let _ = writeln!(dot, "  r{} -> r{rank} [style=invis];", rank - 1);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::to_dot;
+    use crate::algorithm::{Edge, Vertex};
+
+    #[test]
+    fn ordered_ranks_are_kept_in_order() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::new();
+        let a = graph.add_node(Vertex::new(0));
+        let b = graph.add_node(Vertex::new(1));
+        let c = graph.add_node(Vertex::new(2));
+        graph.add_edge(a, b, Edge::default());
+        graph.add_edge(a, c, Edge::default());
+        let dot = to_dot(&graph, &[vec![a], vec![c, b]], true);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("  0 -> 1;\n"));
+        assert!(dot.contains("  { rank=same; r1 [shape=plaintext, label=\"1\"]; 2; 1; }\n"));
+        assert!(dot.contains("  r1 -> 2 [style=invis];\n  2 -> 1 [style=invis];\n"));
+        assert!(dot.contains("  r0 -> r1 [style=invis];\n"));
+        // unranked graphs have no rows
+        let dot = to_dot(&graph, &[], false);
+        assert!(!dot.contains("rank=same"));
+    }
+}
@@ -16,26 +16,50 @@
 //!
 //! See the submodules for each phase for more details on the implementation
 //! and references used.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Instant;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
 
-use crate::configure::{Config, CrossingMinimization, RankingType};
-use crate::{util::weakly_connected_components, Layout, Layouts};
+use crate::configure::{
+    Alignment, Config, CoordinateAssignment, CrossingMinimization, EdgeRouting, RankConstraint,
+    RankingAlgorithm, Shape,
+};
+use crate::progress::{Cancelled, Hooks, Monitor, Phase};
+use crate::util::{weakly_connected_components, Rng};
+use crate::{
+    ClusterBox, Coordinate, EdgeRoute, Geometry, HyperedgeRoute, Lane, Layout, LayoutStats,
+    Layouts, RankExtent, RankSpan,
+};
+use chains::Chains;
+use condensation::Condensation;
 use p0_cycle_removal as p0;
 use p1_layering as p1;
 use p2_reduce_crossings as p2;
 use p3_calculate_coordinates as p3;
 
 use self::p3_calculate_coordinates::VDir;
+use self::transform::Snap;
 
+mod bounds;
+mod chains;
+mod commit_graph;
+mod condensation;
+#[cfg(feature = "debug-dump")]
+mod dump;
 mod p0_cycle_removal;
 mod p1_layering;
 mod p2_reduce_crossings;
 mod p3_calculate_coordinates;
+mod radial;
+mod routing;
+mod transform;
+mod tree_layout;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(super) struct Vertex {
     id: usize,
     rank: i32,
@@ -49,6 +73,38 @@ pub(super) struct Vertex {
     align: NodeIndex,
     shift: isize,
     sink: NodeIndex,
+    /// x-coordinate of the vertex in a previous layout, if there is one.
+    prev_x: Option<isize>,
+    /// x-coordinate of the vertex in a layout the initial order is taken from.
+    start_x: Option<isize>,
+    /// Fixed coordinates of the vertex in the final layout.
+    pin: Option<(isize, isize)>,
+    /// Pinned vertex, which additionally keeps the rank given by its y-coordinate.
+    is_frozen: bool,
+    /// Forces the vertex onto the topmost or bottommost rank.
+    rank_constraint: Option<RankConstraint>,
+    /// Cluster the vertex belongs to.
+    cluster: Option<usize>,
+    /// Lane the vertex is placed in.
+    lane: Option<usize>,
+    /// Width and height of the vertex, its coordinates are its center.
+    size: (isize, isize),
+    /// Width and height the vertex was given, before they are rounded up to
+    /// whole layout units.
+    exact_size: (f64, f64),
+    /// Border of the vertex, where the routes of its edges end.
+    shape: Shape,
+    /// Space kept free left and right, and above and below of the vertex,
+    /// which other vertices keep their distance to, but routes don't.
+    margin: (isize, isize),
+    /// Hyperedge whose trunk and branches meet at this vertex, which is not
+    /// part of the layout.
+    hyperedge: Option<usize>,
+    /// Number of ranks the vertex spans, the vertex itself is in the topmost one.
+    span: usize,
+    /// Vertex spanning several ranks which this vertex stands in for in one
+    /// of its lower ranks, which is not part of the layout.
+    span_of: Option<usize>,
 }
 
 impl Vertex {
@@ -59,6 +115,77 @@ impl Vertex {
         }
     }
 
+    pub(super) fn set_prev_x(&mut self, x: isize) {
+        self.prev_x = Some(x);
+    }
+
+    pub(super) fn set_start_x(&mut self, x: isize) {
+        self.start_x = Some(x);
+    }
+
+    pub(super) fn set_pin(&mut self, x: isize, y: isize) {
+        self.pin = Some((x, y));
+    }
+
+    pub(super) fn freeze(&mut self, x: isize, y: isize) {
+        self.pin = Some((x, y));
+        self.is_frozen = true;
+    }
+
+    pub(super) fn set_rank_constraint(&mut self, constraint: RankConstraint) {
+        self.rank_constraint = Some(constraint);
+    }
+
+    pub(super) fn set_cluster(&mut self, cluster: usize) {
+        self.cluster = Some(cluster);
+    }
+
+    pub(super) fn set_lane(&mut self, lane: usize) {
+        self.lane = Some(lane);
+    }
+
+    pub(super) fn set_size(&mut self, width: f64, height: f64) {
+        self.exact_size = (width.max(0.), height.max(0.));
+        self.size = (
+            self.exact_size.0.ceil() as isize,
+            self.exact_size.1.ceil() as isize,
+        );
+    }
+
+    pub(super) fn set_shape(&mut self, shape: Shape) {
+        self.shape = shape;
+    }
+
+    pub(super) fn set_margin(&mut self, (horizontal, vertical): (usize, usize)) {
+        self.margin = (horizontal as isize, vertical as isize);
+    }
+
+    /// Width and height of the vertex including its margin on both sides.
+    pub(super) fn extent(&self) -> (isize, isize) {
+        (
+            self.size.0 + 2 * self.margin.0,
+            self.size.1 + 2 * self.margin.1,
+        )
+    }
+
+    /// Converts the size, margin and coordinates given to the vertex into
+    /// layout units, of which there are `s` per unit of the coordinates.
+    fn scale_to_layout_units(&mut self, s: isize) {
+        let (w, h) = self.exact_size;
+        self.size = (
+            (w * s as f64).ceil() as isize,
+            (h * s as f64).ceil() as isize,
+        );
+        self.margin = (self.margin.0 * s, self.margin.1 * s);
+        self.prev_x = self.prev_x.map(|x| x * s);
+        self.start_x = self.start_x.map(|x| x * s);
+        self.pin = self.pin.map(|(x, y)| (x * s, y * s));
+    }
+
+    pub(super) fn set_span(&mut self, ranks: usize) {
+        self.span = ranks.max(1);
+    }
+
     #[cfg(test)]
     fn new_test_p1(low: u32, lim: u32, parent: Option<NodeIndex>, is_tree_vertex: bool) -> Self {
         Self {
@@ -74,6 +201,20 @@ impl Vertex {
             align: 0.into(),
             shift: isize::MAX,
             sink: 0.into(),
+            prev_x: None,
+            start_x: None,
+            pin: None,
+            is_frozen: false,
+            rank_constraint: None,
+            cluster: None,
+            lane: None,
+            size: (0, 0),
+            exact_size: (0., 0.),
+            shape: Shape::Rectangle,
+            margin: (0, 0),
+            hyperedge: None,
+            span: 1,
+            span_of: None,
         }
     }
 
@@ -92,14 +233,29 @@ impl Vertex {
             align: align_root_sink,
             shift: isize::MAX,
             sink: align_root_sink,
+            prev_x: None,
+            start_x: None,
+            pin: None,
+            is_frozen: false,
+            rank_constraint: None,
+            cluster: None,
+            lane: None,
+            size: (0, 0),
+            exact_size: (0., 0.),
+            shape: Shape::Rectangle,
+            margin: (0, 0),
+            hyperedge: None,
+            span: 1,
+            span_of: None,
         }
     }
 
     #[cfg(test)]
     pub fn new_with_rank(rank: i32) -> Self {
-        let mut v = Self::default();
-        v.rank = rank;
-        v
+        Self {
+            rank,
+            ..Default::default()
+        }
     }
 }
 
@@ -118,33 +274,269 @@ impl Default for Vertex {
             align: 0.into(),
             shift: isize::MAX,
             sink: 0.into(),
+            prev_x: None,
+            start_x: None,
+            pin: None,
+            is_frozen: false,
+            rank_constraint: None,
+            cluster: None,
+            lane: None,
+            size: (0, 0),
+            exact_size: (0., 0.),
+            shape: Shape::Rectangle,
+            margin: (0, 0),
+            hyperedge: None,
+            span: 1,
+            span_of: None,
         }
     }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub(super) struct Edge {
+    /// Index of the edge in the input graph.
+    id: usize,
     weight: i32,
     cut_value: Option<i32>,
     is_tree_edge: bool,
     has_type_1_conflict: bool,
+    /// Whether the edge is taken into account when assigning ranks.
+    is_constraining: bool,
+    /// Whether both endpoints need to have the same x-coordinate.
+    is_vertical: bool,
+    /// Priority of drawing the edge straight, 0 if it has none.
+    priority: u32,
+    /// Whether the edge points into the opposite direction of the input edge.
+    is_reversed: bool,
+    /// Ports the tail and the head of the input edge are attached to.
+    ports: (Option<Port>, Option<Port>),
+}
+
+/// A port of a vertex, given by its index in the ports of the vertex.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct Port {
+    index: usize,
+    /// Horizontal distance to the center of the vertex.
+    offset: isize,
+}
+
+impl Edge {
+    pub(super) fn set_non_constraining(&mut self) {
+        self.is_constraining = false;
+    }
+
+    pub(super) fn set_vertical(&mut self) {
+        self.is_vertical = true;
+    }
+
+    pub(super) fn set_priority(&mut self, priority: u32) {
+        self.priority = priority;
+    }
+
+    pub(super) fn set_ports(&mut self, tail: Option<usize>, head: Option<usize>) {
+        let port = |index| Port { index, offset: 0 };
+        self.ports = (tail.map(port), head.map(port));
+    }
+
+    /// Returns this edge with its direction flipped, to insert it from head to tail.
+    fn reversed(self) -> Self {
+        Self {
+            is_reversed: !self.is_reversed,
+            ..self
+        }
+    }
+
+    /// Returns an edge for a part of this edge between dummy vertices, which
+    /// only keeps the identity and the constraints of this edge.
+    fn segment(&self) -> Self {
+        Self {
+            id: self.id,
+            is_vertical: self.is_vertical,
+            priority: self.priority,
+            is_reversed: self.is_reversed,
+            ports: self.ports,
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for Edge {
     fn default() -> Self {
         Self {
+            id: 0,
             weight: 1,
             cut_value: None,
             is_tree_edge: false,
             has_type_1_conflict: false,
+            is_constraining: true,
+            is_vertical: false,
+            priority: 0,
+            is_reversed: false,
+            ports: (None, None),
         }
     }
 }
 
+/// Properties of a cluster. Its members are given by [Vertex::cluster].
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Cluster {
+    /// Cluster which contains this cluster.
+    parent: Option<usize>,
+    /// Distance between the members and the border of the box, a quarter of
+    /// the vertex spacing if not set.
+    padding: Option<usize>,
+    /// Width and height of the space reserved for the label at the top of the box.
+    label: (usize, usize),
+}
+
+/// Returns the clusters containing `cluster`, from the outermost one to
+/// `cluster` itself.
+fn cluster_path(clusters: &HashMap<usize, Cluster>, cluster: Option<usize>) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut next = cluster;
+    while let Some(c) = next.filter(|c| !path.contains(c)) {
+        path.push(c);
+        next = clusters.get(&c).and_then(|c| c.parent);
+    }
+    path.reverse();
+    path
+}
+
+/// Constraints on the layout, which relate several vertices to each other.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Constraints {
+    /// Pairs of vertices, where the first one is placed left of the second one
+    /// if both are in the same rank.
+    left_of: Vec<(NodeIndex, NodeIndex)>,
+    /// Groups of vertices in different ranks, which are placed in the same column.
+    alignments: Vec<Vec<NodeIndex>>,
+    /// Properties of the clusters, by their id.
+    clusters: HashMap<usize, Cluster>,
+    /// Horizontal offsets of the ports of vertices, in the order of the ports.
+    ports: HashMap<NodeIndex, Vec<isize>>,
+    /// Sources and targets of hyperedges, in the order they were added.
+    hyperedges: Vec<(NodeIndex, Vec<NodeIndex>)>,
+    /// Ranks given to vertices, which replace ranking if there are any.
+    ranks: HashMap<NodeIndex, u32>,
+    /// Sequences of vertices, which keep their order within each rank.
+    sequences: Vec<Vec<NodeIndex>>,
+    /// Groups of vertices, which are placed next to each other within each rank.
+    groups: Vec<Vec<NodeIndex>>,
+}
+
+impl Constraints {
+    pub(super) fn add_left_of(&mut self, left: NodeIndex, right: NodeIndex) {
+        self.left_of.push((left, right));
+    }
+
+    pub(super) fn add_rank(&mut self, v: NodeIndex, rank: u32) {
+        self.ranks.insert(v, rank);
+    }
+
+    pub(super) fn add_sequence(&mut self, sequence: Vec<NodeIndex>) {
+        self.sequences.push(sequence);
+    }
+
+    pub(super) fn add_group(&mut self, group: Vec<NodeIndex>) {
+        self.groups.push(group);
+    }
+
+    pub(super) fn add_alignment(&mut self, group: Vec<NodeIndex>) {
+        self.alignments.push(group);
+    }
+
+    /// Makes `child` a cluster within `parent`. Returns false if `parent` is
+    /// contained in `child`.
+    pub(super) fn set_cluster_parent(&mut self, child: usize, parent: usize) -> bool {
+        if cluster_path(&self.clusters, Some(parent)).contains(&child) {
+            return false;
+        }
+        self.clusters.entry(child).or_default().parent = Some(parent);
+        true
+    }
+
+    pub(super) fn set_cluster_padding(&mut self, cluster: usize, padding: usize) {
+        self.clusters.entry(cluster).or_default().padding = Some(padding);
+    }
+
+    pub(super) fn set_cluster_label(&mut self, cluster: usize, label: (usize, usize)) {
+        self.clusters.entry(cluster).or_default().label = label;
+    }
+
+    pub(super) fn set_ports(&mut self, v: NodeIndex, offsets: Vec<isize>) {
+        self.ports.insert(v, offsets);
+    }
+
+    pub(super) fn add_hyperedge(&mut self, source: NodeIndex, targets: Vec<NodeIndex>) {
+        self.hyperedges.push((source, targets));
+    }
+
+    /// Copy of the constraints with the offsets of ports and the paddings and
+    /// labels of clusters in layout units, of which there are `s` per unit.
+    fn to_layout_units(&self, s: isize) -> Self {
+        let mut constraints = self.clone();
+        for offsets in constraints.ports.values_mut() {
+            offsets.iter_mut().for_each(|o| *o *= s);
+        }
+        for cluster in constraints.clusters.values_mut() {
+            cluster.padding = cluster.padding.map(|p| p * s as usize);
+            cluster.label = (cluster.label.0 * s as usize, cluster.label.1 * s as usize);
+        }
+        constraints
+    }
+}
+
+/// Limits the number of iterations of a phase, either by a maximum number of
+/// iterations, or by a deadline after which the phase returns the best result
+/// found so far.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Limit {
+    max_iterations: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl Limit {
+    fn new(max_iterations: Option<usize>, deadline: Option<Instant>) -> Self {
+        Self {
+            max_iterations,
+            deadline,
+        }
+    }
+
+    /// Returns a limit of at most `max_iterations`, which keeps the deadline.
+    fn at_most(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations: Some(
+                self.max_iterations
+                    .map_or(max_iterations, |max| max.min(max_iterations)),
+            ),
+            ..self
+        }
+    }
+
+    /// Returns true if no more iterations should be executed, after
+    /// `iterations` iterations are done.
+    fn is_reached(&self, iterations: usize) -> bool {
+        let reached = self.max_iterations.is_some_and(|max| iterations >= max)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if reached {
+            info!(target: "layouting", "Reached iteration limit after {iterations} iterations");
+        }
+        reached
+    }
+}
+
+/// Layouts, statistics and geometry of all connected components.
+pub(super) type Results = (Layouts<usize>, Vec<LayoutStats>, Vec<Geometry>);
+
 pub(super) fn _build_layout_from_edges(edges: &[(u32, u32)], config: Config) -> Layouts<usize> {
     let graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
     // initialize vertex ids to NodeIndex
-    start(graph, config)
+    start(graph, config, &Constraints::default(), &Hooks::default())
+        .map(|(layouts, ..)| layouts)
+        .unwrap_or_default()
 }
 
 pub(super) fn _build_layout_from_graph<T, E>(
@@ -153,15 +545,100 @@ pub(super) fn _build_layout_from_graph<T, E>(
 ) -> Layouts<usize> {
     // does this guarantee that ids will match?
     let algo_graph = graph.map(|_, _| Vertex::default(), |_, _| Edge::default());
-    start(algo_graph, config)
+    start(
+        algo_graph,
+        config,
+        &Constraints::default(),
+        &Hooks::default(),
+    )
+    .map(|(layouts, ..)| layouts)
+    .unwrap_or_default()
+}
+
+pub(super) fn start(
+    graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    constraints: &Constraints,
+    hooks: &Hooks,
+) -> Result<Results, Cancelled> {
+    let mut results = start_subdivided(graph, config, constraints, hooks)?;
+    to_whole_units(&mut results, config.scale());
+    Ok(results)
 }
 
-pub(super) fn start(mut graph: StableDiGraph<Vertex, Edge>, config: Config) -> Layouts<usize> {
+/// Like [start], but returns the coordinates in layout units, of which there
+/// are [scale](Config::scale) per unit of the coordinates.
+pub(super) fn start_subdivided(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    constraints: &Constraints,
+    hooks: &Hooks,
+) -> Result<Results, Cancelled> {
+    let scale = config.scale();
+    let config = config.in_layout_units();
+    let scaled;
+    let constraints = if scale > 1 {
+        info!(target: "layouting", "Subdividing each unit into {scale} layout units");
+        graph
+            .node_weights_mut()
+            .for_each(|v| v.scale_to_layout_units(scale));
+        scaled = constraints.to_layout_units(scale);
+        &scaled
+    } else {
+        constraints
+    };
+    let deadline = config.time_budget.map(|budget| Instant::now() + budget);
     init_graph(&mut graph);
-    weakly_connected_components(graph)
+    resolve_ports(&mut graph, &constraints.ports);
+    insert_hyperedges(&mut graph, &constraints.hyperedges);
+    insert_rank_spans(&mut graph);
+    let components = weakly_connected_components(graph);
+    // weight the progress of each component by its size
+    let total = components
+        .iter()
+        .map(|g| g.node_count())
+        .sum::<usize>()
+        .max(1) as f64;
+    let components = components
         .into_iter()
-        .map(|g| build_layout(g, config))
-        .collect()
+        .map(|g| {
+            let monitor = hooks.monitor(100. * g.node_count() as f64 / total);
+            (g, monitor)
+        })
+        .collect::<Vec<_>>();
+
+    #[cfg(feature = "parallel")]
+    let layouts = {
+        use rayon::prelude::*;
+        components
+            .into_par_iter()
+            .map(|(g, monitor)| build_layout(g, config, constraints, monitor, deadline))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    #[cfg(not(feature = "parallel"))]
+    let layouts = components
+        .into_iter()
+        .map(|(g, monitor)| build_layout(g, config, constraints, monitor, deadline))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut results = (Vec::new(), Vec::new(), Vec::new());
+    for (layout, stats, geometry) in layouts {
+        results.0.push(layout);
+        results.1.push(stats);
+        results.2.push(geometry);
+    }
+    Ok(results)
+}
+
+/// Divides all coordinates of the results by `scale` layout units per unit,
+/// rounding them to the nearest whole unit.
+fn to_whole_units(results: &mut Results, scale: isize) {
+    if scale <= 1 {
+        return;
+    }
+    let round = |v: isize| isize::from_layout_units(v, scale);
+    for (layout, geometry) in results.0.iter_mut().zip(&mut results.2) {
+        transform::map(layout, geometry, |(x, y)| (round(x), round(y)));
+    }
 }
 
 pub(super) fn _map_input_graph<V, E>(graph: &StableDiGraph<V, E>) -> StableDiGraph<Vertex, Edge> {
@@ -176,36 +653,550 @@ fn init_graph(graph: &mut StableDiGraph<Vertex, Edge>) {
         graph[id].align = id;
         graph[id].sink = id;
     }
+    for id in graph.edge_indices().collect::<Vec<_>>() {
+        graph[id].id = id.index();
+    }
 }
 
-fn build_layout(mut graph: StableDiGraph<Vertex, Edge>, config: Config) -> Layout {
+/// Looks up the offsets of the ports of all edges. Ports which don't exist
+/// are dropped.
+fn resolve_ports(graph: &mut StableDiGraph<Vertex, Edge>, ports: &HashMap<NodeIndex, Vec<isize>>) {
+    for e in graph.edge_indices().collect::<Vec<_>>() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let (tail_port, head_port) = graph[e].ports;
+        let resolve = |v: NodeIndex, port: Option<Port>| {
+            let port = port?;
+            match ports.get(&v).and_then(|offsets| offsets.get(port.index)) {
+                Some(offset) => Some(Port {
+                    offset: *offset,
+                    ..port
+                }),
+                None => {
+                    warn!(target: "initializing",
+                        "Vertex {} has no port {}, ignoring it for edge {}",
+                        v.index(),
+                        port.index,
+                        e.index());
+                    None
+                }
+            }
+        };
+        graph[e].ports = (resolve(tail, tail_port), resolve(head, head_port));
+    }
+}
+
+/// Connects the source and the targets of each hyperedge through a junction
+/// vertex, so the hyperedge is laid out like a star. The edge to the junction
+/// is the trunk of the hyperedge, the edges leaving it are its branches, whose
+/// ids are the positions of their targets. Vertices which don't exist are
+/// ignored.
+fn insert_hyperedges(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    hyperedges: &[(NodeIndex, Vec<NodeIndex>)],
+) {
+    for (i, (source, targets)) in hyperedges.iter().enumerate() {
+        // look up the vertices before the junction may take the index of one
+        let targets = targets
+            .iter()
+            .enumerate()
+            .filter(|(_, target)| {
+                let exists = graph.contains_node(**target);
+                if !exists {
+                    warn!(target: "initializing",
+                        "Target {} of hyperedge {i} doesn't exist, ignoring it",
+                        target.index());
+                }
+                exists
+            })
+            .collect::<Vec<_>>();
+        if !graph.contains_node(*source) || targets.is_empty() {
+            warn!(target: "initializing",
+                "Hyperedge {i} has no source or no targets, ignoring it");
+            continue;
+        }
+        let junction = graph.add_node(Vertex {
+            hyperedge: Some(i),
+            ..Default::default()
+        });
+        graph[junction].id = junction.index();
+        graph[junction].root = junction;
+        graph[junction].align = junction;
+        graph[junction].sink = junction;
+        graph.add_edge(*source, junction, Edge::default());
+        for (id, target) in targets {
+            graph.add_edge(
+                junction,
+                *target,
+                Edge {
+                    id,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+/// Adds a column of vertices below each vertex which spans several ranks, one
+/// for each additional rank, connected by vertical edges. Incoming edges stay
+/// at the vertex itself, outgoing edges leave from the bottom of the column.
+fn insert_rank_spans(graph: &mut StableDiGraph<Vertex, Edge>) {
+    let spanning = graph
+        .node_indices()
+        .filter(|v| graph[*v].span > 1)
+        .collect::<Vec<_>>();
+    for top in spanning {
+        let outgoing = graph
+            .edges_directed(top, Direction::Outgoing)
+            .map(|e| e.id())
+            .collect::<Vec<_>>();
+        let mut bottom = top;
+        for _ in 1..graph[top].span {
+            let part = graph.add_node(Vertex {
+                span_of: Some(graph[top].id),
+                cluster: graph[top].cluster,
+                lane: graph[top].lane,
+                size: graph[top].size,
+                shape: graph[top].shape,
+                margin: graph[top].margin,
+                ..Default::default()
+            });
+            graph[part].id = part.index();
+            graph[part].root = part;
+            graph[part].align = part;
+            graph[part].sink = part;
+            graph.add_edge(
+                bottom,
+                part,
+                Edge {
+                    is_vertical: true,
+                    ..Default::default()
+                },
+            );
+            bottom = part;
+        }
+        for e in outgoing {
+            let head = graph.edge_endpoints(e).unwrap().1;
+            let weight = graph.remove_edge(e).unwrap();
+            graph.add_edge(bottom, head, weight);
+        }
+        // the bottom of the vertex needs to be in the bottommost rank
+        if let Some(RankConstraint::Max | RankConstraint::Sink) = graph[top].rank_constraint {
+            graph[bottom].rank_constraint = graph[top].rank_constraint.take();
+        }
+    }
+}
+
+/// Removes all edges which point into the opposite direction of their input
+/// edge, and returns the tail, the head and the id of their input edges.
+fn detach_back_edges(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+) -> Vec<(NodeIndex, NodeIndex, usize)> {
+    let edges = graph
+        .edge_indices()
+        .filter(|e| graph[*e].is_reversed)
+        .collect::<Vec<_>>();
+    info!(target: "layouting", "Detaching {} back edges to route them in channels", edges.len());
+    edges
+        .into_iter()
+        .map(|e| {
+            let (head, tail) = graph.edge_endpoints(e).unwrap();
+            let weight = graph.remove_edge(e).unwrap();
+            (tail, head, weight.id)
+        })
+        .collect()
+}
+
+/// Removes all edges whose head can also be reached from their tail by a
+/// longer path, and returns the tail, the head and the id of their input edges.
+///
+/// Vertical edges and edges of hyperedges are kept, since they don't only
+/// connect their endpoints.
+fn remove_transitive_edges(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+) -> Vec<(NodeIndex, NodeIndex, usize)> {
+    let mut redundant = Vec::new();
+    for tail in graph.node_indices() {
+        // vertices which are reachable by a path of at least two edges
+        let mut reachable = HashSet::new();
+        let mut stack = graph
+            .neighbors_directed(tail, Direction::Outgoing)
+            .flat_map(|child| graph.neighbors_directed(child, Direction::Outgoing))
+            .collect::<Vec<_>>();
+        while let Some(v) = stack.pop() {
+            if reachable.insert(v) {
+                stack.extend(graph.neighbors_directed(v, Direction::Outgoing));
+            }
+        }
+        redundant.extend(graph.edges_directed(tail, Direction::Outgoing).filter(|e| {
+            reachable.contains(&e.target())
+                && !e.weight().is_vertical
+                && e.weight().priority == 0
+                && graph[tail].hyperedge.is_none()
+                && graph[e.target()].hyperedge.is_none()
+        }));
+    }
+    let redundant = redundant.into_iter().map(|e| e.id()).collect::<Vec<_>>();
+    info!(target: "layouting", "Removing {} transitive edges", redundant.len());
+    redundant
+        .into_iter()
+        .map(|e| {
+            let (mut tail, mut head) = graph.edge_endpoints(e).unwrap();
+            let weight = graph.remove_edge(e).unwrap();
+            if weight.is_reversed {
+                std::mem::swap(&mut tail, &mut head);
+            }
+            (tail, head, weight.id)
+        })
+        .collect()
+}
+
+fn build_layout(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    constraints: &Constraints,
+    monitor: Monitor,
+    deadline: Option<Instant>,
+) -> Result<(Layout, LayoutStats, Geometry), Cancelled> {
     info!(target: "layouting", "Start building layout");
     info!(target: "layouting", "Configuration is: {:?}", config);
-    // we don't remember the edges that where reversed for now, since they are
-    // currently not needed
-    let _ = execute_phase_0(&mut graph);
-
-    execute_phase_1(
-        &mut graph,
-        config.minimum_length as i32,
-        config.ranking_type,
-    );
+    monitor.check()?;
+    monitor.report(Phase::CycleRemoval, 0.);
+    let condensation = if config.condense_components {
+        Condensation::new(&mut graph, config, monitor, deadline)?
+    } else {
+        Condensation::default()
+    };
+    // non-constraining edges neither take part in cycle removal nor ranking
+    let detached_edges = p1::detach_non_constraining_edges(&mut graph);
+    let constrained_edges = p1::reverse_constrained_edges(&mut graph);
+    // we only remember the number of edges that where reversed, since the
+    // edges themselves are currently not needed
+    let reversed_edges = constrained_edges + execute_phase_0(&mut graph).len();
+    #[cfg(feature = "debug-dump")]
+    monitor.dump("0-cycle-removal", || dump::to_dot(&graph, &[], false));
+    // redundant edges only add crossings, they are drawn as straight lines
+    let transitive_edges = if config.transitive_reduction {
+        remove_transitive_edges(&mut graph)
+    } else {
+        Vec::new()
+    };
 
-    let layers = execute_phase_2(
-        &mut graph,
-        config.minimum_length as i32,
-        config.dummy_vertices,
-        config.c_minimization,
-        config.transpose,
-    );
+    monitor.check()?;
+    monitor.report(Phase::Ranking, 0.);
+    if constraints.ranks.is_empty() {
+        execute_phase_1(
+            &mut graph,
+            &config,
+            monitor.hooks().ranking.as_deref(),
+            Limit::new(config.max_ranking_iterations, deadline),
+        );
+    } else {
+        // given ranks skip ranking entirely
+        p1::assign_ranks(&mut graph, config.minimum_length as i32, &constraints.ranks);
+    }
+    if let Some(observer) = monitor.observer() {
+        let ranks = graph
+            .node_indices()
+            .map(|v| (v, graph[v].rank as u32))
+            .collect();
+        observer.on_ranked(&graph.map(|_, v| v.id, |_, _| ()), &ranks);
+    }
+    #[cfg(feature = "debug-dump")]
+    monitor.dump("1-ranking", || {
+        dump::to_dot(&graph, &layers_by_rank(&graph), false)
+    });
+    if monitor.stop_before(Phase::CrossingReduction, || {
+        input_vertices(&graph, &layers_by_rank(&graph))
+    }) {
+        return Ok(Default::default());
+    }
+    // edges within a rank are routed once all vertices are placed
+    let flat_edges = p1::attach_non_constraining_edges(&mut graph, detached_edges);
+    // back edges are routed beside the layout instead of through the ranks
+    let back_edges = if config.back_edge_channels {
+        detach_back_edges(&mut graph)
+    } else {
+        Vec::new()
+    };
+    // chains are ordered as a single unit, like the dummy vertices of an edge
+    let chains = if config.contract_chains && !config.commit_graph {
+        let fixed = constraints
+            .left_of
+            .iter()
+            .flat_map(|(left, right)| [*left, *right])
+            .chain(constraints.alignments.iter().flatten().copied())
+            .chain(constraints.sequences.iter().flatten().copied())
+            .chain(constraints.groups.iter().flatten().copied())
+            .chain(flat_edges.iter().flat_map(|(tail, head, _)| [*tail, *head]))
+            .collect();
+        Chains::contract(&mut graph, &fixed)
+    } else {
+        Chains::default()
+    };
+    // lanes take precedence over constraints between single vertices
+    let mut left_of = p2::lane_order(&graph);
+    left_of.extend_from_slice(&constraints.left_of);
+    left_of.extend(p2::sequence_order(&graph, &constraints.sequences));
+    let left_of = p2::LeftOf::new(&graph, &left_of);
+    let groups = p2::Groups::new(&graph, &constraints.groups);
+    let alignments = constraints
+        .alignments
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .copied()
+                .filter(|v| graph.contains_node(*v))
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| group.len() > 1)
+        .collect::<Vec<_>>();
+
+    monitor.check()?;
+    monitor.report(Phase::CrossingReduction, 0.);
+    let children = tree_layout::children_direction(&graph).filter(|_| {
+        left_of.is_empty()
+            && groups.is_empty()
+            && alignments.is_empty()
+            && config.alignment == Alignment::Balanced
+            && flat_edges.is_empty()
+            && monitor.hooks().ordering.is_none()
+            && monitor.hooks().coordinates.is_none()
+            && monitor.hooks().stop_before.is_none()
+            && graph
+                .node_weights()
+                .all(|v| v.cluster.is_none() && v.lane.is_none() && v.extent() == (0, 0))
+    });
+    let ((mut layout, mut geometry), crossings, dummy_vertices) = match children {
+        // every vertex gets a row of its own, so no crossings are reduced
+        _ if config.commit_graph => {
+            monitor.check()?;
+            monitor.report(Phase::CoordinateCalculation, 0.);
+            let layout = commit_graph::calculate_coordinates(
+                &mut graph,
+                config.spacing(),
+                config.rank_distance(),
+            );
+            (layout, 0, 0)
+        }
+        // trees can always be drawn without crossings, so skip crossing reduction
+        Some(children) => {
+            monitor.check()?;
+            monitor.report(Phase::CoordinateCalculation, 0.);
+            let (layout, dummy_vertices) = execute_tree_layout(
+                &mut graph,
+                children,
+                config.minimum_length as i32,
+                config.dummy_vertices,
+                config.spacing(),
+                config.rank_distance(),
+            );
+            (layout, 0, dummy_vertices)
+        }
+        None => {
+            let (layers, crossings, dummy_vertices) = execute_phase_2(
+                &mut graph,
+                config.minimum_length as i32,
+                config.dummy_vertices,
+                config.c_minimization,
+                p2::Sweeps {
+                    transpose: config
+                        .transpose
+                        .then_some(Limit::new(config.max_transpose_iterations, deadline)),
+                    transpose_scan: config
+                        .shuffle_transpose
+                        .then(|| Rng::new(config.seed.unwrap_or(0))),
+                    limit: Limit::new(config.max_sweeps, deadline),
+                    patience: config.patience,
+                    stable_ties: config.stable_ties,
+                    rng: config.seed.map(Rng::new),
+                    restarts: config.restarts,
+                    multilevel: config.multilevel,
+                    communities: config.community_ordering,
+                    left_of,
+                    groups,
+                    clusters: p2::Clusters::new(constraints.clusters.clone()),
+                    flat_edges: flat_edges
+                        .iter()
+                        .map(|(tail, head, _)| (*tail, *head))
+                        .collect(),
+                },
+                monitor,
+            )?;
+
+            if let Some(observer) = monitor.observer() {
+                let input = graph.map(|_, v| (!v.is_dummy).then_some(v.id), |_, _| ());
+                observer.on_ordered(&input, &layers);
+            }
+            if monitor.stop_before(Phase::CoordinateCalculation, || {
+                input_vertices(&graph, &layers)
+            }) {
+                return Ok(Default::default());
+            }
 
-    let layout = execute_phase_3(&mut graph, layers, config.vertex_spacing, config.dummy_size);
+            monitor.check()?;
+            monitor.report(Phase::CoordinateCalculation, 0.);
+            let layout = match monitor.hooks().coordinates.as_deref() {
+                Some(algorithm) => execute_custom_phase_3(
+                    &mut graph,
+                    layers,
+                    algorithm,
+                    config.spacing(),
+                    config.rank_distance(),
+                ),
+                None => execute_phase_3(
+                    &mut graph,
+                    layers,
+                    &alignments,
+                    &constraints.clusters,
+                    &config,
+                ),
+            };
+            (layout, crossings, dummy_vertices)
+        }
+    };
+    chains.expand(&mut layout, &mut geometry);
+    condensation.expand(&mut layout, &mut geometry);
+    let coordinates = layout.0.iter().copied().collect::<HashMap<_, _>>();
+    // the vertices standing in for components were replaced by their members
+    let endpoints = |tail: NodeIndex, head: NodeIndex, id: usize| {
+        let (tail, head) = condensation.endpoints(id, graph[tail].id, graph[head].id);
+        Some((*coordinates.get(&tail)?, *coordinates.get(&head)?))
+    };
+    let vertices = graph
+        .node_weights()
+        .chain(chains.vertices())
+        .chain(condensation.vertices())
+        .filter(|v| !v.is_dummy)
+        .filter_map(|v| Some((*coordinates.get(&v.id)?, v)))
+        .collect::<Vec<_>>();
+    let boxes = vertices
+        .iter()
+        .map(|(center, v)| (*center, v.size))
+        .collect::<Vec<_>>();
+    let spacing = config.spacing() as isize;
+    if !transitive_edges.is_empty() {
+        for (tail, head, id) in &transitive_edges {
+            let Some((tail, head)) = endpoints(*tail, *head, *id) else {
+                continue;
+            };
+            geometry.edges.push(EdgeRoute {
+                edge: *id,
+                points: vec![tail, head],
+            });
+        }
+        geometry.edges.sort_by_key(|route| route.edge);
+        geometry
+            .transitive_edges
+            .extend(transitive_edges.iter().map(|(_, _, id)| *id));
+        geometry.transitive_edges.sort();
+    }
+    if !flat_edges.is_empty() {
+        let flat_edges = flat_edges
+            .iter()
+            .filter_map(|(tail, head, id)| {
+                let (tail, head) = endpoints(*tail, *head, *id)?;
+                Some((*id, tail, head))
+            })
+            .collect::<Vec<_>>();
+        routing::route_flat_edges(
+            &mut geometry,
+            &flat_edges,
+            &boxes,
+            config.rank_distance() as isize,
+        );
+    }
+    if !back_edges.is_empty() {
+        let back_edges = back_edges
+            .iter()
+            .filter_map(|(tail, head, id)| {
+                let (tail, head) = endpoints(*tail, *head, *id)?;
+                Some((*id, tail, head))
+            })
+            .collect::<Vec<_>>();
+        routing::route_back_edges(&mut geometry, &back_edges, &boxes, spacing);
+    }
+    let bends = routing::count_bends(&geometry);
+    match config.edge_routing {
+        EdgeRouting::Polyline => (),
+        EdgeRouting::Orthogonal => {
+            let mut half_heights = HashMap::<isize, isize>::new();
+            for ((_, y), v) in &vertices {
+                let half = half_heights.entry(*y).or_default();
+                *half = (*half).max((v.size.1 + 1) / 2);
+            }
+            routing::route_orthogonally(&mut geometry, &half_heights);
+        }
+        EdgeRouting::Spline => routing::route_splines(&mut geometry),
+    }
+    let borders = vertices
+        .iter()
+        .filter(|(_, v)| v.size != (0, 0))
+        .map(|(center, v)| (*center, (v.size, v.shape)))
+        .collect();
+    routing::clip_endpoints(&mut geometry, &borders);
+    if config.radial {
+        let mut ranks = HashMap::<i32, (isize, usize)>::new();
+        for v in graph.node_weights().chain(chains.vertices()) {
+            let Some((_, y)) = coordinates.get(&v.id) else {
+                continue;
+            };
+            let rank = ranks.entry(v.rank).or_insert((*y, 0));
+            rank.1 += 1;
+        }
+        let mut ranks = ranks.into_iter().collect::<Vec<_>>();
+        ranks.sort();
+        let ranks = ranks.into_iter().map(|(_, rank)| rank).collect::<Vec<_>>();
+        let projection = radial::Projection::new(
+            &ranks,
+            layout.0.iter().map(|(_, (x, _))| *x),
+            config.spacing(),
+        );
+        let split = config.edge_routing != EdgeRouting::Spline;
+        projection.apply(&mut layout, &mut geometry, split);
+    }
+    let sizes = vertices
+        .iter()
+        .map(|(_, v)| (v.id, v.extent()))
+        .collect::<HashMap<_, _>>();
+    bounds::apply(&mut layout, &mut geometry, &sizes, config.padding);
+    if config.mirror {
+        let (min, max) = (geometry.bounds.min.0, geometry.bounds.max.0);
+        transform::map(&mut layout, &mut geometry, |(x, y)| (min + max - x, y));
+    }
+    if let Some(step) = config.grid {
+        let xs = Snap::new(layout.0.iter().map(|(_, (x, _))| *x), step);
+        // ranks are snapped from the top, which keeps the top of a padded layout
+        let ys = config
+            .snap_y
+            .then(|| Snap::new(layout.0.iter().map(|(_, (_, y))| -y), step));
+        transform::map(&mut layout, &mut geometry, |(x, y)| {
+            let y = ys.as_ref().map_or(y, |ys| -ys.apply(-y));
+            (xs.apply(x), y)
+        });
+    }
+    if config.y_down {
+        transform::map(&mut layout, &mut geometry, |(x, y)| (x, -y));
+    }
+    monitor.report(Phase::CoordinateCalculation, 1.);
     debug!(target: "layouting", "Coordinates: {:?}\nwidth: {}, height:{}",
         layout.0,
         layout.1,
         layout.2
     );
-    layout
+    let stats = LayoutStats {
+        crossings: crossings + condensation.stats.crossings,
+        reversed_edges: reversed_edges + condensation.stats.reversed_edges,
+        // the dummy vertices of contracted chains stood in for their vertices
+        dummy_vertices: dummy_vertices.saturating_sub(chains.len())
+            + condensation.stats.dummy_vertices,
+        bends: bends + condensation.stats.bends,
+    };
+    debug!(target: "layouting", "Statistics: {:?}", stats);
+    if let Some(observer) = monitor.observer() {
+        observer.on_positioned(&layout, &geometry);
+    }
+    Ok((layout, stats, geometry))
 }
 
 fn execute_phase_0(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
@@ -216,44 +1207,76 @@ fn execute_phase_0(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
 /// Assign each vertex a rank
 fn execute_phase_1(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    minimum_length: i32,
-    ranking_type: RankingType,
+    config: &Config,
+    ranking: Option<&dyn RankingAlgorithm>,
+    limit: Limit,
 ) {
     info!(target: "layouting", "Executing phase 1: Ranking");
-    p1::rank(graph, minimum_length, ranking_type);
+    let minimum_length = config.minimum_length as i32;
+    match ranking {
+        Some(algorithm) => p1::rank_custom(graph, minimum_length, algorithm),
+        None => p1::rank(graph, minimum_length, config.ranking_type, limit),
+    }
+    if config.promote_vertices {
+        p1::promote_vertices(graph, minimum_length);
+    }
+    if config.minimize_area {
+        p1::minimize_area(graph, minimum_length);
+    }
+    if let Some(ratio) = config.aspect_ratio {
+        p1::approximate_aspect_ratio(graph, minimum_length, ratio);
+    }
+    if let Some(max_width) = config.max_layer_width {
+        p1::limit_width(graph, minimum_length, max_width);
+    }
+    p1::apply_rank_constraints(graph, minimum_length);
+    p1::apply_frozen_ranks(graph, minimum_length, config.rank_distance());
 }
 
-/// Reorder vertices in ranks to reduce crossings
+/// Reorder vertices in ranks to reduce crossings. Returns the layers, the number
+/// of crossings and the number of inserted dummy vertices.
 fn execute_phase_2(
     graph: &mut StableDiGraph<Vertex, Edge>,
     minimum_length: i32,
     dummy_vertices: bool,
     crossing_minimization: CrossingMinimization,
-    transpose: bool,
-) -> Vec<Vec<NodeIndex>> {
+    mut sweeps: p2::Sweeps,
+    monitor: Monitor,
+) -> Result<(Vec<Vec<NodeIndex>>, usize, usize), Cancelled> {
     info!(target: "layouting", "Executing phase 2: Crossing Reduction");
     info!(target: "layouting",
         "Has dummy vertices: {}, heuristic for crossing minimization: {:?}, using transpose: {}",
         dummy_vertices,
         crossing_minimization,
-        transpose
+        sweeps.transpose.is_some()
     );
 
+    let vertices = graph.node_count();
     p2::insert_dummy_vertices(graph, minimum_length);
-    let mut order = p2::ordering(graph, crossing_minimization, transpose);
+    let inserted = graph.node_count() - vertices;
+    p2::prepare_clusters(graph, &sweeps.clusters);
+    // placeholders of clusters may have been put into lanes
+    sweeps.left_of.add(graph, &p2::lane_order(graph));
+    let (mut order, crossings) = match monitor.hooks().ordering.as_deref() {
+        Some(algorithm) => p2::custom_ordering(graph, algorithm, sweeps),
+        None => p2::ordering(graph, crossing_minimization, sweeps, monitor)?,
+    };
     if !dummy_vertices {
         p2::remove_dummy_vertices(graph, &mut order);
     }
-    order
+    Ok((order, crossings, inserted))
 }
 
 /// calculate the final coordinates for each vertex, after the graph was layered and crossings where minimized.
 fn execute_phase_3(
     graph: &mut StableDiGraph<Vertex, Edge>,
     mut layers: Vec<Vec<NodeIndex>>,
-    vertex_spacing: usize,
-    dummy_size: f64,
-) -> Layout {
+    alignments: &[Vec<NodeIndex>],
+    clusters: &HashMap<usize, Cluster>,
+    config: &Config,
+) -> (Layout, Geometry) {
+    let vertex_spacing = config.spacing();
+    let dummy_size = config.dummy_size;
     info!(target: "layouting", "Executing phase 3: Coordinate Calculation");
     info!(target: "layouting", "Dummy vertices size (if enabled): {dummy_size}");
     for n in graph.node_indices().collect::<Vec<_>>() {
@@ -263,10 +1286,17 @@ fn execute_phase_3(
     }
     let width = layers.iter().map(|l| l.len()).max().unwrap_or(0);
     let height = layers.len();
-    let mut layouts = p3::create_layouts(graph, &mut layers, vertex_spacing, dummy_size);
+    let columns = p3::select_columns(graph, &layers, alignments);
+    let spacing = p3::Spacing::new(vertex_spacing, dummy_size);
+    let mut layouts = p3::create_layouts(graph, &mut layers, &columns, spacing);
 
-    p3::align_to_smallest_width_layout(&mut layouts);
-    let mut x_coordinates = p3::calculate_relative_coords(layouts);
+    let mut x_coordinates = match p3::take_single_layout(&mut layouts, config.alignment) {
+        Some(layout) => layout.into_iter().collect(),
+        None => {
+            p3::align_to_smallest_width_layout(graph, &mut layouts);
+            p3::calculate_relative_coords(layouts)
+        }
+    };
     // determine the smallest x-coordinate
     let min = x_coordinates.iter().min_by(|a, b| a.1.cmp(&b.1)).unwrap().1;
 
@@ -275,26 +1305,355 @@ fn execute_phase_3(
         *c -= min;
     }
 
-    let mut v = x_coordinates.iter().collect::<Vec<_>>();
-    v.sort_by(|a, b| a.0.index().cmp(&b.0.index()));
-    // format to NodeIndex: (x, y), width, height
-    (
-        x_coordinates
-            .into_iter()
-            .filter(|(v, _)| !graph[*v].is_dummy)
-            // calculate y coordinate
-            .map(|(v, x)| {
-                (
-                    graph[v].id,
-                    (x, -(graph[v].rank as isize * vertex_spacing as isize)),
-                )
-            })
-            .collect::<Vec<_>>(),
+    // vertices of a previous layout keep their coordinates as far as possible
+    let mut x_coordinates = x_coordinates.into_iter().collect::<HashMap<_, _>>();
+    if config.horizontal_compaction {
+        p3::compact(graph, &layers, &mut x_coordinates, spacing);
+    }
+    if config.center_parents {
+        p3::center_parents(graph, &layers, &mut x_coordinates, spacing);
+    }
+    if config.minimize_bends {
+        p3::minimize_bends(graph, &layers, &mut x_coordinates, spacing);
+    }
+    p3::stabilize(graph, &layers, &mut x_coordinates, spacing);
+    p3::apply_pins(graph, &layers, &mut x_coordinates, spacing);
+    let (clusters, lanes) = p3::separate_clusters(
+        graph,
+        &layers,
+        &mut x_coordinates,
+        spacing,
+        clusters,
+        vertex_spacing as isize / 4,
+    );
+    finish_layout(
+        graph,
+        x_coordinates,
+        &clusters,
+        lanes,
+        config.rank_distance(),
         width,
         height,
     )
 }
 
+/// Calculate the coordinates with a custom algorithm.
+fn execute_custom_phase_3(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    layers: Vec<Vec<NodeIndex>>,
+    algorithm: &dyn CoordinateAssignment,
+    vertex_spacing: usize,
+    rank_spacing: usize,
+) -> (Layout, Geometry) {
+    info!(target: "layouting", "Executing phase 3: Coordinate Calculation with a custom algorithm");
+    for n in graph.node_indices().collect::<Vec<_>>() {
+        if graph[n].is_dummy {
+            graph[n].id = n.index();
+        }
+    }
+    // keeps the indices, so the coordinates refer to vertices of `graph`
+    let input = graph.map(|_, v| (!v.is_dummy).then_some(v.id), |_, _| ());
+    let coordinates = algorithm.assign(&input, &layers, vertex_spacing);
+    let mut x_coordinates = HashMap::new();
+    for v in layers.iter().flatten() {
+        let x = match coordinates.get(v) {
+            // pinned vertices keep their coordinates in the layout
+            Some(&(x, y)) => {
+                graph[*v].pin = Some((x, y));
+                graph[*v].is_frozen = false;
+                x
+            }
+            None => graph[*v].pin.map_or(0, |(x, _)| x),
+        };
+        x_coordinates.insert(*v, x);
+    }
+    let missing = layers
+        .iter()
+        .flatten()
+        .filter(|v| !coordinates.contains_key(v))
+        .count();
+    if missing > 0 {
+        warn!(target: "layouting", "Custom coordinate assignment left out {missing} vertices");
+    }
+    finish_layout(
+        graph,
+        x_coordinates,
+        &[],
+        Vec::new(),
+        rank_spacing,
+        layers.iter().map(|l| l.len()).max().unwrap_or(0),
+        layers.len(),
+    )
+}
+
+/// Calculate the coordinates of a tree directly, without reducing crossings.
+/// Returns the layout and the number of inserted dummy vertices.
+fn execute_tree_layout(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    children: Direction,
+    minimum_length: i32,
+    dummy_vertices: bool,
+    vertex_spacing: usize,
+    rank_spacing: usize,
+) -> ((Layout, Geometry), usize) {
+    info!(target: "layouting", "Component is a tree, calculating coordinates directly");
+    let vertices = graph.node_count();
+    p2::insert_dummy_vertices(graph, minimum_length);
+    let inserted = graph.node_count() - vertices;
+    if !dummy_vertices {
+        p2::remove_dummy_vertices(graph, &mut []);
+    }
+    let mut x_coordinates = tree_layout::calculate_coordinates(graph, children, vertex_spacing);
+    // shift all coordinates so the minimum coordinate is 0
+    let min = x_coordinates.values().min().copied().unwrap_or(0);
+    x_coordinates.values_mut().for_each(|x| *x -= min);
+
+    let mut rank_sizes = HashMap::<i32, usize>::new();
+    for v in graph.node_weights() {
+        *rank_sizes.entry(v.rank).or_default() += 1;
+    }
+    let width = rank_sizes.values().max().copied().unwrap_or(0);
+    let height = rank_sizes.keys().max().map_or(0, |r| *r as usize + 1);
+    (
+        finish_layout(
+            graph,
+            x_coordinates,
+            &[],
+            Vec::new(),
+            rank_spacing,
+            width,
+            height,
+        ),
+        inserted,
+    )
+}
+
+/// Calculates the y-coordinates and creates the layout from the x-coordinates,
+/// together with the routes of all edges, the boxes of all clusters and the lanes.
+fn finish_layout(
+    graph: &StableDiGraph<Vertex, Edge>,
+    x_coordinates: HashMap<NodeIndex, isize>,
+    clusters: &[p3::ClusterBounds],
+    lanes: Vec<Lane>,
+    rank_spacing: usize,
+    width: usize,
+    height: usize,
+) -> (Layout, Geometry) {
+    // ranks move apart if their vertices, or the boxes of clusters ending and
+    // starting between them, don't fit into the rank spacing
+    let spacing = rank_spacing as isize;
+    let mut half = HashMap::<i32, isize>::new();
+    for v in graph.node_weights() {
+        let h = half.entry(v.rank).or_default();
+        *h = (*h).max((v.extent().1 + 1) / 2);
+    }
+    let mut below = half.clone();
+    let mut above = half.clone();
+    for c in clusters {
+        let b = below.entry(c.bottom).or_default();
+        *b = (*b).max(c.below);
+        let a = above.entry(c.top).or_default();
+        *a = (*a).max(c.above);
+    }
+    let mut shifts = vec![0];
+    for rank in 0..half.keys().copied().max().unwrap_or(0) {
+        let extent = below.get(&rank).unwrap_or(&0) + above.get(&(rank + 1)).unwrap_or(&0);
+        let vertices = half.get(&rank).unwrap_or(&0) + half.get(&(rank + 1)).unwrap_or(&0);
+        let shift = vertices.max(extent + spacing / 2 - spacing).max(0);
+        shifts.push(shifts[rank as usize] + shift);
+    }
+    let distance =
+        |rank: i32| rank as isize * spacing + shifts[(rank.max(0) as usize).min(shifts.len() - 1)];
+    // frozen vertices determine the y-coordinate of rank 0
+    let mut y_offsets = graph
+        .node_weights()
+        .filter(|v| v.is_frozen)
+        .filter_map(|v| v.pin.map(|(_, y)| y + distance(v.rank)))
+        .collect::<Vec<_>>();
+    y_offsets.sort();
+    let y_offset = y_offsets.get(y_offsets.len() / 2).copied().unwrap_or(0);
+    let y = |rank: i32| y_offset - distance(rank);
+    let positions = x_coordinates
+        .into_iter()
+        // calculate y coordinate
+        .map(|(v, x)| match graph[v].pin {
+            Some(pin) if !graph[v].is_frozen => (v, pin),
+            _ => (v, (x, y(graph[v].rank))),
+        })
+        .collect::<HashMap<_, _>>();
+    let mut coordinates = positions
+        .iter()
+        .filter(|(v, _)| {
+            !graph[**v].is_dummy && graph[**v].hyperedge.is_none() && graph[**v].span_of.is_none()
+        })
+        .map(|(v, coordinates)| (graph[*v].id, *coordinates))
+        .collect::<Vec<_>>();
+    // hash maps are iterated in arbitrary order, sort to get the same output in each run
+    coordinates.sort_by_key(|(id, _)| *id);
+
+    // edges with a port leave their endpoint at the side facing the next point,
+    // like edges leaving the bottom of a vertex spanning several ranks
+    let attach = |v: NodeIndex, port: Option<Port>, next: (isize, isize)| {
+        let (x, y) = positions[&v];
+        let offset = match port {
+            Some(port) => port.offset,
+            None if graph[v].span_of.is_some() && graph[v].size != (0, 0) => 0,
+            None => return (x, y),
+        };
+        let half = (graph[v].size.1 + 1) / 2;
+        let y = if next.1 < y { y - half } else { y + half };
+        (x + offset, y)
+    };
+    // follow the dummy vertices of each edge until its head
+    let mut edges = Vec::new();
+    let mut trunks = HashMap::new();
+    let mut branches = HashMap::<usize, Vec<EdgeRoute>>::new();
+    for v in graph.node_indices().filter(|v| !graph[*v].is_dummy) {
+        for edge in graph.edges_directed(v, Direction::Outgoing) {
+            let mut points = vec![positions[&v]];
+            let mut head = edge.target();
+            while graph[head].is_dummy {
+                points.push(positions[&head]);
+                head = graph
+                    .neighbors_directed(head, Direction::Outgoing)
+                    .next()
+                    .unwrap();
+            }
+            // the column of a vertex spanning several ranks is part of the vertex
+            if graph[head].span_of.is_some() {
+                continue;
+            }
+            points.push(positions[&head]);
+            let (mut tail, mut head) = (v, head);
+            if edge.weight().is_reversed {
+                points.reverse();
+                (tail, head) = (head, tail);
+            }
+            let (tail_port, head_port) = edge.weight().ports;
+            let n = points.len();
+            points[0] = attach(tail, tail_port, points[1]);
+            points[n - 1] = attach(head, head_port, points[n - 2]);
+            let route = EdgeRoute {
+                edge: edge.weight().id,
+                points,
+            };
+            match (graph[tail].hyperedge, graph[head].hyperedge) {
+                (_, Some(hyperedge)) => {
+                    trunks.insert(hyperedge, route.points);
+                }
+                (Some(hyperedge), _) => branches.entry(hyperedge).or_default().push(route),
+                _ => edges.push(route),
+            }
+        }
+    }
+    edges.sort_by_key(|route| route.edge);
+    let mut hyperedges = trunks
+        .into_iter()
+        .map(|(hyperedge, trunk)| {
+            let mut branches = branches.remove(&hyperedge).unwrap_or_default();
+            branches.sort_by_key(|route| route.edge);
+            HyperedgeRoute {
+                hyperedge,
+                trunk,
+                branches: branches.into_iter().map(|route| route.points).collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    hyperedges.sort_by_key(|route| route.hyperedge);
+    // extents of the vertices spanning several ranks, including their columns
+    let mut spans = HashMap::<usize, RankSpan>::new();
+    for (v, (x, y)) in &positions {
+        let w = &graph[*v];
+        let Some(vertex) = w.span_of.or((w.span > 1).then_some(w.id)) else {
+            continue;
+        };
+        let (width, height) = w.size;
+        let (min, max) = (
+            (x - width / 2, y - height / 2),
+            (x + (width + 1) / 2, y + (height + 1) / 2),
+        );
+        let span = spans.entry(vertex).or_insert(RankSpan { vertex, min, max });
+        span.min = (span.min.0.min(min.0), span.min.1.min(min.1));
+        span.max = (span.max.0.max(max.0), span.max.1.max(max.1));
+    }
+    let mut spans = spans.into_values().collect::<Vec<_>>();
+    spans.sort_by_key(|span| span.vertex);
+    // vertices placed at their pin are not part of their rank
+    let mut ranks = BTreeMap::<i32, (isize, isize)>::new();
+    for v in graph.node_weights() {
+        if v.pin.is_some() && !v.is_frozen {
+            continue;
+        }
+        let height = v.extent().1;
+        let extent = ranks.entry(v.rank).or_default();
+        *extent = (extent.0.max(height / 2), extent.1.max((height + 1) / 2));
+    }
+    let top = ranks.keys().next().copied().unwrap_or(0);
+    let ranks = ranks
+        .into_iter()
+        .map(|(rank, (below, above))| RankExtent {
+            rank: (rank - top) as usize,
+            y: y(rank),
+            min: y(rank) - below,
+            max: y(rank) + above,
+        })
+        .collect();
+    let clusters = clusters
+        .iter()
+        .map(|c| ClusterBox {
+            id: c.id,
+            min: (c.left, y(c.bottom) - c.below),
+            max: (c.right, y(c.top) + c.above),
+        })
+        .collect();
+    // format to NodeIndex: (x, y), width, height
+    (
+        (coordinates, width, height),
+        Geometry {
+            edges,
+            clusters,
+            lanes,
+            hyperedges,
+            transitive_edges: Vec::new(),
+            condensed: Vec::new(),
+            spans,
+            ranks,
+            bounds: Default::default(),
+        },
+    )
+}
+
+/// Returns the vertices of each rank, in no particular order.
+fn layers_by_rank(graph: &StableDiGraph<Vertex, Edge>) -> Vec<Vec<NodeIndex>> {
+    let mut layers = Vec::<Vec<NodeIndex>>::new();
+    for v in graph.node_indices() {
+        let rank = graph[v].rank as usize;
+        layers.resize_with(layers.len().max(rank + 1), Vec::new);
+        layers[rank].push(v);
+    }
+    layers
+}
+
+/// Returns the ids of the vertices of each rank which are part of the input,
+/// leaving out dummy vertices, junctions of hyperedges and the lower parts of
+/// vertices spanning several ranks. Ids within a rank keep their order.
+fn input_vertices(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+) -> Vec<Vec<usize>> {
+    layers
+        .iter()
+        .map(|layer| {
+            layer
+                .iter()
+                .map(|v| &graph[*v])
+                .filter(|w| !w.is_dummy && w.hyperedge.is_none() && w.span_of.is_none())
+                .map(|w| w.id)
+                .collect()
+        })
+        .collect()
+}
+
 fn slack(graph: &StableDiGraph<Vertex, Edge>, edge: EdgeIndex, minimum_length: i32) -> i32 {
     let (tail, head) = graph.edge_endpoints(edge).unwrap();
     graph[head].rank - graph[tail].rank - minimum_length
@@ -0,0 +1,266 @@
+//! Additional constraints on the ranks of vertices.
+//!
+//! Edges can be marked as non-constraining, in which case they are drawn, but
+//! don't influence the ranks of their endpoints (like `constraint=false` in
+//! graphviz). They are removed from the graph before cycle removal and
+//! layering and inserted again afterwards.
+//!
+//! Vertices can be forced onto the topmost or bottommost rank (like `rank=min`
+//! and `rank=max` in graphviz). Edges which point into the wrong direction are
+//! reversed before cycle removal, and the vertices are moved once all ranks
+//! are assigned.
+use std::cmp::Ordering;
+
+use log::{debug, info};
+use petgraph::algo::toposort;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::Incoming;
+
+use crate::configure::RankConstraint;
+
+use super::{normalize, Edge, Vertex};
+
+/// A non-constraining edge which was removed from the graph.
+pub(crate) struct DetachedEdge {
+    tail: NodeIndex,
+    head: NodeIndex,
+    weight: Edge,
+    /// Vertex which keeps the endpoints of the edge connected.
+    anchor: NodeIndex,
+}
+
+/// Removes all non-constraining edges from the graph.
+///
+/// Each edge is replaced by a new vertex with an edge of weight 0 to both of
+/// its endpoints, so that the graph stays connected, without restricting the
+/// ranks of the endpoints relative to each other.
+pub(crate) fn detach_non_constraining_edges(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+) -> Vec<DetachedEdge> {
+    let edges = graph
+        .edge_indices()
+        .filter(|e| !graph[*e].is_constraining)
+        .collect::<Vec<_>>();
+    if edges.is_empty() {
+        return Vec::new();
+    }
+    info!(target: "ranking", "Detaching {} non-constraining edges", edges.len());
+    edges
+        .into_iter()
+        .map(|e| {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            let weight = graph.remove_edge(e).unwrap();
+            let anchor = graph.add_node(Vertex {
+                is_dummy: true,
+                ..Default::default()
+            });
+            for v in [tail, head] {
+                graph.add_edge(
+                    anchor,
+                    v,
+                    Edge {
+                        weight: 0,
+                        ..Default::default()
+                    },
+                );
+            }
+            DetachedEdge {
+                tail,
+                head,
+                weight,
+                anchor,
+            }
+        })
+        .collect()
+}
+
+/// Inserts the detached edges again, after the ranks are assigned.
+///
+/// Edges pointing upwards are reversed. Edges between vertices of the same
+/// rank can't be handled by the following phases, they are returned as flat
+/// edges with their tail, head and id instead.
+pub(crate) fn attach_non_constraining_edges(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    edges: Vec<DetachedEdge>,
+) -> Vec<(NodeIndex, NodeIndex, usize)> {
+    let mut flat_edges = Vec::new();
+    if edges.is_empty() {
+        return flat_edges;
+    }
+    info!(target: "ranking", "Attaching {} non-constraining edges", edges.len());
+    for DetachedEdge {
+        tail,
+        head,
+        weight,
+        anchor,
+    } in edges
+    {
+        graph.remove_node(anchor);
+        match graph[tail].rank.cmp(&graph[head].rank) {
+            Ordering::Less => graph.add_edge(tail, head, weight),
+            Ordering::Greater => graph.add_edge(head, tail, weight.reversed()),
+            Ordering::Equal => {
+                debug!(target: "ranking",
+                    "Keeping edge ({}, {}) apart, since both endpoints have the same rank",
+                    tail.index(),
+                    head.index());
+                flat_edges.push((tail, head, weight.id));
+                continue;
+            }
+        };
+    }
+    normalize(graph);
+    flat_edges
+}
+
+/// Reverses all edges pointing into vertices which need to be in the topmost
+/// rank, or out of vertices which need to be in the bottommost rank. Returns
+/// the number of reversed edges.
+pub(crate) fn reverse_constrained_edges(graph: &mut StableDiGraph<Vertex, Edge>) -> usize {
+    let is_top = |v: &Vertex| {
+        matches!(
+            v.rank_constraint,
+            Some(RankConstraint::Min | RankConstraint::Source)
+        )
+    };
+    let is_bottom = |v: &Vertex| {
+        matches!(
+            v.rank_constraint,
+            Some(RankConstraint::Max | RankConstraint::Sink)
+        )
+    };
+    let edges = graph
+        .edge_indices()
+        .filter(|e| {
+            let (tail, head) = graph.edge_endpoints(*e).unwrap();
+            is_top(&graph[head]) || is_bottom(&graph[tail])
+        })
+        .collect::<Vec<_>>();
+    for e in &edges {
+        let (tail, head) = graph.edge_endpoints(*e).unwrap();
+        let weight = graph.remove_edge(*e).unwrap();
+        graph.add_edge(head, tail, weight.reversed());
+    }
+    debug!(target: "ranking", "Reversed {} edges of vertices with rank constraints", edges.len());
+    edges.len()
+}
+
+/// Moves the vertices with a rank constraint to the topmost or bottommost
+/// rank. Vertices with [RankConstraint::Source] or [RankConstraint::Sink] are
+/// placed in a rank of their own.
+///
+/// Afterwards, vertices are moved down if necessary, so that each edge spans
+/// at least `minimum_length` ranks.
+pub(crate) fn apply_rank_constraints(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    if graph.node_weights().all(|v| v.rank_constraint.is_none()) {
+        return;
+    }
+    info!(target: "ranking", "Moving vertices with rank constraints");
+    // dummy vertices are removed after ranking, so they don't define the top or bottom
+    let ranks = graph
+        .node_weights()
+        .filter(|v| {
+            !v.is_dummy
+                && !matches!(
+                    v.rank_constraint,
+                    Some(RankConstraint::Source | RankConstraint::Sink)
+                )
+        })
+        .map(|v| v.rank);
+    let (min, max) = ranks.fold((i32::MAX, i32::MIN), |(min, max), r| {
+        (min.min(r), max.max(r))
+    });
+    let (min, max) = if min > max { (0, 0) } else { (min, max) };
+    for v in graph.node_weights_mut() {
+        match v.rank_constraint {
+            Some(RankConstraint::Min) => v.rank = min,
+            Some(RankConstraint::Max) => v.rank = max,
+            Some(RankConstraint::Source) => v.rank = min - minimum_length,
+            Some(RankConstraint::Sink) => v.rank = max + minimum_length,
+            None => (),
+        }
+    }
+
+    // constrained vertices connected to each other may be too close
+    for v in toposort(&*graph, None).unwrap() {
+        if let Some(rank) = graph
+            .neighbors_directed(v, Incoming)
+            .map(|n| graph[n].rank + minimum_length)
+            .max()
+        {
+            if graph[v].rank < rank {
+                debug!(target: "ranking", "Moving vertex {} down to rank {rank}", v.index());
+                graph[v].rank = rank;
+            }
+        }
+    }
+    normalize(graph);
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{attach_non_constraining_edges, detach_non_constraining_edges};
+    use crate::algorithm::p1_layering::rank;
+    use crate::algorithm::{Edge, Limit, Vertex};
+    use crate::configure::RankingType;
+
+    #[test]
+    fn non_constraining_edges_do_not_change_ranks() {
+        // 0 -> 1 -> 2 -> 3 and a non-constraining edge 3 -> 0
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (1, 2), (2, 3), (0, 3)]);
+        let e = graph.find_edge(0.into(), 3.into()).unwrap();
+        graph.remove_edge(e);
+        graph.add_edge(
+            3.into(),
+            0.into(),
+            Edge {
+                is_constraining: false,
+                ..Default::default()
+            },
+        );
+
+        let detached = detach_non_constraining_edges(&mut graph);
+        assert!(!petgraph::algo::is_cyclic_directed(&graph));
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
+        attach_non_constraining_edges(&mut graph, detached);
+
+        let ranks = graph.node_weights().map(|v| v.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, [0, 1, 2, 3]);
+        // the edge is reversed, since it points upwards
+        assert!(graph.find_edge(0.into(), 3.into()).is_some());
+        assert_eq!(graph.node_count(), 4);
+    }
+
+    #[test]
+    fn flat_non_constraining_edges_are_kept_apart() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (0, 2)]);
+        graph.add_edge(
+            NodeIndex::new(1),
+            NodeIndex::new(2),
+            Edge {
+                is_constraining: false,
+                ..Default::default()
+            },
+        );
+
+        let detached = detach_non_constraining_edges(&mut graph);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
+        let flat_edges = attach_non_constraining_edges(&mut graph, detached);
+
+        assert_eq!(graph[NodeIndex::new(1)].rank, graph[NodeIndex::new(2)].rank);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(flat_edges, [(NodeIndex::new(1), NodeIndex::new(2), 0)]);
+    }
+}
@@ -7,7 +7,7 @@ use petgraph::{
     Direction::{self, Incoming, Outgoing},
 };
 
-use super::{Edge, Vertex};
+use super::{is_in_subtree, Edge, Vertex};
 
 #[derive(Debug)]
 struct NeighborhoodInfo {
@@ -26,16 +26,40 @@ pub(super) fn init_cutvalues(graph: &mut StableDiGraph<Vertex, Edge>) {
     calculate_cut_values(graph, queue);
 }
 
+/// Updates the cut values after `removed_edge` left the tree and `swap_edge`
+/// entered it, as described by Gansner et al.
+///
+/// Only the cut values of the tree edges on the path between the endpoints of
+/// `swap_edge` change, and each of them changes by the cut value of
+/// `removed_edge`. Expects the low and lim values of the old tree and returns
+/// the least common ancestor of the endpoints of `swap_edge`.
 pub(super) fn update_cutvalues(
     graph: &mut StableDiGraph<Vertex, Edge>,
     removed_edge: EdgeIndex,
     swap_edge: EdgeIndex,
 ) -> NodeIndex {
     info!(target: "cut_values", "Updating outdated cut values");
-    let least_common_ancestor = remove_outdated_cut_values(graph, swap_edge, removed_edge);
-    let queue = VecDeque::from([graph.edge_endpoints(removed_edge).unwrap().0]);
-    debug!(target: "cut_values", "Leaves of tree: {queue:?}");
-    calculate_cut_values(graph, queue);
+    let cut_value = graph[removed_edge].cut_value.unwrap();
+    let (tail, head) = graph.edge_endpoints(swap_edge).unwrap();
+    let (path, least_common_ancestor) = tree_path(graph, tail, head);
+    for (edge, child) in path {
+        // the cut value changes by the cut value of the removed edge, if the
+        // swap edge crosses the cut in the same direction as the tree edge,
+        // and by its negation otherwise.
+        let tail_in_subtree = is_in_subtree(graph, tail, child);
+        let change = if (graph.edge_endpoints(edge).unwrap().0 == child) == tail_in_subtree {
+            cut_value
+        } else {
+            -cut_value
+        };
+        if edge == removed_edge {
+            graph[removed_edge].cut_value = None;
+            graph[swap_edge].cut_value = Some(change);
+        } else {
+            graph[edge].cut_value = graph[edge].cut_value.map(|c| c + change);
+        }
+        trace!(target: "cut_values", "Cut values for edge: {}, {:?}", edge.index(), graph[edge].cut_value);
+    }
     least_common_ancestor
 }
 
@@ -136,31 +160,42 @@ fn get_neighborhood_info(
     })
 }
 
-fn remove_outdated_cut_values(
-    graph: &mut StableDiGraph<Vertex, Edge>,
-    swap_edge: EdgeIndex,
-    removed_edge: EdgeIndex,
-) -> NodeIndex {
-    info!(target: "cut_values", "Remove outtdated cut_values, in order to calculate new ones");
-    graph[removed_edge].cut_value = None;
-    let (mut w, mut x) = graph.edge_endpoints(swap_edge).unwrap();
-    if graph[w].lim > graph[x].lim {
-        std::mem::swap(&mut w, &mut x)
-    }
+/// Returns the tree edges on the path between `u` and `v` together with the
+/// endpoint of each edge which is further away from the root, as well as the
+/// least common ancestor of `u` and `v`.
+fn tree_path(
+    graph: &StableDiGraph<Vertex, Edge>,
+    u: NodeIndex,
+    v: NodeIndex,
+) -> (Vec<(EdgeIndex, NodeIndex)>, NodeIndex) {
+    let (w, x) = if graph[u].lim > graph[v].lim {
+        (v, u)
+    } else {
+        (u, v)
+    };
     debug!(target: "cut_values", 
-        "looking for path connecting endpoints of new edge ({}, {}), removing cut values on the way", 
+        "looking for path connecting endpoints of new edge ({}, {})", 
         w.index(), 
         x.index());
 
+    let mut path = Vec::new();
+    // edges of the old tree are the ones which have a cut value
+    let tree_edge = |l: NodeIndex, parent: NodeIndex| {
+        let mut walker = graph.neighbors_undirected(l).detach();
+        while let Some((edge, n)) = walker.next(graph) {
+            if n == parent && graph[edge].cut_value.is_some() {
+                return edge;
+            }
+        }
+        unreachable!("vertex is connected to its parent via a tree edge")
+    };
     // follow path back until least common ancestor is found
-    // and remove cut_values on the way
     let least_common_ancestor = match graph[w].parent {
         None => w,
         Some(mut parent) => {
             let mut l = w;
             loop {
-                let edge = graph.find_edge_undirected(l, parent).unwrap().0;
-                graph[edge].cut_value = None;
+                path.push((tree_edge(l, parent), l));
                 l = parent;
                 trace!(target: "cut_values", "current node in path: {}", l.index());
                 if graph[l].low <= graph[w].lim && graph[x].lim <= graph[l].lim
@@ -173,13 +208,10 @@ fn remove_outdated_cut_values(
         }
     };
     // record path from x to l
-    // we don't need to care about the order in which the edges are added,
-    // since we only need them to remove the outdated cutvalues.
     let mut l = x;
     while l != least_common_ancestor {
         let parent = graph[l].parent.unwrap();
-        let edge = graph.find_edge_undirected(l, parent).unwrap().0;
-        graph[edge].cut_value = None;
+        path.push((tree_edge(l, parent), l));
         l = parent;
     }
 
@@ -189,7 +221,7 @@ fn remove_outdated_cut_values(
         x.index(), 
         least_common_ancestor.index());
 
-    least_common_ancestor
+    (path, least_common_ancestor)
 }
 
 #[cfg(test)]
@@ -198,13 +230,14 @@ mod tests {
     use petgraph::stable_graph::NodeIndex;
 
     use crate::algorithm::p1_layering::{
-        cut_values::{init_cutvalues, remove_outdated_cut_values, update_cutvalues},
+        cut_values::{init_cutvalues, tree_path, update_cutvalues},
         low_lim::init_low_lim,
         tests::{
             GraphBuilder, CUT_VALUES_EXAMPLE_GRAPH_NEG_CUT_VALUE, EXAMPLE_GRAPH,
             EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE, EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE,
             LOW_LIM_GRAPH, LOW_LIM_GRAPH_LOW_LIM_VALUES,
         },
+        tree::Tree,
         Edge,
     };
 
@@ -329,7 +362,7 @@ mod tests {
     }
 
     #[test]
-    fn remove_outdated_cut_values_low_lim_graph() {
+    fn tree_path_low_lim_graph() {
         let (mut graph, ..) = GraphBuilder::new(&LOW_LIM_GRAPH)
             .with_tree_edges(&LOW_LIM_GRAPH)
             .with_low_lim_values(&LOW_LIM_GRAPH_LOW_LIM_VALUES)
@@ -339,25 +372,21 @@ mod tests {
 
         let tail = 6.into();
         let head = 8.into();
-        let edge = graph.add_edge(tail, head, Edge::default());
-        let actual_lca = remove_outdated_cut_values(&mut graph, edge, edge);
-        let expected_path = [(5, 6), (4, 5), (4, 8)]
+        graph.add_edge(tail, head, Edge::default());
+        let (path, actual_lca) = tree_path(&graph, tail, head);
+        let expected_path = [(4, 5), (5, 6), (4, 8)]
             .into_iter()
             .map(|(t, h)| graph.find_edge(t.into(), h.into()).unwrap())
             .collect::<Vec<_>>();
-        for edge in graph.edge_indices() {
-            if expected_path.contains(&edge) {
-                assert!(graph[edge].cut_value.is_none());
-            } else if graph[edge].is_tree_edge {
-                assert!(graph[edge].cut_value.is_some())
-            }
-        }
+        let mut path = path.into_iter().map(|(e, _)| e).collect::<Vec<_>>();
+        path.sort();
+        assert_eq!(path, expected_path);
         let expected_lca = NodeIndex::from(4_u32);
         assert_eq!(actual_lca, expected_lca);
     }
 
     #[test]
-    fn remove_outdated_cut_values_low_lim_graph_lca_is_root() {
+    fn tree_path_low_lim_graph_lca_is_root() {
         let (mut graph, ..) = GraphBuilder::new(&LOW_LIM_GRAPH)
             .with_tree_edges(&LOW_LIM_GRAPH)
             .with_low_lim_values(&LOW_LIM_GRAPH_LOW_LIM_VALUES)
@@ -367,19 +396,15 @@ mod tests {
 
         let tail = 3.into();
         let head = 8.into();
-        let edge = graph.add_edge(tail, head, Edge::default());
-        let actual_lca = remove_outdated_cut_values(&mut graph, edge, edge);
-        let expected_path = [(1, 3), (0, 1), (4, 8), (0, 4)]
+        graph.add_edge(tail, head, Edge::default());
+        let (path, actual_lca) = tree_path(&graph, tail, head);
+        let expected_path = [(0, 1), (1, 3), (0, 4), (4, 8)]
             .into_iter()
             .map(|(t, h)| graph.find_edge(t.into(), h.into()).unwrap())
             .collect::<Vec<_>>();
-        for edge in graph.edge_indices() {
-            if expected_path.contains(&edge) {
-                assert!(graph[edge].cut_value.is_none());
-            } else if graph[edge].is_tree_edge {
-                assert!(graph[edge].cut_value.is_some())
-            }
-        }
+        let mut path = path.into_iter().map(|(e, _)| e).collect::<Vec<_>>();
+        path.sort();
+        assert_eq!(path, expected_path);
         let expected_lca = NodeIndex::from(0_u32);
         assert_eq!(actual_lca, expected_lca);
     }
@@ -391,7 +416,8 @@ mod tests {
             .with_least_common_ancestor(0)
             .build();
 
-        init_low_lim(&mut graph);
+        let tree = Tree::from_graph(&graph);
+        init_low_lim(&mut graph, &tree);
 
         let swap_edge = graph.find_edge(0.into(), 4.into()).unwrap();
         let removed_edge = graph.find_edge(6.into(), 7.into()).unwrap();
@@ -459,7 +485,8 @@ mod tests {
             .with_least_common_ancestor(0)
             .build();
 
-        init_low_lim(&mut graph);
+        let tree = Tree::from_graph(&graph);
+        init_low_lim(&mut graph, &tree);
 
         let swap_edge = graph.find_edge(0.into(), 4.into()).unwrap();
         let removed_edge = graph.find_edge(6.into(), 7.into()).unwrap();
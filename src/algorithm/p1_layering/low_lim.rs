@@ -1,22 +1,23 @@
-use std::collections::HashSet;
-
 use log::{debug, info, trace};
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::NodeIndexable;
 
-use super::{Edge, Vertex};
+use super::{tree::Tree, Edge, Vertex};
 
-pub(super) fn init_low_lim(graph: &mut StableDiGraph<Vertex, Edge>) {
+pub(super) fn init_low_lim(graph: &mut StableDiGraph<Vertex, Edge>, tree: &Tree) {
     // start at arbitrary root node
     info!(target: "low_lim", "initialzing low, lim and parent values of vertices");
     let root = graph.node_indices().next().unwrap();
     debug!(target: "low_lim", "root of tree: {}", root.index());
     let mut max_lim = graph.node_count() as u32;
     trace!(target: "low_lim", "Maximum Lim value: {max_lim}");
-    dfs_low_lim(graph, root, None, &mut max_lim, &mut HashSet::new());
+    let mut visited = vec![false; graph.node_bound()];
+    dfs_low_lim(graph, tree, root, None, &mut max_lim, &mut visited);
 }
 
 pub(super) fn update_low_lim(
     graph: &mut StableDiGraph<Vertex, Edge>,
+    tree: &Tree,
     least_common_ancestor: NodeIndex,
 ) {
     info!(target: "low_lim", 
@@ -24,14 +25,15 @@ pub(super) fn update_low_lim(
         least_common_ancestor.index());
 
     let parent = graph[least_common_ancestor].parent;
-    let mut visited = match &parent {
-        Some(parent) => HashSet::from([*parent]),
-        None => HashSet::new(),
-    };
+    let mut visited = vec![false; graph.node_bound()];
+    if let Some(parent) = parent {
+        visited[parent.index()] = true;
+    }
     let mut max_lim = graph[least_common_ancestor].lim;
     trace!(target: "low_lim", "Maximum Lim value: {max_lim}");
     dfs_low_lim(
         graph,
+        tree,
         least_common_ancestor,
         parent,
         &mut max_lim,
@@ -41,20 +43,20 @@ pub(super) fn update_low_lim(
 
 fn dfs_low_lim(
     graph: &mut StableDiGraph<Vertex, Edge>,
+    tree: &Tree,
     next: NodeIndex,
     parent: Option<NodeIndex>,
     max_lim: &mut u32,
-    visited: &mut HashSet<NodeIndex>,
+    visited: &mut [bool],
 ) {
-    visited.insert(next);
+    visited[next.index()] = true;
     debug!(target: "low_lim", "calculating values for vertex: {}", next.index());
     graph[next].lim = *max_lim;
     graph[next].parent = parent;
-    let mut walker = graph.neighbors_undirected(next).detach();
-    while let Some((edge, n)) = walker.next(graph) {
-        if !visited.contains(&n) && graph[edge].is_tree_edge {
+    for &(_, n) in tree.neighbors(next) {
+        if !visited[n.index()] {
             *max_lim -= 1;
-            dfs_low_lim(graph, n, Some(next), max_lim, visited);
+            dfs_low_lim(graph, tree, n, Some(next), max_lim, visited);
         }
     }
     graph[next].low = *max_lim;
@@ -76,6 +78,7 @@ mod tests {
             EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE, EXAMPLE_GRAPH_LOW_LIM_VALUES_NEG_CUT_VALUE,
             LOW_LIM_GRAPH_AFTER_UPDATE, LOW_LIM_GRAPH_LOW_LIM_VALUES,
         },
+        tree::Tree,
         Vertex,
     };
 
@@ -89,7 +92,8 @@ mod tests {
             .with_tree_edges(&LOW_LIM_GRAPH)
             .build();
 
-        init_low_lim(&mut graph);
+        let tree = Tree::from_graph(&graph);
+        init_low_lim(&mut graph, &tree);
 
         let v0 = graph[NodeIndex::from(0)];
         let v1 = graph[NodeIndex::from(1)];
@@ -118,7 +122,8 @@ mod tests {
             .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE)
             .build();
 
-        init_low_lim(&mut graph);
+        let tree = Tree::from_graph(&graph);
+        init_low_lim(&mut graph, &tree);
 
         assert_eq!(graph[NodeIndex::from(0)].low, 1);
         assert_eq!(graph[NodeIndex::from(0)].lim, 8);
@@ -154,7 +159,8 @@ mod tests {
             .with_least_common_ancestor(4)
             .build();
 
-        update_low_lim(&mut graph, least_common_ancestor);
+        let tree = Tree::from_graph(&graph);
+        update_low_lim(&mut graph, &tree, least_common_ancestor);
         let v4 = graph[NodeIndex::from(4)];
         let v5 = graph[NodeIndex::from(5)];
         let v6 = graph[NodeIndex::from(6)];
@@ -175,7 +181,8 @@ mod tests {
             .with_least_common_ancestor(0)
             .build();
 
-        update_low_lim(&mut graph, least_common_ancestor);
+        let tree = Tree::from_graph(&graph);
+        update_low_lim(&mut graph, &tree, least_common_ancestor);
         let v0 = graph[NodeIndex::from(0)];
         let v1 = graph[NodeIndex::from(1)];
         let v2 = graph[NodeIndex::from(2)];
@@ -5,49 +5,204 @@
 //!
 //! 1. Original - tries to move each vertex as close to neighbors as possible.
 //! 2. MinimizeEdgeLength - builds a feasible tight tree in order to minimize
-//! edge lengths. This is the technique describe in the paper by Gansner et al.
+//!    edge lengths. This is the technique describe in the paper by Gansner et al.
 //! 3. Up - Move vertices as far up as possible
 //! 4. Down - Move vertices as far down as possible.
+//! 5. MinWidth - builds the ranks from the bottom up, keeping the widest rank
+//!    narrow, as described in the paper "A heuristic for minimum-width graph
+//!    layering with consideration of dummy nodes" by Nikolov et al.
+//! 6. Optimal - minimizes the weighted sum of the lengths of all edges by
+//!    solving its linear program, only with the `lp` feature.
 //!
+mod constraints;
 mod cut_values;
 mod low_lim;
+#[cfg(feature = "lp")]
+mod optimal;
+mod promotion;
 pub(super) mod ranking;
 #[cfg(test)]
 pub(crate) mod tests;
+mod tree;
+mod width;
 
-use log::info;
-use petgraph::stable_graph::{EdgeIndex, StableDiGraph};
+use std::collections::HashMap;
+
+use log::{debug, info, warn};
+use petgraph::algo::toposort;
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
 use petgraph::visit::IntoNodeIdentifiers;
+use petgraph::Direction::{Incoming, Outgoing};
 
-use crate::configure::RankingType;
+use crate::configure::{RankingAlgorithm, RankingType};
 
+pub(super) use self::constraints::{
+    apply_rank_constraints, attach_non_constraining_edges, detach_non_constraining_edges,
+    reverse_constrained_edges,
+};
 use self::cut_values::update_cutvalues;
 use self::low_lim::update_low_lim;
-use self::ranking::{feasible_tree, init_rank, move_vertices_down, move_vertices_up, update_ranks};
+pub(super) use self::promotion::promote_vertices;
+use self::ranking::{
+    feasible_tree, init_rank, move_vertices_down, move_vertices_up, shift_subtree_ranks,
+};
+use self::tree::Tree;
+use self::width::min_width;
+pub(super) use self::width::{approximate_aspect_ratio, limit_width, minimize_area};
+
+use super::{slack, Edge, Limit, Vertex};
 
-use super::{slack, Edge, Vertex};
+/// Maximum number of pivots of the network simplex per edge, after which
+/// ranking stops, even if the ranking is not optimal yet.
+const MAX_PIVOTS_PER_EDGE: usize = 50;
 
 pub(super) fn rank(
     graph: &mut StableDiGraph<Vertex, Edge>,
     minimum_length: i32,
     ranking_type: RankingType,
+    limit: Limit,
 ) {
     info!(target: "ranking", "Start ranking, ranking type: {ranking_type:?}, minimum_length: {minimum_length}");
     init_rank(graph, minimum_length);
     match ranking_type {
         RankingType::Original => original(graph, minimum_length),
-        RankingType::MinimizeEdgeLength => minimize_edge_length(graph, minimum_length),
+        RankingType::MinimizeEdgeLength => minimize_edge_length(graph, minimum_length, limit),
+        // the solver can't be interrupted, so limits are ignored
+        #[cfg(feature = "lp")]
+        RankingType::Optimal => optimal::optimal_ranking(graph, minimum_length),
         RankingType::Up => move_vertices_up(graph, minimum_length),
         RankingType::Down => move_vertices_down(graph, minimum_length),
+        RankingType::MinWidth => min_width(graph, minimum_length),
+    }
+}
+
+/// Assigns the ranks returned by a custom ranking algorithm, see
+/// [assign_ranks].
+pub(super) fn rank_custom(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    algorithm: &dyn RankingAlgorithm,
+) {
+    info!(target: "ranking", "Start ranking with a custom algorithm, minimum_length: {minimum_length}");
+    // keeps the indices, so the ranks refer to vertices of `graph`
+    let input = graph.map(|_, v| v.id, |_, _| ());
+    let ranks = algorithm.rank(&input, minimum_length as u32);
+    assign_ranks(graph, minimum_length, &ranks);
+}
+
+/// Assigns given ranks to the vertices.
+///
+/// Vertices without a rank are put into the first rank. Afterwards vertices
+/// are moved down, until each edge spans at least `minimum_length` ranks.
+pub(super) fn assign_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    ranks: &HashMap<NodeIndex, u32>,
+) {
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        graph[v].rank = ranks.get(&v).map_or(0, |r| *r as i32);
+    }
+    let mut moved = 0;
+    for v in toposort(&*graph, None).unwrap() {
+        if let Some(min) = graph
+            .neighbors_directed(v, Incoming)
+            .map(|n| graph[n].rank + minimum_length)
+            .max()
+        {
+            if graph[v].rank < min {
+                graph[v].rank = min;
+                moved += 1;
+            }
+        }
+    }
+    if moved > 0 {
+        warn!(target: "ranking", "Moved {moved} vertices down, since their incoming edges were too short");
+    }
+    normalize(graph);
+}
+
+/// Moves frozen vertices back to the rank given by their frozen y-coordinate.
+///
+/// All other vertices are moved by the same amount first, and are then moved
+/// up or down, so that each edge spans at least `minimum_length` ranks. If the
+/// frozen ranks contradict each other, frozen vertices are moved down as well.
+pub(super) fn apply_frozen_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    rank_spacing: usize,
+) {
+    let frozen_rank = |v: &Vertex| match v.pin {
+        Some((_, y)) if v.is_frozen => Some((-y as f64 / rank_spacing as f64).round() as i32),
+        _ => None,
+    };
+    let mut offsets = graph
+        .node_weights()
+        .filter_map(|v| frozen_rank(v).map(|r| r - v.rank))
+        .collect::<Vec<_>>();
+    if offsets.is_empty() {
+        return;
+    }
+    info!(target: "ranking", "Moving frozen vertices to their frozen rank");
+    offsets.sort();
+    let offset = offsets[offsets.len() / 2];
+    for v in graph.node_weights_mut() {
+        v.rank = frozen_rank(v).unwrap_or(v.rank + offset);
+    }
+
+    let vertices = toposort(&*graph, None).unwrap();
+    // move vertices above their successors
+    for v in vertices.iter().rev() {
+        if graph[*v].is_frozen {
+            continue;
+        }
+        if let Some(max) = graph
+            .neighbors_directed(*v, Outgoing)
+            .map(|n| graph[n].rank - minimum_length)
+            .min()
+        {
+            graph[*v].rank = graph[*v].rank.min(max);
+        }
+    }
+    // move vertices below their predecessors
+    for v in vertices {
+        if let Some(min) = graph
+            .neighbors_directed(v, Incoming)
+            .map(|n| graph[n].rank + minimum_length)
+            .max()
+        {
+            if graph[v].rank < min {
+                if graph[v].is_frozen {
+                    debug!(target: "ranking", "Frozen vertex {} needs to move to rank {min}", v.index());
+                }
+                graph[v].rank = min;
+            }
+        }
     }
+    normalize(graph);
 }
 
-fn minimize_edge_length(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    feasible_tree(graph, minimum_length);
+fn minimize_edge_length(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    limit: Limit,
+) {
+    let mut tree = feasible_tree(graph, minimum_length);
+    // each exchange keeps the ranking feasible, so we can stop at any time
+    let max_pivots = graph.edge_count().max(1) * MAX_PIVOTS_PER_EDGE;
+    let mut iterations = 0;
     while let Some(removed_edge) = leave_edge(graph) {
+        if limit.is_reached(iterations) {
+            break;
+        }
+        if iterations >= max_pivots {
+            warn!(target: "ranking",
+                "Stopping network simplex after {iterations} pivots, ranking might not be optimal");
+            break;
+        }
+        iterations += 1;
         // swap edges and calculate cut value
         let swap_edge = enter_edge(graph, removed_edge, minimum_length);
-        exchange(graph, removed_edge, swap_edge, minimum_length);
+        exchange(graph, &mut tree, removed_edge, swap_edge, minimum_length);
     }
 
     // don't balance ranks since we want maximum width to
@@ -60,6 +215,10 @@ fn original(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
     move_vertices_down(graph, minimum_length);
 }
 
+/// Returns the tree edge with a negative cut value and the lowest index.
+///
+/// Together with breaking ties in [enter_edge] by the lowest index this is
+/// Bland's rule, which prevents the network simplex from cycling.
 fn leave_edge(graph: &StableDiGraph<Vertex, Edge>) -> Option<EdgeIndex> {
     for edge in graph.edge_indices() {
         if let Some(cut_value) = graph[edge].cut_value {
@@ -92,24 +251,53 @@ fn enter_edge(
     graph
         .edge_indices()
         .filter(|e| !graph[*e].is_tree_edge && is_head_to_tail(graph, *e, u, is_root_in_head))
-        .min_by(|e1, e2| slack(graph, *e1, minimum_length).cmp(&slack(graph, *e2, minimum_length)))
+        .min_by_key(|e| (slack(graph, *e, minimum_length), e.index()))
         .unwrap()
 }
 
 fn exchange(
     graph: &mut StableDiGraph<Vertex, Edge>,
+    tree: &mut Tree,
     removed_edge: EdgeIndex,
     swap_edge: EdgeIndex,
     minimum_length: i32,
 ) {
+    // the subtree below the removed edge is moved, so that the swap edge
+    // becomes tight. All other ranks stay the same.
+    let (tail, head) = graph.edge_endpoints(removed_edge).unwrap();
+    let subtree_root = if graph[tail].lim < graph[head].lim {
+        tail
+    } else {
+        head
+    };
+    let slack = slack(graph, swap_edge, minimum_length);
+    let (swap_tail, _) = graph.edge_endpoints(swap_edge).unwrap();
+    let delta = if is_in_subtree(graph, swap_tail, subtree_root) {
+        slack
+    } else {
+        -slack
+    };
+    shift_subtree_ranks(graph, tree, subtree_root, delta);
+
     // swap edges
     graph[removed_edge].is_tree_edge = false;
     graph[swap_edge].is_tree_edge = true;
 
     // update the graph
     let least_common_ancestor = update_cutvalues(graph, removed_edge, swap_edge);
-    update_low_lim(graph, least_common_ancestor);
-    update_ranks(graph, minimum_length);
+    tree.replace_edge(graph, removed_edge, swap_edge);
+    update_low_lim(graph, tree, least_common_ancestor);
+}
+
+/// Returns whether `v` is part of the subtree of `subtree_root`, according to
+/// the low and lim values of the tree.
+fn is_in_subtree(
+    graph: &StableDiGraph<Vertex, Edge>,
+    v: NodeIndex,
+    subtree_root: NodeIndex,
+) -> bool {
+    let root = &graph[subtree_root];
+    root.low <= graph[v].lim && graph[v].lim <= root.lim
 }
 
 fn normalize(graph: &mut StableDiGraph<Vertex, Edge>) {
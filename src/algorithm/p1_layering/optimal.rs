@@ -0,0 +1,59 @@
+//! Assigns ranks by solving the linear program of the ranking exactly.
+//!
+//! The ranking which minimizes the weighted sum of the lengths of all edges is
+//! the solution of the linear program
+//!
+//! minimize sum(weight(e) * (rank(head(e)) - rank(tail(e))))
+//! subject to rank(head(e)) - rank(tail(e)) >= minimum_length for all edges e,
+//! and rank(v) >= 0 for all vertices v.
+//!
+//! Its constraint matrix is the incidence matrix of a directed graph, which is
+//! totally unimodular, so each basic solution found by the simplex method is
+//! integral.
+use log::{info, warn};
+use microlp::{ComparisonOp, OptimizationDirection, Problem};
+use petgraph::stable_graph::StableDiGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+
+use super::{minimize_edge_length, normalize, Edge, Limit, Vertex};
+
+pub(super) fn optimal_ranking(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Solving linear program of the ranking");
+    // objective coefficient of each rank, which is the weight of all incoming
+    // edges minus the weight of all outgoing edges
+    let mut coefficients = vec![0.; graph.node_bound()];
+    for edge in graph.edge_references() {
+        coefficients[edge.target().index()] += edge.weight().weight as f64;
+        coefficients[edge.source().index()] -= edge.weight().weight as f64;
+    }
+
+    let mut problem = Problem::new(OptimizationDirection::Minimize);
+    let ranks = coefficients
+        .iter()
+        .map(|c| problem.add_var(*c, (0., f64::INFINITY)))
+        .collect::<Vec<_>>();
+    for edge in graph.edge_references() {
+        problem.add_constraint(
+            [
+                (ranks[edge.target().index()], 1.),
+                (ranks[edge.source().index()], -1.),
+            ],
+            ComparisonOp::Ge,
+            minimum_length as f64,
+        );
+    }
+
+    match problem.solve() {
+        Ok(solution) => {
+            for v in graph.node_indices().collect::<Vec<_>>() {
+                graph[v].rank = solution.var_value(ranks[v.index()]).round() as i32;
+            }
+            normalize(graph);
+        }
+        Err(error) => {
+            warn!(target: "ranking",
+                "Unable to solve linear program of the ranking: {error}, minimizing edge lengths instead");
+            minimize_edge_length(graph, minimum_length, Limit::default());
+        }
+    }
+}
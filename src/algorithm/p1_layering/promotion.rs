@@ -0,0 +1,67 @@
+//! Promotion of vertices to reduce the number of dummy vertices.
+//!
+//! Implements the heuristic from the paper "Graph layering by promotion of
+//! nodes" by Nikolov and Tarassov. A vertex is promoted by moving it one rank
+//! up, together with all predecessors which would otherwise be too close to
+//! it. The promotion is kept if it reduces the number of dummy vertices, and
+//! undone otherwise. Vertices are promoted in rounds, until a round doesn't
+//! reduce the number of dummy vertices any further. No vertex is moved above
+//! the first rank, so the height of the ranking never grows.
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use super::{Edge, Vertex};
+
+/// Promotes vertices, as long as this reduces the number of dummy vertices.
+pub(crate) fn promote_vertices(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Promoting vertices to reduce the number of dummy vertices");
+    let vertices = graph
+        .node_indices()
+        .filter(|v| graph.neighbors_directed(*v, Incoming).next().is_some())
+        .collect::<Vec<_>>();
+    let mut round = 0;
+    loop {
+        let mut promotions = 0;
+        for v in &vertices {
+            let mut promoted = Vec::new();
+            match promote(graph, *v, minimum_length, &mut promoted) {
+                Some(difference) if difference < 0 => promotions += 1,
+                _ => promoted.into_iter().for_each(|v| graph[v].rank += 1),
+            }
+        }
+        debug!(target: "ranking", "Promoted {promotions} vertices in round {round}");
+        round += 1;
+        if promotions == 0 {
+            break;
+        }
+    }
+}
+
+/// Moves `v` one rank up, and its predecessors as far as necessary. Returns
+/// the change of the number of dummy vertices, or `None` if a vertex would
+/// be moved above the first rank. All moved vertices are added to `promoted`.
+fn promote(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    v: NodeIndex,
+    minimum_length: i32,
+    promoted: &mut Vec<NodeIndex>,
+) -> Option<isize> {
+    let rank = graph[v].rank;
+    if rank == 0 {
+        return None;
+    }
+    let mut difference = 0;
+    let predecessors = graph.neighbors_directed(v, Incoming).collect::<Vec<_>>();
+    for u in predecessors {
+        if graph[u].rank + minimum_length > rank - 1 {
+            difference += promote(graph, u, minimum_length, promoted)?;
+        }
+    }
+    graph[v].rank -= 1;
+    promoted.push(v);
+    // edges into v get shorter, edges out of v get longer
+    let incoming = graph.neighbors_directed(v, Incoming).count() as isize;
+    let outgoing = graph.neighbors_directed(v, Outgoing).count() as isize;
+    Some(difference - incoming + outgoing)
+}
@@ -1,12 +1,10 @@
-use std::collections::{HashSet, VecDeque};
-
 use log::{debug, info, trace};
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
-    Direction::{self, Incoming, Outgoing},
+    Direction::{Incoming, Outgoing},
 };
 
-use super::{cut_values::init_cutvalues, low_lim::init_low_lim, slack, Edge, Vertex};
+use super::{cut_values::init_cutvalues, low_lim::init_low_lim, slack, tree::Tree, Edge, Vertex};
 
 #[allow(dead_code)]
 pub(crate) fn print_ranks(graph: &StableDiGraph<Vertex, Edge>) {
@@ -21,13 +19,15 @@ pub(crate) fn print_ranks(graph: &StableDiGraph<Vertex, Edge>) {
 
 /// Builds a feasible tree, which means a tree in which each edge has a
 /// minimum amount of slack (edge length = minimum length)
-pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) -> Tree {
     info!(target: "ranking", "building feasible tree");
     let tree_root = graph.node_indices().next().unwrap();
     trace!(target: "ranking", "root of tree is: {}", tree_root.index());
 
     info!(target: "ranking", "Trying to build tight tree.");
-    while tight_tree(graph, tree_root, &mut HashSet::new(), minimum_length) < graph.node_count() {
+    while tight_tree(graph, tree_root, &mut visited_edges(graph), minimum_length)
+        < graph.node_count()
+    {
         debug!(target: "ranking", "unable to build tight tree yet, finding edge which is not tight");
         let edge = find_non_tight_edge(graph, minimum_length);
         let (tail, head) = graph.edge_endpoints(edge).unwrap();
@@ -41,8 +41,21 @@ pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_len
         tighten_edge(graph, delta);
     }
 
+    let tree = Tree::from_graph(graph);
     init_cutvalues(graph);
-    init_low_lim(graph);
+    init_low_lim(graph, &tree);
+    tree
+}
+
+/// Returns a visited flag for each edge of the graph.
+fn visited_edges(graph: &StableDiGraph<Vertex, Edge>) -> Vec<bool> {
+    vec![
+        false;
+        graph
+            .edge_indices()
+            .next_back()
+            .map_or(0, |e| e.index() + 1)
+    ]
 }
 
 pub(super) fn move_vertices_up(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
@@ -76,31 +89,27 @@ pub(super) fn move_vertices_down(graph: &mut StableDiGraph<Vertex, Edge>, minimu
     }
 }
 
-pub(super) fn update_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    info!(target: "ranking", "Updating node ranks");
-    let node = graph.node_indices().next().unwrap();
-    let mut visited = HashSet::from([node]);
-    graph[node].rank = 0;
-    let mut queue = VecDeque::from([node]);
-
-    while let Some(parent) = queue.pop_front() {
-        update_neighbor_ranks(
-            graph,
-            parent,
-            Outgoing,
-            1,
-            &mut queue,
-            &mut visited,
-            minimum_length,
-        );
-        update_neighbor_ranks(
-            graph,
-            parent,
-            Incoming,
-            -1,
-            &mut queue,
-            &mut visited,
-            minimum_length,
+/// Moves all vertices in the subtree of `subtree_root` by `delta` ranks.
+///
+/// The subtree is walked via the tree, so the parent of each vertex is used
+/// to avoid walking back up.
+pub(super) fn shift_subtree_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    tree: &Tree,
+    subtree_root: NodeIndex,
+    delta: i32,
+) {
+    info!(target: "ranking", "Moving subtree of {} by {delta} ranks", subtree_root.index());
+    let mut stack = vec![subtree_root];
+    while let Some(v) = stack.pop() {
+        graph[v].rank += delta;
+        trace!(target: "ranking", "updating ranks of {}, new rank is: {}", v.index(), graph[v].rank);
+        let parent = graph[v].parent;
+        stack.extend(
+            tree.neighbors(v)
+                .iter()
+                .map(|(_, n)| *n)
+                .filter(|n| Some(*n) != parent),
         );
     }
 }
@@ -110,7 +119,7 @@ pub(super) fn update_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_leng
 fn tight_tree(
     graph: &mut StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
-    visited: &mut HashSet<EdgeIndex>,
+    visited: &mut [bool],
     minimum_length: i32,
 ) -> usize {
     // start from topmost nodes.
@@ -126,8 +135,8 @@ fn tight_tree(
         let (tail, head) = graph.edge_endpoints(edge).unwrap();
         let other = if tail == vertex { head } else { tail };
 
-        if !visited.contains(&edge) {
-            visited.insert(edge);
+        if !visited[edge.index()] {
+            visited[edge.index()] = true;
             if graph[edge].is_tree_edge {
                 node_count += tight_tree(graph, other, visited, minimum_length);
             } else if slack(graph, edge, minimum_length) == 0 && !graph[other].is_tree_vertex {
@@ -182,45 +191,23 @@ fn tighten_edge(graph: &mut StableDiGraph<Vertex, Edge>, delta: i32) {
     }
 }
 
-fn update_neighbor_ranks(
-    graph: &mut StableDiGraph<Vertex, Edge>,
-    parent: NodeIndex,
-    direction: Direction,
-    coefficient: i32,
-    queue: &mut VecDeque<NodeIndex>,
-    visited: &mut HashSet<NodeIndex>,
-    minimum_length: i32,
-) {
-    let mut walker = graph.neighbors_directed(parent, direction).detach();
-    while let Some((edge, other)) = walker.next(graph) {
-        if !graph[edge].is_tree_edge || visited.contains(&other) {
-            continue;
-        }
-        graph[other].rank = graph[parent].rank + minimum_length * coefficient;
-        trace!(target: "ranking", "updating ranks of {}, new rank is: {}", other.index(), graph[other].rank);
-        queue.push_back(other);
-        visited.insert(other);
-    }
-}
-
 #[cfg(test)]
 mod tests {
 
-    use std::collections::HashSet;
-
     use petgraph::Direction::{Incoming, Outgoing};
 
     use crate::algorithm::p1_layering::{
-        ranking::{feasible_tree, tight_tree},
+        ranking::{feasible_tree, tight_tree, visited_edges},
         slack,
         tests::{
             EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE, EXAMPLE_GRAPH_NON_TIGHT_INITIAL_RANKING,
         },
+        tree::Tree,
     };
 
     use super::{
         super::tests::{GraphBuilder, EXAMPLE_GRAPH},
-        init_rank, update_ranks,
+        init_low_lim, init_rank, shift_subtree_ranks,
     };
 
     #[test]
@@ -261,7 +248,8 @@ mod tests {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
         init_rank(&mut graph, 1);
         let number_of_nodes = graph.node_count();
-        tight_tree(&mut graph, 0.into(), &mut HashSet::new(), 1);
+        let mut visited = visited_edges(&graph);
+        tight_tree(&mut graph, 0.into(), &mut visited, 1);
 
         assert_eq!(
             graph
@@ -284,7 +272,8 @@ mod tests {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
         let number_of_nodes = graph.node_count();
         init_rank(&mut graph, 1);
-        tight_tree(&mut graph, 4.into(), &mut HashSet::new(), 1);
+        let mut visited = visited_edges(&graph);
+        tight_tree(&mut graph, 4.into(), &mut visited, 1);
 
         assert_eq!(
             graph
@@ -371,8 +360,8 @@ mod tests {
     }
 
     #[test]
-    fn update_ranks_example_graph() {
-        let (mut graph, minimum_length, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
+    fn shift_subtree_ranks_example_graph() {
+        let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
             .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE)
             .with_ranks(&[
                 (0, 0),
@@ -396,7 +385,9 @@ mod tests {
             (6, 2),
             (7, 4),
         ];
-        update_ranks(&mut graph, minimum_length);
+        let tree = Tree::from_graph(&graph);
+        init_low_lim(&mut graph, &tree);
+        shift_subtree_ranks(&mut graph, &tree, 4.into(), -1);
 
         for id in graph.node_indices() {
             let rank = graph[id].rank;
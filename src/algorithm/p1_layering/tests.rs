@@ -1,7 +1,11 @@
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
 
 use crate::algorithm::p1_layering::{
-    cut_values::init_cutvalues, enter_edge, is_head_to_tail, leave_edge, low_lim::init_low_lim,
+    cut_values::init_cutvalues,
+    enter_edge, exchange, is_head_to_tail, leave_edge,
+    low_lim::init_low_lim,
+    ranking::{feasible_tree, init_rank},
+    tree::Tree,
 };
 
 use super::{Edge, Vertex};
@@ -186,7 +190,7 @@ impl GraphBuilder {
     #[allow(dead_code)]
     pub(super) fn with_connecting_path(mut self, connecting_path: &[(u32, u32)]) -> Self {
         self.connecting_path = connecting_path
-            .into_iter()
+            .iter()
             .map(|(tail, head)| {
                 self.graph
                     .find_edge_undirected((*tail).into(), (*head).into())
@@ -239,7 +243,8 @@ fn leave_edge_has_negative_cut_value() {
         .build();
 
     init_cutvalues(&mut graph);
-    init_low_lim(&mut graph);
+    let tree = Tree::from_graph(&graph);
+    init_low_lim(&mut graph, &tree);
 
     let leave_edge = leave_edge(&graph);
     assert!(leave_edge.is_some());
@@ -255,7 +260,8 @@ fn leave_edge_has_no_negative_cut_value() {
         .build();
 
     init_cutvalues(&mut graph);
-    init_low_lim(&mut graph);
+    let tree = Tree::from_graph(&graph);
+    init_low_lim(&mut graph, &tree);
 
     let leave_edge = leave_edge(&graph);
     assert!(leave_edge.is_none());
@@ -325,7 +331,8 @@ fn enter_edge_find_edge() {
         .build();
 
     init_cutvalues(&mut graph);
-    init_low_lim(&mut graph);
+    let tree = Tree::from_graph(&graph);
+    init_low_lim(&mut graph, &tree);
 
     let leave_edge = leave_edge(&graph).unwrap();
     let enter_edge = enter_edge(&mut graph, leave_edge, 1);
@@ -334,12 +341,70 @@ fn enter_edge_find_edge() {
     assert!(head == NodeIndex::from(4) || head == NodeIndex::from(5));
 }
 
+#[test]
+fn enter_edge_breaks_ties_by_lowest_index() {
+    let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
+        .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE)
+        .build();
+
+    init_cutvalues(&mut graph);
+    let tree = Tree::from_graph(&graph);
+    init_low_lim(&mut graph, &tree);
+
+    // (0, 4) and (0, 5) have the same slack
+    let leave_edge = leave_edge(&graph).unwrap();
+    let enter_edge = enter_edge(&mut graph, leave_edge, 1);
+    assert_eq!(enter_edge, graph.find_edge(0.into(), 4.into()).unwrap());
+}
+
+#[test]
+fn incremental_cut_values_match_recomputed_ones() {
+    // random dag, in which every vertex has an edge to one of its successors
+    let mut rng = crate::util::Rng::new(3);
+    let mut edges = (0..99)
+        .map(|v| (v, v + 1 + rng.below(100 - v as usize - 1) as u32))
+        .collect::<Vec<_>>();
+    edges.extend((0..200).filter_map(|_| {
+        let (u, v) = (rng.below(100) as u32, rng.below(100) as u32);
+        (u < v).then_some((u, v))
+    }));
+    edges.sort();
+    edges.dedup();
+    let (mut graph, minimum_length, ..) = GraphBuilder::new(&edges).build();
+    init_rank(&mut graph, minimum_length);
+    let mut tree = feasible_tree(&mut graph, minimum_length);
+
+    let mut pivots = 0;
+    while let Some(removed_edge) = leave_edge(&graph) {
+        pivots += 1;
+        let swap_edge = enter_edge(&mut graph, removed_edge, minimum_length);
+        exchange(
+            &mut graph,
+            &mut tree,
+            removed_edge,
+            swap_edge,
+            minimum_length,
+        );
+
+        let mut recomputed = graph.clone();
+        for e in recomputed.edge_weights_mut() {
+            e.cut_value = None;
+        }
+        init_cutvalues(&mut recomputed);
+        for e in graph.edge_indices() {
+            assert_eq!(graph[e].cut_value, recomputed[e].cut_value);
+        }
+    }
+    assert!(pivots > 0);
+}
+
 mod integration {
 
     use crate::configure::{Config, RankingType};
+    use crate::progress::Hooks;
     use petgraph::stable_graph::StableDiGraph;
 
-    use crate::algorithm::p1_layering::{rank, slack, Edge, Vertex};
+    use crate::algorithm::p1_layering::{rank, slack, Edge, Limit, Vertex};
 
     use super::{GraphBuilder, EXAMPLE_GRAPH};
 
@@ -368,34 +433,68 @@ mod integration {
     #[test]
     fn run_algorithm_example_graph() {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
+    #[cfg(feature = "lp")]
+    #[test]
+    fn optimal_ranking_is_not_longer_than_network_simplex() {
+        use graph_generator::GraphLayout;
+        let edges = GraphLayout::new_from_num_nodes(100, 2).build_edges();
+        let total_length = |ranking_type| {
+            let (mut graph, ..) = GraphBuilder::new(&edges).build();
+            rank(&mut graph, 1, ranking_type, Limit::default());
+            assert_eq!(graph.node_weights().map(|w| w.rank).min(), Some(0));
+            graph
+                .edge_indices()
+                .map(|e| {
+                    let (tail, head) = graph.edge_endpoints(e).unwrap();
+                    let length = graph[head].rank - graph[tail].rank;
+                    assert!(length >= 1);
+                    length * graph[e].weight
+                })
+                .sum::<i32>()
+        };
+        assert!(
+            total_length(RankingType::Optimal) <= total_length(RankingType::MinimizeEdgeLength)
+        );
+    }
+
     #[test]
     fn run_algorithm_tree_500_nodes_three_edges_per_node() {
         use graph_generator::GraphLayout;
         let edges = GraphLayout::new_from_num_nodes(500, 3)
             .build_edges()
             .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
             .collect::<Vec<_>>();
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
     #[test]
     fn run_algorithm_random_graph_1000_nodes() {
         use graph_generator::RandomLayout;
-        let edges = RandomLayout::new(1000)
-            .build_edges()
-            .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
-            .collect::<Vec<_>>();
+        let edges = RandomLayout::new(1000).build_edges();
         println!("built random layout");
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
@@ -431,9 +530,122 @@ mod integration {
         ];
 
         let (graph, ..) = GraphBuilder::new(&edges).build();
-        let mut cfg = Config::default();
-        cfg.ranking_type = RankingType::Up;
-        cfg.dummy_vertices = true;
-        crate::algorithm::start(graph, cfg);
+        let cfg = Config {
+            ranking_type: RankingType::Up,
+            dummy_vertices: true,
+            ..Default::default()
+        };
+        crate::algorithm::start(graph, cfg, &Default::default(), &Hooks::default()).unwrap();
+    }
+}
+
+mod width {
+    use crate::algorithm::p1_layering::width::{
+        approximate_aspect_ratio, limit_width, min_width, minimize_area, rank_widths,
+    };
+    use crate::algorithm::p1_layering::{rank, slack, Limit};
+    use crate::configure::RankingType;
+
+    use super::GraphBuilder;
+
+    /// Edges of a root with `n` paths of two vertices below it.
+    fn paths(n: u32) -> Vec<(u32, u32)> {
+        (1..=n).flat_map(|i| [(0, i), (i, n + i)]).collect()
+    }
+
+    #[test]
+    fn wide_rankings_approach_aspect_ratio() {
+        let (mut graph, ..) = GraphBuilder::new(&paths(12)).build();
+        rank(&mut graph, 1, RankingType::Up, Limit::default());
+        let ratio = |widths: Vec<usize>| *widths.iter().max().unwrap() as f64 / widths.len() as f64;
+        assert_eq!(ratio(rank_widths(&graph)), 4.);
+        approximate_aspect_ratio(&mut graph, 1, 1.);
+        let after = ratio(rank_widths(&graph));
+        assert!(after < 2., "aspect ratio is {after}");
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+    }
+
+    #[test]
+    fn ranks_are_limited_to_max_width() {
+        let (mut graph, ..) = GraphBuilder::new(&paths(12)).build();
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            Limit::default(),
+        );
+        limit_width(&mut graph, 1, 5);
+        for rank in 0..=graph.node_weights().map(|v| v.rank).max().unwrap() {
+            assert!(graph.node_weights().filter(|v| v.rank == rank).count() <= 5);
+        }
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+        // the 24 vertices below the root are packed into few ranks
+        assert_eq!(graph[petgraph::stable_graph::NodeIndex::from(0)].rank, 0);
+        assert!(graph.node_weights().all(|v| v.rank <= 6));
+    }
+
+    #[test]
+    fn area_is_minimized() {
+        let area = |widths: Vec<usize>| widths.iter().max().unwrap() * widths.len();
+        // a path with a leaf below each of its vertices
+        let edges = (0..12)
+            .flat_map(|i| [(i, i + 1), (i, i + 13)])
+            .collect::<Vec<_>>();
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        // all leaves are in the last rank, their edges pass through the others
+        rank(&mut graph, 1, RankingType::Down, Limit::default());
+        let before = area(rank_widths(&graph));
+        minimize_area(&mut graph, 1);
+        let after = area(rank_widths(&graph));
+        assert!(after < before, "area is {after}, was {before}");
+        assert!(after <= 2 * 13, "area is {after}");
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+        // a ranking with the smallest area is kept
+        let ranks = graph.node_weights().map(|v| v.rank).collect::<Vec<_>>();
+        minimize_area(&mut graph, 1);
+        assert_eq!(
+            graph.node_weights().map(|v| v.rank).collect::<Vec<_>>(),
+            ranks
+        );
+    }
+
+    #[test]
+    fn min_width_keeps_ranks_narrow() {
+        // separate paths, which can be placed below each other
+        let edges = (0..12)
+            .flat_map(|i| [(3 * i, 3 * i + 1), (3 * i + 1, 3 * i + 2)])
+            .collect::<Vec<_>>();
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank(&mut graph, 1, RankingType::Up, Limit::default());
+        assert_eq!(rank_widths(&graph).into_iter().max(), Some(12));
+        min_width(&mut graph, 1);
+        let width = rank_widths(&graph).into_iter().max().unwrap();
+        assert!(width < 12, "widest rank has {width} vertices");
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+        min_width(&mut graph, 2);
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 2) >= 0));
+    }
+}
+
+mod promotion {
+    use crate::algorithm::p1_layering::promote_vertices;
+    use crate::algorithm::p1_layering::width::rank_widths;
+    use petgraph::stable_graph::NodeIndex;
+
+    use super::GraphBuilder;
+
+    #[test]
+    fn promotion_removes_dummy_vertices() {
+        // 4 has two predecessors in the first rank and one successor in the last
+        let edges = [(0, 1), (1, 2), (2, 3), (0, 4), (5, 4), (4, 3)];
+        let ranks = [(0, 0), (1, 1), (2, 2), (3, 3), (4, 2), (5, 0)];
+        let (mut graph, ..) = GraphBuilder::new(&edges).with_ranks(&ranks).build();
+        let dummies = |widths: Vec<usize>| widths.iter().sum::<usize>() - 6;
+        assert_eq!(dummies(rank_widths(&graph)), 2);
+        promote_vertices(&mut graph, 1);
+        assert_eq!(graph[NodeIndex::from(4)].rank, 1);
+        assert_eq!(dummies(rank_widths(&graph)), 1);
+        // the first rank stays first
+        assert!(graph.node_weights().all(|v| (0..=3).contains(&v.rank)));
     }
 }
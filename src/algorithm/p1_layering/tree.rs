@@ -0,0 +1,110 @@
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::NodeIndexable;
+
+use super::{Edge, Vertex};
+
+/// Adjacency lists of the tree edges of the feasible tree, indexed by vertex.
+///
+/// The graph itself still marks tree edges via [Edge::is_tree_edge], but
+/// walking the tree via the graph needs to skip all non-tree edges, which is
+/// what dominates the network simplex loop for dense graphs.
+#[derive(Debug, Default)]
+pub(super) struct Tree {
+    neighbors: Vec<Vec<(EdgeIndex, NodeIndex)>>,
+}
+
+impl Tree {
+    /// Collects all edges of the graph which are marked as tree edges.
+    ///
+    /// Neighbors are stored in the order in which the graph returns them.
+    pub(super) fn from_graph(graph: &StableDiGraph<Vertex, Edge>) -> Self {
+        let mut neighbors = vec![Vec::new(); graph.node_bound()];
+        for v in graph.node_indices() {
+            let mut walker = graph.neighbors_undirected(v).detach();
+            while let Some((edge, n)) = walker.next(graph) {
+                if graph[edge].is_tree_edge {
+                    neighbors[v.index()].push((edge, n));
+                }
+            }
+        }
+        Self { neighbors }
+    }
+
+    pub(super) fn add_edge(&mut self, edge: EdgeIndex, tail: NodeIndex, head: NodeIndex) {
+        let bound = tail.index().max(head.index()) + 1;
+        if self.neighbors.len() < bound {
+            self.neighbors.resize(bound, Vec::new());
+        }
+        self.neighbors[tail.index()].push((edge, head));
+        self.neighbors[head.index()].push((edge, tail));
+    }
+
+    pub(super) fn remove_edge(&mut self, edge: EdgeIndex, tail: NodeIndex, head: NodeIndex) {
+        for v in [tail, head] {
+            self.neighbors[v.index()].retain(|(e, _)| *e != edge);
+        }
+    }
+
+    /// Removes `removed_edge` from the tree and adds `swap_edge` instead.
+    pub(super) fn replace_edge(
+        &mut self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        removed_edge: EdgeIndex,
+        swap_edge: EdgeIndex,
+    ) {
+        let (tail, head) = graph.edge_endpoints(removed_edge).unwrap();
+        self.remove_edge(removed_edge, tail, head);
+        let (tail, head) = graph.edge_endpoints(swap_edge).unwrap();
+        self.add_edge(swap_edge, tail, head);
+    }
+
+    /// Returns the tree edges incident to `v` and the vertex on their other end.
+    pub(super) fn neighbors(&self, v: NodeIndex) -> &[(EdgeIndex, NodeIndex)] {
+        self.neighbors.get(v.index()).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{EdgeIndex, NodeIndex};
+
+    use super::Tree;
+    use crate::algorithm::p1_layering::tests::{GraphBuilder, LOW_LIM_GRAPH};
+
+    #[test]
+    fn add_and_remove_edges() {
+        let (graph, ..) = GraphBuilder::new(&LOW_LIM_GRAPH)
+            .with_tree_edges(&LOW_LIM_GRAPH)
+            .build();
+        let mut tree = Tree::from_graph(&graph);
+        let n = NodeIndex::new;
+        assert_eq!(tree.neighbors(n(0)).len(), 2);
+
+        let edge = graph.find_edge(n(0), n(1)).unwrap();
+        tree.remove_edge(edge, n(0), n(1));
+        assert!(tree.neighbors(n(0)).iter().all(|(e, _)| *e != edge));
+        assert!(tree.neighbors(n(1)).iter().all(|(_, v)| *v != n(0)));
+
+        let new_edge = EdgeIndex::new(graph.edge_count());
+        tree.add_edge(new_edge, n(3), n(7));
+        assert!(tree.neighbors(n(3)).contains(&(new_edge, n(7))));
+        assert!(tree.neighbors(n(7)).contains(&(new_edge, n(3))));
+    }
+
+    #[test]
+    fn replace_edge() {
+        let (mut graph, ..) = GraphBuilder::new(&LOW_LIM_GRAPH)
+            .with_tree_edges(&LOW_LIM_GRAPH)
+            .build();
+        let mut tree = Tree::from_graph(&graph);
+        let n = NodeIndex::new;
+        let removed_edge = graph.find_edge(n(4), n(5)).unwrap();
+        let swap_edge = graph.add_edge(n(3), n(5), Default::default());
+        tree.replace_edge(&graph, removed_edge, swap_edge);
+
+        assert_eq!(tree.neighbors(n(4)).len(), 3);
+        assert!(!tree.neighbors(n(5)).contains(&(removed_edge, n(4))));
+        assert!(tree.neighbors(n(5)).contains(&(swap_edge, n(3))));
+        assert_eq!(tree.neighbors(n(3)).last(), Some(&(swap_edge, n(5))));
+    }
+}
@@ -0,0 +1,345 @@
+//! Trades the height of a ranking against its width.
+//!
+//! The MinWidth heuristic assigns ranks from scratch, building the ranks from
+//! the bottom up while keeping each of them narrow.
+//!
+//! The width of a rank is the number of its vertices together with the number
+//! of edges passing through it, which get a dummy vertex there. Ranks are made
+//! narrower by pushing vertices down into the next rank which still has room,
+//! in the order of their current rank, like the list scheduling of the
+//! Coffman-Graham algorithm. Edges into pushed vertices get longer, and their
+//! successors are pushed down as well if necessary.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use super::{normalize, ranking::move_vertices_up, Edge, Vertex};
+
+/// Ranks of all vertices of a graph.
+type Ranks = Vec<(NodeIndex, i32)>;
+
+/// Maximum number of width limits tried by [minimize_area].
+const MAX_AREA_ITERATIONS: usize = 32;
+
+fn ranks(graph: &StableDiGraph<Vertex, Edge>) -> Ranks {
+    graph
+        .node_indices()
+        .map(|v| (v, graph[v].rank))
+        .collect::<Vec<_>>()
+}
+
+fn restore(graph: &mut StableDiGraph<Vertex, Edge>, ranks: &[(NodeIndex, i32)]) {
+    for (v, rank) in ranks {
+        graph[*v].rank = *rank;
+    }
+}
+
+/// Returns the largest number of real vertices in a rank.
+fn widest_rank(graph: &StableDiGraph<Vertex, Edge>) -> usize {
+    graph
+        .node_indices()
+        .fold(HashMap::<i32, usize>::new(), |mut widths, v| {
+            if is_real(&graph[v]) {
+                *widths.entry(graph[v].rank).or_default() += 1;
+            }
+            widths
+        })
+        .into_values()
+        .max()
+        .unwrap_or(1)
+}
+
+/// Returns whether `v` counts towards the width of its rank when pushing
+/// vertices. Dummy vertices and junctions of hyperedges don't.
+fn is_real(v: &Vertex) -> bool {
+    !v.is_dummy && v.hyperedge.is_none()
+}
+
+/// Returns the width of each rank, including the dummy vertices of the edges
+/// passing through it.
+pub(super) fn rank_widths(graph: &StableDiGraph<Vertex, Edge>) -> Vec<usize> {
+    let height = graph.node_weights().map(|v| v.rank + 1).max().unwrap_or(0);
+    let mut widths = vec![0; height as usize];
+    for v in graph.node_weights() {
+        widths[v.rank as usize] += 1;
+    }
+    for e in graph.edge_references() {
+        let (tail, head) = (graph[e.source()].rank, graph[e.target()].rank);
+        for rank in tail + 1..head {
+            widths[rank as usize] += 1;
+        }
+    }
+    widths
+}
+
+/// Pushes vertices down, until no rank contains more than `max_width` real
+/// vertices. Vertices are placed in the order of their rank, and within each
+/// rank, vertices with the most successors are placed first, as pushing them
+/// down pushes down the most other vertices.
+pub(crate) fn limit_width(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    max_width: usize,
+) {
+    let max_width = max_width.max(1);
+    let key = |v: NodeIndex| {
+        let successors = graph.edges_directed(v, Outgoing).count();
+        Reverse((graph[v].rank, usize::MAX - successors, v.index()))
+    };
+    let mut in_degrees = graph
+        .node_indices()
+        .map(|v| (v, graph.edges_directed(v, Incoming).count()))
+        .collect::<HashMap<_, _>>();
+    let mut queue = in_degrees
+        .iter()
+        .filter(|(_, d)| **d == 0)
+        .map(|(v, _)| (key(*v), *v))
+        .collect::<BinaryHeap<_>>();
+    let mut vertices = Vec::with_capacity(graph.node_count());
+    while let Some((_, v)) = queue.pop() {
+        vertices.push(v);
+        for n in graph.neighbors_directed(v, Outgoing) {
+            let d = in_degrees.get_mut(&n).unwrap();
+            *d -= 1;
+            if *d == 0 {
+                queue.push((key(n), n));
+            }
+        }
+    }
+    // vertices are placed after their predecessors, so they are pushed below them
+    let mut widths = HashMap::<i32, usize>::new();
+    let mut pushed = 0;
+    for v in vertices {
+        let mut rank = graph
+            .neighbors_directed(v, Incoming)
+            .map(|n| graph[n].rank + minimum_length)
+            .max()
+            .unwrap_or(0)
+            .max(graph[v].rank);
+        if is_real(&graph[v]) {
+            while widths.get(&rank).is_some_and(|w| *w >= max_width) {
+                rank += 1;
+            }
+            *widths.entry(rank).or_default() += 1;
+        }
+        if rank != graph[v].rank {
+            pushed += 1;
+        }
+        graph[v].rank = rank;
+    }
+    info!(target: "ranking", "Pushed {pushed} vertices down to keep ranks at most {max_width} wide");
+    normalize(graph);
+}
+
+/// Returns how far the ratio of the width to the height of the ranking is
+/// from `ratio`, on a logarithmic scale, so layouts twice as wide and twice as
+/// tall as requested are equally far off.
+fn distance(graph: &StableDiGraph<Vertex, Edge>, ratio: f64) -> f64 {
+    let widths = rank_widths(graph);
+    let width = widths.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let height = widths.len().max(1) as f64;
+    ((width / height) / ratio).ln().abs()
+}
+
+/// Changes the ranks, so the ratio of the width to the height of the layout
+/// gets as close to `ratio` as possible. Layouts which are too wide become
+/// narrower by limiting the width of each rank. Layouts which are too tall
+/// are replaced by the longest path ranking, if that is any better.
+pub(crate) fn approximate_aspect_ratio(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    ratio: f64,
+) {
+    if !(ratio.is_finite() && ratio > 0.) || graph.node_count() == 0 {
+        return;
+    }
+    let initial = ranks(graph);
+    let mut best = (distance(graph, ratio), initial.clone());
+    info!(target: "ranking", "Approximating aspect ratio {ratio}");
+
+    // the longest path ranking has the smallest height
+    move_vertices_up(graph, minimum_length);
+    normalize(graph);
+    let d = distance(graph, ratio);
+    if d < best.0 {
+        best = (d, ranks(graph));
+    }
+    restore(graph, &initial);
+
+    // narrower ranks make the layout taller, search for the width limit at
+    // which it gets too narrow
+    let (mut low, mut high) = (1, widest_rank(graph));
+    while low <= high {
+        let width = low + (high - low) / 2;
+        limit_width(graph, minimum_length, width);
+        let widths = rank_widths(graph);
+        let current = widths.iter().copied().max().unwrap_or(0) as f64 / widths.len() as f64;
+        let d = distance(graph, ratio);
+        debug!(target: "ranking", "Width limit {width} results in aspect ratio {current:.2}");
+        if d < best.0 {
+            best = (d, ranks(graph));
+        }
+        restore(graph, &initial);
+        if current > ratio {
+            high = width - 1;
+        } else {
+            low = width + 1;
+        }
+    }
+    restore(graph, &best.1);
+}
+
+/// Returns the area of the ranking, given by the width of its widest rank,
+/// including dummy vertices, times the number of ranks.
+fn area(graph: &StableDiGraph<Vertex, Edge>) -> usize {
+    let widths = rank_widths(graph);
+    widths.iter().copied().max().unwrap_or(0) * widths.len()
+}
+
+/// Changes the ranks, so the area of the layout gets as small as possible,
+/// trading the number of ranks against the width of the widest one.
+///
+/// Besides the current ranking, the longest path ranking and the MinWidth
+/// heuristic are tried, as well as limiting the width of each rank to up to
+/// [MAX_AREA_ITERATIONS] values between one vertex and the widest rank. The
+/// ranking with the smallest area is kept, which is the current one if no
+/// other ranking is smaller.
+pub(crate) fn minimize_area(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    if graph.node_count() == 0 {
+        return;
+    }
+    info!(target: "ranking", "Minimizing the area of the ranking");
+    let initial = ranks(graph);
+    let mut best = (area(graph), initial.clone());
+    let mut keep_if_smaller = |graph: &mut StableDiGraph<Vertex, Edge>, name: &str| {
+        let a = area(graph);
+        debug!(target: "ranking", "{name} results in area {a}");
+        if a < best.0 {
+            best = (a, ranks(graph));
+        }
+        restore(graph, &initial);
+    };
+
+    move_vertices_up(graph, minimum_length);
+    normalize(graph);
+    keep_if_smaller(graph, "Longest path ranking");
+
+    min_width(graph, minimum_length);
+    keep_if_smaller(graph, "MinWidth");
+
+    let max_width = widest_rank(graph);
+    let step = max_width.div_ceil(MAX_AREA_ITERATIONS).max(1);
+    for width in (1..max_width).step_by(step) {
+        limit_width(graph, minimum_length, width);
+        keep_if_smaller(graph, &format!("Width limit {width}"));
+    }
+    info!(target: "ranking", "Smallest area of the ranking is {}", best.0);
+    restore(graph, &best.1);
+}
+
+/// Upper bounds on the width of a rank tried by [min_width].
+const MIN_WIDTH_BOUNDS: [usize; 4] = [1, 2, 3, 4];
+/// Factors of the bound on the width of the edges leaving a rank upwards
+/// tried by [min_width].
+const MIN_WIDTH_FACTORS: [usize; 2] = [1, 2];
+
+/// Assigns ranks with the MinWidth heuristic by Nikolov, Tarassov and Branke,
+/// which keeps the widest rank narrow, counting dummy vertices. Runs the
+/// heuristic for several bounds and keeps the ranking whose widest rank is
+/// narrowest, and of those the one with the fewest ranks.
+pub(crate) fn min_width(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Assigning ranks with the MinWidth heuristic");
+    let mut best: Option<((usize, usize), Ranks)> = None;
+    for bound in MIN_WIDTH_BOUNDS {
+        for factor in MIN_WIDTH_FACTORS {
+            min_width_layers(graph, minimum_length, bound, factor * bound);
+            let widths = rank_widths(graph);
+            let key = (widths.iter().copied().max().unwrap_or(0), widths.len());
+            debug!(target: "ranking",
+                "MinWidth with bound {bound} and factor {factor} has width {} and height {}",
+                key.0,
+                key.1);
+            if best.as_ref().is_none_or(|(b, _)| key < *b) {
+                let ranks = graph.node_indices().map(|v| (v, graph[v].rank)).collect();
+                best = Some((key, ranks));
+            }
+        }
+    }
+    if let Some((_, ranks)) = best {
+        for (v, rank) in ranks {
+            graph[v].rank = rank;
+        }
+    }
+}
+
+/// Builds the ranks from the bottom up. Vertices whose successors are all in
+/// lower ranks are added to the current rank, those with the most successors
+/// first, as each of them removes the dummy vertices of its outgoing edges.
+/// A new rank is started once the current rank is at least `bound` wide and
+/// the next vertex has no successors, or once the edges leaving the current
+/// rank upwards are at least `up_bound` wide.
+fn min_width_layers(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    bound: usize,
+    up_bound: usize,
+) {
+    // number of successors of each vertex which are not in a finished rank
+    let mut remaining = graph
+        .node_indices()
+        .map(|v| (v, graph.edges_directed(v, Outgoing).count()))
+        .collect::<HashMap<_, _>>();
+    let key = |v: NodeIndex| {
+        (
+            graph.edges_directed(v, Outgoing).count(),
+            Reverse(v.index()),
+        )
+    };
+    let mut candidates = remaining
+        .iter()
+        .filter(|(_, r)| **r == 0)
+        .map(|(v, _)| (key(*v), *v))
+        .collect::<BinaryHeap<_>>();
+    let mut layers = HashMap::new();
+    let (mut layer, mut current) = (0, Vec::new());
+    let (mut width, mut width_up) = (0isize, 0isize);
+    while layers.len() < graph.node_count() {
+        let next = candidates.pop();
+        if let Some(((out_degree, _), v)) = next {
+            layers.insert(v, layer);
+            current.push(v);
+            width += 1 - out_degree as isize;
+            width_up += graph.edges_directed(v, Incoming).count() as isize;
+        }
+        let full =
+            next.is_some_and(|((out_degree, _), _)| width >= bound as isize && out_degree == 0);
+        if next.is_none() || full || width_up >= up_bound as isize {
+            // vertices become candidates once all their successors are in
+            // a finished rank
+            for v in current.drain(..) {
+                for n in graph.neighbors_directed(v, Incoming) {
+                    let r = remaining.get_mut(&n).unwrap();
+                    *r -= 1;
+                    if *r == 0 {
+                        candidates.push((key(n), n));
+                    }
+                }
+            }
+            if next.is_none() && candidates.is_empty() {
+                // only possible if the graph has a cycle
+                break;
+            }
+            layer += 1;
+            (width, width_up) = (width_up, 0);
+        }
+    }
+    let top = layers.values().copied().max().unwrap_or(0);
+    for (v, l) in layers {
+        graph[v].rank = (top - l) * minimum_length.max(1);
+    }
+    normalize(graph);
+}
@@ -0,0 +1,346 @@
+//! Keeps the vertices of each cluster next to each other.
+//!
+//! Clusters are ordered once per run, by the mean relative position of their
+//! members in the initial order, and keep this order in every rank, so that
+//! their bounding boxes don't overlap. After a rank is ordered, the members of
+//! each cluster are moved next to each other, to the mean position of the
+//! members, while keeping their relative order. Nested clusters are grouped
+//! the same way within their parent. Transpose only swaps vertices whose
+//! innermost cluster is the same, or two vertices which are not part of any
+//! cluster.
+use std::collections::{BTreeSet, HashMap};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::Outgoing;
+
+use crate::algorithm::{cluster_path, Cluster, Edge, Vertex};
+
+use super::constraints::{arrange, reaches};
+use super::LeftOf;
+
+/// The hierarchy of the clusters and their order, which is the same in all ranks.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Clusters {
+    clusters: HashMap<usize, Cluster>,
+    /// Position of each cluster in the order of all clusters
+    order: HashMap<usize, usize>,
+}
+
+/// A vertex of a rank, with its position and the clusters containing it.
+struct Member {
+    pos: usize,
+    vertex: NodeIndex,
+    path: Vec<usize>,
+}
+
+impl Clusters {
+    pub(crate) fn new(clusters: HashMap<usize, Cluster>) -> Self {
+        Self {
+            clusters,
+            order: HashMap::new(),
+        }
+    }
+
+    /// Orders the clusters by the mean relative position of their members in
+    /// `order`, including the members of nested clusters.
+    pub(super) fn set_order(
+        &mut self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        order: &[Vec<NodeIndex>],
+    ) {
+        let mut positions = HashMap::<usize, (f64, usize)>::new();
+        for rank in order {
+            for (pos, v) in rank.iter().enumerate() {
+                for cluster in cluster_path(&self.clusters, graph[*v].cluster) {
+                    let (sum, n) = positions.entry(cluster).or_default();
+                    *sum += (pos as f64 + 0.5) / rank.len() as f64;
+                    *n += 1;
+                }
+            }
+        }
+        let mut clusters = positions
+            .into_iter()
+            .map(|(cluster, (sum, n))| (cluster, sum / n as f64))
+            .collect::<Vec<_>>();
+        clusters.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        self.order = clusters
+            .into_iter()
+            .enumerate()
+            .map(|(pos, (cluster, _))| (cluster, pos))
+            .collect();
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Moves the members of each cluster in `vertices` next to each other.
+    ///
+    /// Each cluster is placed at the mean position of its members, but clusters
+    /// keep their order and constraints between vertices of different
+    /// clusters are kept, unless they contradict the order of the clusters.
+    pub(super) fn group(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        rank: usize,
+        vertices: &mut [NodeIndex],
+        left_of: &LeftOf,
+    ) {
+        if vertices.iter().all(|v| graph[*v].cluster.is_none()) {
+            return;
+        }
+        let members = vertices
+            .iter()
+            .enumerate()
+            .map(|(pos, v)| Member {
+                pos,
+                vertex: *v,
+                path: cluster_path(&self.clusters, graph[*v].cluster),
+            })
+            .collect();
+        let ordered = self.order_units(graph, members, 0, left_of.pairs(rank));
+        vertices.copy_from_slice(&ordered);
+    }
+
+    /// Orders `members`, which are all contained in the same clusters up to
+    /// `depth`. Vertices contained in the same cluster at `depth` form a
+    /// single unit, all other vertices are units of their own.
+    fn order_units(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        members: Vec<Member>,
+        depth: usize,
+        left_of: &[(NodeIndex, NodeIndex)],
+    ) -> Vec<NodeIndex> {
+        let mut units = Vec::<Vec<Member>>::new();
+        let mut unit_of_cluster = HashMap::new();
+        let mut unit_of_vertex = HashMap::new();
+        for member in members {
+            let unit = match member.path.get(depth) {
+                Some(cluster) => *unit_of_cluster.entry(*cluster).or_insert(units.len()),
+                None => units.len(),
+            };
+            if unit == units.len() {
+                units.push(Vec::new());
+            }
+            unit_of_vertex.insert(member.vertex, unit);
+            units[unit].push(member);
+        }
+
+        let clusters = unit_of_cluster
+            .iter()
+            .map(|(cluster, unit)| (self.order.get(cluster).copied(), *unit))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|(_, unit)| unit)
+            .collect::<Vec<_>>();
+        let mut constraints = clusters
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect::<Vec<_>>();
+        for (left, right) in left_of {
+            if let (Some(&l), Some(&r)) = (unit_of_vertex.get(left), unit_of_vertex.get(right)) {
+                if l != r && !reaches(&constraints, r, l) {
+                    constraints.push((l, r));
+                } else if l != r {
+                    debug!(target: "crossing_reduction",
+                        "Constraint {} left of {} contradicts the order of the clusters",
+                        graph[*left].id,
+                        graph[*right].id);
+                }
+            }
+        }
+
+        let position =
+            |unit: &[Member]| unit.iter().map(|m| m.pos).sum::<usize>() as f64 / unit.len() as f64;
+        let mut preferred = (0..units.len()).collect::<Vec<_>>();
+        preferred.sort_by(|a, b| position(&units[*a]).total_cmp(&position(&units[*b])));
+        let mut order = (0..units.len()).collect::<Vec<_>>();
+        arrange(&mut order, &preferred, &constraints);
+
+        let cluster_units = unit_of_cluster.into_values().collect::<BTreeSet<_>>();
+        let mut units = units.into_iter().map(Some).collect::<Vec<_>>();
+        let mut ordered = Vec::new();
+        for unit in order {
+            let members = units[unit].take().unwrap();
+            if cluster_units.contains(&unit) {
+                ordered.extend(self.order_units(graph, members, depth + 1, left_of));
+            } else {
+                ordered.extend(members.into_iter().map(|m| m.vertex));
+            }
+        }
+        ordered
+    }
+
+    /// Groups the members of each cluster in all ranks.
+    pub(super) fn repair(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        order: &mut [Vec<NodeIndex>],
+        left_of: &LeftOf,
+    ) {
+        for (rank, vertices) in order.iter_mut().enumerate() {
+            self.group(graph, rank, vertices, left_of);
+        }
+    }
+}
+
+/// Assigns the dummy vertices of each edge to the innermost cluster which
+/// contains both of its endpoints. Then inserts a dummy vertex into each rank
+/// between the topmost and the bottommost member of a cluster which doesn't
+/// contain a member, so that no other vertex is placed inside its bounding box.
+/// The dummy vertices of a cluster whose members are all in the same lane are
+/// put into this lane.
+pub(crate) fn prepare_clusters(graph: &mut StableDiGraph<Vertex, Edge>, clusters: &Clusters) {
+    let clusters = &clusters.clusters;
+    if graph.node_weights().all(|v| v.cluster.is_none()) {
+        return;
+    }
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        if graph[v].is_dummy {
+            continue;
+        }
+        let tail = cluster_path(clusters, graph[v].cluster);
+        let mut chains = Vec::new();
+        for edge in graph.edges_directed(v, Outgoing) {
+            let mut dummies = Vec::new();
+            let mut head = edge.target();
+            while graph[head].is_dummy {
+                dummies.push(head);
+                head = graph.neighbors_directed(head, Outgoing).next().unwrap();
+            }
+            let head = cluster_path(clusters, graph[head].cluster);
+            let common = tail.iter().zip(&head).take_while(|(t, h)| t == h).count();
+            chains.push((dummies, common.checked_sub(1).map(|i| tail[i])));
+        }
+        for (dummies, cluster) in chains {
+            for d in dummies {
+                graph[d].cluster = cluster;
+            }
+        }
+    }
+
+    let mut ranks = HashMap::<usize, BTreeSet<i32>>::new();
+    let mut lanes = HashMap::<usize, BTreeSet<usize>>::new();
+    for v in graph.node_weights() {
+        for cluster in cluster_path(clusters, v.cluster) {
+            ranks.entry(cluster).or_default().insert(v.rank);
+            lanes.entry(cluster).or_default().extend(v.lane);
+        }
+    }
+    let lanes = lanes
+        .into_iter()
+        .map(|(cluster, lanes)| match lanes.len() {
+            1 => (cluster, lanes.first().copied()),
+            _ => (cluster, None),
+        })
+        .collect::<HashMap<_, _>>();
+    for v in graph.node_weights_mut().filter(|v| v.is_dummy) {
+        if let Some(cluster) = v.cluster {
+            v.lane = lanes[&cluster];
+        }
+    }
+    info!(target: "crossing_reduction", "Inserting placeholders into ranks of {} clusters", ranks.len());
+    for (cluster, ranks) in ranks {
+        let (Some(&top), Some(&bottom)) = (ranks.first(), ranks.last()) else {
+            continue;
+        };
+        let lane = lanes[&cluster];
+        for rank in (top..bottom).filter(|r| !ranks.contains(r)) {
+            let v = graph.add_node(Vertex {
+                rank,
+                is_dummy: true,
+                cluster: Some(cluster),
+                lane,
+                ..Default::default()
+            });
+            graph[v].align = v;
+            graph[v].root = v;
+            graph[v].sink = v;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{prepare_clusters, Clusters};
+    use crate::algorithm::p2::LeftOf;
+    use crate::algorithm::{Cluster, Edge, Vertex};
+
+    fn rank_of_five(clusters: [Option<usize>; 5]) -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::new();
+        for cluster in clusters {
+            graph.add_node(Vertex {
+                cluster,
+                ..Vertex::new_with_rank(0)
+            });
+        }
+        graph
+    }
+
+    #[test]
+    fn members_are_grouped() {
+        let graph = rank_of_five([Some(0), None, Some(0), None, Some(1)]);
+        let n = NodeIndex::new;
+        let mut order = vec![vec![n(0), n(1), n(2), n(3), n(4)]];
+        let mut clusters = Clusters::default();
+        clusters.set_order(&graph, &order);
+        clusters.repair(&graph, &mut order, &LeftOf::default());
+        assert_eq!(order[0], [n(0), n(2), n(1), n(3), n(4)]);
+    }
+
+    #[test]
+    fn clusters_keep_their_order() {
+        let graph = rank_of_five([Some(0), None, Some(1), None, Some(1)]);
+        let n = NodeIndex::new;
+        let mut clusters = Clusters::default();
+        clusters.set_order(&graph, &[vec![n(0), n(1), n(2), n(3), n(4)]]);
+        // cluster 1 would be placed left of cluster 0 by the mean position of its members
+        let mut vertices = vec![n(2), n(4), n(1), n(3), n(0)];
+        clusters.group(&graph, 0, &mut vertices, &LeftOf::default());
+        assert_eq!(vertices, [n(1), n(3), n(0), n(2), n(4)]);
+    }
+
+    #[test]
+    fn nested_clusters_are_grouped() {
+        // cluster 1 is nested in cluster 0
+        let graph = rank_of_five([Some(1), Some(0), None, Some(1), Some(0)]);
+        let n = NodeIndex::new;
+        let mut clusters = Clusters::new(HashMap::from([(
+            1,
+            Cluster {
+                parent: Some(0),
+                ..Default::default()
+            },
+        )]));
+        let mut order = vec![vec![n(0), n(1), n(2), n(3), n(4)]];
+        clusters.set_order(&graph, &order);
+        clusters.repair(&graph, &mut order, &LeftOf::default());
+        assert_eq!(order[0], [n(1), n(0), n(3), n(4), n(2)]);
+    }
+
+    #[test]
+    fn placeholders_fill_gaps() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::new();
+        for rank in [0, 3] {
+            graph.add_node(Vertex {
+                cluster: Some(0),
+                ..Vertex::new_with_rank(rank)
+            });
+        }
+        prepare_clusters(&mut graph, &Clusters::default());
+        let mut ranks = graph
+            .node_weights()
+            .filter(|v| v.is_dummy && v.cluster == Some(0))
+            .map(|v| v.rank)
+            .collect::<Vec<_>>();
+        ranks.sort();
+        assert_eq!(ranks, [1, 2]);
+    }
+}
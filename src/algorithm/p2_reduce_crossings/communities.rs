@@ -0,0 +1,142 @@
+//! Orders the ranks community by community before the sweeps start.
+//!
+//! Communities are found by label propagation, as in "Near linear time
+//! algorithm to detect community structures in large-scale networks" by
+//! Raghavan et al. (2007): each vertex starts with a label of its own and
+//! repeatedly takes the label which is most common among its neighbors, until
+//! no label changes. Vertices with the same label form a community.
+//! Communities are ordered by the mean relative position of their members in
+//! the initial order and keep this order in every rank, so densely connected
+//! parts of the graph start out next to each other instead of interleaved.
+use std::collections::HashMap;
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::algorithm::{Edge, Vertex};
+
+/// Maximum number of rounds of label propagation, which usually converges
+/// after a few rounds.
+const MAX_ROUNDS: usize = 32;
+
+/// Returns the community of each vertex.
+///
+/// Vertices are visited in the order of their indices and keep their label
+/// if it is one of the most common ones among their neighbors, otherwise ties
+/// are broken by the smallest label, so the result is deterministic.
+pub(super) fn find(graph: &StableDiGraph<Vertex, Edge>) -> HashMap<NodeIndex, usize> {
+    let mut label = graph
+        .node_indices()
+        .map(|v| (v, v.index()))
+        .collect::<HashMap<_, _>>();
+    for round in 1..=MAX_ROUNDS {
+        let mut changed = false;
+        for v in graph.node_indices() {
+            let mut counts = HashMap::<usize, usize>::new();
+            for n in graph.neighbors_undirected(v) {
+                *counts.entry(label[&n]).or_default() += 1;
+            }
+            let Some(max) = counts.values().max().copied() else {
+                continue;
+            };
+            if counts.get(&label[&v]) == Some(&max) {
+                continue;
+            }
+            let best = counts
+                .into_iter()
+                .filter(|(_, c)| *c == max)
+                .map(|(l, _)| l)
+                .min()
+                .unwrap();
+            label.insert(v, best);
+            changed = true;
+        }
+        if !changed {
+            debug!(target: "crossing_reduction", "Label propagation converged after {round} rounds");
+            break;
+        }
+    }
+    label
+}
+
+/// Sorts each rank of `order` by the community of its vertices. Vertices of
+/// the same community keep their relative order.
+pub(super) fn pre_order(order: &mut [Vec<NodeIndex>], communities: &HashMap<NodeIndex, usize>) {
+    let mut positions = HashMap::<usize, (f64, usize)>::new();
+    for rank in order.iter() {
+        for (pos, v) in rank.iter().enumerate() {
+            let (sum, n) = positions.entry(communities[v]).or_default();
+            *sum += (pos as f64 + 0.5) / rank.len() as f64;
+            *n += 1;
+        }
+    }
+    let mut ids = positions
+        .into_iter()
+        .map(|(community, (sum, n))| (community, sum / n as f64))
+        .collect::<Vec<_>>();
+    ids.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    info!(target: "crossing_reduction", "Ordering ranks by {} communities", ids.len());
+    let position = ids
+        .into_iter()
+        .enumerate()
+        .map(|(pos, (community, _))| (community, pos))
+        .collect::<HashMap<_, _>>();
+    for rank in order {
+        rank.sort_by_key(|v| position[&communities[v]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{find, pre_order};
+    use crate::algorithm::{Edge, Vertex};
+
+    /// Two complete bipartite graphs between rank 0 and 1, whose vertices
+    /// alternate in each rank, connected by a single edge.
+    fn interleaved_modules() -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::new();
+        for rank in [0, 0, 0, 0, 1, 1, 1, 1] {
+            graph.add_node(Vertex::new_with_rank(rank));
+        }
+        let n = NodeIndex::new;
+        for (top, bottom) in [
+            (0, 4),
+            (0, 6),
+            (2, 4),
+            (2, 6),
+            (1, 5),
+            (1, 7),
+            (3, 5),
+            (3, 7),
+            (2, 5),
+        ] {
+            graph.add_edge(n(top), n(bottom), Edge::default());
+        }
+        graph
+    }
+
+    #[test]
+    fn modules_are_communities() {
+        let graph = interleaved_modules();
+        let n = NodeIndex::new;
+        let communities = find(&graph);
+        for module in [[0, 2, 4, 6], [1, 3, 5, 7]] {
+            assert!(module
+                .iter()
+                .all(|v| communities[&n(*v)] == communities[&n(module[0])]));
+        }
+        assert_ne!(communities[&n(0)], communities[&n(1)]);
+    }
+
+    #[test]
+    fn ranks_are_ordered_community_by_community() {
+        let graph = interleaved_modules();
+        let n = NodeIndex::new;
+        let mut order = vec![vec![n(0), n(1), n(2), n(3)], vec![n(4), n(5), n(6), n(7)]];
+        pre_order(&mut order, &find(&graph));
+        assert_eq!(order[0], [n(0), n(2), n(1), n(3)]);
+        assert_eq!(order[1], [n(4), n(6), n(5), n(7)]);
+    }
+}
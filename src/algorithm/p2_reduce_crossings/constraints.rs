@@ -0,0 +1,383 @@
+//! Constraints on the order of vertices within a rank.
+//!
+//! A vertex can be required to be placed left of another vertex whenever both
+//! end up in the same rank. Ranks with constraints are ordered with the
+//! constrained barycenter heuristic from "A Fast and Simple Heuristic for
+//! Constrained Two-Level Crossing Reduction" by Forster (2004): vertices whose
+//! values violate a constraint are merged into a group, whose value is the
+//! weighted mean of the values of its members. Transpose never swaps the
+//! vertices of a constraint. Lanes are ordered by constraining all vertices of
+//! a lane to be left of the vertices of the next lane in the same rank.
+//! Sequences of vertices given in a fixed order constrain each vertex to be
+//! left of the next vertex of the sequence in the same rank.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+
+use log::{debug, warn};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use crate::algorithm::{Edge, Vertex};
+
+/// Pairs of vertices `(left, right)` in the same rank, where `left` needs to
+/// be placed left of `right`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LeftOf {
+    pairs: HashSet<(NodeIndex, NodeIndex)>,
+    ranks: HashMap<usize, Vec<(NodeIndex, NodeIndex)>>,
+}
+
+impl LeftOf {
+    /// Keeps all pairs whose vertices are in the same rank. Pairs which
+    /// contradict the previous ones can't be satisfied and are dropped.
+    pub(crate) fn new(
+        graph: &StableDiGraph<Vertex, Edge>,
+        pairs: &[(NodeIndex, NodeIndex)],
+    ) -> Self {
+        let mut left_of = Self::default();
+        left_of.add(graph, pairs);
+        left_of
+    }
+
+    /// Adds the pairs whose vertices are in the same rank, like [LeftOf::new].
+    pub(crate) fn add(
+        &mut self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        pairs: &[(NodeIndex, NodeIndex)],
+    ) {
+        for &(left, right) in pairs {
+            if left == right
+                || !graph.contains_node(left)
+                || !graph.contains_node(right)
+                || graph[left].rank != graph[right].rank
+                || self.pairs.contains(&(left, right))
+            {
+                continue;
+            }
+            let rank = graph[left].rank as usize;
+            if self.reaches(rank, right, left) {
+                warn!(target: "crossing_reduction",
+                    "Ignoring constraint {} left of {}, since it contradicts other constraints",
+                    graph[left].id,
+                    graph[right].id);
+                continue;
+            }
+            self.pairs.insert((left, right));
+            self.ranks.entry(rank).or_default().push((left, right));
+        }
+        debug!(target: "crossing_reduction", "Vertices have {} order constraints", self.pairs.len());
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns true if the order of `rank` is constrained.
+    pub(super) fn constrains(&self, rank: usize) -> bool {
+        self.ranks.contains_key(&rank)
+    }
+
+    /// Returns true if `v`, which is currently left of `w`, may be swapped with it.
+    pub(super) fn allows_swap(&self, v: NodeIndex, w: NodeIndex) -> bool {
+        !self.pairs.contains(&(v, w))
+    }
+
+    /// Returns true if there is a path of constraints from `from` to `to`.
+    fn reaches(&self, rank: usize, from: NodeIndex, to: NodeIndex) -> bool {
+        reaches(self.pairs(rank), from, to)
+    }
+
+    /// Returns the constraints of `rank`.
+    pub(super) fn pairs(&self, rank: usize) -> &[(NodeIndex, NodeIndex)] {
+        self.ranks.get(&rank).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the constraints of `rank` as pairs of indices into `vertices`.
+    fn constraints(&self, rank: usize, vertices: &[NodeIndex]) -> Vec<(usize, usize)> {
+        let index = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect::<HashMap<_, _>>();
+        self.ranks.get(&rank).map_or(Vec::new(), |pairs| {
+            pairs
+                .iter()
+                .filter_map(|(l, r)| Some((*index.get(l)?, *index.get(r)?)))
+                .collect()
+        })
+    }
+
+    /// Moves vertices which violate a constraint as little as possible, all
+    /// other vertices keep their relative order.
+    pub(super) fn repair(&self, order: &mut [Vec<NodeIndex>]) {
+        for (rank, vertices) in order.iter_mut().enumerate() {
+            if self.constrains(rank) {
+                let constraints = self.constraints(rank, vertices);
+                let preferred = (0..vertices.len()).collect::<Vec<_>>();
+                arrange(vertices, &preferred, &constraints);
+            }
+        }
+    }
+
+    /// Orders the vertices of a rank by their values, such that all
+    /// constraints are satisfied.
+    ///
+    /// The values are weighted with the number of neighbors in the fixed rank.
+    /// Vertices without a value (the median of a vertex without neighbors) are
+    /// placed at the end.
+    pub(super) fn order_rank(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        rank: usize,
+        vertices: &mut [NodeIndex],
+        values: &HashMap<NodeIndex, f64>,
+        move_down: bool,
+    ) {
+        let dir = if move_down { Incoming } else { Outgoing };
+        let n = vertices.len();
+        let constraints = self.constraints(rank, vertices);
+        let mut groups = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Group {
+                members: vec![i],
+                value: values[v].min(n as f64),
+                weight: graph.neighbors_directed(*v, dir).count().max(1) as f64,
+            })
+            .collect::<Vec<_>>();
+        let mut root = (0..n).collect::<Vec<_>>();
+        fn find(root: &mut [usize], mut i: usize) -> usize {
+            while root[i] != i {
+                root[i] = root[root[i]];
+                i = root[i];
+            }
+            i
+        }
+
+        // visit the vertices in topological order of the constraints and merge
+        // the groups of violated constraints into the group of the vertex
+        let mut incoming = vec![0; n];
+        for (_, r) in &constraints {
+            incoming[*r] += 1;
+        }
+        let mut stack = (0..n).filter(|i| incoming[*i] == 0).collect::<Vec<_>>();
+        while let Some(v) = stack.pop() {
+            let target = find(&mut root, v);
+            loop {
+                let mut violated = None::<usize>;
+                for (l, r) in &constraints {
+                    let (source, r) = (find(&mut root, *l), find(&mut root, *r));
+                    if r != target
+                        || source == target
+                        || groups[source].value < groups[target].value
+                    {
+                        continue;
+                    }
+                    if violated.is_none_or(|s| groups[s].value < groups[source].value) {
+                        violated = Some(source);
+                    }
+                }
+                let Some(source) = violated else {
+                    break;
+                };
+                root[source] = target;
+                let source = std::mem::take(&mut groups[source]);
+                let group = &mut groups[target];
+                group.value = (source.value * source.weight + group.value * group.weight)
+                    / (source.weight + group.weight);
+                group.weight += source.weight;
+                group.members.splice(0..0, source.members);
+            }
+            for (l, r) in &constraints {
+                if *l == v {
+                    incoming[*r] -= 1;
+                    if incoming[*r] == 0 {
+                        stack.push(*r);
+                    }
+                }
+            }
+        }
+
+        let mut roots = (0..n).filter(|i| root[*i] == *i).collect::<Vec<_>>();
+        roots.sort_by(|a, b| groups[*a].value.total_cmp(&groups[*b].value));
+        let preferred = roots
+            .into_iter()
+            .flat_map(|g| std::mem::take(&mut groups[g].members))
+            .collect::<Vec<_>>();
+        arrange(vertices, &preferred, &constraints);
+    }
+}
+
+/// Vertices of a rank which are placed next to each other.
+#[derive(Default)]
+struct Group {
+    members: Vec<usize>,
+    value: f64,
+    weight: f64,
+}
+
+/// Returns true if there is a path in `pairs` from `from` to `to`.
+pub(super) fn reaches<T: Copy + Eq + Hash>(pairs: &[(T, T)], from: T, to: T) -> bool {
+    let mut stack = vec![from];
+    let mut visited = HashSet::new();
+    while let Some(v) = stack.pop() {
+        if v == to {
+            return true;
+        }
+        if visited.insert(v) {
+            stack.extend(pairs.iter().filter(|(l, _)| *l == v).map(|(_, r)| *r));
+        }
+    }
+    false
+}
+
+/// Arranges `vertices` in the order of the indices in `preferred`, except
+/// for vertices which need to be moved to satisfy the constraints.
+pub(super) fn arrange<T: Copy>(
+    vertices: &mut [T],
+    preferred: &[usize],
+    constraints: &[(usize, usize)],
+) {
+    let n = vertices.len();
+    let mut position = vec![0; n];
+    for (pos, i) in preferred.iter().enumerate() {
+        position[*i] = pos;
+    }
+    let mut successors = vec![Vec::new(); n];
+    let mut predecessors = vec![0; n];
+    for (l, r) in constraints {
+        successors[*l].push(*r);
+        predecessors[*r] += 1;
+    }
+    // always place the available vertex which comes first in the preferred order
+    let mut available = (0..n)
+        .filter(|i| predecessors[*i] == 0)
+        .map(|i| (position[i], i))
+        .collect::<BTreeSet<_>>();
+    let mut arranged = Vec::with_capacity(n);
+    while let Some((_, i)) = available.pop_first() {
+        arranged.push(vertices[i]);
+        for s in &successors[i] {
+            predecessors[*s] -= 1;
+            if predecessors[*s] == 0 {
+                available.insert((position[*s], *s));
+            }
+        }
+    }
+    debug_assert_eq!(arranged.len(), n, "constraints of rank contain a cycle");
+    vertices.copy_from_slice(&arranged);
+}
+
+/// Returns constraints which place the vertices of each lane left of the
+/// vertices of all lanes with a larger id in the same rank.
+pub(crate) fn lane_order(graph: &StableDiGraph<Vertex, Edge>) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut lanes = BTreeMap::<(i32, usize), Vec<NodeIndex>>::new();
+    for v in graph.node_indices() {
+        if let Some(lane) = graph[v].lane {
+            lanes.entry((graph[v].rank, lane)).or_default().push(v);
+        }
+    }
+    // constraints between adjacent lanes imply all others
+    let lanes = lanes.into_iter().collect::<Vec<_>>();
+    let mut pairs = Vec::new();
+    for w in lanes.windows(2) {
+        let (((rank, _), left), ((next, _), right)) = (&w[0], &w[1]);
+        if rank == next {
+            for l in left {
+                pairs.extend(right.iter().map(|r| (*l, *r)));
+            }
+        }
+    }
+    pairs
+}
+
+/// Returns constraints which keep the vertices of each sequence in their
+/// order among the vertices of the same rank.
+pub(crate) fn sequence_order(
+    graph: &StableDiGraph<Vertex, Edge>,
+    sequences: &[Vec<NodeIndex>],
+) -> Vec<(NodeIndex, NodeIndex)> {
+    let mut pairs = Vec::new();
+    for sequence in sequences {
+        // constraints between adjacent vertices imply all others
+        let mut last = HashMap::<i32, NodeIndex>::new();
+        for v in sequence.iter().filter(|v| graph.contains_node(**v)) {
+            if let Some(left) = last.insert(graph[*v].rank, *v) {
+                pairs.push((left, *v));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{lane_order, sequence_order, LeftOf};
+    use crate::algorithm::{Edge, Vertex};
+
+    fn rank_of_four() -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::new();
+        for _ in 0..4 {
+            graph.add_node(Vertex::new_with_rank(0));
+        }
+        graph
+    }
+
+    #[test]
+    fn contradicting_constraints_are_dropped() {
+        let graph = rank_of_four();
+        let n = NodeIndex::new;
+        let left_of = LeftOf::new(&graph, &[(n(0), n(1)), (n(1), n(2)), (n(2), n(0))]);
+        assert!(!left_of.allows_swap(n(0), n(1)));
+        assert!(!left_of.allows_swap(n(1), n(2)));
+        assert!(left_of.allows_swap(n(2), n(0)));
+    }
+
+    #[test]
+    fn sequences_constrain_adjacent_vertices_of_each_rank() {
+        let mut graph = rank_of_four();
+        graph.add_node(Vertex::new_with_rank(1));
+        let n = NodeIndex::new;
+        let pairs = sequence_order(&graph, &[vec![n(3), n(4), n(1), n(0)], vec![n(2), n(9)]]);
+        assert_eq!(pairs, [(n(3), n(1)), (n(1), n(0))]);
+    }
+
+    #[test]
+    fn repair_moves_constrained_vertices_only() {
+        let graph = rank_of_four();
+        let n = NodeIndex::new;
+        let left_of = LeftOf::new(&graph, &[(n(3), n(1))]);
+        let mut order = vec![vec![n(0), n(1), n(2), n(3)]];
+        left_of.repair(&mut order);
+        assert_eq!(order[0], [n(0), n(2), n(3), n(1)]);
+    }
+
+    #[test]
+    fn violated_constraints_are_merged() {
+        let graph = rank_of_four();
+        let n = NodeIndex::new;
+        let left_of = LeftOf::new(&graph, &[(n(2), n(0))]);
+        let values = HashMap::from([(n(0), 0.), (n(1), 1.5), (n(2), 2.), (n(3), 3.)]);
+        let mut vertices = vec![n(0), n(1), n(2), n(3)];
+        left_of.order_rank(&graph, 0, &mut vertices, &values, true);
+        // 2 and 0 are merged into a group with value 1, which is left of 1
+        assert_eq!(vertices, [n(2), n(0), n(1), n(3)]);
+    }
+
+    #[test]
+    fn lanes_are_ordered_by_id() {
+        let mut graph = rank_of_four();
+        let n = NodeIndex::new;
+        for (v, lane) in [(0, 2), (1, 0), (3, 2)] {
+            graph[n(v)].set_lane(lane);
+        }
+        let left_of = LeftOf::new(&graph, &lane_order(&graph));
+        let mut order = vec![vec![n(0), n(1), n(2), n(3)]];
+        left_of.repair(&mut order);
+        assert_eq!(order[0], [n(1), n(0), n(2), n(3)]);
+    }
+}
@@ -0,0 +1,177 @@
+//! Evolutionary crossing reduction, for drawings where the number of crossings
+//! matters more than the time it takes to find them.
+//!
+//! A population of orders is started from the result of the usual sweeps and
+//! random orders. In each generation two parents are chosen by tournament and
+//! combined by taking each rank from either of them. The child is mutated by
+//! sifting a few random vertices to the position in their rank with the fewest
+//! crossings, and replaces the worst order of the population if it has fewer
+//! crossings.
+use log::{debug, info};
+use petgraph::stable_graph::StableDiGraph;
+
+use crate::progress::{Cancelled, Monitor, Phase};
+use crate::util::Rng;
+
+use super::{init_order, Order, Sweeps};
+use crate::algorithm::{Edge, Limit, Vertex};
+
+/// Number of orders in the population
+const POPULATION: usize = 16;
+/// Number of generations, if no time budget is given
+const GENERATIONS: usize = 500;
+/// Number of vertices which are sifted in each child
+const MUTATIONS: usize = 2;
+
+/// Evolves orders starting with `order`, which has `crossings` crossings.
+/// Returns the best order and its number of crossings, which is never worse
+/// than the initial one.
+pub(super) fn ordering(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: Order,
+    crossings: usize,
+    sweeps: &Sweeps,
+    monitor: Monitor,
+) -> Result<(Order, usize), Cancelled> {
+    let mut rng = sweeps.rng.clone().unwrap_or(Rng::new(0));
+    let mut population = vec![(crossings, order)];
+    while population.len() < POPULATION {
+        let order = init_order(graph, Some(&mut rng), false);
+        population.push((order.crossings(graph), order));
+    }
+    // run until the time budget is used up, if there is one
+    let generations = match sweeps.limit.deadline {
+        Some(_) => None,
+        None => Some(GENERATIONS),
+    };
+    let limit = Limit::new(generations, sweeps.limit.deadline);
+    info!(target: "crossing_reduction", "Evolving {POPULATION} orders, starting with {crossings} crossings");
+    for generation in 0.. {
+        if limit.is_reached(generation) || population.iter().any(|(c, _)| *c == 0) {
+            break;
+        }
+        monitor.check()?;
+        if let Some(generations) = generations {
+            monitor.report(
+                Phase::CrossingReduction,
+                generation as f64 / generations as f64,
+            );
+        }
+        let a = tournament(&population, &mut rng);
+        let b = tournament(&population, &mut rng);
+        let mut child = crossover(&population[a].1, &population[b].1, &mut rng);
+        for _ in 0..MUTATIONS {
+            sift(graph, &mut child, &mut rng);
+        }
+        let crossings = child.crossings(graph);
+        let worst = (0..population.len())
+            .max_by_key(|i| population[*i].0)
+            .unwrap();
+        if crossings < population[worst].0 {
+            population[worst] = (crossings, child);
+        }
+    }
+    let (crossings, order) = population.into_iter().min_by_key(|(c, _)| *c).unwrap();
+    debug!(target: "crossing_reduction", "Evolution resulted in {crossings} crossings");
+    Ok((order, crossings))
+}
+
+/// Returns the index of the better one of two random orders of the population.
+fn tournament(population: &[(usize, Order)], rng: &mut Rng) -> usize {
+    let a = rng.below(population.len());
+    let b = rng.below(population.len());
+    if population[a].0 <= population[b].0 {
+        a
+    } else {
+        b
+    }
+}
+
+/// Takes each rank from either of the parents.
+fn crossover(a: &Order, b: &Order, rng: &mut Rng) -> Order {
+    let layers = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a, b)| if rng.below(2) == 0 { a } else { b }.clone())
+        .collect();
+    Order::new(layers)
+}
+
+/// Moves a random vertex to the position in its rank with the fewest
+/// crossings, counted against both adjacent ranks.
+fn sift(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, rng: &mut Rng) {
+    let ranks = (0..order.max_rank())
+        .filter(|r| order[*r].len() > 1)
+        .collect::<Vec<_>>();
+    if ranks.is_empty() {
+        return;
+    }
+    let r = ranks[rng.below(ranks.len())];
+    let from = rng.below(order[r].len());
+    let v = order[r][from];
+    let others = order[r]
+        .iter()
+        .copied()
+        .filter(|w| *w != v)
+        .collect::<Vec<_>>();
+    // crossings of v with the vertices it is moved past, relative to placing
+    // it left of all of them
+    let (mut best, mut best_crossings, mut crossings) = (0, 0, 0isize);
+    for (i, w) in others.iter().enumerate() {
+        crossings += order.cross_count_two_vertices(*w, v, graph) as isize
+            - order.cross_count_two_vertices(v, *w, graph) as isize;
+        if crossings < best_crossings {
+            (best, best_crossings) = (i + 1, crossings);
+        }
+    }
+    let mut rank = others;
+    rank.insert(best, v);
+    for (pos, w) in rank.iter().enumerate() {
+        order.positions.insert(*w, pos);
+    }
+    order._inner[r] = rank;
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::NodeIndex;
+
+    use super::{ordering, sift};
+    use crate::algorithm::p2_reduce_crossings::{init_order, tests::layered_graph, Order, Sweeps};
+    use crate::progress::Hooks;
+    use crate::util::Rng;
+
+    #[test]
+    fn sifting_never_adds_crossings() {
+        let graph = layered_graph(4, 8, 5);
+        let mut rng = Rng::new(1);
+        let mut order = init_order(&graph, Some(&mut rng), false);
+        for _ in 0..50 {
+            let crossings = order.crossings(&graph);
+            sift(&graph, &mut order, &mut rng);
+            assert!(order.crossings(&graph) <= crossings);
+            assert_eq!(Order::new(order._inner.clone()).positions, order.positions);
+        }
+    }
+
+    #[test]
+    fn evolution_improves_initial_order() {
+        let graph = layered_graph(4, 8, 5);
+        let order = init_order(&graph, Some(&mut Rng::new(2)), false);
+        let initial = order.crossings(&graph);
+        let hooks = Hooks::default();
+        let (order, crossings) = ordering(
+            &graph,
+            order,
+            initial,
+            &Sweeps::default(),
+            hooks.monitor(100.),
+        )
+        .unwrap();
+        assert!(crossings < initial);
+        assert_eq!(order.crossings(&graph), crossings);
+        let mut vertices = order.iter().flatten().copied().collect::<Vec<_>>();
+        vertices.sort();
+        assert_eq!(vertices, (0..32).map(NodeIndex::new).collect::<Vec<_>>());
+    }
+}
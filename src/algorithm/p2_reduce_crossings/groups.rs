@@ -0,0 +1,219 @@
+//! Keeps the vertices of each group next to each other within their rank.
+//!
+//! Unlike clusters, groups don't reserve space across ranks and have no order
+//! of their own. When a rank is ordered, the members of a group form a single
+//! unit, whose value is the weighted mean of the values of its members, like
+//! the merged groups of the constrained barycenter heuristic. Members keep
+//! their relative order within the unit. Transpose never swaps a member of a
+//! group with another vertex, unless neither of them shares its rank with
+//! other members of its group.
+use std::collections::{HashMap, HashSet};
+
+use log::{debug, warn};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use crate::algorithm::{Edge, Vertex};
+
+use super::constraints::{arrange, reaches};
+use super::LeftOf;
+
+/// The group of each vertex which shares its rank with other members of its group.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Groups {
+    group: HashMap<NodeIndex, usize>,
+    ranks: HashSet<usize>,
+}
+
+impl Groups {
+    /// Assigns each vertex to the first group containing it. Vertices which
+    /// are the only member of their group in their rank are left out.
+    pub(crate) fn new(graph: &StableDiGraph<Vertex, Edge>, groups: &[Vec<NodeIndex>]) -> Self {
+        let mut group = HashMap::new();
+        for (id, members) in groups.iter().enumerate() {
+            for v in members.iter().filter(|v| graph.contains_node(**v)) {
+                let other = *group.entry(*v).or_insert(id);
+                if other != id {
+                    warn!(target: "crossing_reduction",
+                        "Vertex {} is already part of group {other}, ignoring group {id}",
+                        graph[*v].id);
+                }
+            }
+        }
+        let mut sizes = HashMap::<(usize, i32), usize>::new();
+        for (v, id) in &group {
+            *sizes.entry((*id, graph[*v].rank)).or_default() += 1;
+        }
+        group.retain(|v, id| sizes[&(*id, graph[*v].rank)] > 1);
+        let ranks = group.keys().map(|v| graph[*v].rank as usize).collect();
+        debug!(target: "crossing_reduction", "{} vertices need to be kept next to the members of their group", group.len());
+        Self { group, ranks }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.group.is_empty()
+    }
+
+    /// Returns true if `rank` contains several members of a group.
+    pub(super) fn constrains(&self, rank: usize) -> bool {
+        self.ranks.contains(&rank)
+    }
+
+    /// Returns true if swapping the adjacent vertices `v` and `w` keeps all
+    /// groups together.
+    pub(super) fn allows_swap(&self, v: NodeIndex, w: NodeIndex) -> bool {
+        self.group.get(&v) == self.group.get(&w)
+    }
+
+    /// Moves the members of each group in `vertices` next to each other.
+    ///
+    /// Each group is placed by the weighted mean of the values of its
+    /// members, where the values are weighted with the number of neighbors
+    /// in the fixed rank. Constraints between vertices of different units
+    /// are kept, unless they contradict each other.
+    pub(super) fn group(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        rank: usize,
+        vertices: &mut [NodeIndex],
+        values: &HashMap<NodeIndex, f64>,
+        move_down: bool,
+        left_of: &LeftOf,
+    ) {
+        if !self.constrains(rank) {
+            return;
+        }
+        let dir = if move_down { Incoming } else { Outgoing };
+        let n = vertices.len() as f64;
+        self.order_units(graph, rank, vertices, left_of, |_, v| {
+            (
+                values[v].min(n),
+                graph.neighbors_directed(*v, dir).count().max(1) as f64,
+            )
+        });
+    }
+
+    /// Moves the members of each group next to each other, to the mean
+    /// position of its members.
+    pub(super) fn repair(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        order: &mut [Vec<NodeIndex>],
+        left_of: &LeftOf,
+    ) {
+        for (rank, vertices) in order.iter_mut().enumerate() {
+            if self.constrains(rank) {
+                self.order_units(graph, rank, vertices, left_of, |pos, _| (pos as f64, 1.));
+            }
+        }
+    }
+
+    /// Orders the units of `vertices` by their value, where `value` returns
+    /// the value and the weight of a vertex by its position.
+    fn order_units<F>(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        rank: usize,
+        vertices: &mut [NodeIndex],
+        left_of: &LeftOf,
+        value: F,
+    ) where
+        F: Fn(usize, &NodeIndex) -> (f64, f64),
+    {
+        let mut units = Vec::<(Vec<NodeIndex>, f64, f64)>::new();
+        let mut unit_of_group = HashMap::new();
+        let mut unit_of_vertex = HashMap::new();
+        for (pos, v) in vertices.iter().enumerate() {
+            let unit = match self.group.get(v) {
+                Some(group) => *unit_of_group.entry(*group).or_insert(units.len()),
+                None => units.len(),
+            };
+            if unit == units.len() {
+                units.push(Default::default());
+            }
+            let (value, weight) = value(pos, v);
+            let (members, sum, weights) = &mut units[unit];
+            members.push(*v);
+            *sum += value * weight;
+            *weights += weight;
+            unit_of_vertex.insert(*v, unit);
+        }
+
+        let mut constraints = Vec::new();
+        for (left, right) in left_of.pairs(rank) {
+            if let (Some(&l), Some(&r)) = (unit_of_vertex.get(left), unit_of_vertex.get(right)) {
+                if l != r && !reaches(&constraints, r, l) {
+                    constraints.push((l, r));
+                } else if l != r {
+                    debug!(target: "crossing_reduction",
+                        "Constraint {} left of {} would split a group",
+                        graph[*left].id,
+                        graph[*right].id);
+                }
+            }
+        }
+
+        let mean = |(_, sum, weights): &(Vec<NodeIndex>, f64, f64)| sum / weights;
+        let mut preferred = (0..units.len()).collect::<Vec<_>>();
+        preferred.sort_by(|a, b| mean(&units[*a]).total_cmp(&mean(&units[*b])));
+        let mut order = (0..units.len()).collect::<Vec<_>>();
+        arrange(&mut order, &preferred, &constraints);
+        let ordered = order
+            .into_iter()
+            .flat_map(|unit| std::mem::take(&mut units[unit].0))
+            .collect::<Vec<_>>();
+        vertices.copy_from_slice(&ordered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::Groups;
+    use crate::algorithm::p2::LeftOf;
+    use crate::algorithm::{Edge, Vertex};
+
+    fn rank_of_five() -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::new();
+        for _ in 0..5 {
+            graph.add_node(Vertex::new_with_rank(0));
+        }
+        graph
+    }
+
+    #[test]
+    fn members_are_placed_by_their_mean_value() {
+        let graph = rank_of_five();
+        let n = NodeIndex::new;
+        let groups = Groups::new(&graph, &[vec![n(0), n(4)]]);
+        let values = HashMap::from([(n(0), 0.), (n(1), 1.), (n(2), 1.5), (n(3), 3.), (n(4), 4.)]);
+        let mut vertices = vec![n(0), n(1), n(2), n(3), n(4)];
+        groups.group(&graph, 0, &mut vertices, &values, true, &LeftOf::default());
+        // the group has value 2, so it is placed between 2 and 3
+        assert_eq!(vertices, [n(1), n(2), n(0), n(4), n(3)]);
+    }
+
+    #[test]
+    fn repair_keeps_constraints_between_units() {
+        let graph = rank_of_five();
+        let n = NodeIndex::new;
+        let groups = Groups::new(&graph, &[vec![n(1), n(4)]]);
+        let left_of = LeftOf::new(&graph, &[(n(3), n(1))]);
+        let mut order = vec![vec![n(0), n(1), n(2), n(3), n(4)]];
+        groups.repair(&graph, &mut order, &left_of);
+        assert_eq!(order[0], [n(0), n(2), n(3), n(1), n(4)]);
+    }
+
+    #[test]
+    fn members_alone_in_their_rank_are_free() {
+        let mut graph = rank_of_five();
+        graph.add_node(Vertex::new_with_rank(1));
+        let n = NodeIndex::new;
+        let groups = Groups::new(&graph, &[vec![n(0), n(5)]]);
+        assert!(groups.is_empty());
+        assert!(groups.allows_swap(n(0), n(1)));
+    }
+}
@@ -1,18 +1,39 @@
+mod clusters;
+mod communities;
+mod constraints;
+#[cfg(feature = "evolutionary")]
+mod evolutionary;
+mod groups;
+mod multilevel;
+mod planar;
 #[cfg(test)]
 mod tests;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use petgraph::algo::toposort;
-use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::stable_graph::{EdgeReference, NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
 use petgraph::Direction::{Incoming, Outgoing};
 
-use crate::configure::CrossingMinimization;
-use crate::util::{iterate, radix_sort, IterDir};
+use crate::configure::{CrossingMinimization, OrderingAlgorithm};
+use crate::progress::{Cancelled, Monitor, Phase};
+use crate::util::{iterate, radix_sort, IterDir, Rng};
 
-use super::{slack, Edge, Vertex};
+use super::{slack, Edge, Limit, Vertex};
+
+pub(crate) use clusters::{prepare_clusters, Clusters};
+pub(crate) use constraints::{lane_order, sequence_order, LeftOf};
+pub(crate) use groups::Groups;
+
+/// Minimum number of vertices in a rank to calculate barycenter/median values in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_RANK_SIZE: usize = 512;
+/// Minimum number of ranks to use transpose in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_RANKS: usize = 8;
 
 #[derive(Clone)]
 struct Order {
@@ -67,14 +88,22 @@ impl Order {
     ) -> usize {
         let mut crossings = 0;
         for dir in [Incoming, Outgoing] {
-            let mut v_adjacent = graph
-                .neighbors_directed(v, dir)
-                .map(|n| *self.positions.get(&n).unwrap())
-                .collect::<Vec<_>>();
-            let mut w_adjacent = graph
-                .neighbors_directed(w, dir)
-                .map(|n| *self.positions.get(&n).unwrap())
-                .collect::<Vec<_>>();
+            // edges attached to different ports of a neighbor may cross
+            let adjacent = |v: NodeIndex| {
+                graph
+                    .edges_directed(v, dir)
+                    .map(|e| {
+                        let n = if dir == Incoming {
+                            e.source()
+                        } else {
+                            e.target()
+                        };
+                        (*self.positions.get(&n).unwrap(), port_offset(graph, e, n))
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let mut v_adjacent = adjacent(v);
+            let mut w_adjacent = adjacent(w);
             v_adjacent.sort();
             w_adjacent.sort();
             crossings += Self::calculate_cross_count_two_vertices(&v_adjacent, &w_adjacent);
@@ -82,14 +111,13 @@ impl Order {
         crossings
     }
 
-    fn calculate_cross_count_two_vertices(v_adjacent: &[usize], w_adjacent: &[usize]) -> usize {
+    fn calculate_cross_count_two_vertices<T: Ord>(v_adjacent: &[T], w_adjacent: &[T]) -> usize {
         let mut all_crossings = 0;
         let mut k = 0;
         for i in v_adjacent {
-            let i = *i;
             let mut crossings = k;
-            while k < w_adjacent.len() && w_adjacent[k] < i {
-                let j = w_adjacent[k];
+            while k < w_adjacent.len() && w_adjacent[k] < *i {
+                let j = &w_adjacent[k];
                 if i > j {
                     crossings += 1;
                 }
@@ -112,6 +140,13 @@ impl Order {
         // find initial edge order
         let north = &self[rank];
         let south = &self[rank + 1];
+        if north.iter().any(|v| {
+            graph
+                .edges_directed(*v, Outgoing)
+                .any(|e| e.weight().ports != (None, None))
+        }) {
+            return self.bilayer_port_cross_count(graph, rank);
+        }
         let mut len = south.len();
         let mut key_length = 0;
         while len > 0 {
@@ -135,6 +170,36 @@ impl Order {
         Self::count_crossings(edge_endpoint_positions, south.len())
     }
 
+    /// Counts crossings like [Order::bilayer_cross_count], but edges are
+    /// ordered by the ports they are attached to within each vertex.
+    fn bilayer_port_cross_count(&self, graph: &StableDiGraph<Vertex, Edge>, rank: usize) -> usize {
+        let mut edges = self[rank]
+            .iter()
+            .flat_map(|v| graph.edges_directed(*v, Outgoing))
+            .filter(|e| graph[e.source()].rank.abs_diff(graph[e.target()].rank) == 1)
+            .filter_map(|e| {
+                let north = (
+                    self.positions[&e.source()],
+                    port_offset(graph, e, e.source()),
+                );
+                let south = (
+                    *self.positions.get(&e.target())?,
+                    port_offset(graph, e, e.target()),
+                );
+                Some((north, south))
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        let mut south = edges.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        south.sort();
+        south.dedup();
+        let endpoints = edges
+            .iter()
+            .map(|(_, s)| south.binary_search(s).unwrap())
+            .collect();
+        Self::count_crossings(endpoints, south.len())
+    }
+
     fn count_crossings(endpoints: Vec<usize>, south_len: usize) -> usize {
         // build the accumulator tree
         let mut c = 0;
@@ -192,6 +257,26 @@ impl DerefMut for Order {
     }
 }
 
+/// Returns the horizontal offset of the port `edge` is attached to at `v`,
+/// which is 0 for edges without a port and for dummy vertices.
+fn port_offset(
+    graph: &StableDiGraph<Vertex, Edge>,
+    edge: EdgeReference<Edge>,
+    v: NodeIndex,
+) -> isize {
+    if graph[v].is_dummy {
+        return 0;
+    }
+    let (tail, head) = edge.weight().ports;
+    // the ports belong to the endpoints of the input edge
+    let port = if (v == edge.source()) != edge.weight().is_reversed {
+        tail
+    } else {
+        head
+    };
+    port.map_or(0, |p| p.offset)
+}
+
 pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
     // find all edges that have slack of greater than 0.
     // and insert dummy vertices
@@ -205,8 +290,8 @@ pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>, min
                 tail.index(), 
                 head.index());
 
-            // we don't need to remember edges that where removed
-            graph.remove_edge(edge);
+            // the dummy edges only keep the identity and constraints of the removed edge
+            let dummy_edge = graph.remove_edge(edge).unwrap().segment();
             for rank in (graph[tail].rank + 1)..graph[head].rank {
                 // usize usize::MAX id as reserved value for a dummy vertex
                 let d = Vertex {
@@ -218,10 +303,10 @@ pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>, min
                 graph[new].root = new;
                 graph[new].sink = new;
                 graph[new].rank = rank;
-                graph.add_edge(tail, new, Edge::default());
+                graph.add_edge(tail, new, dummy_edge);
                 tail = new;
             }
-            graph.add_edge(tail, head, Edge::default()); // add last dummy edge connecting to the head
+            graph.add_edge(tail, head, dummy_edge); // add last dummy edge connecting to the head
         }
     }
 }
@@ -239,18 +324,19 @@ pub(super) fn remove_dummy_vertices(
     let vertices = toposort(&*graph, None).unwrap();
     for v in vertices {
         let mut edges = Vec::new();
-        for mut n in graph.neighbors_directed(v, Outgoing) {
+        for edge in graph.edges_directed(v, Outgoing) {
+            let mut n = edge.target();
             if graph[n].is_dummy {
                 while graph[n].is_dummy {
                     let dummy_neighbors = graph.neighbors_directed(n, Outgoing).collect::<Vec<_>>();
                     //assert_eq!(dummy_neighbors.len(), 1);
                     n = dummy_neighbors[0];
                 }
-                edges.push((v, n));
+                edges.push((v, n, edge.weight().segment()));
             }
         }
-        for (tail, head) in edges {
-            graph.add_edge(tail, head, Edge::default());
+        for (tail, head, weight) in edges {
+            graph.add_edge(tail, head, weight);
         }
     }
     // remove from order
@@ -260,26 +346,353 @@ pub(super) fn remove_dummy_vertices(
     graph.retain_nodes(|g, v| !g[v].is_dummy);
 }
 
+/// Limits for the sweeps of crossing reduction and the random number
+/// generator used for arbitrary choices, if seeded.
+#[derive(Clone, Default)]
+pub(super) struct Sweeps {
+    /// Limit for transpose, if it is used at all
+    pub(super) transpose: Option<Limit>,
+    /// Shuffles the order in which transpose visits ranks and pairs of
+    /// vertices in each pass, if set
+    pub(super) transpose_scan: Option<Rng>,
+    pub(super) limit: Limit,
+    /// Number of sweeps without improvement, after which sweeps stop
+    pub(super) patience: usize,
+    /// Break ties between vertices by their order in the input, see [input_order]
+    pub(super) stable_ties: bool,
+    pub(super) rng: Option<Rng>,
+    /// Number of additional runs from different random initial orders
+    pub(super) restarts: usize,
+    /// Coarsen large graphs before reducing crossings, see [multilevel]
+    pub(super) multilevel: bool,
+    /// Order the initial ranks community by community, see [communities]
+    pub(super) communities: bool,
+    /// Vertices which need to be placed left of other vertices in their rank
+    pub(super) left_of: LeftOf,
+    /// Vertices which are kept next to the other members of their group
+    pub(super) groups: Groups,
+    /// Hierarchy of the clusters, their order is determined by the first run
+    pub(super) clusters: Clusters,
+    /// Endpoints of edges within a rank, which are placed next to each other
+    /// if this doesn't add crossings
+    pub(super) flat_edges: Vec<(NodeIndex, NodeIndex)>,
+}
+
 // TODO: Maybe write store all upper neighbors on vertex directly
+/// Orders the vertices of each rank to reduce crossings. Returns the order and
+/// its number of crossings.
 pub(super) fn ordering(
     graph: &mut StableDiGraph<Vertex, Edge>,
     crossing_minimization: CrossingMinimization,
-    transpose: bool,
-) -> Vec<Vec<NodeIndex>> {
-    let order = init_order(graph);
+    sweeps: Sweeps,
+    monitor: Monitor,
+) -> Result<(Vec<Vec<NodeIndex>>, usize), Cancelled> {
     // move downwards for crossing reduction
-    let cm_method = match crossing_minimization {
+    let cm_method: CMMethod = match crossing_minimization {
         CrossingMinimization::Barycenter => self::barycenter,
         CrossingMinimization::Median => self::median,
+        CrossingMinimization::Best => {
+            // both heuristics start from the same state, ties keep barycenter
+            let barycenter = ordering_with(graph, self::barycenter, sweeps.clone(), monitor)?;
+            let median = ordering_with(graph, self::median, sweeps, monitor)?;
+            debug!(target: "crossing_reduction",
+                "Barycenter resulted in {} crossings, median in {}", barycenter.1, median.1);
+            return Ok(if median.1 < barycenter.1 {
+                median
+            } else {
+                barycenter
+            });
+        }
+        #[cfg(feature = "evolutionary")]
+        CrossingMinimization::Evolutionary => {
+            // the sweeps give the population a good start
+            let (order, crossings) =
+                ordering_with(graph, self::barycenter, sweeps.clone(), monitor)?;
+            if !sweeps.left_of.is_empty()
+                || !sweeps.groups.is_empty()
+                || !sweeps.clusters.is_empty()
+                || graph
+                    .node_weights()
+                    .any(|w| w.prev_x.is_some() || w.start_x.is_some() || w.pin.is_some())
+            {
+                info!(target: "crossing_reduction",
+                    "Constraints can't be evolved, keeping the order of the sweeps");
+                return Ok((order, crossings));
+            }
+            let (order, crossings) =
+                evolutionary::ordering(graph, Order::new(order), crossings, &sweeps, monitor)?;
+            let (order, crossings) = pull_flat_edges(graph, order, crossings, &sweeps);
+            return Ok((order._inner, crossings));
+        }
     };
-    let order = reduce_crossings_bilayer_sweep(graph, order, cm_method, transpose);
-    order._inner
+    ordering_with(graph, cm_method, sweeps, monitor)
+}
+
+/// Orders the vertices of each rank with a single heuristic, see [ordering].
+fn ordering_with(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    cm_method: CMMethod,
+    mut sweeps: Sweeps,
+    monitor: Monitor,
+) -> Result<(Vec<Vec<NodeIndex>>, usize), Cancelled> {
+    // st-planar graphs are drawn without crossings directly
+    if let Some(order) = planar::ordering(graph, &sweeps) {
+        return Ok((order._inner, 0));
+    }
+    if sweeps.multilevel
+        && sweeps.left_of.is_empty()
+        && sweeps.groups.is_empty()
+        && graph.node_weights().all(|w| w.cluster.is_none())
+        && graph.node_count() > multilevel::COARSEST_SIZE
+        && graph
+            .node_weights()
+            .all(|w| w.prev_x.is_none() && w.start_x.is_none() && w.pin.is_none())
+    {
+        let (order, crossings) = multilevel::ordering(
+            graph,
+            cm_method,
+            &mut sweeps,
+            monitor,
+            multilevel::COARSEST_SIZE,
+        )?;
+        let (order, crossings) = pull_flat_edges(graph, order, crossings, &sweeps);
+        return Ok((order._inner, crossings));
+    }
+    // the first run uses the configured rng (if any), restarts are seeded
+    // from it, or by their number if no seed was given
+    let seeds = (1..=sweeps.restarts)
+        .map(|run| match sweeps.rng.as_mut() {
+            Some(rng) => rng.next_u64(),
+            None => run as u64,
+        })
+        .collect::<Vec<_>>();
+    let runs = seeds.len() + 1;
+    let rngs = std::iter::once(sweeps.rng.take())
+        .chain(seeds.into_iter().map(|seed| Some(Rng::new(seed))));
+
+    let communities = if sweeps.communities {
+        communities::find(graph)
+    } else {
+        HashMap::new()
+    };
+
+    let mut best: Option<(usize, Order)> = None;
+    for (run, rng) in rngs.enumerate() {
+        sweeps.rng = rng;
+        let mut order = init_order(graph, sweeps.rng.as_mut(), sweeps.stable_ties);
+        if sweeps.communities {
+            communities::pre_order(&mut order, &communities);
+            order = Order::new(order._inner);
+        }
+        apply_previous_order(graph, &mut order);
+        if !sweeps.left_of.is_empty() {
+            sweeps.left_of.repair(&mut order);
+            order = Order::new(order._inner);
+        }
+        if !sweeps.groups.is_empty() {
+            sweeps.groups.repair(graph, &mut order, &sweeps.left_of);
+            order = Order::new(order._inner);
+        }
+        if run == 0 {
+            sweeps.clusters.set_order(graph, &order);
+        }
+        if !sweeps.clusters.is_empty() {
+            sweeps.clusters.repair(graph, &mut order, &sweeps.left_of);
+            order = Order::new(order._inner);
+        }
+        // vertices of a previous layout and pinned vertices are pulled towards their position
+        let anchors = order
+            .positions
+            .iter()
+            .filter(|(v, _)| graph[**v].prev_x.is_some() || graph[**v].pin.is_some())
+            .map(|(v, pos)| (*v, *pos as f64))
+            .collect::<HashMap<_, _>>();
+        let (order, crossings) = reduce_crossings_bilayer_sweep(
+            graph,
+            order,
+            cm_method,
+            &anchors,
+            &mut sweeps,
+            monitor,
+            (run, runs),
+        )?;
+        if runs > 1 {
+            debug!(target: "crossing_reduction", "Run {run} resulted in {crossings} crossings");
+        }
+        if best.as_ref().is_none_or(|(c, _)| crossings < *c) {
+            best = Some((crossings, order));
+        }
+    }
+    let (mut crossings, mut order) = best.unwrap();
+    // pinned vertices need to be ordered by their x-coordinate
+    if graph.node_weights().any(|w| w.pin.is_some()) {
+        sort_by_x(graph, &mut order, |w| w.pin.map(|(x, _)| x));
+        sweeps.left_of.repair(&mut order);
+        sweeps.groups.repair(graph, &mut order, &sweeps.left_of);
+        sweeps.clusters.repair(graph, &mut order, &sweeps.left_of);
+        order = Order::new(order._inner);
+        crossings = order.crossings(graph);
+    }
+    let (order, crossings) = pull_flat_edges(graph, order, crossings, &sweeps);
+    Ok((order._inner, crossings))
+}
+
+/// Orders the vertices of each rank with a custom algorithm, starting from
+/// the initial order. Returns the order and the number of crossings.
+///
+/// Vertices which the algorithm places in the wrong rank or more than once
+/// are ignored, and missing vertices are appended in their initial order.
+/// Order constraints, groups and clusters are repaired afterwards.
+pub(super) fn custom_ordering(
+    graph: &StableDiGraph<Vertex, Edge>,
+    algorithm: &dyn OrderingAlgorithm,
+    mut sweeps: Sweeps,
+) -> (Vec<Vec<NodeIndex>>, usize) {
+    info!(target: "crossing_reduction", "Ordering vertices with a custom algorithm");
+    let initial = init_order(graph, None, sweeps.stable_ties);
+    // keeps the indices, so the order refers to vertices of `graph`
+    let input = graph.map(|_, v| (!v.is_dummy).then_some(v.id), |_, _| ());
+    let mut returned = algorithm.order(&input, initial._inner.clone()).into_iter();
+    let mut misplaced = 0;
+    let layers = initial
+        .iter()
+        .map(|rank| {
+            let mut remaining = rank.iter().copied().collect::<HashSet<_>>();
+            let mut layer = Vec::with_capacity(rank.len());
+            for v in returned.next().unwrap_or_default() {
+                if remaining.remove(&v) {
+                    layer.push(v);
+                } else {
+                    misplaced += 1;
+                }
+            }
+            misplaced += remaining.len();
+            layer.extend(rank.iter().filter(|v| remaining.contains(v)));
+            layer
+        })
+        .collect::<Vec<_>>();
+    if misplaced > 0 {
+        warn!(target: "crossing_reduction",
+            "Custom ordering misplaced or left out {misplaced} vertices");
+    }
+    let mut order = Order::new(layers);
+    if !sweeps.left_of.is_empty() {
+        sweeps.left_of.repair(&mut order);
+    }
+    if !sweeps.groups.is_empty() {
+        sweeps.groups.repair(graph, &mut order, &sweeps.left_of);
+    }
+    sweeps.clusters.set_order(graph, &order);
+    if !sweeps.clusters.is_empty() {
+        sweeps.clusters.repair(graph, &mut order, &sweeps.left_of);
+    }
+    let order = Order::new(order._inner);
+    let crossings = order.crossings(graph);
+    let (order, crossings) = pull_flat_edges(graph, order, crossings, &sweeps);
+    (order._inner, crossings)
+}
+
+/// Moves the endpoints of each flat edge next to each other, by moving one of
+/// them next to the other one, if this doesn't add crossings. Ranks with
+/// order constraints, groups or pinned vertices keep their order, as do
+/// graphs with clusters.
+fn pull_flat_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    mut order: Order,
+    mut crossings: usize,
+    sweeps: &Sweeps,
+) -> (Order, usize) {
+    if sweeps.flat_edges.is_empty() || !sweeps.clusters.is_empty() {
+        return (order, crossings);
+    }
+    info!(target: "crossing_reduction", "Moving the endpoints of {} flat edges together", sweeps.flat_edges.len());
+    for &(u, v) in &sweeps.flat_edges {
+        let (Some(pu), Some(pv)) = (order.positions.get(&u), order.positions.get(&v)) else {
+            continue;
+        };
+        let r = graph[u].rank as usize;
+        if graph[v].rank != graph[u].rank
+            || pu.abs_diff(*pv) <= 1
+            || sweeps.left_of.constrains(r)
+            || sweeps.groups.constrains(r)
+            || order[r].iter().any(|w| graph[*w].pin.is_some())
+        {
+            continue;
+        }
+        let mut best: Option<(usize, Order)> = None;
+        for (moved, fixed) in [(v, u), (u, v)] {
+            let from = order.positions[&moved];
+            let mut rank = order[r].clone();
+            rank.remove(from);
+            let to = rank.iter().position(|w| *w == fixed).unwrap()
+                + usize::from(from > order.positions[&fixed]);
+            rank.insert(to, moved);
+            let mut layers = order._inner.clone();
+            layers[r] = rank;
+            let candidate = Order::new(layers);
+            let c = candidate.crossings(graph);
+            if c <= crossings && best.as_ref().is_none_or(|(b, _)| c < *b) {
+                best = Some((c, candidate));
+            }
+        }
+        if let Some((c, candidate)) = best {
+            (crossings, order) = (c, candidate);
+        }
+    }
+    (order, crossings)
 }
 
 type CMMethod =
     fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &HashMap<NodeIndex, usize>) -> f64;
 
-fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
+/// Reorders the vertices of each rank which were part of a previous layout or
+/// the layout to start from, or are pinned, by their previous (or pinned)
+/// x-coordinate. All other vertices keep their position.
+fn apply_previous_order(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order) {
+    if graph
+        .node_weights()
+        .all(|w| w.prev_x.is_none() && w.start_x.is_none() && w.pin.is_none())
+    {
+        return;
+    }
+    info!(target: "crossing_reduction", "Ordering vertices by their position in the previous layout");
+    sort_by_x(graph, order, |w| {
+        w.pin.map(|(x, _)| x).or(w.prev_x).or(w.start_x)
+    });
+    *order = Order::new(std::mem::take(&mut order._inner));
+}
+
+/// Sorts all vertices of each rank for which `x` returns a value, while all
+/// other vertices keep their position.
+fn sort_by_x(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut [Vec<NodeIndex>],
+    x: fn(&Vertex) -> Option<isize>,
+) {
+    for rank in order {
+        let slots = rank
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| x(&graph[**v]).is_some())
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        let mut sorted = slots.iter().map(|pos| rank[*pos]).collect::<Vec<_>>();
+        sorted.sort_by_key(|v| x(&graph[*v]));
+        for (pos, v) in slots.into_iter().zip(sorted) {
+            rank[pos] = v;
+        }
+    }
+}
+
+/// Builds the initial order via dfs. If `rng` is given, start vertices and
+/// neighbors are visited in random order.
+/// Orders the vertices of each rank by a dfs, which visits vertices in input
+/// order if `stable_ties` is set, and randomly if `rng` is given otherwise.
+fn init_order(
+    graph: &StableDiGraph<Vertex, Edge>,
+    mut rng: Option<&mut Rng>,
+    stable_ties: bool,
+) -> Order {
     info!(target: "crossing_reduction", 
         "Initializing order of vertices in each rank via dfs.");
 
@@ -288,13 +701,21 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
         order: &mut Vec<Vec<NodeIndex>>,
         graph: &StableDiGraph<Vertex, Edge>,
         visited: &mut HashSet<NodeIndex>,
+        mut rng: Option<&mut Rng>,
+        stable_ties: bool,
     ) {
         if !visited.contains(&v) {
             visited.insert(v);
             order[graph[v].rank as usize].push(v);
-            graph
-                .neighbors_directed(v, Outgoing)
-                .for_each(|n| dfs(n, order, graph, visited))
+            let mut neighbors = graph.neighbors_directed(v, Outgoing).collect::<Vec<_>>();
+            if stable_ties {
+                neighbors.sort_by_key(|n| input_order(graph, *n));
+            } else if let Some(rng) = rng.as_deref_mut() {
+                rng.shuffle(&mut neighbors);
+            }
+            for n in neighbors {
+                dfs(n, order, graph, visited, rng.as_deref_mut(), stable_ties);
+            }
         }
     }
 
@@ -307,9 +728,22 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
     let mut visited = HashSet::new();
 
     // build initial order via dfs
-    graph
-        .node_indices()
-        .for_each(|v| dfs(v, &mut order, graph, &mut visited));
+    let mut start = graph.node_indices().collect::<Vec<_>>();
+    if stable_ties {
+        start.sort_by_key(|v| input_order(graph, *v));
+    } else if let Some(rng) = rng.as_deref_mut() {
+        rng.shuffle(&mut start);
+    }
+    for v in start {
+        dfs(
+            v,
+            &mut order,
+            graph,
+            &mut visited,
+            rng.as_deref_mut(),
+            stable_ties,
+        );
+    }
 
     Order::new(order)
 }
@@ -318,18 +752,32 @@ fn reduce_crossings_bilayer_sweep(
     graph: &StableDiGraph<Vertex, Edge>,
     mut order: Order,
     cm_method: CMMethod,
-    transpose: bool,
-) -> Order {
+    anchors: &HashMap<NodeIndex, f64>,
+    sweeps: &mut Sweeps,
+    monitor: Monitor,
+    (run, runs): (usize, usize),
+) -> Result<(Order, usize), Cancelled> {
     info!(target: "crossing_reduction", "Reducing crossings via bilayer sweep");
     let mut best_crossings = order.crossings(graph);
     debug!(target: "crossing_reduction", "Initial number of crossings: {best_crossings}");
     let mut last_best = 0;
     let mut best = order.clone();
     for i in 0.. {
-        order = order_layer(graph, i % 2 == 0, &order, cm_method);
-        if transpose {
-            self::transpose(graph, &mut order, i % 2 == 0);
+        if sweeps.limit.is_reached(i) {
+            break;
         }
+        monitor.check()?;
+        // the number of sweeps isn't known in advance, so just approach the end of the run
+        let fraction = (run as f64 + i as f64 / (i + 1) as f64) / runs as f64;
+        monitor.report(Phase::CrossingReduction, fraction);
+        order = order_layer(graph, i % 2 == 0, &order, cm_method, anchors, sweeps);
+        if let Some(limit) = sweeps.transpose {
+            self::transpose(graph, &mut order, i % 2 == 0, limit, sweeps);
+        }
+        #[cfg(feature = "debug-dump")]
+        monitor.dump(&format!("2-sweep-{run}-{i:03}"), || {
+            super::dump::to_dot(graph, &order._inner, true)
+        });
         let crossings = order.crossings(graph);
         trace!(target: "crossing_reduction", "Current number of crossings: {crossings}");
         if crossings < best_crossings {
@@ -340,18 +788,38 @@ fn reduce_crossings_bilayer_sweep(
         } else {
             last_best += 1;
         }
-        if last_best == 4 {
-            info!(target: "crossing_reduction", "Didn't improve after 4 sweeps, returning");
-            return best;
+        if last_best >= sweeps.patience.max(1) {
+            info!(target: "crossing_reduction", "Didn't improve after {last_best} sweeps, returning");
+            return Ok((best, best_crossings));
         }
     }
-    best
+    Ok((best, best_crossings))
 }
 
-fn transpose(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, move_down: bool) {
+fn transpose(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut Order,
+    move_down: bool,
+    limit: Limit,
+    sweeps: &mut Sweeps,
+) {
+    let Sweeps {
+        left_of,
+        groups,
+        rng,
+        transpose_scan,
+        ..
+    } = sweeps;
+    let mut rng = rng.as_mut();
+    let mut scan = transpose_scan.as_mut();
     trace!(target: "crossings_reduction", 
         "Using transpose, try to swap vertices in each layer manually to reduce cross count");
 
+    #[cfg(feature = "parallel")]
+    if order.max_rank() >= PARALLEL_MIN_RANKS {
+        return transpose_parallel(graph, order, limit, left_of, groups, rng, scan);
+    }
+
     let mut improved = true;
     let iter_dir = if move_down {
         IterDir::Forward
@@ -359,31 +827,161 @@ fn transpose(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, move_down:
         IterDir::Backward
     };
 
-    while improved {
+    let mut iterations = 0;
+    while improved && !limit.is_reached(iterations) {
+        iterations += 1;
         improved = false;
-        for r in iterate(iter_dir, order.max_rank()) {
+        let mut ranks = iterate(iter_dir, order.max_rank()).collect::<Vec<_>>();
+        if let Some(scan) = scan.as_deref_mut() {
+            scan.shuffle(&mut ranks);
+        }
+        for r in ranks {
             trace!(target: "reduce_crossings", "Transpose vertices in rank {r}");
-            for i in 0..order._inner[r].len() - 1 {
+            let mut pairs = (0..order._inner[r].len() - 1).collect::<Vec<_>>();
+            if let Some(scan) = scan.as_deref_mut() {
+                scan.shuffle(&mut pairs);
+            }
+            for i in pairs {
                 let v = order._inner[r][i];
                 let w = order._inner[r][i + 1];
+                if !left_of.allows_swap(v, w)
+                    || !groups.allows_swap(v, w)
+                    || graph[v].cluster != graph[w].cluster
+                {
+                    continue;
+                }
                 let v_w_crossing = order.cross_count_two_vertices(v, w, graph);
                 let w_v_crossing = order.cross_count_two_vertices(w, v, graph);
                 if v_w_crossing > w_v_crossing {
                     improved = true;
                     order.exchange(i, i + 1, r);
+                } else if v_w_crossing == w_v_crossing {
+                    // randomly break ties, without counting it as an improvement
+                    if let Some(rng) = rng.as_deref_mut() {
+                        if rng.below(2) == 0 {
+                            order.exchange(i, i + 1, r);
+                        }
+                    }
+                }
+            }
+        }
+        trace!(target: "reduce_crossings", "Did improve: {improved}");
+    }
+}
+
+/// Like [transpose], but processes all ranks with the same parity in parallel.
+/// Swapping two vertices only changes the crossings with the adjacent ranks,
+/// so ranks which are not adjacent can be transposed independently.
+#[cfg(feature = "parallel")]
+fn transpose_parallel(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut Order,
+    limit: Limit,
+    left_of: &LeftOf,
+    groups: &Groups,
+    mut rng: Option<&mut Rng>,
+    mut scan: Option<&mut Rng>,
+) {
+    use rayon::prelude::*;
+
+    let mut improved = true;
+    let mut iterations = 0;
+    while improved && !limit.is_reached(iterations) {
+        iterations += 1;
+        improved = false;
+        for parity in [0, 1] {
+            // each rank gets its own rng, so the result doesn't depend on scheduling
+            let seed = rng.as_deref_mut().map(|rng| rng.next_u64());
+            let scan_seed = scan.as_deref_mut().map(|scan| scan.next_u64());
+            let ranks = (parity..order.max_rank()).step_by(2).collect::<Vec<_>>();
+            let transposed = ranks
+                .into_par_iter()
+                .map(|r| {
+                    let mut rank = order[r].clone();
+                    let mut rng = seed.map(|seed| Rng::new(seed ^ r as u64));
+                    let mut scan = scan_seed.map(|seed| Rng::new(seed ^ r as u64));
+                    let improved = transpose_rank(
+                        graph,
+                        order,
+                        &mut rank,
+                        left_of,
+                        groups,
+                        rng.as_mut(),
+                        scan.as_mut(),
+                    );
+                    (r, rank, improved)
+                })
+                .collect::<Vec<_>>();
+            for (r, rank, rank_improved) in transposed {
+                improved |= rank_improved;
+                for (pos, v) in rank.iter().enumerate() {
+                    order.positions.insert(*v, pos);
                 }
+                order[r] = rank;
             }
         }
         trace!(target: "reduce_crossings", "Did improve: {improved}");
     }
 }
 
+/// Swaps adjacent vertices of `rank` if this reduces crossings. Positions of
+/// the adjacent ranks are taken from `order`. Pairs are visited from left to
+/// right, or in the order given by `scan`.
+#[cfg(feature = "parallel")]
+fn transpose_rank(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &Order,
+    rank: &mut [NodeIndex],
+    left_of: &LeftOf,
+    groups: &Groups,
+    mut rng: Option<&mut Rng>,
+    scan: Option<&mut Rng>,
+) -> bool {
+    let mut improved = false;
+    let mut pairs = (0..rank.len().saturating_sub(1)).collect::<Vec<_>>();
+    if let Some(scan) = scan {
+        scan.shuffle(&mut pairs);
+    }
+    for i in pairs {
+        if !left_of.allows_swap(rank[i], rank[i + 1])
+            || !groups.allows_swap(rank[i], rank[i + 1])
+            || graph[rank[i]].cluster != graph[rank[i + 1]].cluster
+        {
+            continue;
+        }
+        let v_w_crossing = order.cross_count_two_vertices(rank[i], rank[i + 1], graph);
+        let w_v_crossing = order.cross_count_two_vertices(rank[i + 1], rank[i], graph);
+        if v_w_crossing > w_v_crossing {
+            improved = true;
+            rank.swap(i, i + 1);
+        } else if v_w_crossing == w_v_crossing {
+            if let Some(rng) = rng.as_deref_mut() {
+                if rng.below(2) == 0 {
+                    rank.swap(i, i + 1);
+                }
+            }
+        }
+    }
+    improved
+}
+
 fn order_layer(
     graph: &StableDiGraph<Vertex, Edge>,
     move_down: bool,
     cur_order: &Order,
     cm_method: CMMethod,
+    anchors: &HashMap<NodeIndex, f64>,
+    sweeps: &mut Sweeps,
 ) -> Order {
+    let Sweeps {
+        left_of,
+        groups,
+        clusters,
+        rng,
+        stable_ties,
+        ..
+    } = sweeps;
+    let mut rng = rng.as_mut();
     let mut new_order = vec![Vec::new(); cur_order.max_rank()];
     let mut positions = cur_order.positions.clone();
     let dir: Vec<usize> = if move_down {
@@ -405,12 +1003,36 @@ fn order_layer(
         );
 
         new_order[rank].clone_from(&cur_order[rank]);
-        let ordering = new_order[rank]
-            .iter()
-            .map(|n| (*n, cm_method(graph, *n, move_down, &positions)))
-            .collect::<HashMap<NodeIndex, f64>>();
+        // sorting is stable, so shuffling beforehand breaks ties randomly
+        if *stable_ties {
+            new_order[rank].sort_by_key(|v| input_order(graph, *v));
+        } else if let Some(rng) = rng.as_deref_mut() {
+            rng.shuffle(&mut new_order[rank]);
+        }
+        let ordering = rank_values(&new_order[rank], |n| {
+            let value = cm_method(graph, *n, move_down, &positions);
+            match anchors.get(n) {
+                Some(anchor) => (*n, (value + anchor) / 2.),
+                None => (*n, value),
+            }
+        });
 
-        new_order[rank].sort_by(|a, b| ordering.get(a).partial_cmp(&ordering.get(b)).unwrap());
+        if left_of.constrains(rank) {
+            left_of.order_rank(graph, rank, &mut new_order[rank], &ordering, move_down);
+        } else {
+            new_order[rank].sort_by(|a, b| ordering.get(a).partial_cmp(&ordering.get(b)).unwrap());
+        }
+        groups.group(
+            graph,
+            rank,
+            &mut new_order[rank],
+            &ordering,
+            move_down,
+            left_of,
+        );
+        if !clusters.is_empty() {
+            clusters.group(graph, rank, &mut new_order[rank], left_of);
+        }
 
         new_order[rank].iter().enumerate().for_each(|(pos, v)| {
             positions.insert(*v, pos);
@@ -427,6 +1049,36 @@ fn order_layer(
     Order::new(new_order)
 }
 
+/// Position of a vertex in the input, which breaks ties between vertices with
+/// the same value if [Sweeps::stable_ties] is set. Dummy vertices come after
+/// all vertices of the input, in the order of their edges.
+fn input_order(graph: &StableDiGraph<Vertex, Edge>, v: NodeIndex) -> (bool, usize) {
+    if graph[v].is_dummy {
+        let edge = graph.edges_directed(v, Incoming).next();
+        (true, edge.map_or(usize::MAX, |e| e.weight().id))
+    } else {
+        (false, graph[v].id)
+    }
+}
+
+/// Calculates the value of each vertex of a rank, in parallel for large ranks.
+fn rank_values<F>(rank: &[NodeIndex], value: F) -> HashMap<NodeIndex, f64>
+where
+    F: Fn(&NodeIndex) -> (NodeIndex, f64) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if rank.len() >= PARALLEL_MIN_RANK_SIZE {
+        use rayon::prelude::*;
+        return rank
+            .par_iter()
+            .map(value)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect();
+    }
+    rank.iter().map(value).collect()
+}
+
 fn barycenter(
     graph: &StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
@@ -0,0 +1,222 @@
+//! Multilevel crossing reduction for large graphs.
+//!
+//! The layered graph is coarsened by repeatedly merging pairs of vertices in
+//! the same rank which share a neighbor, until it is small enough or can't be
+//! reduced any further. The coarsest graph is ordered with the usual sweeps.
+//! Afterwards each level is expanded again, starting with the order of the
+//! coarser level, and the order is refined with a few more sweeps.
+use std::collections::{HashMap, HashSet};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use crate::progress::{Cancelled, Monitor};
+
+use super::{init_order, reduce_crossings_bilayer_sweep, CMMethod, Order, Sweeps};
+use crate::algorithm::{Edge, Vertex};
+
+/// Number of vertices below which the graph isn't coarsened any further
+pub(super) const COARSEST_SIZE: usize = 1000;
+/// Maximum number of sweeps when refining the order of a level
+const REFINEMENT_SWEEPS: usize = 4;
+
+/// A coarsened version of a finer graph.
+struct Level {
+    graph: StableDiGraph<Vertex, Edge>,
+    /// The vertices of the finer graph which were merged into each vertex.
+    members: HashMap<NodeIndex, Vec<NodeIndex>>,
+}
+
+/// Orders the vertices of each rank by coarsening the graph until it has at
+/// most `coarsest_size` vertices. Returns the order and its number of crossings.
+pub(super) fn ordering(
+    graph: &StableDiGraph<Vertex, Edge>,
+    cm_method: CMMethod,
+    sweeps: &mut Sweeps,
+    monitor: Monitor,
+    coarsest_size: usize,
+) -> Result<(Order, usize), Cancelled> {
+    let mut levels = Vec::<Level>::new();
+    loop {
+        let finer = levels.last().map_or(graph, |l| &l.graph);
+        if finer.node_count() <= coarsest_size {
+            break;
+        }
+        match coarsen(finer) {
+            Some(level) => levels.push(level),
+            None => break,
+        }
+    }
+    info!(target: "crossing_reduction",
+        "Coarsened graph with {} vertices in {} levels",
+        graph.node_count(),
+        levels.len()
+    );
+
+    let runs = levels.len() + 1;
+    let coarsest = levels.last().map_or(graph, |l| &l.graph);
+    let order = init_order(coarsest, sweeps.rng.as_mut(), sweeps.stable_ties);
+    let anchors = HashMap::new();
+    let (mut order, mut crossings) = reduce_crossings_bilayer_sweep(
+        coarsest,
+        order,
+        cm_method,
+        &anchors,
+        sweeps,
+        monitor,
+        (0, runs),
+    )?;
+
+    sweeps.limit = sweeps.limit.at_most(REFINEMENT_SWEEPS);
+    for i in (0..levels.len()).rev() {
+        let finer = if i == 0 { graph } else { &levels[i - 1].graph };
+        let expanded = order
+            .iter()
+            .map(|rank| {
+                rank.iter()
+                    .flat_map(|v| levels[i].members[v].iter().copied())
+                    .collect()
+            })
+            .collect();
+        (order, crossings) = reduce_crossings_bilayer_sweep(
+            finer,
+            Order::new(expanded),
+            cm_method,
+            &anchors,
+            sweeps,
+            monitor,
+            (levels.len() - i, runs),
+        )?;
+        debug!(target: "crossing_reduction", "Crossings after refining level {i}: {crossings}");
+    }
+    Ok((order, crossings))
+}
+
+/// Merges pairs of vertices in the same rank, which are both successors or
+/// both predecessors of a vertex. Returns `None` if the graph could only be
+/// reduced by less than 10%.
+fn coarsen(graph: &StableDiGraph<Vertex, Edge>) -> Option<Level> {
+    let mut coarse = StableDiGraph::<Vertex, Edge>::new();
+    let mut members = HashMap::new();
+    let mut merged_into = HashMap::new();
+
+    for u in graph.node_indices() {
+        for dir in [Outgoing, Incoming] {
+            let mut free = graph
+                .neighbors_directed(u, dir)
+                .filter(|n| !merged_into.contains_key(n))
+                .collect::<Vec<_>>();
+            free.sort_by_key(|n| (graph[*n].rank, *n));
+            free.dedup();
+            let mut i = 0;
+            while i + 1 < free.len() {
+                let (a, b) = (free[i], free[i + 1]);
+                if graph[a].rank != graph[b].rank {
+                    i += 1;
+                    continue;
+                }
+                let c = coarse.add_node(Vertex {
+                    rank: graph[a].rank,
+                    ..Default::default()
+                });
+                merged_into.insert(a, c);
+                merged_into.insert(b, c);
+                members.insert(c, vec![a, b]);
+                i += 2;
+            }
+        }
+    }
+    // all other vertices are kept as they are
+    for v in graph.node_indices() {
+        merged_into.entry(v).or_insert_with(|| {
+            let c = coarse.add_node(Vertex {
+                rank: graph[v].rank,
+                ..Default::default()
+            });
+            members.insert(c, vec![v]);
+            c
+        });
+    }
+    if coarse.node_count() * 10 > graph.node_count() * 9 {
+        return None;
+    }
+
+    let mut edges = HashSet::new();
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let edge = (merged_into[&tail], merged_into[&head]);
+        if edges.insert(edge) {
+            coarse.add_edge(edge.0, edge.1, Edge::default());
+        }
+    }
+    Some(Level {
+        graph: coarse,
+        members,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::NodeIndex;
+
+    use super::{coarsen, ordering};
+    use crate::algorithm::p2_reduce_crossings::{barycenter, tests::layered_graph, Order, Sweeps};
+    use crate::algorithm::Limit;
+    use crate::configure::PATIENCE_DEFAULT;
+    use crate::progress::Hooks;
+
+    #[test]
+    fn coarsen_merges_vertices_of_same_rank() {
+        let graph = layered_graph(4, 30, 11);
+        let level = coarsen(&graph).unwrap();
+        assert!(level.graph.node_count() < graph.node_count());
+        let mut members = level
+            .members
+            .values()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        members.sort();
+        assert_eq!(members, graph.node_indices().collect::<Vec<_>>());
+        for (c, members) in &level.members {
+            assert!(members
+                .iter()
+                .all(|v| graph[*v].rank == level.graph[*c].rank));
+        }
+    }
+
+    #[test]
+    fn multilevel_order_is_valid() {
+        let graph = layered_graph(6, 40, 11);
+        let mut sweeps = Sweeps {
+            transpose: Some(Limit::default()),
+            transpose_scan: None,
+            limit: Limit::default(),
+            patience: PATIENCE_DEFAULT,
+            stable_ties: false,
+            rng: None,
+            restarts: 0,
+            multilevel: true,
+            communities: false,
+            left_of: Default::default(),
+            groups: Default::default(),
+            clusters: Default::default(),
+            flat_edges: Vec::new(),
+        };
+        let hooks = Hooks::default();
+        let (order, crossings) =
+            ordering(&graph, barycenter, &mut sweeps, hooks.monitor(100.), 20).unwrap();
+        assert_eq!(
+            Order::new(order._inner.clone()).crossings(&graph),
+            crossings
+        );
+        for (r, rank) in order.iter().enumerate() {
+            assert_eq!(rank.len(), 40);
+            assert!(rank.iter().all(|v| graph[*v].rank == r as i32));
+        }
+        let mut vertices = order.iter().flatten().copied().collect::<Vec<_>>();
+        vertices.sort();
+        assert_eq!(vertices, (0..240).map(NodeIndex::new).collect::<Vec<_>>());
+    }
+}
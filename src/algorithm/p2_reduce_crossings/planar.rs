@@ -0,0 +1,380 @@
+//! Orders st-planar graphs without crossings, instead of reducing crossings
+//! with sweeps.
+//!
+//! A graph with a single source s and a single sink t has an upward planar
+//! drawing if and only if it is still planar once the edge (s, t) is added, as
+//! shown in "Algorithms for plane representations of acyclic digraphs" by Di
+//! Battista and Tamassia. Adding (s, t) also makes the graph biconnected, so
+//! planarity is tested by embedding it with the algorithm of Demoucron,
+//! Malgrange and Pertuiset.
+//!
+//! The faces of the embedding are ordered from left to right by the dual
+//! graph, which has an edge from the face on one side of each edge of the
+//! graph to the face on the other side, leaving out (s, t). The vertices of
+//! each rank are then ordered by the first face to the side of their outgoing
+//! edges, which gives a drawing without crossings if each edge connects
+//! neighboring ranks.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use log::{debug, info};
+use petgraph::stable_graph::StableDiGraph;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+
+use super::{Order, Sweeps};
+use crate::algorithm::{Edge, Vertex};
+
+/// A part of the graph which isn't embedded yet.
+///
+/// This is either a single edge between two embedded vertices, or a connected
+/// component of vertices which aren't embedded, together with the edges
+/// connecting it to the embedded vertices.
+struct Fragment {
+    /// The embedded vertices the fragment is connected to.
+    attachments: Vec<usize>,
+    /// The component of the fragment, `None` if it is a single edge.
+    component: Option<usize>,
+}
+
+/// Orders the vertices of each rank without crossings, if the graph is an
+/// st-planar graph in which each edge connects neighboring ranks.
+///
+/// Returns `None` otherwise, or if there are order constraints, groups,
+/// clusters, pinned vertices or a previous layout, which need the sweeps.
+pub(super) fn ordering(graph: &StableDiGraph<Vertex, Edge>, sweeps: &Sweeps) -> Option<Order> {
+    if !sweeps.left_of.is_empty()
+        || !sweeps.groups.is_empty()
+        || graph.node_weights().any(|w| {
+            w.cluster.is_some() || w.prev_x.is_some() || w.start_x.is_some() || w.pin.is_some()
+        })
+    {
+        return None;
+    }
+
+    let vertices = graph.node_indices().collect::<Vec<_>>();
+    let mut index = vec![usize::MAX; graph.node_bound()];
+    for (i, v) in vertices.iter().enumerate() {
+        index[v.index()] = i;
+    }
+    // parallel edges don't change the embedding
+    let mut outgoing = vec![Vec::new(); vertices.len()];
+    let mut is_source = vec![true; vertices.len()];
+    for edge in graph.edge_references() {
+        let (tail, head) = (edge.source(), edge.target());
+        if graph[head].rank != graph[tail].rank + 1 {
+            return None;
+        }
+        let (tail, head) = (index[tail.index()], index[head.index()]);
+        if !outgoing[tail].contains(&head) {
+            outgoing[tail].push(head);
+            is_source[head] = false;
+        }
+    }
+
+    let max_rank = graph.node_weights().map(|w| w.rank as usize).max()?;
+    let mut layers = vec![Vec::new(); max_rank + 1];
+    for v in &vertices {
+        layers[graph[*v].rank as usize].push(*v);
+    }
+    if layers.iter().all(|l| l.len() <= 1) {
+        return Some(Order::new(layers));
+    }
+
+    let mut sources = (0..vertices.len()).filter(|v| is_source[*v]);
+    let mut sinks = (0..vertices.len()).filter(|v| outgoing[*v].is_empty());
+    let (Some(source), None, Some(sink), None) =
+        (sources.next(), sources.next(), sinks.next(), sinks.next())
+    else {
+        debug!(target: "crossing_reduction", "Graph doesn't have a single source and sink");
+        return None;
+    };
+
+    // t is at least two ranks below s, since some rank has two vertices, so
+    // (s, t) isn't an edge of the graph yet
+    let mut adjacency = vec![Vec::new(); vertices.len()];
+    for (tail, heads) in outgoing.iter().enumerate() {
+        for head in heads {
+            adjacency[tail].push(*head);
+            adjacency[*head].push(tail);
+        }
+    }
+    adjacency[source].push(sink);
+    adjacency[sink].push(source);
+    let edges = adjacency.iter().map(Vec::len).sum::<usize>() / 2;
+    if edges > 3 * vertices.len() - 6 {
+        debug!(target: "crossing_reduction", "Graph has too many edges to be planar");
+        return None;
+    }
+
+    // a path from s to t, which is closed into a cycle by (s, t)
+    let mut cycle = vec![source];
+    while let Some(next) = outgoing[*cycle.last().unwrap()].first() {
+        cycle.push(*next);
+    }
+    let Some(faces) = embed(&adjacency, cycle) else {
+        debug!(target: "crossing_reduction", "Graph isn't st-planar");
+        return None;
+    };
+
+    let positions = order_faces(&faces, &outgoing)?;
+    for layer in &mut layers {
+        layer.sort_by_key(|v| {
+            let v = index[v.index()];
+            outgoing[v].first().map_or(0, |head| positions[&(v, *head)])
+        });
+    }
+    let order = Order::new(layers);
+    if order.crossings(graph) > 0 {
+        debug!(target: "crossing_reduction", "Order of st-planar graph has crossings");
+        return None;
+    }
+    info!(target: "crossing_reduction", "Graph is st-planar, skipping sweeps");
+    Some(order)
+}
+
+/// Returns the position of the face containing each edge of the graph when
+/// traversing it from tail to head, in a topological order of the dual graph.
+///
+/// Returns `None` if the dual graph contains a cycle, which doesn't happen for
+/// an embedding of an st-planar graph.
+fn order_faces(
+    faces: &[Vec<usize>],
+    outgoing: &[Vec<usize>],
+) -> Option<HashMap<(usize, usize), usize>> {
+    let mut face_of = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (i, v) in face.iter().enumerate() {
+            face_of.insert((*v, face[(i + 1) % face.len()]), f);
+        }
+    }
+
+    let mut dual = vec![Vec::new(); faces.len()];
+    let mut in_degree = vec![0; faces.len()];
+    for (tail, heads) in outgoing.iter().enumerate() {
+        for head in heads {
+            dual[face_of[&(tail, *head)]].push(face_of[&(*head, tail)]);
+            in_degree[face_of[&(*head, tail)]] += 1;
+        }
+    }
+    let mut queue = (0..faces.len())
+        .filter(|f| in_degree[*f] == 0)
+        .collect::<VecDeque<_>>();
+    let mut positions = vec![usize::MAX; faces.len()];
+    let mut position = 0;
+    while let Some(f) = queue.pop_front() {
+        positions[f] = position;
+        position += 1;
+        for g in &dual[f] {
+            in_degree[*g] -= 1;
+            if in_degree[*g] == 0 {
+                queue.push_back(*g);
+            }
+        }
+    }
+    if position < faces.len() {
+        debug!(target: "crossing_reduction", "Dual graph of the embedding has a cycle");
+        return None;
+    }
+
+    Some(
+        outgoing
+            .iter()
+            .enumerate()
+            .flat_map(|(tail, heads)| heads.iter().map(move |head| (tail, *head)))
+            .map(|edge| (edge, positions[face_of[&edge]]))
+            .collect(),
+    )
+}
+
+fn edge_key(u: usize, v: usize) -> (usize, usize) {
+    (u.min(v), u.max(v))
+}
+
+/// Embeds a biconnected graph into the plane, starting with `cycle`.
+///
+/// Returns the faces of the embedding as cycles of vertices, which traverse
+/// each edge once in each direction, or `None` if the graph isn't planar.
+fn embed(adjacency: &[Vec<usize>], cycle: Vec<usize>) -> Option<Vec<Vec<usize>>> {
+    let edges = adjacency.iter().map(Vec::len).sum::<usize>() / 2;
+    let mut embedded = vec![false; adjacency.len()];
+    let mut embedded_edges = HashSet::new();
+    for (i, v) in cycle.iter().enumerate() {
+        embedded[*v] = true;
+        embedded_edges.insert(edge_key(*v, cycle[(i + 1) % cycle.len()]));
+    }
+    let mut faces = vec![cycle.iter().rev().copied().collect::<Vec<_>>(), cycle];
+
+    while embedded_edges.len() < edges {
+        let (fragments, components) = fragments(adjacency, &embedded, &embedded_edges);
+        let mut faces_of = vec![Vec::new(); adjacency.len()];
+        for (f, face) in faces.iter().enumerate() {
+            for v in face {
+                faces_of[*v].push(f);
+            }
+        }
+
+        // a fragment which fits into a single face needs to go there
+        let mut chosen = None;
+        for fragment in &fragments {
+            let (first, rest) = fragment.attachments.split_first()?;
+            let mut admissible = faces_of[*first]
+                .iter()
+                .filter(|f| rest.iter().all(|a| faces_of[*a].contains(f)));
+            match (admissible.next(), admissible.next()) {
+                (None, _) => return None,
+                (Some(face), None) => {
+                    chosen = Some((fragment, *face));
+                    break;
+                }
+                (Some(face), Some(_)) => {
+                    chosen = chosen.or(Some((fragment, *face)));
+                }
+            }
+        }
+        let (fragment, face) = chosen?;
+
+        let path = path(adjacency, &embedded, &components, fragment);
+        for (i, v) in path.iter().enumerate() {
+            embedded[*v] = true;
+            if let Some(next) = path.get(i + 1) {
+                embedded_edges.insert(edge_key(*v, *next));
+            }
+        }
+        let (first, second) = split_face(&faces[face], &path);
+        faces[face] = first;
+        faces.push(second);
+    }
+    Some(faces)
+}
+
+/// Returns all fragments of the graph, together with the component of each
+/// vertex which isn't embedded.
+fn fragments(
+    adjacency: &[Vec<usize>],
+    embedded: &[bool],
+    embedded_edges: &HashSet<(usize, usize)>,
+) -> (Vec<Fragment>, Vec<Option<usize>>) {
+    let mut fragments = Vec::new();
+    for (u, neighbors) in adjacency.iter().enumerate() {
+        for v in neighbors {
+            if embedded[u] && embedded[*v] && u < *v && !embedded_edges.contains(&edge_key(u, *v)) {
+                fragments.push(Fragment {
+                    attachments: vec![u, *v],
+                    component: None,
+                });
+            }
+        }
+    }
+
+    let mut components = vec![None; adjacency.len()];
+    let mut component = 0;
+    for start in 0..adjacency.len() {
+        if embedded[start] || components[start].is_some() {
+            continue;
+        }
+        let mut attachments = Vec::new();
+        let mut stack = vec![start];
+        components[start] = Some(component);
+        while let Some(u) = stack.pop() {
+            for v in &adjacency[u] {
+                if embedded[*v] {
+                    attachments.push(*v);
+                } else if components[*v].is_none() {
+                    components[*v] = Some(component);
+                    stack.push(*v);
+                }
+            }
+        }
+        attachments.sort();
+        attachments.dedup();
+        fragments.push(Fragment {
+            attachments,
+            component: Some(component),
+        });
+        component += 1;
+    }
+    (fragments, components)
+}
+
+/// Returns a path through the fragment between two of its attachments.
+fn path(
+    adjacency: &[Vec<usize>],
+    embedded: &[bool],
+    components: &[Option<usize>],
+    fragment: &Fragment,
+) -> Vec<usize> {
+    let start = fragment.attachments[0];
+    let Some(component) = fragment.component else {
+        return fragment.attachments.clone();
+    };
+    let in_component = |v: &usize| components[*v] == Some(component);
+    let first = *adjacency[start].iter().find(|v| in_component(v)).unwrap();
+    let mut parents = HashMap::from([(first, start)]);
+    let mut queue = VecDeque::from([first]);
+    while let Some(u) = queue.pop_front() {
+        if let Some(end) = adjacency[u].iter().find(|v| embedded[**v] && **v != start) {
+            let mut path = vec![*end, u];
+            while let Some(parent) = parents.get(path.last().unwrap()) {
+                path.push(*parent);
+            }
+            path.reverse();
+            return path;
+        }
+        for v in adjacency[u].iter().filter(|v| in_component(v)) {
+            if !parents.contains_key(v) {
+                parents.insert(*v, u);
+                queue.push_back(*v);
+            }
+        }
+    }
+    unreachable!("fragments have at least two attachments")
+}
+
+/// Splits a face along a path between two of its vertices.
+fn split_face(face: &[usize], path: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let (start, end) = (path[0], path[path.len() - 1]);
+    let position = |v| face.iter().position(|u| *u == v).unwrap();
+    let (start_position, end_position) = (position(start), position(end));
+    let boundary = |from: usize, to: usize| {
+        let len = (to + face.len() - from) % face.len() + 1;
+        face.iter().cycle().skip(from).take(len).copied()
+    };
+    let inner = &path[1..path.len() - 1];
+    let first = boundary(start_position, end_position)
+        .chain(inner.iter().rev().copied())
+        .collect();
+    let second = boundary(end_position, start_position)
+        .chain(inner.iter().copied())
+        .collect();
+    (first, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::embed;
+
+    fn complete_graph(n: usize) -> Vec<Vec<usize>> {
+        (0..n)
+            .map(|u| (0..n).filter(|v| *v != u).collect())
+            .collect()
+    }
+
+    #[test]
+    fn embeds_k4() {
+        let faces = embed(&complete_graph(4), vec![0, 1, 2]).unwrap();
+        // euler's formula
+        assert_eq!(faces.len(), 6 - 4 + 2);
+        // each edge is traversed once in each direction
+        let mut darts = faces
+            .iter()
+            .flat_map(|f| (0..f.len()).map(|i| (f[i], f[(i + 1) % f.len()])))
+            .collect::<Vec<_>>();
+        darts.sort();
+        darts.dedup();
+        assert_eq!(darts.len(), 12);
+    }
+
+    #[test]
+    fn k5_is_not_planar() {
+        assert!(embed(&complete_graph(5), vec![0, 1, 2]).is_none());
+    }
+}
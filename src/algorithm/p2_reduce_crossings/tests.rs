@@ -1,6 +1,7 @@
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 
 use super::{Edge, Vertex};
+use crate::util::Rng;
 
 static ONE_DUMMY: [(u32, u32); 9] = [
     (0, 1),
@@ -151,6 +152,29 @@ impl GraphBuilder {
     }
 }
 
+/// Layered graph with `ranks` ranks of `width` vertices, where each vertex
+/// has two successors in the next rank, chosen randomly from `seed`.
+pub(super) fn layered_graph(ranks: usize, width: usize, seed: u64) -> StableDiGraph<Vertex, Edge> {
+    let mut rng = Rng::new(seed);
+    let mut graph = StableDiGraph::new();
+    let layers = (0..ranks)
+        .map(|r| {
+            (0..width)
+                .map(|_| graph.add_node(Vertex::new_with_rank(r as i32)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for r in 1..ranks {
+        for v in &layers[r - 1] {
+            for _ in 0..2 {
+                let w = layers[r][rng.below(width)];
+                graph.add_edge(*v, w, Edge::default());
+            }
+        }
+    }
+    graph
+}
+
 #[cfg(test)]
 mod insert_dummy_vertices {
 
@@ -165,6 +189,7 @@ mod insert_dummy_vertices {
             },
         },
         configure::Config,
+        progress::Hooks,
     };
 
     use super::{GraphBuilder, ONE_DUMMY_RANKS};
@@ -258,14 +283,19 @@ mod insert_dummy_vertices {
             e.0 -= 1;
             e.1 -= 1;
         }
-        let g = StableDiGraph::from_edges(&edges);
+        let g = StableDiGraph::from_edges(edges);
         let c = Config::default();
-        crate::algorithm::start(g, c);
+        crate::algorithm::start(g, c, &Default::default(), &Hooks::default()).unwrap();
     }
 }
 
 mod init_order {
-    use crate::algorithm::p2_reduce_crossings::insert_dummy_vertices;
+    use petgraph::stable_graph::StableDiGraph;
+
+    use crate::algorithm::p2_reduce_crossings::{
+        apply_previous_order, insert_dummy_vertices, Order,
+    };
+    use crate::algorithm::{Edge, Vertex};
 
     use super::{
         GraphBuilder, COMPLEX_EXAMPLE, COMPLEX_EXAMPLE_RANKS, ONE_DUMMY, ONE_DUMMY_RANKS,
@@ -312,12 +342,41 @@ mod init_order {
             }
         }
     }
+
+    #[test]
+    fn previous_order_is_restored() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::new();
+        let root = graph.add_node(Vertex::new_with_rank(0));
+        let mut children = Vec::new();
+        for prev_x in [Some(20), None, Some(0), Some(10)] {
+            let v = graph.add_node(Vertex {
+                prev_x,
+                ..Vertex::new_with_rank(1)
+            });
+            graph.add_edge(root, v, Edge::default());
+            children.push(v);
+        }
+        let mut order = Order::new(vec![vec![root], children.clone()]);
+        apply_previous_order(&graph, &mut order);
+        // the new vertex keeps its position, the others are sorted by x
+        assert_eq!(
+            order[1],
+            vec![children[2], children[1], children[3], children[0]]
+        );
+        assert_eq!(order.positions[&children[0]], 3);
+    }
 }
 
 // TODO: Add new tests for Order crosscount
 #[cfg(test)]
 mod order {
-    use crate::algorithm::{p2::order_layer, p2_reduce_crossings::Order, Edge, Vertex};
+    use std::collections::HashMap;
+
+    use crate::algorithm::{
+        p2::{order_layer, Sweeps},
+        p2_reduce_crossings::Order,
+        Edge, Port, Vertex,
+    };
     use petgraph::stable_graph::StableDiGraph;
 
     #[test]
@@ -337,6 +396,39 @@ mod order {
         assert_eq!(order.bilayer_cross_count(&graph, 0), 2);
     }
 
+    #[test]
+    fn port_crossings() {
+        let mut graph = StableDiGraph::new();
+        let n0 = graph.add_node(Vertex::new_with_rank(0));
+        let s0 = graph.add_node(Vertex::new_with_rank(1));
+        let s1 = graph.add_node(Vertex::new_with_rank(1));
+
+        let port = |index, offset| Some(Port { index, offset });
+        graph.add_edge(
+            n0,
+            s0,
+            Edge {
+                ports: (port(1, 5), None),
+                ..Default::default()
+            },
+        );
+        graph.add_edge(
+            n0,
+            s1,
+            Edge {
+                ports: (port(0, -5), None),
+                ..Default::default()
+            },
+        );
+
+        let order = Order::new(vec![vec![n0], vec![s0, s1]]);
+        assert_eq!(order.bilayer_cross_count(&graph, 0), 1);
+        assert_eq!(order.cross_count_two_vertices(s0, s1, &graph), 1);
+        assert_eq!(order.cross_count_two_vertices(s1, s0, &graph), 0);
+        let order = Order::new(vec![vec![n0], vec![s1, s0]]);
+        assert_eq!(order.bilayer_cross_count(&graph, 0), 0);
+    }
+
     #[test]
     fn four_crossings() {
         let mut graph = StableDiGraph::new();
@@ -427,6 +519,8 @@ mod order {
             false,
             &order,
             crate::algorithm::p2_reduce_crossings::barycenter,
+            &HashMap::new(),
+            &mut Sweeps::default(),
         );
         assert_eq!(
             expected_order._inner[0],
@@ -434,3 +528,215 @@ mod order {
         );
     }
 }
+
+mod restarts {
+    use crate::algorithm::p2_reduce_crossings::{
+        insert_dummy_vertices, ordering, LeftOf, Order, Sweeps,
+    };
+    use crate::algorithm::Limit;
+    use crate::configure::{CrossingMinimization, PATIENCE_DEFAULT};
+    use crate::progress::Hooks;
+
+    use super::{GraphBuilder, COMPLEX_EXAMPLE, COMPLEX_EXAMPLE_RANKS};
+
+    fn crossings(restarts: usize, seed: Option<u64>) -> usize {
+        let (mut graph, minimum_length) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph, minimum_length);
+        let sweeps = Sweeps {
+            transpose: Some(Limit::default()),
+            transpose_scan: None,
+            limit: Limit::default(),
+            patience: PATIENCE_DEFAULT,
+            stable_ties: false,
+            rng: seed.map(crate::util::Rng::new),
+            restarts,
+            multilevel: false,
+            communities: false,
+            left_of: LeftOf::default(),
+            groups: Default::default(),
+            clusters: Default::default(),
+            flat_edges: Vec::new(),
+        };
+        let hooks = Hooks::default();
+        let (order, crossings) = ordering(
+            &mut graph,
+            CrossingMinimization::Barycenter,
+            sweeps,
+            hooks.monitor(100.),
+        )
+        .unwrap();
+        assert_eq!(Order::new(order).crossings(&graph), crossings);
+        crossings
+    }
+
+    #[test]
+    fn restarts_never_increase_crossings() {
+        assert!(crossings(8, None) <= crossings(0, None));
+        assert!(crossings(8, Some(5)) <= crossings(0, Some(5)));
+    }
+}
+
+mod planar {
+    use crate::algorithm::p2_reduce_crossings::{insert_dummy_vertices, planar, Sweeps};
+
+    use super::GraphBuilder;
+
+    #[test]
+    fn st_planar_graphs_are_ordered_without_crossings() {
+        // a ladder, which isn't a tree
+        let edges = [(0, 1), (0, 2), (1, 3), (2, 4), (1, 4), (3, 5), (4, 5)];
+        let ranks = [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2), (5, 3)];
+        let (mut graph, minimum_length) =
+            GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        insert_dummy_vertices(&mut graph, minimum_length);
+        let order = planar::ordering(&graph, &Sweeps::default()).unwrap();
+        assert_eq!(order.crossings(&graph), 0);
+    }
+
+    #[test]
+    fn grids_are_ordered_without_crossings() {
+        // edges point right and down, ranked by their distance to the corner
+        let size = 6;
+        let mut edges = Vec::new();
+        let mut ranks = Vec::new();
+        for row in 0..size {
+            for column in 0..size {
+                let v = row * size + column;
+                ranks.push((v, row + column));
+                if column + 1 < size {
+                    edges.push((v, v + 1));
+                }
+                if row + 1 < size {
+                    edges.push((v, v + size));
+                }
+            }
+        }
+        let (graph, _) = GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        let order = planar::ordering(&graph, &Sweeps::default()).unwrap();
+        assert_eq!(order.crossings(&graph), 0);
+    }
+
+    #[test]
+    fn graphs_containing_k33_are_swept() {
+        // a source above and a sink below a complete bipartite graph between
+        // two ranks of three vertices
+        let mut edges = vec![(0, 1), (0, 2), (0, 3), (4, 7), (5, 7), (6, 7)];
+        let mut ranks = vec![(0, 0), (7, 3)];
+        for top in 1..4 {
+            ranks.push((top, 1));
+            ranks.push((top + 3, 2));
+            for bottom in 4..7 {
+                edges.push((top, bottom));
+            }
+        }
+        let (graph, _) = GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        assert!(planar::ordering(&graph, &Sweeps::default()).is_none());
+    }
+
+    #[test]
+    fn graphs_with_several_sources_are_swept() {
+        let edges = [(0, 2), (1, 2), (0, 3), (1, 3), (2, 4), (3, 4)];
+        let ranks = [(0, 0), (1, 0), (2, 1), (3, 1), (4, 2)];
+        let (graph, _) = GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        assert!(planar::ordering(&graph, &Sweeps::default()).is_none());
+    }
+}
+
+mod left_of {
+    use crate::algorithm::p2_reduce_crossings::{insert_dummy_vertices, ordering, LeftOf, Sweeps};
+    use crate::algorithm::Limit;
+    use crate::configure::{CrossingMinimization, PATIENCE_DEFAULT};
+    use crate::progress::Hooks;
+
+    use super::{GraphBuilder, COMPLEX_EXAMPLE, COMPLEX_EXAMPLE_RANKS};
+
+    #[test]
+    fn constraints_are_satisfied() {
+        let (mut graph, minimum_length) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph, minimum_length);
+        // pairs of vertices in the same rank, in reversed order of their index
+        let pairs = graph
+            .node_indices()
+            .flat_map(|v| graph.node_indices().map(move |w| (v, w)))
+            .filter(|(v, w)| v > w && graph[*v].rank == graph[*w].rank)
+            .step_by(3)
+            .collect::<Vec<_>>();
+        for (crossing_minimization, seed) in [
+            (CrossingMinimization::Barycenter, None),
+            (CrossingMinimization::Median, Some(3)),
+        ] {
+            let sweeps = Sweeps {
+                transpose: Some(Limit::default()),
+                transpose_scan: None,
+                limit: Limit::default(),
+                patience: PATIENCE_DEFAULT,
+                stable_ties: false,
+                rng: seed.map(crate::util::Rng::new),
+                restarts: 2,
+                multilevel: false,
+                communities: false,
+                left_of: LeftOf::new(&graph, &pairs),
+                groups: Default::default(),
+                clusters: Default::default(),
+                flat_edges: Vec::new(),
+            };
+            let hooks = Hooks::default();
+            let (order, _) = ordering(
+                &mut graph,
+                crossing_minimization,
+                sweeps,
+                hooks.monitor(100.),
+            )
+            .unwrap();
+            for (v, w) in &pairs {
+                let rank = &order[graph[*v].rank as usize];
+                let pos = |v| rank.iter().position(|n| *n == v).unwrap();
+                assert!(pos(*v) < pos(*w), "{v:?} is not left of {w:?}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+mod transpose_parallel {
+    use crate::algorithm::p2_reduce_crossings::{
+        init_order, insert_dummy_vertices, transpose_parallel, Groups, LeftOf,
+    };
+    use crate::algorithm::Limit;
+    use crate::util::Rng;
+
+    use super::{GraphBuilder, COMPLEX_EXAMPLE, COMPLEX_EXAMPLE_RANKS};
+
+    #[test]
+    fn transpose_parallel_never_increases_crossings() {
+        let (mut graph, minimum_length) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph, minimum_length);
+        for seed in 0..8 {
+            let mut order = init_order(&graph, Some(&mut Rng::new(seed)), false);
+            let before = order.crossings(&graph);
+            // odd seeds shuffle the order in which pairs are visited
+            let mut scan = (seed % 2 == 1).then(|| Rng::new(seed));
+            transpose_parallel(
+                &graph,
+                &mut order,
+                Limit::default(),
+                &LeftOf::default(),
+                &Groups::default(),
+                None,
+                scan.as_mut(),
+            );
+            assert!(order.crossings(&graph) <= before);
+            for rank in order.iter() {
+                for (pos, v) in rank.iter().enumerate() {
+                    assert_eq!(order.positions[v], pos);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Keeps all vertices which are not part of a cluster out of its bounding box.
+//!
+//! The bounding box of a cluster contains all of its members and nested
+//! clusters, extended by its padding and the space reserved for its label.
+//! Other vertices and the boxes of other clusters keep a distance of half the
+//! vertex spacing to the box in every rank the cluster spans. Since crossing
+//! reduction keeps the members of each cluster next to each other and the
+//! clusters in the same order in every rank, these constraints can always be
+//! satisfied, and vertices are moved right as little as necessary.
+//!
+//! Lanes are separated by borders, which keep a distance of half the vertex
+//! spacing to all vertices of the lanes next to them. Boxes of clusters whose
+//! members are all in the same lane are kept inside of the lane.
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use log::{info, warn};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::algorithm::{cluster_path, Cluster, Edge, Vertex};
+use crate::Lane;
+
+use super::{separation, Spacing};
+
+/// Bounding box of a cluster, given by x-coordinates and ranks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ClusterBounds {
+    pub(crate) id: usize,
+    pub(crate) left: isize,
+    pub(crate) right: isize,
+    pub(crate) top: i32,
+    pub(crate) bottom: i32,
+    /// Distance between the topmost rank and the top of the box
+    pub(crate) above: isize,
+    /// Distance between the bottommost rank and the bottom of the box
+    pub(crate) below: isize,
+}
+
+/// Vertices and borders of cluster boxes, which are placed relative to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Item {
+    Vertex(NodeIndex),
+    Left(usize),
+    Right(usize),
+    /// Border left of the lane with the given index, in the order of the lanes.
+    Border(usize),
+}
+
+/// Members of a cluster which are not part of a nested cluster, all members
+/// including those of nested clusters, and the parent of the cluster.
+#[derive(Default)]
+struct Content {
+    direct: Vec<NodeIndex>,
+    all: Vec<NodeIndex>,
+    parent: Option<usize>,
+}
+
+/// Moves vertices right until no vertex is inside the bounding box of a
+/// cluster it doesn't belong to and all lanes are separated, and returns the
+/// bounds of all clusters and lanes. Clusters without a padding of their own
+/// use `default_padding`.
+pub(crate) fn separate_clusters(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+    clusters: &HashMap<usize, Cluster>,
+    default_padding: isize,
+) -> (Vec<ClusterBounds>, Vec<Lane>) {
+    let lanes = layers
+        .iter()
+        .flatten()
+        .filter_map(|v| graph[*v].lane)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    if lanes.is_empty() && layers.iter().flatten().all(|v| graph[*v].cluster.is_none()) {
+        return (Vec::new(), Vec::new());
+    }
+    let lane_of = |v: NodeIndex| {
+        graph[v]
+            .lane
+            .map(|lane| lanes.binary_search(&lane).unwrap())
+    };
+    // placeholders only keep other vertices out of the ranks they fill, their
+    // position is irrelevant
+    let is_placeholder =
+        |v: NodeIndex| graph[v].is_dummy && graph.neighbors_undirected(v).next().is_none();
+    let paths = layers
+        .iter()
+        .flatten()
+        .map(|v| (*v, cluster_path(clusters, graph[*v].cluster)))
+        .collect::<HashMap<_, _>>();
+    // keyed by depth first, so that nested clusters come before their parents
+    let mut content = BTreeMap::<(usize, usize), Content>::new();
+    for v in layers.iter().flatten() {
+        let path = &paths[v];
+        for (depth, cluster) in path.iter().enumerate() {
+            let entry = content.entry((usize::MAX - depth, *cluster)).or_default();
+            entry.parent = depth.checked_sub(1).map(|d| path[d]);
+            if !is_placeholder(*v) {
+                entry.all.push(*v);
+                if depth + 1 == path.len() {
+                    entry.direct.push(*v);
+                }
+            }
+        }
+    }
+    info!(target: "coordinate_calculation", "Moving vertices out of the boxes of {} clusters", content.len());
+    let padding = |cluster: usize| {
+        clusters
+            .get(&cluster)
+            .and_then(|c| c.padding)
+            .map_or(default_padding, |p| p as isize)
+    };
+    let label = |cluster: usize| {
+        clusters
+            .get(&cluster)
+            .map_or((0, 0), |c| (c.label.0 as isize, c.label.1 as isize))
+    };
+
+    // half of the width and height of a vertex
+    let half = |v: &NodeIndex| {
+        let (width, height) = graph[*v].extent();
+        ((width + 1) / 2, (height + 1) / 2)
+    };
+    let vertex_spacing = spacing.vertex();
+    let mut placed = x_coordinates
+        .iter()
+        .map(|(v, x)| (Item::Vertex(*v), *x))
+        .collect::<HashMap<_, _>>();
+    let mut successors = HashMap::<Item, Vec<(Item, isize)>>::new();
+    let mut add = |from: Item, to: Item, gap: isize| {
+        successors.entry(from).or_default().push((to, gap));
+    };
+    for (&(_, cluster), content) in &content {
+        let x = content.all.iter().map(|v| x_coordinates[v] - half(v).0);
+        let min = x.min().unwrap_or(0);
+        let x = content.all.iter().map(|v| x_coordinates[v] + half(v).0);
+        let max = x.max().unwrap_or(0);
+        placed.insert(Item::Left(cluster), min - padding(cluster));
+        placed.insert(Item::Right(cluster), max + padding(cluster));
+        for v in &content.direct {
+            let gap = padding(cluster) + half(v).0;
+            add(Item::Left(cluster), Item::Vertex(*v), gap);
+            add(Item::Vertex(*v), Item::Right(cluster), gap);
+        }
+        add(Item::Left(cluster), Item::Right(cluster), label(cluster).0);
+        if let Some(parent) = content.parent {
+            add(Item::Left(parent), Item::Left(cluster), padding(parent));
+            add(Item::Right(cluster), Item::Right(parent), padding(parent));
+        }
+        let cluster_lanes = content
+            .all
+            .iter()
+            .filter_map(|v| lane_of(*v))
+            .collect::<BTreeSet<_>>();
+        if let [lane] = cluster_lanes.into_iter().collect::<Vec<_>>()[..] {
+            add(Item::Border(lane), Item::Left(cluster), vertex_spacing / 2);
+            add(
+                Item::Right(cluster),
+                Item::Border(lane + 1),
+                vertex_spacing / 2,
+            );
+        }
+    }
+    if !lanes.is_empty() {
+        let vertices = layers.iter().flatten().filter(|v| lane_of(**v).is_some());
+        let x = vertices.clone().map(|v| x_coordinates[v] - half(v).0);
+        let min = x.min().unwrap();
+        for border in 0..=lanes.len() {
+            placed.insert(Item::Border(border), min - vertex_spacing / 2);
+        }
+        for v in vertices {
+            let lane = lane_of(*v).unwrap();
+            let gap = vertex_spacing / 2 + half(v).0;
+            add(Item::Border(lane), Item::Vertex(*v), gap);
+            add(Item::Vertex(*v), Item::Border(lane + 1), gap);
+        }
+    }
+    for layer in layers {
+        for w in layer.windows(2) {
+            let (left, right) = (&paths[&w[0]], &paths[&w[1]]);
+            let common = left.iter().zip(right).take_while(|(l, r)| l == r).count();
+            if common == left.len() && common == right.len() {
+                // keep the original distance if it was smaller, e.g. for dummy vertices
+                let separation = separation(graph, w[0], w[1], spacing);
+                let gap = (x_coordinates[&w[1]] - x_coordinates[&w[0]]).clamp(0, separation);
+                add(Item::Vertex(w[0]), Item::Vertex(w[1]), gap);
+            } else {
+                let (from, to) = (left.get(common), right.get(common));
+                let gap = vertex_spacing / 2
+                    + from.map_or(half(&w[0]).0, |_| 0)
+                    + to.map_or(half(&w[1]).0, |_| 0);
+                add(
+                    from.map_or(Item::Vertex(w[0]), |c| Item::Right(*c)),
+                    to.map_or(Item::Vertex(w[1]), |c| Item::Left(*c)),
+                    gap,
+                );
+            }
+        }
+    }
+
+    // move each item right of its predecessors, in topological order
+    let mut predecessors = placed.keys().map(|i| (*i, 0)).collect::<HashMap<_, _>>();
+    for (successor, _) in successors.values().flatten() {
+        *predecessors.get_mut(successor).unwrap() += 1;
+    }
+    let mut stack = predecessors
+        .iter()
+        .filter(|(_, n)| **n == 0)
+        .map(|(i, _)| *i)
+        .collect::<Vec<_>>();
+    let mut visited = 0;
+    while let Some(item) = stack.pop() {
+        visited += 1;
+        for (successor, gap) in successors.get(&item).map_or(&[][..], Vec::as_slice) {
+            let min = placed[&item] + gap;
+            let x = placed.get_mut(successor).unwrap();
+            *x = (*x).max(min);
+            let n = predecessors.get_mut(successor).unwrap();
+            *n -= 1;
+            if *n == 0 {
+                stack.push(*successor);
+            }
+        }
+    }
+    if visited == placed.len() {
+        for (v, x) in x_coordinates.iter_mut() {
+            *x = placed[&Item::Vertex(*v)];
+        }
+    } else {
+        warn!(target: "coordinate_calculation", "Clusters are not in the same order in all ranks, they may overlap");
+        placed.clear();
+    }
+
+    // fit the boxes to their content, nested clusters first
+    let mut bounds = HashMap::<usize, ClusterBounds>::new();
+    for (&(_, cluster), content_of) in &content {
+        let nested = content
+            .iter()
+            .filter(|(_, c)| c.parent == Some(cluster))
+            .filter_map(|((_, child), _)| bounds.get(child).copied())
+            .collect::<Vec<_>>();
+        let Some(mut b) = content_of
+            .direct
+            .iter()
+            .map(|v| ClusterBounds {
+                id: cluster,
+                left: x_coordinates[v] - half(v).0,
+                right: x_coordinates[v] + half(v).0,
+                top: graph[*v].rank,
+                bottom: graph[*v].rank,
+                above: half(v).1,
+                below: half(v).1,
+            })
+            .chain(nested)
+            .reduce(|a, b| ClusterBounds {
+                id: cluster,
+                left: a.left.min(b.left),
+                right: a.right.max(b.right),
+                top: a.top.min(b.top),
+                bottom: a.bottom.max(b.bottom),
+                above: match a.top.cmp(&b.top) {
+                    Ordering::Less => a.above,
+                    Ordering::Equal => a.above.max(b.above),
+                    Ordering::Greater => b.above,
+                },
+                below: match a.bottom.cmp(&b.bottom) {
+                    Ordering::Less => b.below,
+                    Ordering::Equal => a.below.max(b.below),
+                    Ordering::Greater => a.below,
+                },
+            })
+        else {
+            continue;
+        };
+        let padding = padding(cluster);
+        let (width, height) = label(cluster);
+        b.id = cluster;
+        b.left -= padding;
+        b.right += padding;
+        b.above += padding + height;
+        b.below += padding;
+        // widen the box for the label, within the space reserved for it
+        if let (Some(left), Some(right)) = (
+            placed.get(&Item::Left(cluster)),
+            placed.get(&Item::Right(cluster)),
+        ) {
+            b.right = b.right.max((b.left + width).min(*right));
+            b.left = b.left.min(b.right - width).max(*left);
+        }
+        bounds.insert(cluster, b);
+    }
+    let mut bounds = bounds.into_values().collect::<Vec<_>>();
+    bounds.sort_by_key(|b| b.id);
+
+    let lanes = lanes
+        .iter()
+        .enumerate()
+        .map(|(i, id)| match placed.get(&Item::Border(i)) {
+            Some(min) => Lane {
+                id: *id,
+                min: *min,
+                max: placed[&Item::Border(i + 1)],
+            },
+            None => {
+                let vertices = layers.iter().flatten().filter(|v| lane_of(**v) == Some(i));
+                let left = vertices.clone().map(|v| x_coordinates[v] - half(v).0);
+                let right = vertices.map(|v| x_coordinates[v] + half(v).0);
+                Lane {
+                    id: *id,
+                    min: left.min().unwrap() - vertex_spacing / 2,
+                    max: right.max().unwrap() + vertex_spacing / 2,
+                }
+            }
+        })
+        .collect();
+    (bounds, lanes)
+}
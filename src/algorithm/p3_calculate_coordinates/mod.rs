@@ -1,26 +1,82 @@
+mod clusters;
 #[cfg(test)]
 mod tests;
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 
-use log::info;
+use log::{debug, info, warn};
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 use petgraph::visit::EdgeRef;
-use petgraph::Direction::Incoming;
+use petgraph::Direction::{Incoming, Outgoing};
+
+use crate::configure::Alignment;
 
 use super::{slack, Edge, Vertex};
 
+pub(super) use clusters::{separate_clusters, ClusterBounds};
+
+/// Space kept free between neighbors in a rank, of which each of them takes
+/// half: the vertex spacing, or the share of it given by the dummy size for
+/// dummy vertices.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Spacing {
+    vertex: isize,
+    dummy: isize,
+}
+
+impl Spacing {
+    pub(super) fn new(vertex_spacing: usize, dummy_size: f64) -> Self {
+        let vertex = vertex_spacing as isize;
+        Self {
+            vertex,
+            dummy: (vertex as f64 * dummy_size.clamp(0., 1.)).round() as isize,
+        }
+    }
+
+    /// Spacing between vertices which are no dummy vertices.
+    pub(super) fn vertex(&self) -> isize {
+        self.vertex
+    }
+
+    fn of(&self, v: &Vertex) -> isize {
+        if v.is_dummy {
+            self.dummy
+        } else {
+            self.vertex
+        }
+    }
+}
+
+/// Returns the minimum distance between the x-coordinates of `left` and
+/// `right`, if both are next to each other in a rank, given by their widths
+/// and the spacing each of them takes.
+fn separation(
+    graph: &StableDiGraph<Vertex, Edge>,
+    left: NodeIndex,
+    right: NodeIndex,
+    spacing: Spacing,
+) -> isize {
+    let (left, right) = (&graph[left], &graph[right]);
+    (spacing.of(left) + spacing.of(right)) / 2 + (left.extent().0 + right.extent().0 + 1) / 2
+}
+
 pub(super) fn create_layouts(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &mut [Vec<NodeIndex>],
-    vertex_spacing: usize,
-    dummy_size: f64,
+    columns: &Columns,
+    spacing: Spacing,
 ) -> Vec<HashMap<NodeIndex, isize>> {
     info!(target: "coordinate_calculation", "Creating individual layouts for coordinate calculation");
     let mut layouts = Vec::new();
     mark_type_1_conflicts(graph, layers);
     // calculate the coordinates for each direction
     for _v_dir in [VDir::Down, VDir::Up] {
+        // the graph is reversed for the upward direction
+        let upper = match _v_dir {
+            VDir::Down => &columns.upper,
+            VDir::Up => &columns.lower,
+        };
         for h_dir in [HDir::Right, HDir::Left] {
             // reset root, align and sink values
             info!(target: "coordinate_calculation",
@@ -29,8 +85,8 @@ pub(super) fn create_layouts(
                 h_dir);
 
             reset_alignment(graph, layers);
-            create_vertical_alignments(graph, layers);
-            let mut layout = do_horizontal_compaction(graph, layers, vertex_spacing, dummy_size);
+            create_vertical_alignments(graph, layers, upper);
+            let mut layout = do_horizontal_compaction(graph, layers, spacing);
             // flip x_coordinates if we went from right to left
             if let HDir::Left = h_dir {
                 layout.values_mut().for_each(|x| *x = -*x);
@@ -52,14 +108,37 @@ pub(super) fn create_layouts(
     layouts
 }
 
-pub(crate) fn align_to_smallest_width_layout(aligned_layouts: &mut [HashMap<NodeIndex, isize>]) {
+/// Takes the layout of a single alignment out of the layouts created by
+/// [create_layouts], or returns `None` if all of them are balanced.
+pub(super) fn take_single_layout(
+    layouts: &mut Vec<HashMap<NodeIndex, isize>>,
+    alignment: Alignment,
+) -> Option<HashMap<NodeIndex, isize>> {
+    // layouts are created downwards first, aligning with upper neighbors,
+    // and from left to right first
+    let index = match alignment {
+        Alignment::Balanced => return None,
+        Alignment::UpLeft => 0,
+        Alignment::UpRight => 1,
+        Alignment::DownLeft => 2,
+        Alignment::DownRight => 3,
+    };
+    info!(target: "coordinate_calculation", "Using the layout of alignment {alignment:?}");
+    Some(layouts.swap_remove(index))
+}
+
+pub(crate) fn align_to_smallest_width_layout(
+    graph: &StableDiGraph<Vertex, Edge>,
+    aligned_layouts: &mut [HashMap<NodeIndex, isize>],
+) {
     info!(target: "coordinate_calculation", "Aligning all layouts to the one with the smallest width");
-    // determine minimum and maximum coordinate of each layout, plus the width
+    // determine the leftmost and rightmost border of each layout, plus the width
     let min_max: Vec<(isize, isize, isize)> = aligned_layouts
         .iter()
         .map(|c| {
-            let min = *c.values().min().unwrap();
-            let max = *c.values().max().unwrap();
+            let width = |v: &NodeIndex| graph[*v].extent().0;
+            let min = c.iter().map(|(v, x)| x - width(v) / 2).min().unwrap();
+            let max = c.iter().map(|(v, x)| x + (width(v) + 1) / 2).max().unwrap();
             (min, max, max - min)
         })
         .collect();
@@ -95,7 +174,7 @@ pub(crate) fn calculate_relative_coords(
     // sort all 4 coordinates per vertex in ascending order
     for l in &aligned_layouts {
         let mut v = l.iter().collect::<Vec<_>>();
-        v.sort_by(|a, b| a.0.index().cmp(&b.0.index()));
+        v.sort_by_key(|a| a.0.index());
         // format to NodeIndex: (x, y), width, height
         // println!("{v:?}\n");
     }
@@ -112,13 +191,403 @@ pub(crate) fn calculate_relative_coords(
     }
 
     // create final layout, by averaging the two median values
-    // try to use something like mean
+    // try to use something like mean, rounding down so negative coordinates
+    // keep the separation of their neighbors
     sorted_layouts
         .into_iter()
-        .map(|(k, v)| (*k, (v[0] + v[1] + v[2] + v[3]) / 4))
+        .map(|(k, v)| (*k, (v[0] + v[1] + v[2] + v[3]).div_euclid(4)))
         .collect::<Vec<_>>()
 }
 
+/// Removes horizontal space which no rank needs.
+///
+/// The distinct x-coordinates of all vertices are sorted, and each gap between
+/// two consecutive ones is shrunk by as much as the neighbors in each rank,
+/// whose x-coordinates enclose the gap, can move closer together without
+/// getting closer than their separation. No x-coordinate moves past another
+/// one, so vertices which are aligned stay aligned, and the layout never gets
+/// wider.
+pub(super) fn compact(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+) {
+    info!(target: "coordinate_calculation", "Compacting the layout horizontally");
+    let mut xs = x_coordinates.values().copied().collect::<Vec<_>>();
+    xs.sort();
+    xs.dedup();
+    if xs.len() < 2 {
+        return;
+    }
+    let index = |x: isize| xs.binary_search(&x).unwrap();
+    // slack of neighbors, by the index of the gap they start at
+    let mut starts = vec![Vec::new(); xs.len()];
+    for layer in layers {
+        for w in layer.windows(2) {
+            let (u, v) = (x_coordinates[&w[0]], x_coordinates[&w[1]]);
+            if u < v {
+                let slack = v - u - separation(graph, w[0], w[1], spacing);
+                starts[index(u)].push((slack.max(0), index(v)));
+            }
+        }
+    }
+    // slack is stored increased by the space removed so far when it is
+    // added, so all enclosing neighbors use up the removed space together
+    let mut enclosing = BinaryHeap::new();
+    let mut removed = 0;
+    let mut shifts = vec![0; xs.len()];
+    for k in 0..xs.len() - 1 {
+        for (slack, end) in &starts[k] {
+            enclosing.push(Reverse((slack + removed, *end)));
+        }
+        while enclosing.peek().is_some_and(|Reverse((_, end))| *end <= k) {
+            enclosing.pop();
+        }
+        let gap = xs[k + 1] - xs[k];
+        let available = enclosing
+            .peek()
+            .map_or(gap, |Reverse((slack, _))| (slack - removed).min(gap));
+        removed += available;
+        shifts[k + 1] = removed;
+    }
+    if removed > 0 {
+        info!(target: "coordinate_calculation", "Removed {removed} units of horizontal space");
+    }
+    for x in x_coordinates.values_mut() {
+        *x -= shifts[index(*x)];
+    }
+}
+
+/// Moves each vertex towards the average x-coordinate of its children.
+///
+/// Ranks are processed from the bottom up, so parents follow their children
+/// after these were moved. Vertices are moved as close as their neighbors
+/// allow, without changing the order of a rank or decreasing the distance
+/// between neighbors below their separation.
+pub(super) fn center_parents(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+) {
+    info!(target: "coordinate_calculation", "Moving parents towards the center of their children");
+    for layer in layers.iter().rev() {
+        for (i, v) in layer.iter().enumerate() {
+            let children = graph
+                .neighbors_directed(*v, Outgoing)
+                .filter(|c| graph[*c].rank > graph[*v].rank)
+                .map(|c| x_coordinates[&c])
+                .collect::<Vec<_>>();
+            if children.is_empty() {
+                continue;
+            }
+            let center = children
+                .iter()
+                .sum::<isize>()
+                .div_euclid(children.len() as isize);
+            let x = x_coordinates[v];
+            // keep the original distance if it was already smaller, e.g. for dummy vertices
+            let lower = match i {
+                0 => isize::MIN,
+                _ => {
+                    let u = x_coordinates[&layer[i - 1]];
+                    u + separation(graph, layer[i - 1], *v, spacing).min(x - u)
+                }
+            };
+            let upper = match layer.get(i + 1) {
+                None => isize::MAX,
+                Some(w) => {
+                    let separation = separation(graph, *v, *w, spacing);
+                    let w = x_coordinates[w];
+                    w - separation.min(w - x)
+                }
+            };
+            x_coordinates.insert(*v, center.min(upper).max(lower));
+        }
+    }
+}
+
+/// Maximum number of passes of [minimize_bends] over all edges.
+const MAX_BEND_PASSES: usize = 4;
+
+/// Moves dummy vertices onto the straight line between the endpoints of their
+/// edge, so the edge doesn't bend at them.
+///
+/// The x-coordinate of each dummy vertex on the line is interpolated by its
+/// rank. Dummy vertices are moved as close to it as their neighbors allow,
+/// without changing the order of a rank or decreasing the distance between
+/// neighbors below their separation. Moving one dummy vertex may make room
+/// for another one, so all edges are straightened again, until no dummy
+/// vertex moves or [MAX_BEND_PASSES] passes are done.
+pub(super) fn minimize_bends(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+) {
+    // dummy vertices of each edge, together with the endpoints of the edge
+    let mut chains = Vec::new();
+    for tail in graph.node_indices().filter(|v| !graph[*v].is_dummy) {
+        for mut next in graph.neighbors_directed(tail, Outgoing) {
+            let mut chain = vec![tail];
+            while graph[next].is_dummy {
+                chain.push(next);
+                match graph.neighbors_directed(next, Outgoing).next() {
+                    Some(n) => next = n,
+                    None => break,
+                }
+            }
+            if chain.len() > 1 && !graph[next].is_dummy {
+                chain.push(next);
+                chains.push(chain);
+            }
+        }
+    }
+    if chains.is_empty() {
+        return;
+    }
+    info!(target: "coordinate_calculation", "Moving dummy vertices of {} edges to remove bends", chains.len());
+    let positions = layers
+        .iter()
+        .flat_map(|l| l.iter().enumerate().map(|(pos, v)| (*v, pos)))
+        .collect::<HashMap<_, _>>();
+    for pass in 0..MAX_BEND_PASSES {
+        let mut moved = 0;
+        for chain in &chains {
+            let (tail, head) = (chain[0], chain[chain.len() - 1]);
+            let (x_tail, x_head) = (x_coordinates[&tail], x_coordinates[&head]);
+            let (r_tail, r_head) = (graph[tail].rank as isize, graph[head].rank as isize);
+            for v in &chain[1..chain.len() - 1] {
+                // round half up, like the rest of the layout
+                let (num, den) = (
+                    (x_head - x_tail) * (graph[*v].rank as isize - r_tail),
+                    r_head - r_tail,
+                );
+                let target = x_tail + (2 * num + den).div_euclid(2 * den);
+                let layer = &layers[graph[*v].rank as usize];
+                let i = positions[v];
+                let x = x_coordinates[v];
+                // keep the original distance if it was already smaller
+                let lower = match i {
+                    0 => isize::MIN,
+                    _ => {
+                        let u = x_coordinates[&layer[i - 1]];
+                        u + separation(graph, layer[i - 1], *v, spacing).min(x - u)
+                    }
+                };
+                let upper = match layer.get(i + 1) {
+                    None => isize::MAX,
+                    Some(w) => {
+                        let separation = separation(graph, *v, *w, spacing);
+                        let w = x_coordinates[w];
+                        w - separation.min(w - x)
+                    }
+                };
+                let new = target.min(upper).max(lower);
+                if new != x {
+                    x_coordinates.insert(*v, new);
+                    moved += 1;
+                }
+            }
+        }
+        debug!(target: "coordinate_calculation", "Moved {moved} dummy vertices in pass {pass}");
+        if moved == 0 {
+            break;
+        }
+    }
+}
+
+/// Moves vertices which were part of a previous layout back towards their
+/// previous x-coordinate.
+///
+/// First the whole layout is translated by the median distance of those
+/// vertices to their previous position. Afterwards each vertex is moved as
+/// close as possible to its previous x-coordinate, without changing the order
+/// of a layer or decreasing the distance between neighbors below their separation.
+pub(super) fn stabilize(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+) {
+    let mut offsets = x_coordinates
+        .iter()
+        .filter_map(|(v, x)| graph[*v].prev_x.map(|prev| prev - x))
+        .collect::<Vec<_>>();
+    if offsets.is_empty() {
+        return;
+    }
+    info!(target: "coordinate_calculation", "Moving vertices towards their position in the previous layout");
+    offsets.sort();
+    let offset = offsets[offsets.len() / 2];
+    x_coordinates.values_mut().for_each(|x| *x += offset);
+
+    for layer in layers {
+        for (i, v) in layer.iter().enumerate() {
+            let Some(prev) = graph[*v].prev_x else {
+                continue;
+            };
+            let x = x_coordinates[v];
+            // keep the original distance if it was already smaller, e.g. for dummy vertices
+            let lower = match i {
+                0 => isize::MIN,
+                _ => {
+                    let u = x_coordinates[&layer[i - 1]];
+                    u + separation(graph, layer[i - 1], *v, spacing).min(x - u)
+                }
+            };
+            let upper = match layer.get(i + 1) {
+                None => isize::MAX,
+                Some(w) => {
+                    let separation = separation(graph, *v, *w, spacing);
+                    let w = x_coordinates[w];
+                    w - separation.min(w - x)
+                }
+            };
+            x_coordinates.insert(*v, prev.min(upper).max(lower));
+        }
+    }
+}
+
+/// Moves pinned vertices to their fixed x-coordinate.
+///
+/// The layout is translated by the median distance of the pinned vertices to
+/// their pinned position first. Afterwards, all other vertices in the layer of a
+/// pinned vertex are pushed away from it, so the distance between neighbors is
+/// kept. If pinned vertices leave too little space for the vertices between them,
+/// these may end up closer than their separation.
+pub(super) fn apply_pins(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    spacing: Spacing,
+) {
+    let mut offsets = x_coordinates
+        .iter()
+        .filter_map(|(v, x)| graph[*v].pin.map(|(pin, _)| pin - x))
+        .collect::<Vec<_>>();
+    if offsets.is_empty() {
+        return;
+    }
+    info!(target: "coordinate_calculation", "Moving pinned vertices to their fixed position");
+    offsets.sort();
+    let offset = offsets[offsets.len() / 2];
+    x_coordinates.values_mut().for_each(|x| *x += offset);
+
+    for layer in layers {
+        if layer.iter().all(|v| graph[*v].pin.is_none()) {
+            continue;
+        }
+        // keep the original distance if it was smaller, e.g. for dummy vertices
+        let gaps = layer
+            .windows(2)
+            .map(|w| {
+                (x_coordinates[&w[1]] - x_coordinates[&w[0]])
+                    .clamp(0, separation(graph, w[0], w[1], spacing))
+            })
+            .collect::<Vec<_>>();
+        for v in layer {
+            if let Some((x, _)) = graph[*v].pin {
+                x_coordinates.insert(*v, x);
+            }
+        }
+        for i in 1..layer.len() {
+            if graph[layer[i]].pin.is_none() {
+                let min = x_coordinates[&layer[i - 1]] + gaps[i - 1];
+                let x = x_coordinates.get_mut(&layer[i]).unwrap();
+                *x = (*x).max(min);
+            }
+        }
+        for i in (0..layer.len() - 1).rev() {
+            if graph[layer[i]].pin.is_none() {
+                let max = x_coordinates[&layer[i + 1]] - gaps[i];
+                let x = x_coordinates.get_mut(&layer[i]).unwrap();
+                *x = (*x).min(max);
+            }
+        }
+    }
+}
+
+/// Pairs of vertices in adjacent ranks, which are placed in the same block
+/// and therefore get the same x-coordinate.
+#[derive(Debug, Default)]
+pub(super) struct Columns {
+    /// The upper vertex of each pair, by the lower one
+    upper: HashMap<NodeIndex, NodeIndex>,
+    /// The lower vertex of each pair, by the upper one
+    lower: HashMap<NodeIndex, NodeIndex>,
+}
+
+/// Collects the pairs of vertices which need to be placed in the same column,
+/// which are the endpoints of edges drawn vertically and consecutive members
+/// of alignment groups, as well as the endpoints of edges with a priority.
+///
+/// Pairs between two ranks must neither cross nor share a vertex, and need to
+/// be in adjacent ranks. Pairs which conflict with a previous one are ignored,
+/// where vertical edges come first, ordered by their index, followed by the
+/// alignment groups and edges by descending priority. Only conflicts of
+/// vertical edges and alignment groups are logged, edges with a priority are
+/// just aligned where possible.
+pub(super) fn select_columns(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    alignments: &[Vec<NodeIndex>],
+) -> Columns {
+    let mut columns = Columns::default();
+    if alignments.is_empty()
+        && graph
+            .edge_weights()
+            .all(|e| !e.is_vertical && e.priority == 0)
+    {
+        return columns;
+    }
+    info!(target: "coordinate_calculation", "Selecting vertices which are placed in the same column");
+    let positions = layers
+        .iter()
+        .flat_map(|l| l.iter().enumerate().map(|(pos, v)| (*v, pos)))
+        .collect::<HashMap<_, _>>();
+    let vertical_edges = graph
+        .edge_indices()
+        .filter(|e| graph[*e].is_vertical)
+        .map(|e| graph.edge_endpoints(e).unwrap());
+    let group_members = alignments.iter().flat_map(|group| {
+        let mut members = group.clone();
+        members.sort_by_key(|v| graph[*v].rank);
+        members.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()
+    });
+    let mut prioritized = graph
+        .edge_indices()
+        .filter(|e| !graph[*e].is_vertical && graph[*e].priority > 0)
+        .collect::<Vec<_>>();
+    prioritized.sort_by_key(|e| (Reverse(graph[*e].priority), e.index()));
+    let required = vertical_edges.chain(group_members).map(|pair| (pair, true));
+    let preferred = prioritized
+        .into_iter()
+        .map(|e| (graph.edge_endpoints(e).unwrap(), false));
+    // positions of the selected pairs, by the rank of their upper vertex
+    let mut selected = HashMap::<i32, Vec<(usize, usize)>>::new();
+    for ((upper, lower), is_required) in required.chain(preferred) {
+        let (u, l) = (positions[&upper], positions[&lower]);
+        let pairs = selected.entry(graph[upper].rank).or_default();
+        if graph[lower].rank - graph[upper].rank == 1
+            && pairs
+                .iter()
+                .all(|(s_u, s_l)| (*s_u < u && *s_l < l) || (*s_u > u && *s_l > l))
+        {
+            pairs.push((u, l));
+            columns.upper.insert(lower, upper);
+            columns.lower.insert(upper, lower);
+        } else if is_required {
+            warn!(target: "coordinate_calculation",
+                "Vertices {} and {} can't be placed in the same column, since they conflict with other columns or aren't in adjacent ranks",
+                graph[upper].id,
+                graph[lower].id);
+        }
+    }
+    columns
+}
+
 fn is_incident_to_inner_segment(graph: &StableDiGraph<Vertex, Edge>, id: NodeIndex) -> bool {
     graph[id].is_dummy
         && graph
@@ -194,15 +663,39 @@ pub(super) fn reset_alignment(graph: &mut StableDiGraph<Vertex, Edge>, layers: &
 // TODO: Change this so the graph gets rotated outside of the function
 /// Aligns the graph in so called blocks, which are used in the next step
 /// to determine the x-coordinate of a vertex.
+///
+/// Vertices are always aligned with the vertex given by `upper`, if there is one.
 fn create_vertical_alignments(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &mut [Vec<NodeIndex>],
+    upper: &HashMap<NodeIndex, NodeIndex>,
 ) {
     info!(target: "coordinate_calculation", "Creating vertical alignments");
     for layer in layers {
+        // columns are aligned first, all other alignments must not cross
+        // them, so they are limited by the next column on the right
+        let columns = layer
+            .iter()
+            .map(|v| upper.get(v).copied())
+            .collect::<Vec<_>>();
+        let mut bounds = vec![usize::MAX; layer.len()];
+        let mut bound = usize::MAX;
+        for (i, v) in layer.iter().copied().enumerate().rev() {
+            bounds[i] = bound;
+            if let Some(u) = columns[i] {
+                graph[u].align = v;
+                graph[v].root = graph[u].root;
+                graph[v].align = graph[v].root;
+                bound = graph[u].pos;
+            }
+        }
         let mut r = -1;
 
-        for v in layer.iter().copied() {
+        for (i, v) in layer.iter().copied().enumerate() {
+            if let Some(u) = columns[i] {
+                r = graph[u].pos as isize;
+                continue;
+            }
             let mut edges = graph
                 .edges_directed(v, Incoming)
                 .filter(|e| slack(graph, e.id(), 1) == 0)
@@ -225,6 +718,7 @@ fn create_vertical_alignments(
 
                     if !graph[edge_id].has_type_1_conflict
                         && r < graph[median_neighbor].pos as isize
+                        && graph[median_neighbor].pos < bounds[i]
                     {
                         graph[median_neighbor].align = v;
                         graph[v].root = graph[median_neighbor].root;
@@ -240,11 +734,10 @@ fn create_vertical_alignments(
 fn do_horizontal_compaction(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &[Vec<NodeIndex>],
-    vertex_spacing: usize,
-    dummy_size: f64,
+    spacing: Spacing,
 ) -> HashMap<NodeIndex, isize> {
     info!(target: "coordinate_calculation", "calculating coordinates for layout.");
-    let mut x_coordinates = place_blocks(graph, layers, vertex_spacing as isize, dummy_size);
+    let mut x_coordinates = place_blocks(graph, layers, spacing);
     // calculate class shifts
     info!(target: "coordinate_calculation", "move blocks as close together as possible");
     for i in 0..layers.len() {
@@ -264,10 +757,10 @@ fn do_horizontal_compaction(
                     v = graph[v].align;
                     j += 1;
 
-                    if graph[v].pos > 1 {
+                    if graph[v].pos > 0 {
                         let u = pred(graph[v], layers);
                         let distance_v_u = *x_coordinates.get(&v).unwrap()
-                            - (*x_coordinates.get(&u).unwrap() + vertex_spacing as isize);
+                            - (*x_coordinates.get(&u).unwrap() + separation(graph, u, v, spacing));
                         let u_sink = graph[u].sink;
                         graph[u_sink].shift = graph[u_sink]
                             .shift
@@ -296,8 +789,7 @@ fn do_horizontal_compaction(
 fn place_blocks(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &[Vec<NodeIndex>],
-    vertex_spacing: isize,
-    dummy_size: f64,
+    spacing: Spacing,
 ) -> HashMap<NodeIndex, isize> {
     info!(target: "coordinate_calculation", "Placing vertices in blocks.");
     let mut x_coordinates = HashMap::new();
@@ -307,14 +799,7 @@ fn place_blocks(
         .filter(|v| graph[*v].root == *v)
         .collect::<Vec<_>>()
     {
-        place_block(
-            graph,
-            layers,
-            root,
-            &mut x_coordinates,
-            vertex_spacing,
-            dummy_size,
-        );
+        place_block(graph, layers, root, &mut x_coordinates, spacing);
     }
     x_coordinates
 }
@@ -323,8 +808,7 @@ fn place_block(
     layers: &[Vec<NodeIndex>],
     root: NodeIndex,
     x_coordinates: &mut HashMap<NodeIndex, isize>,
-    vertex_spacing: isize,
-    dummy_size: f64,
+    spacing: Spacing,
 ) {
     if x_coordinates.get(&root).is_some() {
         return;
@@ -333,24 +817,20 @@ fn place_block(
     let mut w = root;
     loop {
         if graph[w].pos > 0 {
-            let u = graph[pred(graph[w], layers)].root;
-            place_block(graph, layers, u, x_coordinates, vertex_spacing, dummy_size);
+            let p = pred(graph[w], layers);
+            let u = graph[p].root;
+            place_block(graph, layers, u, x_coordinates, spacing);
             // initialize sink of current node to have the same sink as the root
             if graph[root].sink == root {
                 graph[root].sink = graph[u].sink;
             }
             if graph[root].sink == graph[u].sink {
-                let vertex_size = if graph[root].is_dummy {
-                    vertex_spacing as f64 * (1. - dummy_size)
-                } else {
-                    0.
-                } as isize;
                 x_coordinates.insert(
                     root,
                     *x_coordinates
                         .get(&root)
                         .unwrap()
-                        .max(&(x_coordinates.get(&u).unwrap() + vertex_spacing - vertex_size)),
+                        .max(&(x_coordinates.get(&u).unwrap() + separation(graph, p, w, spacing))),
                 );
             }
         }
@@ -375,7 +855,7 @@ fn pred(vertex: Vertex, layers: &[Vec<NodeIndex>]) -> NodeIndex {
 ///
 /// It is used to determine classes of a block, calculate the x-coordinates of a block
 /// in regard to its class and shift classes together as close as possible.
-
+///
 /// Represents the horizontal direction in which the algorithm is run
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum HDir {
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 
 use crate::algorithm::p3_calculate_coordinates::{
-    create_vertical_alignments, mark_type_1_conflicts,
+    create_vertical_alignments, mark_type_1_conflicts, select_columns,
 };
 
 use super::{reset_alignment, Edge, Vertex};
@@ -40,7 +42,7 @@ fn create_test_layout() -> (StableDiGraph<Vertex, Edge>, Vec<Vec<NodeIndex>>) {
         (25, 15),
     ];
 
-    let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(&edges);
+    let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
     let layers: Vec<Vec<NodeIndex>> = [
         vec![0, 1],
         vec![2, 3, 16, 4, 17, 18, 5, 6],
@@ -83,7 +85,7 @@ fn alignment_down_right() {
 
     // down right means no rotation
     reset_alignment(&mut g, &l);
-    create_vertical_alignments(&mut g, &mut l);
+    create_vertical_alignments(&mut g, &mut l, &HashMap::new());
     // verify roots
     assert_eq!(g[NodeIndex::from(0)].root, 0.into());
     assert_eq!(g[NodeIndex::from(1)].root, 1.into());
@@ -141,6 +143,36 @@ fn alignment_down_right() {
     assert_eq!(g[NodeIndex::from(25)].align, 9.into());
 }
 
+#[test]
+fn alignment_columns() {
+    let (mut g, mut l) = create_test_layout();
+    let n = NodeIndex::new;
+    for (tail, head) in [(5, 8), (6, 8), (12, 15)] {
+        let edge = g.find_edge(n(tail), n(head)).unwrap();
+        g[edge].is_vertical = true;
+    }
+    mark_type_1_conflicts(&mut g, &l);
+    let columns = select_columns(&g, &l, &[vec![n(11), n(7)], vec![n(0), n(7)]]);
+    // (6, 8) shares its head with (5, 8) and 0 isn't in the rank above 7
+    assert_eq!(columns.upper.get(&n(8)), Some(&n(5)));
+    assert_eq!(columns.upper.get(&n(7)), None);
+    assert_eq!(columns.lower.get(&n(7)), Some(&n(11)));
+
+    reset_alignment(&mut g, &l);
+    create_vertical_alignments(&mut g, &mut l, &columns.upper);
+    assert_eq!(g[n(7)].align, n(11));
+    assert_eq!(g[n(10)].root, n(10));
+    // columns are aligned even with a type 1 conflict
+    assert_eq!(g[n(5)].align, n(8));
+    assert_eq!(g[n(8)].root, n(5));
+    assert_eq!(g[n(4)].align, n(4));
+    assert_eq!(g[n(12)].align, n(15));
+    assert_eq!(g[n(15)].root, n(6));
+    // other alignments must not cross (5, 8)
+    assert_eq!(g[n(18)].align, n(18));
+    assert_eq!(g[n(20)].root, n(20));
+}
+
 #[test]
 fn alignment_down_left() {
     let (mut g, mut l) = create_test_layout();
@@ -148,28 +180,20 @@ fn alignment_down_left() {
     // down left means reverse each layer
     l.iter_mut().for_each(|l| l.reverse());
     reset_alignment(&mut g, &l);
-    create_vertical_alignments(&mut g, &mut l);
+    create_vertical_alignments(&mut g, &mut l, &HashMap::new());
 
     // block root 0
     for n in [0, 6] {
         assert_eq!(g[NodeIndex::from(n)].root, 0.into());
     }
     // block root 1
-    for n in [1] {
-        assert_eq!(g[NodeIndex::from(n)].root, 1.into());
-    }
+    assert_eq!(g[NodeIndex::from(1)].root, 1.into());
     // block root 2
-    for n in [2] {
-        assert_eq!(g[NodeIndex::from(n)].root, 2.into());
-    }
+    assert_eq!(g[NodeIndex::from(2)].root, 2.into());
     // block root 3
-    for n in [3] {
-        assert_eq!(g[NodeIndex::from(n)].root, 3.into());
-    }
+    assert_eq!(g[NodeIndex::from(3)].root, 3.into());
     // block root 16
-    for n in [16] {
-        assert_eq!(g[NodeIndex::from(n)].root, 16.into());
-    }
+    assert_eq!(g[NodeIndex::from(16)].root, 16.into());
     // block root 4
     for n in [4, 8] {
         assert_eq!(g[NodeIndex::from(n)].root, 4.into());
@@ -199,9 +223,7 @@ fn alignment_down_left() {
         assert_eq!(g[NodeIndex::from(n)].root, 10.into());
     }
     // block root 22
-    for n in [22] {
-        assert_eq!(g[NodeIndex::from(n)].root, 22.into());
-    }
+    assert_eq!(g[NodeIndex::from(22)].root, 22.into());
 }
 
 #[test]
@@ -213,7 +235,7 @@ fn alignment_up_right() {
     g.reverse();
     l.reverse();
     reset_alignment(&mut g, &l);
-    create_vertical_alignments(&mut g, &mut l);
+    create_vertical_alignments(&mut g, &mut l, &HashMap::new());
 
     for n in [13, 10] {
         assert_eq!(g[NodeIndex::from(n)].root, 13.into())
@@ -224,9 +246,7 @@ fn alignment_up_right() {
     for n in [15, 23, 19, 17] {
         assert_eq!(g[NodeIndex::from(n)].root, 15.into())
     }
-    for n in [22] {
-        assert_eq!(g[NodeIndex::from(n)].root, 22.into())
-    }
+    assert_eq!(g[NodeIndex::from(22)].root, 22.into());
     for n in [24, 20, 18, 0] {
         assert_eq!(g[NodeIndex::from(n)].root, 24.into())
     }
@@ -239,21 +259,11 @@ fn alignment_up_right() {
     for n in [8, 3] {
         assert_eq!(g[NodeIndex::from(n)].root, 8.into())
     }
-    for n in [2] {
-        assert_eq!(g[NodeIndex::from(n)].root, 2.into())
-    }
-    for n in [16] {
-        assert_eq!(g[NodeIndex::from(n)].root, 16.into())
-    }
-    for n in [4] {
-        assert_eq!(g[NodeIndex::from(n)].root, 4.into())
-    }
-    for n in [6] {
-        assert_eq!(g[NodeIndex::from(n)].root, 6.into())
-    }
-    for n in [1] {
-        assert_eq!(g[NodeIndex::from(n)].root, 1.into())
-    }
+    assert_eq!(g[NodeIndex::from(2)].root, 2.into());
+    assert_eq!(g[NodeIndex::from(16)].root, 16.into());
+    assert_eq!(g[NodeIndex::from(4)].root, 4.into());
+    assert_eq!(g[NodeIndex::from(6)].root, 6.into());
+    assert_eq!(g[NodeIndex::from(1)].root, 1.into());
 }
 
 #[test]
@@ -265,14 +275,12 @@ fn alignment_up_left() {
     l.reverse();
     l.iter_mut().for_each(|l| l.reverse());
     reset_alignment(&mut g, &l);
-    create_vertical_alignments(&mut g, &mut l);
+    create_vertical_alignments(&mut g, &mut l, &HashMap::new());
 
     for n in [15, 25, 9] {
         assert_eq!(g[NodeIndex::from(n)].root, 15.into())
     }
-    for n in [14] {
-        assert_eq!(g[NodeIndex::from(n)].root, 14.into())
-    }
+    assert_eq!(g[NodeIndex::from(14)].root, 14.into());
     for n in [13, 22] {
         assert_eq!(g[NodeIndex::from(n)].root, 13.into())
     }
@@ -288,31 +296,21 @@ fn alignment_up_left() {
     for n in [11, 7] {
         assert_eq!(g[NodeIndex::from(n)].root, 11.into())
     }
-    for n in [10] {
-        assert_eq!(g[NodeIndex::from(n)].root, 10.into())
-    }
+    assert_eq!(g[NodeIndex::from(10)].root, 10.into());
     for n in [4, 8] {
         assert_eq!(g[NodeIndex::from(n)].root, 8.into())
     }
-    for n in [0] {
-        assert_eq!(g[NodeIndex::from(n)].root, 0.into())
-    }
-    for n in [2] {
-        assert_eq!(g[NodeIndex::from(n)].root, 2.into())
-    }
-    for n in [3] {
-        assert_eq!(g[NodeIndex::from(n)].root, 3.into())
-    }
-    for n in [16] {
-        assert_eq!(g[NodeIndex::from(n)].root, 16.into())
-    }
+    assert_eq!(g[NodeIndex::from(0)].root, 0.into());
+    assert_eq!(g[NodeIndex::from(2)].root, 2.into());
+    assert_eq!(g[NodeIndex::from(3)].root, 3.into());
+    assert_eq!(g[NodeIndex::from(16)].root, 16.into());
 }
 
 #[test]
 fn place_blocks() {
     let (mut g, mut l) = create_test_layout();
-    mark_type_1_conflicts(&mut g, &mut l);
-    create_vertical_alignments(&mut g, &mut l);
+    mark_type_1_conflicts(&mut g, &l);
+    create_vertical_alignments(&mut g, &mut l, &HashMap::new());
 
     let block_1: Vec<NodeIndex> = [
         0, 1, 2, 3, 4, 5, 6, 8, 9, 12, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25,
@@ -325,7 +323,7 @@ fn place_blocks() {
         .map(|v| v.into())
         .collect();
 
-    let x_coordinates = super::place_blocks(&mut g, &l, 10, 1.);
+    let x_coordinates = super::place_blocks(&mut g, &l, super::Spacing::new(10, 1.));
 
     assert_eq!(x_coordinates.len(), 26);
     for v in block_1 {
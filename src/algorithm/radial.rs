@@ -0,0 +1,185 @@
+//! Radial projection of a finished layout.
+//!
+//! The ranks of the layout become concentric circles around the first rank,
+//! and the x-coordinates become angles, so the order within each rank is kept
+//! as the order around the circle. The width of the layout is one full turn.
+//! Each circle keeps at least the distance of the ranks in the layered layout
+//! to the next inner one, and circles with more than one vertex are large
+//! enough that vertices one vertex spacing apart in the layered layout stay
+//! one vertex spacing apart on the circle. A first rank with a single vertex
+//! is placed in the center.
+//!
+//! Points between ranks, like the bends of routes, lie on circles whose
+//! radius is interpolated between both ranks. Segments of polyline routes are
+//! split, so they follow the circles instead of cutting across them. Boxes of
+//! clusters, components and vertices spanning several ranks are replaced by
+//! the bounding box of their projected outline. Lanes, which become sectors,
+//! and ranks, which become rings, are left out.
+use std::f64::consts::TAU;
+
+use log::info;
+
+use super::routing;
+use crate::{Geometry, Layout};
+
+type Coordinates = (isize, isize);
+
+/// Number of points on each side of a box whose projection bounds the box.
+const BOX_SAMPLES: isize = 8;
+
+/// Maps coordinates of a layered layout to circles.
+pub(super) struct Projection {
+    min_x: isize,
+    width: f64,
+    /// y-coordinate and radius of each rank, from the first rank to the last.
+    ranks: Vec<(isize, f64)>,
+    spacing: isize,
+}
+
+impl Projection {
+    /// Creates the projection of a layout, given the y-coordinate and the
+    /// number of vertices of each rank, ordered from the first rank to the
+    /// last, and the x-coordinates of all vertices.
+    pub(super) fn new(
+        ranks: &[(isize, usize)],
+        xs: impl Iterator<Item = isize>,
+        vertex_spacing: usize,
+    ) -> Self {
+        let spacing = vertex_spacing as isize;
+        let (min_x, max_x) = xs.fold((isize::MAX, isize::MIN), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+        let width = (max_x - min_x + spacing).max(1) as f64;
+        let min_radius = width / TAU;
+        let mut radii = Vec::<(isize, f64)>::with_capacity(ranks.len());
+        for &(y, vertices) in ranks {
+            let radius = match radii.last() {
+                Some(&(prev, radius)) => radius + (prev - y) as f64,
+                None if vertices == 1 => 0.,
+                None => min_radius,
+            };
+            let radius = if vertices > 1 {
+                radius.max(min_radius)
+            } else {
+                radius
+            };
+            radii.push((y, radius));
+        }
+        info!(target: "layouting",
+            "Projecting {} ranks onto circles, innermost radius {:.1}",
+            radii.len(),
+            radii.first().map_or(0., |(_, r)| *r));
+        Self {
+            min_x,
+            width,
+            ranks: radii,
+            spacing,
+        }
+    }
+
+    /// Returns the radius of the circle for the y-coordinate `y`.
+    fn radius(&self, y: isize) -> f64 {
+        let (Some(&(top, inner)), Some(&(bottom, outer))) = (self.ranks.first(), self.ranks.last())
+        else {
+            return 0.;
+        };
+        if y >= top {
+            return (inner - (y - top) as f64).max(0.);
+        }
+        if y <= bottom {
+            return outer + (bottom - y) as f64;
+        }
+        let i = self.ranks.iter().position(|(r, _)| *r < y).unwrap();
+        let ((y1, r1), (y2, r2)) = (self.ranks[i - 1], self.ranks[i]);
+        r1 + (r2 - r1) * (y1 - y) as f64 / (y1 - y2) as f64
+    }
+
+    /// Returns the projection of the point `(x, y)`.
+    pub(super) fn project(&self, (x, y): Coordinates) -> Coordinates {
+        let radius = self.radius(y);
+        let angle = TAU * (x - self.min_x) as f64 / self.width;
+        (
+            (radius * angle.cos()).round() as isize,
+            (radius * angle.sin()).round() as isize,
+        )
+    }
+
+    /// Projects all vertices, routes and boxes of a layout. Routes are split
+    /// into pieces no longer than the vertex spacing, unless they are splines.
+    pub(super) fn apply(&self, layout: &mut Layout, geometry: &mut Geometry, split: bool) {
+        for (_, coordinates) in &mut layout.0 {
+            *coordinates = self.project(*coordinates);
+        }
+        for points in routing::routes(geometry) {
+            let route = if split {
+                self.split(points)
+            } else {
+                points.clone()
+            };
+            *points = route.into_iter().map(|p| self.project(p)).collect();
+            points.dedup();
+        }
+        for c in &mut geometry.clusters {
+            (c.min, c.max) = self.project_box(c.min, c.max);
+        }
+        for c in &mut geometry.condensed {
+            (c.min, c.max) = self.project_box(c.min, c.max);
+        }
+        for span in &mut geometry.spans {
+            (span.min, span.max) = self.project_box(span.min, span.max);
+        }
+        geometry.lanes.clear();
+        geometry.ranks.clear();
+    }
+
+    /// Adds points to `route`, so no segment is longer than the vertex spacing.
+    fn split(&self, route: &[Coordinates]) -> Vec<Coordinates> {
+        let mut points = Vec::with_capacity(route.len());
+        points.extend(route.first());
+        for w in route.windows(2) {
+            let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+            let pieces = ((x2 - x1).abs().max((y2 - y1).abs()) / self.spacing.max(1)).max(1);
+            points.extend(
+                (1..=pieces).map(|i| (x1 + (x2 - x1) * i / pieces, y1 + (y2 - y1) * i / pieces)),
+            );
+        }
+        points
+    }
+
+    /// Returns the bounding box of the projected outline of a box.
+    fn project_box(&self, min: Coordinates, max: Coordinates) -> (Coordinates, Coordinates) {
+        let outline = (0..BOX_SAMPLES).flat_map(|i| {
+            let x = min.0 + (max.0 - min.0) * i / BOX_SAMPLES;
+            let y = min.1 + (max.1 - min.1) * i / BOX_SAMPLES;
+            let (x2, y2) = (
+                max.0 - (max.0 - min.0) * i / BOX_SAMPLES,
+                max.1 - (max.1 - min.1) * i / BOX_SAMPLES,
+            );
+            [(x, min.1), (max.0, y), (x2, max.1), (min.0, y2)]
+        });
+        let (mut lo, mut hi) = ((isize::MAX, isize::MAX), (isize::MIN, isize::MIN));
+        for (x, y) in outline.map(|p| self.project(p)) {
+            lo = (lo.0.min(x), lo.1.min(y));
+            hi = (hi.0.max(x), hi.1.max(y));
+        }
+        (lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Projection;
+
+    #[test]
+    fn radii_are_interpolated_between_ranks() {
+        // a single vertex in the first rank is placed in the center
+        let projection =
+            Projection::new(&[(0, 1), (-10, 3), (-30, 2)], [0, 10, 20].into_iter(), 10);
+        assert_eq!(projection.project((0, 0)), (0, 0));
+        assert_eq!(projection.radius(-10), 10.);
+        assert_eq!(projection.radius(-20), 20.);
+        assert_eq!(projection.radius(-40), 40.);
+        assert_eq!(projection.project((0, -10)), (10, 0));
+        assert_eq!(projection.project((10, -30)), (-15, 26));
+    }
+}
@@ -0,0 +1,436 @@
+//! Alternative routes for the edges of a finished layout.
+//!
+//! By default, edges are polylines through their dummy vertices. Orthogonal
+//! routes replace each diagonal segment by a vertical segment down to a track
+//! in the channel between both ranks, a horizontal segment along that track,
+//! and another vertical segment. Segments in the same channel which overlap
+//! horizontally are put on different tracks, which are spread evenly over the
+//! space between the vertices of both ranks.
+//!
+//! Splines replace each segment by a cubic Bézier curve, whose control points
+//! lie at half the height of the segment above and below its ends. All curves
+//! start and end vertically, so the curves of an edge join smoothly at its
+//! dummy vertices.
+//!
+//! Flat edges between vertices of the same rank are horizontal lines. If
+//! other vertices lie between both endpoints, the edge leaves its endpoints
+//! upwards and passes above all of them.
+//!
+//! Back edges may be routed in channels beside the vertices they span. Each
+//! channel keeps half the vertex spacing to everything between both endpoints,
+//! including the channels of the back edges it contains.
+//!
+//! Finally, routes which start or end at the center of a vertex with a size
+//! are clipped at its border, in the direction of the next point of the route.
+use std::collections::HashMap;
+use std::iter;
+
+use log::info;
+
+use crate::configure::Shape;
+use crate::{EdgeRoute, Geometry};
+
+type Coordinates = (isize, isize);
+
+/// Returns the number of bends of the routes of all edges, which are the
+/// points between the ends of a route at which it changes its direction by
+/// more than rounding the coordinates to whole units explains.
+pub(super) fn count_bends(geometry: &Geometry) -> usize {
+    geometry
+        .edges
+        .iter()
+        .flat_map(|route| route.points.windows(3))
+        .filter(|w| {
+            let ((x1, y1), (x2, y2), (x3, y3)) = (w[0], w[1], w[2]);
+            // distance of the middle point to the line through both others,
+            // measured along the longer axis of the line
+            let cross = (x2 - x1) * (y3 - y1) - (x3 - x1) * (y2 - y1);
+            cross.abs() > (x3 - x1).abs().max((y3 - y1).abs())
+        })
+        .count()
+}
+
+/// Replaces the routes of all edges and hyperedges by orthogonal ones.
+/// `half_heights` contains the half height of the highest vertex at each
+/// y-coordinate.
+pub(super) fn route_orthogonally(geometry: &mut Geometry, half_heights: &HashMap<isize, isize>) {
+    let mut routes = routes(geometry);
+
+    // diagonal segments, by the channel between the y-coordinates of their ends
+    let mut channels = HashMap::<(isize, isize), Vec<(isize, isize, usize, usize)>>::new();
+    for (r, points) in routes.iter().enumerate() {
+        for (i, w) in points.windows(2).enumerate() {
+            let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+            if x1 != x2 && y1 != y2 {
+                let channel = channels.entry((y1.max(y2), y1.min(y2))).or_default();
+                channel.push((x1.min(x2), x1.max(x2), r, i));
+            }
+        }
+    }
+    info!(target: "routing", "Routing edges orthogonally through {} channels", channels.len());
+
+    let mut tracks = HashMap::<(usize, usize), isize>::new();
+    for ((upper, lower), mut segments) in channels {
+        // left edge algorithm: put each segment on the first track that is free
+        segments.sort();
+        let mut ends = Vec::<isize>::new();
+        let mut assigned = Vec::new();
+        for (left, right, r, i) in segments {
+            let track = match ends.iter().position(|end| *end < left) {
+                Some(track) => track,
+                None => {
+                    ends.push(isize::MIN);
+                    ends.len() - 1
+                }
+            };
+            ends[track] = right;
+            assigned.push((track, r, i));
+        }
+        let mut top = upper - half_heights.get(&upper).unwrap_or(&0);
+        let mut bottom = lower + half_heights.get(&lower).unwrap_or(&0);
+        if top <= bottom {
+            (top, bottom) = (upper, lower);
+        }
+        let n = ends.len() as isize + 1;
+        for (track, r, i) in assigned {
+            tracks.insert((r, i), top - (top - bottom) * (track as isize + 1) / n);
+        }
+    }
+
+    for (r, points) in routes.iter_mut().enumerate() {
+        let mut route = Vec::with_capacity(points.len());
+        for (i, w) in points.windows(2).enumerate() {
+            route.push(w[0]);
+            if let Some(y) = tracks.get(&(r, i)) {
+                route.push((w[0].0, *y));
+                route.push((w[1].0, *y));
+            }
+        }
+        route.extend(points.last());
+        **points = remove_collinear(route);
+    }
+}
+
+/// Replaces the routes of all edges and hyperedges by cubic Bézier curves.
+/// Each route starts with its first point, followed by the two control points
+/// and the end point of each curve.
+pub(super) fn route_splines(geometry: &mut Geometry) {
+    info!(target: "routing", "Routing {} edges as splines", geometry.edges.len());
+    for points in routes(geometry) {
+        let mut route = Vec::with_capacity(3 * points.len());
+        route.extend(points.first());
+        for w in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+            let y = y1 + (y2 - y1) / 2;
+            route.extend([(x1, y), (x2, y), (x2, y2)]);
+        }
+        *points = route;
+    }
+}
+
+/// Adds routes for flat edges, which are given by their id and the
+/// coordinates of their tail and head. `boxes` contains the centers and sizes
+/// of all vertices.
+pub(super) fn route_flat_edges(
+    geometry: &mut Geometry,
+    flat_edges: &[(usize, Coordinates, Coordinates)],
+    boxes: &[(Coordinates, (isize, isize))],
+    vertex_spacing: isize,
+) {
+    info!(target: "routing", "Routing {} flat edges", flat_edges.len());
+    for &(edge, tail, head) in flat_edges {
+        let (left, right) = (tail.0.min(head.0), tail.0.max(head.0));
+        // tops of the vertices between both endpoints and of the endpoints
+        let tops = boxes
+            .iter()
+            .filter(|((x, y), (_, h))| {
+                tail.1 == head.1
+                    && (left..=right).contains(x)
+                    && (y - h / 2..=y + (h + 1) / 2).contains(&tail.1)
+            })
+            .map(|((x, y), (_, h))| (*x, y + (h + 1) / 2));
+        let mut blocked = false;
+        let mut top = tail.1;
+        for (x, y) in tops {
+            blocked |= left < x && x < right;
+            top = top.max(y);
+        }
+        let points = if blocked {
+            let y = top + vertex_spacing / 4;
+            vec![tail, (tail.0, y), (head.0, y), head]
+        } else {
+            vec![tail, head]
+        };
+        geometry.edges.push(EdgeRoute { edge, points });
+    }
+    geometry.edges.sort_by_key(|route| route.edge);
+}
+
+/// Adds routes for back edges, which are given by their id and the
+/// coordinates of their tail and head. `boxes` contains the centers and sizes
+/// of all vertices.
+pub(super) fn route_back_edges(
+    geometry: &mut Geometry,
+    back_edges: &[(usize, Coordinates, Coordinates)],
+    boxes: &[(Coordinates, (isize, isize))],
+    vertex_spacing: isize,
+) {
+    info!(target: "routing", "Routing {} back edges in channels", back_edges.len());
+    // horizontal and vertical extents of everything channels need to avoid
+    let mut obstacles = routes(geometry)
+        .into_iter()
+        .flat_map(|points| points.iter().map(|(x, y)| ((*x, *x), (*y, *y))))
+        .collect::<Vec<_>>();
+    obstacles.extend(
+        boxes
+            .iter()
+            .map(|((x, y), (w, h))| ((x - w / 2, x + (w + 1) / 2), (y - h / 2, y + (h + 1) / 2))),
+    );
+    obstacles.extend(
+        geometry
+            .clusters
+            .iter()
+            .map(|c| ((c.min.0, c.max.0), (c.min.1, c.max.1))),
+    );
+
+    // inner loops first, so outer loops are routed around them
+    let mut back_edges = back_edges.to_vec();
+    back_edges.sort_by_key(|(id, tail, head)| ((tail.1 - head.1).abs(), *id));
+    for (edge, tail, head) in back_edges {
+        let (bottom, top) = (tail.1.min(head.1), tail.1.max(head.1));
+        let spanned = obstacles
+            .iter()
+            .filter(|(_, (min, max))| *min <= top && *max >= bottom)
+            .map(|(x, _)| *x);
+        let (left, right) = spanned.fold((isize::MAX, isize::MIN), |(l, r), (min, max)| {
+            (l.min(min), r.max(max))
+        });
+        let center = (tail.0 + head.0) / 2;
+        let x = if center - left < right - center {
+            left - vertex_spacing / 2
+        } else {
+            right + vertex_spacing / 2
+        };
+        obstacles.push(((x, x), (bottom, top)));
+        geometry.edges.push(EdgeRoute {
+            edge,
+            points: vec![tail, (x, tail.1), (x, head.1), head],
+        });
+    }
+    geometry.edges.sort_by_key(|route| route.edge);
+}
+
+/// Moves the endpoints of all routes which are at the center of a vertex in
+/// `borders` onto the border of its shape, given by its size.
+pub(super) fn clip_endpoints(
+    geometry: &mut Geometry,
+    borders: &HashMap<Coordinates, ((isize, isize), Shape)>,
+) {
+    for points in routes(geometry) {
+        let n = points.len();
+        if n < 2 {
+            continue;
+        }
+        for (end, next) in [(0, 1), (n - 1, n - 2)] {
+            if let Some((size, shape)) = borders.get(&points[end]) {
+                points[end] = clip(points[end], points[next], *size, *shape);
+            }
+        }
+    }
+}
+
+/// Returns the point where the line from `center` to `next` leaves a shape
+/// of the given size around `center`.
+fn clip(center: Coordinates, next: Coordinates, size: (isize, isize), shape: Shape) -> Coordinates {
+    let (dx, dy) = ((next.0 - center.0) as f64, (next.1 - center.1) as f64);
+    let (a, b) = (size.0 as f64 / 2., size.1 as f64 / 2.);
+    // the part of the distance to next for which the line stays within the shape
+    let ratio = |d: f64, r: f64| if d == 0. { 0. } else { (d / r).abs() };
+    let t = match shape {
+        Shape::Rectangle => 1. / ratio(dx, a).max(ratio(dy, b)),
+        Shape::Ellipse => 1. / ratio(dx, a).hypot(ratio(dy, b)),
+    };
+    // a point inside the shape, or no direction at all
+    if t >= 1. {
+        return center;
+    }
+    (
+        center.0 + (dx * t).round() as isize,
+        center.1 + (dy * t).round() as isize,
+    )
+}
+
+/// Returns the points of the routes of all edges, followed by those of the
+/// trunks and branches of all hyperedges.
+pub(super) fn routes(geometry: &mut Geometry) -> Vec<&mut Vec<Coordinates>> {
+    let hyperedges = geometry
+        .hyperedges
+        .iter_mut()
+        .flat_map(|route| iter::once(&mut route.trunk).chain(route.branches.iter_mut()));
+    geometry
+        .edges
+        .iter_mut()
+        .map(|route| &mut route.points)
+        .chain(hyperedges)
+        .collect()
+}
+
+/// Removes points which lie on a straight vertical or horizontal line
+/// between their neighbors, as well as duplicates.
+fn remove_collinear(points: Vec<Coordinates>) -> Vec<Coordinates> {
+    let mut route = Vec::<Coordinates>::with_capacity(points.len());
+    for (i, p) in points.iter().enumerate() {
+        if route.last() == Some(p) {
+            continue;
+        }
+        if let (Some(prev), Some(next)) = (route.last(), points.get(i + 1)) {
+            let vertical = prev.0 == p.0 && p.0 == next.0;
+            let horizontal = prev.1 == p.1 && p.1 == next.1;
+            if (vertical || horizontal) && p != next {
+                continue;
+            }
+        }
+        route.push(*p);
+    }
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        clip_endpoints, route_back_edges, route_flat_edges, route_orthogonally, route_splines,
+    };
+    use crate::configure::Shape;
+    use crate::{EdgeRoute, Geometry};
+
+    fn geometry(routes: &[&[(isize, isize)]]) -> Geometry {
+        Geometry {
+            edges: routes
+                .iter()
+                .enumerate()
+                .map(|(edge, points)| EdgeRoute {
+                    edge,
+                    points: points.to_vec(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn segments_become_axis_aligned() {
+        let mut geometry = geometry(&[
+            &[(0, 0), (20, -10), (20, -20), (0, -30)],
+            &[(0, 0), (0, -10)],
+        ]);
+        route_orthogonally(&mut geometry, &HashMap::new());
+        assert_eq!(
+            geometry.edges[0].points,
+            [(0, 0), (0, -5), (20, -5), (20, -25), (0, -25), (0, -30)]
+        );
+        assert_eq!(geometry.edges[1].points, [(0, 0), (0, -10)]);
+    }
+
+    #[test]
+    fn overlapping_segments_use_different_tracks() {
+        let mut geometry = geometry(&[
+            &[(0, 0), (20, -12)],
+            &[(10, 0), (30, -12)],
+            &[(40, 0), (50, -12)],
+        ]);
+        let half_heights = HashMap::from([(0, 2), (-12, 1)]);
+        route_orthogonally(&mut geometry, &half_heights);
+        // the free space is between -2 and -11, split into three parts
+        assert_eq!(
+            geometry.edges[0].points,
+            [(0, 0), (0, -5), (20, -5), (20, -12)]
+        );
+        assert_eq!(
+            geometry.edges[1].points,
+            [(10, 0), (10, -8), (30, -8), (30, -12)]
+        );
+        assert_eq!(
+            geometry.edges[2].points,
+            [(40, 0), (40, -5), (50, -5), (50, -12)]
+        );
+    }
+
+    #[test]
+    fn segments_become_curves() {
+        let mut geometry = geometry(&[&[(0, 0), (20, -10), (20, -20)]]);
+        route_splines(&mut geometry);
+        assert_eq!(
+            geometry.edges[0].points,
+            [
+                (0, 0),
+                (0, -5),
+                (20, -5),
+                (20, -10),
+                (20, -15),
+                (20, -15),
+                (20, -20)
+            ]
+        );
+    }
+
+    #[test]
+    fn endpoints_are_clipped_at_borders() {
+        let mut geometry = geometry(&[&[(0, 0), (30, -40)], &[(0, 0), (0, -40)]]);
+        let borders = HashMap::from([
+            ((0, 0), ((20, 10), Shape::Rectangle)),
+            ((30, -40), ((20, 20), Shape::Ellipse)),
+        ]);
+        clip_endpoints(&mut geometry, &borders);
+        // the line leaves the rectangle at its bottom, and the circle after
+        // a fifth of its length
+        assert_eq!(geometry.edges[0].points, [(4, -5), (24, -32)]);
+        assert_eq!(geometry.edges[1].points, [(0, -5), (0, -40)]);
+    }
+
+    #[test]
+    fn flat_edges_pass_above_vertices_between_them() {
+        let mut geometry = geometry(&[]);
+        let boxes = [
+            ((0, 0), (0, 0)),
+            ((10, 0), (0, 0)),
+            ((20, 0), (6, 8)),
+            ((30, 0), (0, 0)),
+        ];
+        route_flat_edges(
+            &mut geometry,
+            &[(1, (30, 0), (0, 0)), (0, (0, 0), (10, 0))],
+            &boxes,
+            10,
+        );
+        assert_eq!(geometry.edges[0].points, [(0, 0), (10, 0)]);
+        assert_eq!(geometry.edges[1].points, [(30, 0), (30, 6), (0, 6), (0, 0)]);
+    }
+
+    #[test]
+    fn nested_back_edges_use_separate_channels() {
+        let mut geometry = geometry(&[&[(-20, 0), (-20, -10)], &[(0, -10), (0, -20)]]);
+        let boxes = [((0, 0), (0, 0)), ((0, -10), (6, 4)), ((0, -20), (0, 0))];
+        route_back_edges(
+            &mut geometry,
+            &[(3, (0, -20), (0, 0)), (2, (0, -10), (0, 0))],
+            &boxes,
+            10,
+        );
+        assert_eq!(
+            geometry.edges.iter().map(|r| r.edge).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        // both loops are closer to the right side, where the inner loop
+        // passes the vertex in the middle and the outer loop the inner one
+        assert_eq!(
+            geometry.edges[2].points,
+            [(0, -10), (8, -10), (8, 0), (0, 0)]
+        );
+        assert_eq!(
+            geometry.edges[3].points,
+            [(0, -20), (13, -20), (13, 0), (0, 0)]
+        );
+    }
+}
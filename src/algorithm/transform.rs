@@ -0,0 +1,104 @@
+//! Transformations of all coordinates of a finished layout.
+use super::routing;
+use crate::{Bounds, Geometry, Layout};
+
+type Coordinates = (isize, isize);
+
+/// Maps the coordinates of all vertices, routes and boxes of a layout with
+/// `f`, which maps each axis on its own. Boxes keep their smallest
+/// coordinates in `min` and their largest in `max`.
+pub(super) fn map(
+    layout: &mut Layout,
+    geometry: &mut Geometry,
+    f: impl Fn(Coordinates) -> Coordinates,
+) {
+    let span = |min: Coordinates, max: Coordinates| {
+        let (a, b) = (f(min), f(max));
+        ((a.0.min(b.0), a.1.min(b.1)), (a.0.max(b.0), a.1.max(b.1)))
+    };
+    for (_, coordinates) in &mut layout.0 {
+        *coordinates = f(*coordinates);
+    }
+    for points in routing::routes(geometry) {
+        points.iter_mut().for_each(|p| *p = f(*p));
+    }
+    for c in &mut geometry.clusters {
+        (c.min, c.max) = span(c.min, c.max);
+    }
+    for c in &mut geometry.condensed {
+        (c.min, c.max) = span(c.min, c.max);
+    }
+    for s in &mut geometry.spans {
+        (s.min, s.max) = span(s.min, s.max);
+    }
+    for lane in &mut geometry.lanes {
+        let ((min, _), (max, _)) = span((lane.min, 0), (lane.max, 0));
+        (lane.min, lane.max) = (min, max);
+    }
+    for rank in &mut geometry.ranks {
+        let ((_, min), (_, max)) = span((0, rank.min), (0, rank.max));
+        (rank.y, rank.min, rank.max) = (f((0, rank.y)).1, min, max);
+    }
+    let (min, max) = span(geometry.bounds.min, geometry.bounds.max);
+    geometry.bounds = Bounds { min, max };
+}
+
+/// Monotone map of coordinates along one axis, which moves the given values
+/// onto multiples of a grid step without moving any two of them closer
+/// together. Coordinates in between are interpolated.
+pub(super) struct Snap {
+    /// Values and the multiples of the step they are moved to, in ascending order.
+    knots: Vec<(isize, isize)>,
+}
+
+impl Snap {
+    pub(super) fn new(values: impl Iterator<Item = isize>, step: usize) -> Self {
+        let step = step.max(1) as isize;
+        let up = |v: isize| (v + step - 1).div_euclid(step) * step;
+        let mut values = values.collect::<Vec<_>>();
+        values.sort();
+        values.dedup();
+        let mut knots = Vec::<(isize, isize)>::with_capacity(values.len());
+        for v in values {
+            let snapped = match knots.last() {
+                // the nearest multiple, unless that is closer to the previous value
+                Some(&(u, su)) => ((v + step / 2).div_euclid(step) * step).max(up(su + v - u)),
+                None => up(v),
+            };
+            knots.push((v, snapped));
+        }
+        Self { knots }
+    }
+
+    pub(super) fn apply(&self, v: isize) -> isize {
+        let (Some(first), Some(last)) = (self.knots.first(), self.knots.last()) else {
+            return v;
+        };
+        match self.knots.binary_search_by_key(&v, |(k, _)| *k) {
+            Ok(i) => self.knots[i].1,
+            Err(0) => v + first.1 - first.0,
+            Err(i) if i == self.knots.len() => v + last.1 - last.0,
+            Err(i) => {
+                let ((a, sa), (b, sb)) = (self.knots[i - 1], self.knots[i]);
+                sa + ((v - a) * (sb - sa)).div_euclid(b - a)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Snap;
+
+    #[test]
+    fn snapping_keeps_distances() {
+        let snap = Snap::new([3, 14, 14, 21, 40].into_iter(), 10);
+        // 14 would be closer than 11 to 3 at 10, so it's moved to 30
+        let snapped = [3, 14, 21, 40].map(|v| snap.apply(v));
+        assert_eq!(snapped, [10, 30, 40, 60]);
+        // values in between and outside are moved along
+        assert_eq!(snap.apply(0), 7);
+        assert_eq!(snap.apply(30), 49);
+        assert_eq!(snap.apply(45), 65);
+    }
+}
@@ -0,0 +1,185 @@
+//! Fast path for components which are trees.
+//!
+//! A tree can always be drawn without crossings, so crossing reduction is
+//! skipped and the x-coordinates are calculated directly, roughly following
+//! the 1981 paper "Tidier Drawings of Trees" by Reingold and Tilford. Each
+//! subtree is laid out on its own and placed as close to its left sibling as
+//! the contours of both allow, while the parent is centered above its children.
+use std::collections::HashMap;
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::Direction::{self, Incoming, Outgoing};
+
+use super::{Edge, Vertex};
+
+/// Leftmost and rightmost x-coordinate of a subtree in each rank, relative to
+/// the root of the subtree.
+type Contour = HashMap<i32, (isize, isize)>;
+
+/// Returns the direction in which the children of a vertex are found, if the
+/// graph is a tree.
+///
+/// The graph is expected to be connected and acyclic, so it is a tree if
+/// every vertex has at most one incoming (or outgoing) edge. Vertices which
+/// are part of a previous layout or pinned, and edges which need to be drawn
+/// vertically or straight are handled by the regular phases, so they disable
+/// the fast path.
+pub(super) fn children_direction(graph: &StableDiGraph<Vertex, Edge>) -> Option<Direction> {
+    if graph
+        .node_weights()
+        .any(|w| w.prev_x.is_some() || w.pin.is_some())
+        || graph
+            .edge_weights()
+            .any(|e| e.is_vertical || e.priority > 0)
+    {
+        return None;
+    }
+    [Outgoing, Incoming].into_iter().find(|dir| {
+        graph
+            .node_indices()
+            .all(|v| graph.neighbors_directed(v, dir.opposite()).nth(1).is_none())
+    })
+}
+
+/// Calculates the x-coordinate of each vertex of a tree, whose children are
+/// found in direction `children`.
+pub(super) fn calculate_coordinates(
+    graph: &StableDiGraph<Vertex, Edge>,
+    children: Direction,
+    vertex_spacing: usize,
+) -> HashMap<NodeIndex, isize> {
+    info!(target: "tree_layout", "Calculating coordinates of tree");
+    let root = graph
+        .node_indices()
+        .find(|v| {
+            graph
+                .neighbors_directed(*v, children.opposite())
+                .next()
+                .is_none()
+        })
+        .expect("Tree has no root");
+
+    let mut offsets = HashMap::new();
+    place_subtree(graph, root, children, vertex_spacing as isize, &mut offsets);
+
+    // offsets are relative to the parent, so accumulate them from the root
+    let mut x_coordinates = HashMap::from([(root, 0)]);
+    let mut stack = vec![root];
+    while let Some(v) = stack.pop() {
+        for c in graph.neighbors_directed(v, children) {
+            x_coordinates.insert(c, x_coordinates[&v] + offsets[&c]);
+            stack.push(c);
+        }
+    }
+    x_coordinates
+}
+
+/// Places the subtree of `v` and returns its contour. The offset of each child
+/// relative to its parent is stored in `offsets`.
+fn place_subtree(
+    graph: &StableDiGraph<Vertex, Edge>,
+    v: NodeIndex,
+    children: Direction,
+    spacing: isize,
+    offsets: &mut HashMap<NodeIndex, isize>,
+) -> Contour {
+    let mut child_vertices = graph.neighbors_directed(v, children).collect::<Vec<_>>();
+    child_vertices.sort();
+
+    // place the children next to each other, relative to the first child
+    let mut contour = Contour::new();
+    let mut child_offsets: Vec<(NodeIndex, isize)> = Vec::new();
+    for c in child_vertices {
+        let child_contour = place_subtree(graph, c, children, spacing, offsets);
+        let offset = match child_offsets.last() {
+            None => 0,
+            Some((_, last)) => child_contour
+                .iter()
+                .filter_map(|(rank, (left, _))| {
+                    contour.get(rank).map(|(_, right)| right - left + spacing)
+                })
+                .fold(last + spacing, isize::max),
+        };
+        for (rank, (left, right)) in child_contour {
+            let entry = contour
+                .entry(rank)
+                .or_insert((left + offset, right + offset));
+            entry.0 = entry.0.min(left + offset);
+            entry.1 = entry.1.max(right + offset);
+        }
+        child_offsets.push((c, offset));
+    }
+
+    // center the parent above its children
+    let center = match (child_offsets.first(), child_offsets.last()) {
+        (Some((_, first)), Some((_, last))) => (first + last) / 2,
+        _ => 0,
+    };
+    for (c, offset) in child_offsets {
+        offsets.insert(c, offset - center);
+    }
+    let mut contour = contour
+        .into_iter()
+        .map(|(rank, (left, right))| (rank, (left - center, right - center)))
+        .collect::<Contour>();
+    contour.insert(graph[v].rank, (0, 0));
+    contour
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+    use petgraph::Direction::{Incoming, Outgoing};
+
+    use super::{calculate_coordinates, children_direction};
+    use crate::algorithm::{Edge, Vertex};
+
+    fn tree(edges: &[(u32, u32)], ranks: &[i32]) -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
+        for (v, rank) in ranks.iter().enumerate() {
+            graph[NodeIndex::new(v)].rank = *rank;
+        }
+        graph
+    }
+
+    #[test]
+    fn detect_trees() {
+        let out_tree = tree(&[(0, 1), (0, 2), (1, 3)], &[0, 1, 1, 2]);
+        assert_eq!(children_direction(&out_tree), Some(Outgoing));
+        let in_tree = tree(&[(1, 0), (2, 0), (3, 1)], &[2, 1, 1, 0]);
+        assert_eq!(children_direction(&in_tree), Some(Incoming));
+        let diamond = tree(&[(0, 1), (0, 2), (1, 3), (2, 3)], &[0, 1, 1, 2]);
+        assert_eq!(children_direction(&diamond), None);
+    }
+
+    #[test]
+    fn subtrees_do_not_overlap() {
+        //       0
+        //    1     2
+        //  3   4     5
+        // 6 7
+        let graph = tree(
+            &[(0, 1), (0, 2), (1, 3), (1, 4), (2, 5), (3, 6), (3, 7)],
+            &[0, 1, 1, 2, 2, 2, 3, 3],
+        );
+        let x = calculate_coordinates(&graph, Outgoing, 10);
+        let mut by_rank = HashMap::<i32, Vec<isize>>::new();
+        for (v, x) in &x {
+            by_rank.entry(graph[*v].rank).or_default().push(*x);
+        }
+        for xs in by_rank.values_mut() {
+            xs.sort();
+            assert!(xs.windows(2).all(|w| w[1] - w[0] >= 10));
+        }
+        // parents are centered above their children
+        let x = |v: u32| x[&NodeIndex::new(v as usize)];
+        assert_eq!(x(3), (x(6) + x(7)) / 2);
+        assert_eq!(x(1), (x(3) + x(4)) / 2);
+        assert_eq!(x(2), x(5));
+        // children keep their order
+        assert!(x(1) < x(2) && x(3) < x(4) && x(4) < x(5) && x(6) < x(7));
+    }
+}
@@ -0,0 +1,939 @@
+//! Command line interface for calculating layouts.
+//!
+//! Reads a graph in the DOT language or as an edge list and writes its layout
+//! as svg, in the `plain` format of graphviz, as json or drawn as text.
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::io::Read;
+use std::iter::Peekable;
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::CharIndices;
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use rust_sugiyama::configure::{Config, CrossingMinimization, RankingType};
+use rust_sugiyama::render::ascii;
+use rust_sugiyama::{from_graph, graphviz, Layouts};
+
+static USAGE: &str = "\
+Usage: sugiyama [OPTIONS] [INPUT]
+
+Reads a graph from INPUT, or stdin if it is omitted or '-', and writes its layout.
+
+Options:
+  -i, --input-format <dot|edges>        format of the input, derived from the extension
+                                        of INPUT by default (.dot and .gv are dot)
+  -f, --format <svg|plain|json|ascii>   format of the output, derived from the extension
+                                        of the output file by default, otherwise svg
+  -o, --output <FILE>                   write the layout to FILE instead of stdout
+      --minimum-length <N>              minimum edge length between layers
+      --vertex-spacing <N>              minimum spacing between vertices on the same layer
+      --rank-spacing <N>                minimum spacing between layers, the vertex spacing
+                                        by default
+      --dummy-vertices <y|n>            include dummy vertices when calculating the layout
+      --dummy-size <F>                  size of dummy vertices, 1 >= F > 0
+      --ranking-type <original|minimize|up|down|min_width|optimal>
+                                        how vertices are placed vertically, optimal
+                                        needs the lp feature
+      --crossing-minimization <barycenter|median|best>
+                                        heuristic used for crossing reduction
+      --transpose <y|n>                 use transpose to further reduce crossings
+      --time-budget <MS>                stop ranking and crossing reduction after MS
+                                        milliseconds and use the best result so far
+      --max-ranking-iterations <N>      maximum number of network simplex iterations
+      --max-sweeps <N>                  maximum number of crossing reduction sweeps
+      --patience <N>                    stop crossing reduction after N sweeps without
+                                        fewer crossings
+      --max-transpose-iterations <N>    maximum number of transpose iterations per sweep
+      --shuffle-transpose <y|n>         visit vertices in a random order in each pass
+                                        of transpose
+      --stable-ties <y|n>               place vertices which are added first further left
+                                        if crossing minimization can't tell them apart
+      --community-ordering <y|n>        start crossing reduction from ranks ordered by
+                                        communities of densely connected vertices
+      --seed <N>                        make arbitrary choices of crossing reduction
+                                        randomly, reproducible for the same seed
+      --restarts <N>                    repeat crossing reduction N times from random
+                                        initial orders and keep the best one
+      --multilevel <y|n>                order a coarsened graph first, for large graphs
+      --transitive-reduction <y|n>      leave out edges which are implied by longer paths
+      --condense-components <y|n>       lay out strongly connected components on their own
+      --contract-chains <y|n>           order paths of vertices like a single long edge
+      --commit-graph <y|n>              one vertex per row in as few columns as possible
+      --radial <y|n>                    place ranks on concentric circles
+      --aspect-ratio <R>                trade the height of the layout against its width,
+                                        so width / height gets close to R
+      --max-layer-width <N>             push vertices into lower ranks until no rank
+                                        contains more than N vertices
+      --promote-vertices <y|n>          move vertices up if this saves dummy vertices
+      --config <FILE>                   read configuration values from a .toml or .json
+                                        file, requires the json or toml feature
+      --time                            print the time needed for the layout to stderr
+      --stats                           print the number of crossings, reversed edges,
+                                        dummy vertices and bends of each component to stderr
+  -h, --help                            print this message
+
+Configuration values are read from the environment variables used by
+CoordinatesBuilder::configure_from_env first, and then from the options.
+
+An edge list contains one edge per line, given as two vertex names separated by
+whitespace or '->'. Lines containing only one name declare a vertex, lines starting
+with '#' are ignored.
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InputFormat {
+    Dot,
+    Edges,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Svg,
+    Plain,
+    Json,
+    Ascii,
+}
+
+#[derive(Debug)]
+struct Options {
+    input: Option<String>,
+    input_format: Option<InputFormat>,
+    output: Option<String>,
+    format: Option<OutputFormat>,
+    config: Config,
+    time: bool,
+    stats: bool,
+    help: bool,
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match parse_args(&args).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("sugiyama: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if options.help {
+        print!("{USAGE}");
+        return Ok(());
+    }
+
+    let input = match options.input.as_deref() {
+        None | Some("-") => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("unable to read stdin: {e}"))?;
+            input
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("unable to read {path}: {e}"))?
+        }
+    };
+    let input_format =
+        options
+            .input_format
+            .unwrap_or(match extension(options.input.as_deref()).as_deref() {
+                Some("dot") | Some("gv") => InputFormat::Dot,
+                _ => InputFormat::Edges,
+            });
+    let graph = match input_format {
+        InputFormat::Dot => read_dot(&input).map_err(|e| e.to_string())?,
+        InputFormat::Edges => parse_edge_list(&input)?,
+    };
+
+    let start = Instant::now();
+    let (layouts, stats) = from_graph(&graph)
+        .with_config(options.config)
+        .build_with_stats();
+    if options.time {
+        eprintln!(
+            "layout of {} vertices and {} edges: {}ms",
+            graph.node_count(),
+            graph.edge_count(),
+            start.elapsed().as_millis()
+        );
+    }
+    if options.stats {
+        for (i, s) in stats.iter().enumerate() {
+            eprintln!(
+                "component {i}: {} crossings, {} reversed edges, {} dummy vertices, {} bends",
+                s.crossings, s.reversed_edges, s.dummy_vertices, s.bends
+            );
+        }
+    }
+
+    let format = options
+        .format
+        .unwrap_or(match extension(options.output.as_deref()).as_deref() {
+            Some("plain") | Some("txt") => OutputFormat::Plain,
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Svg,
+        });
+    let spacing = options.config.vertex_spacing.max(0.).round() as usize;
+    let output = match format {
+        OutputFormat::Svg => write_svg(&graph, &layouts, spacing),
+        OutputFormat::Plain => graphviz::write_plain(&graph, &layouts, spacing),
+        OutputFormat::Json => write_json(&graph, &layouts),
+        OutputFormat::Ascii => ascii::draw(&graph, &layouts),
+    };
+
+    match options.output {
+        Some(path) => {
+            std::fs::write(&path, output).map_err(|e| format!("unable to write {path}: {e}"))
+        }
+        None => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
+
+fn extension(path: Option<&str>) -> Option<String> {
+    path.and_then(|p| Path::new(p).extension())
+        .map(|e| e.to_string_lossy().to_lowercase())
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        input: None,
+        input_format: None,
+        output: None,
+        format: None,
+        config: Config::new_from_env(),
+        time: false,
+        stats: false,
+        help: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if name.len() > 1 && name.starts_with('-') {
+            if matches!(name, "-h" | "--help") {
+                options.help = true;
+                continue;
+            }
+            if name == "--time" {
+                options.time = true;
+                continue;
+            }
+            if name == "--stats" {
+                options.stats = true;
+                continue;
+            }
+            let value = match inline {
+                Some(v) => v,
+                None => args
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for {name}"))?,
+            };
+            let invalid = |e: &dyn std::fmt::Display| format!("invalid value for {name}: {e}");
+            let config = &mut options.config;
+            match name {
+                "-i" | "--input-format" => {
+                    options.input_format = Some(match value.as_str() {
+                        "dot" => InputFormat::Dot,
+                        "edges" => InputFormat::Edges,
+                        v => return Err(invalid(&v)),
+                    })
+                }
+                "-f" | "--format" => {
+                    options.format = Some(match value.as_str() {
+                        "svg" => OutputFormat::Svg,
+                        "plain" => OutputFormat::Plain,
+                        "json" => OutputFormat::Json,
+                        "ascii" => OutputFormat::Ascii,
+                        v => return Err(invalid(&v)),
+                    })
+                }
+                "-o" | "--output" => options.output = Some(value),
+                "--minimum-length" => {
+                    config.minimum_length = value.parse().map_err(|e| invalid(&e))?
+                }
+                "--vertex-spacing" => {
+                    config.vertex_spacing = parse_spacing(&value).ok_or(invalid(&value))?
+                }
+                "--rank-spacing" => {
+                    config.rank_spacing = Some(parse_spacing(&value).ok_or(invalid(&value))?)
+                }
+                "--dummy-vertices" => {
+                    config.dummy_vertices = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--dummy-size" => config.dummy_size = value.parse().map_err(|e| invalid(&e))?,
+                "--ranking-type" => {
+                    config.ranking_type = RankingType::try_from(value).map_err(|e| invalid(&e))?
+                }
+                "--crossing-minimization" => {
+                    config.c_minimization =
+                        CrossingMinimization::try_from(value).map_err(|e| invalid(&e))?
+                }
+                "--transpose" => config.transpose = parse_bool(&value).ok_or(invalid(&value))?,
+                "--time-budget" => {
+                    config.time_budget = Some(Duration::from_millis(
+                        value.parse().map_err(|e| invalid(&e))?,
+                    ))
+                }
+                "--max-ranking-iterations" => {
+                    config.max_ranking_iterations = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "--max-sweeps" => config.max_sweeps = Some(value.parse().map_err(|e| invalid(&e))?),
+                "--patience" => config.patience = value.parse().map_err(|e| invalid(&e))?,
+                "--max-transpose-iterations" => {
+                    config.max_transpose_iterations = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "--shuffle-transpose" => {
+                    config.shuffle_transpose = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--stable-ties" => {
+                    config.stable_ties = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--community-ordering" => {
+                    config.community_ordering = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--seed" => config.seed = Some(value.parse().map_err(|e| invalid(&e))?),
+                "--restarts" => config.restarts = value.parse().map_err(|e| invalid(&e))?,
+                "--multilevel" => config.multilevel = parse_bool(&value).ok_or(invalid(&value))?,
+                "--transitive-reduction" => {
+                    config.transitive_reduction = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--condense-components" => {
+                    config.condense_components = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--contract-chains" => {
+                    config.contract_chains = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--commit-graph" => {
+                    config.commit_graph = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                "--radial" => config.radial = parse_bool(&value).ok_or(invalid(&value))?,
+                "--aspect-ratio" => {
+                    config.aspect_ratio = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "--max-layer-width" => {
+                    config.max_layer_width = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "--promote-vertices" => {
+                    config.promote_vertices = parse_bool(&value).ok_or(invalid(&value))?
+                }
+                #[cfg(any(feature = "json", feature = "toml"))]
+                "--config" => *config = config.read_file(&value).map_err(|e| e.to_string())?,
+                _ => return Err(format!("unknown option {name}, see --help")),
+            }
+        } else if options.input.is_none() {
+            options.input = Some(arg.clone());
+        } else {
+            return Err(format!("unexpected argument {arg}, see --help"));
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "y" => Some(true),
+        "n" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses a spacing, which may be fractional but not negative.
+fn parse_spacing(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.)
+}
+
+fn parse_edge_list(input: &str) -> Result<StableDiGraph<String, ()>, String> {
+    let mut graph = StableDiGraph::new();
+    let mut ids = HashMap::new();
+    let mut node = |graph: &mut StableDiGraph<String, ()>, id: &str| {
+        *ids.entry(id.to_string())
+            .or_insert_with(|| graph.add_node(id.to_string()))
+    };
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let names = line
+            .split(|c: char| c.is_whitespace())
+            .filter(|s| !s.is_empty() && *s != "->")
+            .collect::<Vec<_>>();
+        match names.as_slice() {
+            [v] => {
+                node(&mut graph, v);
+            }
+            [tail, head] => {
+                let tail = node(&mut graph, tail);
+                let head = node(&mut graph, head);
+                graph.add_edge(tail, head, ());
+            }
+            _ => return Err(format!("invalid edge in line {}: {line}", i + 1)),
+        }
+    }
+    Ok(graph)
+}
+
+/// Errors which may occur while reading a DOT file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DotError {
+    /// A token was found where it is not allowed, contains the token.
+    UnexpectedToken(String),
+    /// The input ended before the graph was closed.
+    UnexpectedEnd,
+    /// A quoted string, html string or comment was not terminated.
+    Unterminated(&'static str),
+}
+
+impl Display for DotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedToken(t) => write!(f, "unexpected token in dot file: '{t}'"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of dot file"),
+            Self::Unterminated(what) => write!(f, "unterminated {what} in dot file"),
+        }
+    }
+}
+
+impl std::error::Error for DotError {}
+
+/// Reads a graph in the DOT language, whose node weights are the node ids.
+///
+/// Only the structure of the graph is read, attributes are ignored. Nodes are
+/// added in the order they first appear in. Edges of undirected graphs are
+/// directed from the left to the right hand side, edges from or to subgraphs
+/// connect every node of the subgraph. Only the first graph of the file is read.
+fn read_dot(input: &str) -> Result<StableDiGraph<String, ()>, DotError> {
+    info!(target: "graphviz", "Reading dot file of {} bytes", input.len());
+    let mut parser = DotParser {
+        tokens: tokenize(input)?.into_iter().peekable(),
+        graph: StableDiGraph::new(),
+        ids: HashMap::new(),
+    };
+    parser.parse_graph()?;
+    debug!(target: "graphviz",
+        "Read {} nodes and {} edges",
+        parser.graph.node_count(),
+        parser.graph.edge_count());
+    Ok(parser.graph)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Id(String),
+    Punct(char),
+    EdgeOp,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{id}"),
+            Self::Punct(c) => write!(f, "{c}"),
+            Self::EdgeOp => write!(f, "->"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, DotError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    let mut line_start = true;
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            // lines starting with '#' are output of the c preprocessor
+            '#' if line_start => {
+                skip_until(&mut chars, "\n");
+                continue;
+            }
+            '/' if chars.next_if(|(_, c)| *c == '/').is_some() => {
+                skip_until(&mut chars, "\n");
+                line_start = true;
+                continue;
+            }
+            '/' if chars.next_if(|(_, c)| *c == '*').is_some() => {
+                if !skip_until(&mut chars, "*/") {
+                    return Err(DotError::Unterminated("comment"));
+                }
+            }
+            '-' if chars.next_if(|(_, c)| *c == '>' || *c == '-').is_some() => {
+                tokens.push(Token::EdgeOp)
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => tokens.push(Token::Punct(c)),
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, '"')) => id.push('"'),
+                            // line continuation
+                            Some((_, '\n')) => (),
+                            Some((_, c)) => {
+                                id.push('\\');
+                                id.push(c);
+                            }
+                            None => return Err(DotError::Unterminated("string")),
+                        },
+                        Some((_, c)) => id.push(c),
+                        None => return Err(DotError::Unterminated("string")),
+                    }
+                }
+                tokens.push(Token::Id(id));
+            }
+            '<' => {
+                let mut depth = 1;
+                let start = i + 1;
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '<')) => depth += 1,
+                        Some((j, '>')) => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break j;
+                            }
+                        }
+                        Some(_) => (),
+                        None => return Err(DotError::Unterminated("html string")),
+                    }
+                };
+                tokens.push(Token::Id(input[start..end].to_string()));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut end = i + c.len_utf8();
+                while let Some((j, c)) = chars
+                    .next_if(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '.' || *c == '-')
+                {
+                    end = j + c.len_utf8();
+                }
+                tokens.push(Token::Id(input[i..end].to_string()));
+            }
+            c => return Err(DotError::UnexpectedToken(c.to_string())),
+        }
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+    }
+    Ok(tokens)
+}
+
+/// Advances the iterator past `pattern`, returns false if it was not found.
+fn skip_until(chars: &mut Peekable<CharIndices>, pattern: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let mut skipped = Vec::new();
+    for (_, c) in chars.by_ref() {
+        skipped.push(c);
+        if skipped.ends_with(&pattern) {
+            return true;
+        }
+    }
+    false
+}
+
+struct DotParser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+    graph: StableDiGraph<String, ()>,
+    ids: HashMap<String, NodeIndex>,
+}
+
+impl DotParser {
+    fn next(&mut self) -> Result<Token, DotError> {
+        self.tokens.next().ok_or(DotError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), DotError> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            t => Err(DotError::UnexpectedToken(t.to_string())),
+        }
+    }
+
+    fn next_is(&mut self, token: &Token) -> bool {
+        self.tokens.next_if_eq(token).is_some()
+    }
+
+    fn next_is_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|t| matches!(t, Token::Id(id) if id.eq_ignore_ascii_case(keyword)))
+            .is_some()
+    }
+
+    fn node(&mut self, id: String) -> NodeIndex {
+        if let Some(v) = self.ids.get(&id) {
+            return *v;
+        }
+        let v = self.graph.add_node(id.clone());
+        self.ids.insert(id, v);
+        v
+    }
+
+    fn parse_graph(&mut self) -> Result<(), DotError> {
+        self.next_is_keyword("strict");
+        if !self.next_is_keyword("digraph") && !self.next_is_keyword("graph") {
+            return Err(self.tokens.next().map_or(DotError::UnexpectedEnd, |t| {
+                DotError::UnexpectedToken(t.to_string())
+            }));
+        }
+        self.tokens.next_if(|t| matches!(t, Token::Id(_)));
+        self.expect('{')?;
+        self.parse_statements()?;
+        Ok(())
+    }
+
+    /// Parses statements until the closing brace, returns the nodes that
+    /// were mentioned.
+    fn parse_statements(&mut self) -> Result<HashSet<NodeIndex>, DotError> {
+        let mut nodes = HashSet::new();
+        loop {
+            match self.next()? {
+                Token::Punct('}') => return Ok(nodes),
+                Token::Punct(';') => (),
+                Token::Id(id)
+                    if ["graph", "node", "edge"]
+                        .iter()
+                        .any(|k| id.eq_ignore_ascii_case(k))
+                        && self.tokens.peek() == Some(&Token::Punct('[')) =>
+                {
+                    self.parse_attributes()?
+                }
+                Token::Id(_) if self.next_is(&Token::Punct('=')) => {
+                    self.next()?;
+                }
+                t => {
+                    let mut tail = self.parse_operand(t)?;
+                    nodes.extend(&tail);
+                    while self.next_is(&Token::EdgeOp) {
+                        let t = self.next()?;
+                        let head = self.parse_operand(t)?;
+                        nodes.extend(&head);
+                        for v in &tail {
+                            for w in &head {
+                                self.graph.add_edge(*v, *w, ());
+                            }
+                        }
+                        tail = head;
+                    }
+                    if self.tokens.peek() == Some(&Token::Punct('[')) {
+                        self.parse_attributes()?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parses a node id or a subgraph, starting with `token`.
+    fn parse_operand(&mut self, token: Token) -> Result<Vec<NodeIndex>, DotError> {
+        match token {
+            Token::Punct('{') => self.parse_statements().map(sorted),
+            Token::Id(id) if id.eq_ignore_ascii_case("subgraph") => {
+                self.tokens.next_if(|t| matches!(t, Token::Id(_)));
+                self.expect('{')?;
+                self.parse_statements().map(sorted)
+            }
+            Token::Id(id) => {
+                // skip the port
+                while self.next_is(&Token::Punct(':')) {
+                    self.next()?;
+                }
+                Ok(vec![self.node(id)])
+            }
+            t => Err(DotError::UnexpectedToken(t.to_string())),
+        }
+    }
+
+    fn parse_attributes(&mut self) -> Result<(), DotError> {
+        while self.next_is(&Token::Punct('[')) {
+            loop {
+                match self.next()? {
+                    Token::Punct(']') => break,
+                    Token::Id(_) | Token::Punct(',') | Token::Punct(';') | Token::Punct('=') => (),
+                    t => return Err(DotError::UnexpectedToken(t.to_string())),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sorted(nodes: HashSet<NodeIndex>) -> Vec<NodeIndex> {
+    let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+    nodes.sort();
+    nodes
+}
+
+/// Returns the coordinates of each vertex, with y increasing downwards and
+/// components placed next to each other, as well as the total width and height.
+fn arrange(
+    layouts: &Layouts<NodeIndex>,
+    spacing: isize,
+) -> (HashMap<NodeIndex, (isize, isize)>, isize, isize) {
+    let mut coordinates = HashMap::new();
+    let (mut width, mut height) = (0, 0);
+    for (layout, ..) in layouts {
+        let Some(min_x) = layout.iter().map(|(_, (x, _))| *x).min() else {
+            continue;
+        };
+        let max_x = layout.iter().map(|(_, (x, _))| *x).max().unwrap();
+        for (v, (x, y)) in layout {
+            coordinates.insert(*v, (width + x - min_x + spacing, spacing - y));
+            height = height.max(2 * spacing - y);
+        }
+        width += max_x - min_x + spacing;
+    }
+    (coordinates, width + spacing, height)
+}
+
+fn write_svg(
+    graph: &StableDiGraph<String, ()>,
+    layouts: &Layouts<NodeIndex>,
+    vertex_spacing: usize,
+) -> String {
+    let spacing = vertex_spacing.max(1) as isize;
+    let (coordinates, width, height) = arrange(layouts, spacing);
+    let radius = spacing as f64 / 4.;
+
+    let mut s = String::new();
+    let _ = writeln!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        if let (Some((x1, y1)), Some((x2, y2))) = (coordinates.get(&tail), coordinates.get(&head)) {
+            let _ = writeln!(
+                s,
+                r#"  <line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="black"/>"#
+            );
+        }
+    }
+    for v in graph.node_indices() {
+        if let Some((x, y)) = coordinates.get(&v) {
+            let _ = writeln!(
+                s,
+                r#"  <circle cx="{x}" cy="{y}" r="{radius}" fill="lightgrey" stroke="black"/>"#
+            );
+            let _ = writeln!(
+                s,
+                r#"  <text x="{x}" y="{y}" font-size="{radius}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                escape_xml(&graph[v])
+            );
+        }
+    }
+    s.push_str("</svg>\n");
+    s
+}
+
+fn write_json(graph: &StableDiGraph<String, ()>, layouts: &Layouts<NodeIndex>) -> String {
+    let mut s = String::from("[");
+    for (i, (layout, width, height)) in layouts.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        let _ = write!(
+            s,
+            "\n  {{\"width\": {width}, \"height\": {height}, \"coordinates\": {{"
+        );
+        for (j, (v, (x, y))) in layout.iter().enumerate() {
+            if j > 0 {
+                s.push(',');
+            }
+            let _ = write!(s, "\n    \"{}\": [{x}, {y}]", escape_json(&graph[*v]));
+        }
+        s.push_str("\n  }}");
+    }
+    s.push_str("\n]\n");
+    s
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_args, parse_edge_list, read_dot, write_json, DotError, InputFormat, OutputFormat,
+    };
+    use petgraph::stable_graph::StableDiGraph;
+    use rust_sugiyama::configure::RankingType;
+    use rust_sugiyama::from_graph;
+    use std::time::Duration;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn edges(g: &StableDiGraph<String, ()>) -> Vec<(&str, &str)> {
+        g.edge_indices()
+            .map(|e| g.edge_endpoints(e).unwrap())
+            .map(|(t, h)| (g[t].as_str(), g[h].as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn read_dot_simple() {
+        let g = read_dot(
+            r#"
+            # preprocessor line
+            strict digraph "G" {
+                // a comment
+                node [shape=box];
+                rankdir = LR;
+                a -> b -> "c d" [label="x"]; /* another
+                comment */
+                b:p1:n -> a
+                e
+            }"#,
+        )
+        .unwrap();
+        let names = g.node_weights().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c d", "e"]);
+        assert_eq!(edges(&g), [("a", "b"), ("b", "c d"), ("b", "a")]);
+    }
+
+    #[test]
+    fn read_dot_subgraphs() {
+        let g = read_dot("graph { a -- { b c }; subgraph s { d } -- e }").unwrap();
+        assert_eq!(edges(&g), [("a", "b"), ("a", "c"), ("d", "e")]);
+    }
+
+    #[test]
+    fn read_dot_errors() {
+        assert_eq!(
+            read_dot("digraph { a -> ").err(),
+            Some(DotError::UnexpectedEnd)
+        );
+        assert_eq!(
+            read_dot("digraph { \"a }").err(),
+            Some(DotError::Unterminated("string"))
+        );
+        assert_eq!(
+            read_dot("tree { a }").err(),
+            Some(DotError::UnexpectedToken("tree".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = parse_args(&args(&[
+            "graph.txt",
+            "-i",
+            "dot",
+            "--format=plain",
+            "--vertex-spacing",
+            "30",
+            "--rank-spacing=50",
+            "--ranking-type=up",
+            "--transpose",
+            "n",
+            "--time",
+            "--stats",
+            "--time-budget=250",
+            "--max-sweeps",
+            "8",
+            "--patience=2",
+            "--shuffle-transpose=y",
+            "--stable-ties=y",
+            "--community-ordering=y",
+            "--seed=3",
+            "--restarts",
+            "4",
+            "--multilevel=y",
+            "--transitive-reduction=y",
+            "--condense-components=y",
+            "--contract-chains=y",
+            "--commit-graph=y",
+            "--radial=y",
+            "--aspect-ratio=1.5",
+            "--max-layer-width=6",
+            "--promote-vertices=y",
+        ]))
+        .unwrap();
+        assert_eq!(options.input.as_deref(), Some("graph.txt"));
+        assert_eq!(options.input_format, Some(InputFormat::Dot));
+        assert_eq!(options.format, Some(OutputFormat::Plain));
+        assert_eq!(options.config.vertex_spacing, 30.);
+        assert_eq!(options.config.rank_spacing, Some(50.));
+        assert_eq!(options.config.ranking_type, RankingType::Up);
+        assert!(!options.config.transpose);
+        assert!(options.time);
+        assert!(options.stats);
+        assert_eq!(options.config.time_budget, Some(Duration::from_millis(250)));
+        assert_eq!(options.config.max_sweeps, Some(8));
+        assert_eq!(options.config.patience, 2);
+        assert!(options.config.shuffle_transpose);
+        assert!(options.config.stable_ties);
+        assert!(options.config.community_ordering);
+        assert_eq!(options.config.max_ranking_iterations, None);
+        assert_eq!(options.config.seed, Some(3));
+        assert_eq!(options.config.restarts, 4);
+        assert!(options.config.multilevel);
+        assert!(options.config.transitive_reduction);
+        assert!(options.config.condense_components);
+        assert!(options.config.contract_chains);
+        assert!(options.config.commit_graph);
+        assert!(options.config.radial);
+        assert_eq!(options.config.aspect_ratio, Some(1.5));
+        assert_eq!(options.config.max_layer_width, Some(6));
+        assert!(options.config.promote_vertices);
+    }
+
+    #[test]
+    fn parse_invalid_options() {
+        assert!(parse_args(&args(&["--vertex-spacing", "-1"])).is_err());
+        assert!(parse_args(&args(&["--dummy-vertices", "yes"])).is_err());
+        assert!(parse_args(&args(&["--output"])).is_err());
+        assert!(parse_args(&args(&["--unknown", "1"])).is_err());
+        assert!(parse_args(&args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn edge_list() {
+        let g = parse_edge_list("# comment\na b\nb -> c\n\nd\n").unwrap();
+        assert_eq!(g.node_count(), 4);
+        assert_eq!(g.edge_count(), 2);
+        assert!(parse_edge_list("a b c").is_err());
+    }
+
+    #[test]
+    fn json_output() {
+        let g = parse_edge_list("a \"b\"").unwrap();
+        let json = write_json(&g, &from_graph(&g).build());
+        assert!(json.contains(r#""a": [0, 0]"#));
+        assert!(json.contains(r#""\"b\"": [0, -10]"#));
+    }
+}
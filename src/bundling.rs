@@ -0,0 +1,176 @@
+//! Bundling of edges which run between the same regions of a layout.
+//!
+//! Edges whose tails are close to each other and whose heads are close to
+//! each other are drawn through a shared corridor, which reduces the clutter
+//! of dense graphs where many long edges run in parallel.
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{bundling::Bundling, from_edges};
+//!
+//! let edges = [(0, 1), (1, 2), (2, 3), (0, 3), (0, 3)];
+//! let (_, geometry) = from_edges(&edges).build_with_geometry();
+//! let bundling = Bundling::new(&geometry[0].edges, 10);
+//! // both edges from 0 to 3 pass through the same points
+//! assert_eq!(bundling.bundles[0].edges, vec![3, 4]);
+//! assert_eq!(bundling.routes[3].points, bundling.routes[4].points);
+//! ```
+use std::collections::HashMap;
+
+use crate::EdgeRoute;
+
+type Coordinates = (isize, isize);
+
+/// Edges which share a corridor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeBundle {
+    /// Indices of the edges in the bundle, in ascending order.
+    pub edges: Vec<usize>,
+    /// Points of the corridor, which all edges of the bundle pass through
+    /// between their own endpoints. Empty for edges between adjacent ranks.
+    pub corridor: Vec<Coordinates>,
+}
+
+/// Routes of edges, where edges between the same regions are bundled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bundling {
+    /// Routes of all edges in the given order, where the points between the
+    /// endpoints of bundled edges are replaced by the corridor of their bundle.
+    pub routes: Vec<EdgeRoute>,
+    /// Bundles of at least two edges, ordered by their first edge.
+    pub bundles: Vec<EdgeBundle>,
+}
+
+impl Bundling {
+    /// Bundles the edges of `routes`, as returned in the
+    /// [Geometry](crate::Geometry) of a layout.
+    ///
+    /// Endpoints at the same y-coordinate which are at most `radius` apart
+    /// from their neighbors form a region, and edges which start in the same
+    /// region, end in the same region and span the same number of ranks are
+    /// bundled. The corridor of a bundle is the average of the routes of its
+    /// edges, so it may pass closer to vertices than the original routes.
+    pub fn new(routes: &[EdgeRoute], radius: usize) -> Self {
+        let regions = regions(routes, radius as isize);
+        let mut groups = HashMap::<_, Vec<usize>>::new();
+        for (i, route) in routes.iter().enumerate() {
+            let (Some(tail), Some(head)) = (route.points.first(), route.points.last()) else {
+                continue;
+            };
+            groups
+                .entry((regions[tail], regions[head], route.points.len()))
+                .or_default()
+                .push(i);
+        }
+        let mut groups = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect::<Vec<_>>();
+        groups.sort();
+
+        let mut bundling = Self {
+            routes: routes.to_vec(),
+            bundles: Vec::new(),
+        };
+        for group in groups {
+            let n = routes[group[0]].points.len();
+            let corridor = (1..n - 1)
+                .map(|i| {
+                    let x = group.iter().map(|r| routes[*r].points[i].0).sum::<isize>();
+                    (x / group.len() as isize, routes[group[0]].points[i].1)
+                })
+                .collect::<Vec<_>>();
+            for r in &group {
+                bundling.routes[*r].points[1..n - 1].copy_from_slice(&corridor);
+            }
+            let mut edges = group.iter().map(|r| routes[*r].edge).collect::<Vec<_>>();
+            edges.sort();
+            bundling.bundles.push(EdgeBundle { edges, corridor });
+        }
+        bundling
+    }
+}
+
+/// Assigns each endpoint of the routes to a region, given by its y-coordinate
+/// and the index of the region at that y-coordinate.
+fn regions(routes: &[EdgeRoute], radius: isize) -> HashMap<Coordinates, (isize, usize)> {
+    let mut endpoints = routes
+        .iter()
+        .flat_map(|route| route.points.first().into_iter().chain(route.points.last()))
+        .map(|(x, y)| (*y, *x))
+        .collect::<Vec<_>>();
+    endpoints.sort();
+    endpoints.dedup();
+    let mut regions = HashMap::new();
+    let mut region = 0;
+    for (i, (y, x)) in endpoints.iter().enumerate() {
+        if i > 0 {
+            let (prev_y, prev_x) = endpoints[i - 1];
+            if prev_y != *y {
+                region = 0;
+            } else if x - prev_x > radius {
+                region += 1;
+            }
+        }
+        regions.insert((*x, *y), (*y, region));
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bundling, EdgeBundle};
+    use crate::EdgeRoute;
+
+    fn route(edge: usize, points: &[(isize, isize)]) -> EdgeRoute {
+        EdgeRoute {
+            edge,
+            points: points.to_vec(),
+        }
+    }
+
+    #[test]
+    fn bundle_edges_between_close_endpoints() {
+        let routes = [
+            route(0, &[(0, 0), (0, -10), (0, -20), (5, -30)]),
+            route(1, &[(5, 0), (10, -10), (20, -20), (10, -30)]),
+            // starts in the same region, but ends far away
+            route(2, &[(10, 0), (20, -10), (40, -20), (60, -30)]),
+            route(3, &[(40, 0), (40, -10)]),
+        ];
+        let bundling = Bundling::new(&routes, 10);
+        assert_eq!(
+            bundling.bundles,
+            vec![EdgeBundle {
+                edges: vec![0, 1],
+                corridor: vec![(5, -10), (10, -20)],
+            }]
+        );
+        assert_eq!(bundling.routes[0].points[1..3], [(5, -10), (10, -20)]);
+        assert_eq!(bundling.routes[1].points[1..3], [(5, -10), (10, -20)]);
+        // endpoints and unbundled edges keep their routes
+        assert_eq!(bundling.routes[0].points[0], (0, 0));
+        assert_eq!(bundling.routes[1].points[3], (10, -30));
+        assert_eq!(bundling.routes[2..], routes[2..]);
+    }
+
+    #[test]
+    fn bundle_parallel_edges() {
+        let routes = [
+            route(0, &[(0, 0), (0, -10)]),
+            route(1, &[(0, 0), (0, -10)]),
+            route(2, &[(0, 0), (10, -10), (0, -20)]),
+        ];
+        let bundling = Bundling::new(&routes, 0);
+        assert_eq!(
+            bundling.bundles,
+            vec![EdgeBundle {
+                edges: vec![0, 1],
+                corridor: Vec::new(),
+            }]
+        );
+        assert_eq!(bundling.routes, routes);
+    }
+}
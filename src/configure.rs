@@ -1,11 +1,18 @@
-use std::{env, marker::PhantomData};
+use std::{collections::HashMap, env, marker::PhantomData, sync::Arc, time::Duration};
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use log::{error, trace};
-use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use log::{error, trace, warn};
+use petgraph::graph::DiGraph;
+use petgraph::stable_graph::{EdgeIndex, IndexType, NodeIndex, StableDiGraph};
 
 use crate::{
-    algorithm::{self, Edge, Vertex},
-    Layouts,
+    algorithm::{self, Constraints, Edge, Vertex},
+    progress::{CancellationToken, Cancelled, Hooks, Phase, PhaseObserver, Progress},
+    Coordinate, Geometry, LayoutStats, Layouts, Orderings, Rankings,
 };
 
 // Default values for configuration
@@ -16,20 +23,103 @@ pub static RANKING_TYPE_DEFAULT: RankingType = RankingType::MinimizeEdgeLength;
 pub static C_MINIMIZATION_DEFAULT: CrossingMinimization = CrossingMinimization::Barycenter;
 pub static TRANSPOSE_DEFAULT: bool = true;
 pub static DUMMY_SIZE_DEFAULT: f64 = 1.0;
+pub static PATIENCE_DEFAULT: usize = 4;
+
+// Largest number of steps each subdivision is divided into, so fractional
+// spacings are kept exactly
+static MAX_SPACING_STEPS: usize = 100;
 
 static ENV_MINIMUM_LENGTH: &str = "RUST_GRAPH_MIN_LEN";
 static ENV_VERTEX_SPACING: &str = "RUST_GRAPH_V_SPACING";
+static ENV_RANK_SPACING: &str = "RUST_GRAPH_R_SPACING";
 static ENV_DUMMY_VERTICES: &str = "RUST_GRAPH_DUMMIES";
 static ENV_RANKING_TYPE: &str = "RUST_GRAPH_R_TYPE";
 static ENV_CROSSING_MINIMIZATION: &str = "RUST_GRAPH_CROSS_MIN";
 static ENV_TRANSPOSE: &str = "RUST_GRAPH_TRANSPOSE";
 static ENV_DUMMY_SIZE: &str = "RUST_GRAPH_DUMMY_SIZE";
+static ENV_MAX_SWEEPS: &str = "RUST_GRAPH_MAX_SWEEPS";
+
+pub trait IntoCoordinates {
+    /// Identifies the vertices of the input in the layouts.
+    type Key;
+
+    /// Returns the key of the vertex at `index`, given the keys stored by the
+    /// builder, which are only used by [Keyed] inputs.
+    fn key(keys: &[Self::Key], index: usize) -> Self::Key;
+}
+
+impl<V, E, Ix: IndexType> IntoCoordinates for StableDiGraph<V, E, Ix> {
+    type Key = NodeIndex<Ix>;
+
+    fn key(_: &[Self::Key], index: usize) -> Self::Key {
+        NodeIndex::new(index)
+    }
+}
+impl<V, E, Ix: IndexType> IntoCoordinates for DiGraph<V, E, Ix> {
+    type Key = NodeIndex<Ix>;
+
+    fn key(_: &[Self::Key], index: usize) -> Self::Key {
+        NodeIndex::new(index)
+    }
+}
+impl IntoCoordinates for &[(u32, u32)] {
+    type Key = usize;
+
+    fn key(_: &[Self::Key], index: usize) -> Self::Key {
+        index
+    }
+}
+impl IntoCoordinates for (&[u32], &[(u32, u32)]) {
+    type Key = usize;
+
+    fn key(_: &[Self::Key], index: usize) -> Self::Key {
+        index
+    }
+}
+
+/// Input whose vertices are identified by keys of type `K`, like the vertices
+/// of a `DiGraphMap`, see [from_graphmap](crate::from_graphmap).
+pub struct Keyed<K>(PhantomData<K>);
+
+impl<K: Copy> IntoCoordinates for Keyed<K> {
+    type Key = K;
+
+    fn key(keys: &[K], index: usize) -> K {
+        keys[index]
+    }
+}
+
+/// Graphs of petgraph, whose vertices and edges are identified by their
+/// [NodeIndex] and [EdgeIndex] in the layout, see [from_graph](crate::from_graph)
+/// and [from_digraph](crate::from_digraph).
+///
+/// The graphs may use any [IndexType], so indices of graphs declared with
+/// `usize` or `u64` indices don't need to be converted.
+pub trait PetgraphInput: IntoCoordinates {
+    type NodeWeight;
+    type Ix: IndexType;
+
+    /// Returns each vertex of the graph with its weight.
+    fn node_weights(&self) -> Vec<(NodeIndex<Self::Ix>, &Self::NodeWeight)>;
+}
+
+impl<V, E, Ix: IndexType> PetgraphInput for StableDiGraph<V, E, Ix> {
+    type NodeWeight = V;
+    type Ix = Ix;
 
-pub trait IntoCoordinates {}
+    fn node_weights(&self) -> Vec<(NodeIndex<Ix>, &V)> {
+        self.node_indices().map(|v| (v, &self[v])).collect()
+    }
+}
 
-impl<V, E> IntoCoordinates for StableDiGraph<V, E> {}
-impl IntoCoordinates for &[(u32, u32)] {}
-impl IntoCoordinates for (&[u32], &[(u32, u32)]) {}
+impl<V, E, Ix: IndexType> PetgraphInput for DiGraph<V, E, Ix> {
+    type NodeWeight = V;
+    type Ix = Ix;
+
+    fn node_weights(&self) -> Vec<(NodeIndex<Ix>, &V)> {
+        self.node_indices().map(|v| (v, &self[v])).collect()
+    }
+}
 
 macro_rules! read_env {
     ($field:expr, $cb:tt, $env:ident) => {
@@ -48,23 +138,242 @@ macro_rules! read_env {
 ///
 /// Struct fields are:
 /// 1. minimum_edge: length between layers
-/// 2. vertex_spacing: minimum spacing between vertices on the same layer
+/// 2. vertex_spacing: minimum spacing between vertices on the same layer, or
+///    between the borders of vertices with a size, like `nodesep` of graphviz
 /// 3. dummy_vertices: should dummie vertices be included when calculating the layout
 /// 4. ranking_type: defines how vertices are places vertically, see [RankingType]
 /// 5. c_minimization: which heuristic to use when minimizing edge crossings, see [CrossingMinimization]
 /// 6. transpose: try to further reduce crossings, by swaping vertices in a layer, may increase runtime significantly
+/// 7. time_budget: maximum time spent on ranking and crossing minimization,
+///    after which the best result found so far is used
+/// 8. max_ranking_iterations: maximum number of iterations of network simplex,
+///    if ranking type is [RankingType::MinimizeEdgeLength]
+/// 9. max_sweeps: maximum number of sweeps during crossing minimization
+/// 10. max_transpose_iterations: maximum number of iterations of transpose per sweep
+/// 11. seed: if set, arbitrary choices during crossing minimization (initial order,
+///     ties and transpose) are made randomly, reproducible for the same seed
+/// 12. restarts: number of additional runs of crossing minimization from different
+///     random initial orders, the order with the fewest crossings is kept
+/// 13. multilevel: speed up crossing minimization for large graphs, by first ordering a
+///     coarsened version of the graph and refining that order, ignores restarts
+/// 14. edge_routing: how the routes of edges are drawn, see [EdgeRouting]
+/// 15. back_edge_channels: route edges which were reversed to remove cycles in
+///     a channel beside the vertices they span, instead of through the ranks
+/// 16. transitive_reduction: leave out edges whose endpoints are connected by a
+///     longer path as well, which only add crossings
+/// 17. condense_components: lay out each strongly connected component on its
+///     own and place it as a single box in the layout of the remaining graph
+/// 18. contract_chains: order paths of vertices with a single incoming and
+///     outgoing edge like a single long edge
+/// 19. commit_graph: place each vertex in a row of its own and use as few
+///     columns as possible, keeping chains of first outgoing edges straight,
+///     like the history of a version control system
+/// 20. radial: place the ranks on concentric circles around the first rank,
+///     with the order within each rank going around the circle
+/// 21. aspect_ratio: if set, ranks are made narrower or the ranking is made
+///     shorter, so the ratio of the width to the height of the layout gets close to it
+/// 22. max_layer_width: if set, vertices are pushed into lower ranks until no
+///     rank contains more vertices, not counting dummy vertices
+/// 23. promote_vertices: after ranking, move vertices up if this saves dummy
+///     vertices, without adding ranks
+/// 24. rank_spacing: if set, minimum spacing between adjacent ranks, or between
+///     the borders of their vertices, like `ranksep` of graphviz. Otherwise
+///     ranks are the vertex spacing apart
+/// 25. padding: if set, border around the drawing of each component, which is
+///     moved so its [bounds](crate::Bounds) start at the origin and extend to
+///     the right and downwards
+/// 26. y_down: emit coordinates with the y-axis pointing down, like SVG, so the
+///     topmost rank has the smallest y-coordinate instead of the largest
+/// 27. mirror: mirror the layout horizontally within its bounds, so the order
+///     within each rank is reversed
+/// 28. grid: if set, x-coordinates of vertices are moved onto multiples of it,
+///     keeping their order and the distances between them
+/// 29. snap_y: move y-coordinates of ranks onto the grid as well
+/// 30. subdivisions: number of steps each unit of the coordinates is divided
+///     into while calculating the layout, so fractional sizes and distances
+///     are kept up to a step. Fractional spacings divide each step further
+///     until they are whole. Coordinates are rounded to whole units, unless
+///     they are built as floating point numbers, see [Coordinate](crate::Coordinate)
+/// 31. horizontal_compaction: after placing the vertices, remove horizontal
+///     space which no rank needs, without changing the order of the vertices
+///     or moving them closer than the vertex spacing
+/// 32. alignment: which alignments of the Brandes-Köpf coordinate assignment
+///     are used, and whether they are balanced, see [Alignment]
+/// 33. center_parents: after placing the vertices, move each vertex towards
+///     the center of its children, like in drawings of trees
+/// 34. minimize_area: after ranking, trade the number of ranks against the
+///     width of the widest rank, so the area of the layout gets as small as
+///     possible
+/// 35. minimize_bends: after placing the vertices, move dummy vertices onto
+///     the straight line between the endpoints of their edge where possible,
+///     see [LayoutStats::bends](crate::LayoutStats::bends)
+/// 36. patience: number of consecutive sweeps without fewer crossings, after
+///     which crossing minimization stops
+/// 37. shuffle_transpose: visit ranks and pairs of vertices in a random order
+///     in each pass of transpose, reproducible for the same seed
+/// 38. stable_ties: break ties of crossing minimization by the order in which
+///     vertices were added, instead of randomly or by the previous sweep
+/// 39. community_ordering: before crossing minimization, detect communities of
+///     densely connected vertices and order the initial ranks community by
+///     community, which helps dense, modular graphs. Not used for graphs
+///     which are ordered with multilevel crossing minimization
+///
+/// Coordinates given to the builder, like pins and previous layouts, as well as
+/// layouts given to the functions of this crate, like [validate](crate::validate)
+/// or [render](crate::render), use the default convention, with the y-axis
+/// pointing up and without mirroring.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct Config {
     pub minimum_length: u32,
-    pub vertex_spacing: usize,
+    pub vertex_spacing: f64,
     pub dummy_vertices: bool,
     pub dummy_size: f64,
     pub ranking_type: RankingType,
     pub c_minimization: CrossingMinimization,
     pub transpose: bool,
+    pub time_budget: Option<Duration>,
+    pub max_ranking_iterations: Option<usize>,
+    pub max_sweeps: Option<usize>,
+    pub max_transpose_iterations: Option<usize>,
+    pub seed: Option<u64>,
+    pub restarts: usize,
+    pub multilevel: bool,
+    pub edge_routing: EdgeRouting,
+    pub back_edge_channels: bool,
+    pub transitive_reduction: bool,
+    pub condense_components: bool,
+    pub contract_chains: bool,
+    pub commit_graph: bool,
+    pub radial: bool,
+    pub aspect_ratio: Option<f64>,
+    pub max_layer_width: Option<usize>,
+    pub promote_vertices: bool,
+    pub rank_spacing: Option<f64>,
+    pub padding: Option<usize>,
+    pub y_down: bool,
+    pub mirror: bool,
+    pub grid: Option<usize>,
+    pub snap_y: bool,
+    pub subdivisions: usize,
+    pub horizontal_compaction: bool,
+    pub alignment: Alignment,
+    pub center_parents: bool,
+    pub minimize_area: bool,
+    pub minimize_bends: bool,
+    pub patience: usize,
+    pub shuffle_transpose: bool,
+    pub stable_ties: bool,
+    pub community_ordering: bool,
 }
 
 impl Config {
+    /// Preset for large graphs or interactive use: ranks vertices as high as
+    /// possible, orders them with a few sweeps of the barycenter heuristic
+    /// without transpose, and coarsens large graphs first.
+    pub fn fast() -> Self {
+        Self {
+            ranking_type: RankingType::Up,
+            c_minimization: CrossingMinimization::Barycenter,
+            transpose: false,
+            max_sweeps: Some(8),
+            multilevel: true,
+            ..Default::default()
+        }
+    }
+
+    /// Preset close to dot: minimizes edge lengths and orders vertices with
+    /// the median heuristic and transpose, with bounded numbers of sweeps
+    /// and transpose iterations.
+    pub fn balanced() -> Self {
+        Self {
+            ranking_type: RankingType::MinimizeEdgeLength,
+            c_minimization: CrossingMinimization::Median,
+            transpose: true,
+            max_sweeps: Some(24),
+            max_transpose_iterations: Some(10),
+            ..Default::default()
+        }
+    }
+
+    /// Preset for small graphs or offline rendering: finds the optimal
+    /// ranking with the `lp` feature and minimizes edge lengths otherwise,
+    /// saves dummy vertices where possible and keeps the best of several
+    /// orderings with unlimited sweeps and transpose.
+    pub fn quality() -> Self {
+        Self {
+            #[cfg(feature = "lp")]
+            ranking_type: RankingType::Optimal,
+            #[cfg(not(feature = "lp"))]
+            ranking_type: RankingType::MinimizeEdgeLength,
+            c_minimization: CrossingMinimization::Median,
+            transpose: true,
+            restarts: 4,
+            promote_vertices: true,
+            ..Default::default()
+        }
+    }
+
+    /// Spacing between vertices in the same rank, rounded to whole units.
+    pub(crate) fn spacing(&self) -> usize {
+        self.vertex_spacing.max(0.).round() as usize
+    }
+
+    /// Spacing between adjacent ranks, which is the vertex spacing unless
+    /// [rank_spacing](Self::rank_spacing) is set, rounded to whole units.
+    pub(crate) fn rank_distance(&self) -> usize {
+        self.rank_spacing
+            .unwrap_or(self.vertex_spacing)
+            .max(0.)
+            .round() as usize
+    }
+
+    /// Number of layout units per unit of the coordinates, which is the
+    /// smallest multiple of the [subdivisions](Self::subdivisions) in which
+    /// the spacings are whole, or the subdivisions if there is none up to a
+    /// hundred steps per subdivision.
+    pub(crate) fn scale(&self) -> isize {
+        let subdivisions = self.subdivisions.max(1);
+        (1..=MAX_SPACING_STEPS)
+            .map(|steps| subdivisions * steps)
+            .find(|&s| self.spacings_are_whole(s))
+            .unwrap_or(subdivisions) as isize
+    }
+
+    /// Whether the vertex and rank spacing are whole in `scale` layout units.
+    fn spacings_are_whole(&self, scale: usize) -> bool {
+        [Some(self.vertex_spacing), self.rank_spacing]
+            .into_iter()
+            .flatten()
+            .all(|spacing| {
+                let units = spacing.max(0.) * scale as f64;
+                (units - units.round()).abs() < 1e-9
+            })
+    }
+
+    /// Copy of the config with all distances given in layout units, which
+    /// the layout is calculated in.
+    pub(crate) fn in_layout_units(self) -> Self {
+        let s = self.scale();
+        if !self.spacings_are_whole(s as usize) {
+            warn!(target: "initializing",
+                "Rounding the vertex spacing {} and rank spacing {:?} to steps of 1/{s}",
+                self.vertex_spacing, self.rank_spacing);
+        }
+        Self {
+            vertex_spacing: self.vertex_spacing * s as f64,
+            rank_spacing: self.rank_spacing.map(|r| r * s as f64),
+            padding: self.padding.map(|p| p * s as usize),
+            grid: self.grid.map(|g| g * s as usize),
+            subdivisions: 1,
+            ..self
+        }
+    }
+
     /// Create a new config by reading in environment variables.
     /// See [CoordinatesBuilder::configure_from_env] for a detailed description of environment variables.
     pub fn new_from_env() -> Self {
@@ -97,36 +406,289 @@ impl Config {
 
         read_env!(
             self.vertex_spacing,
-            (|x| x.parse::<usize>()),
+            (|x| x.parse::<f64>()),
             ENV_VERTEX_SPACING
         );
 
+        read_env!(
+            self.rank_spacing,
+            (|x| x.parse::<f64>().map(Some)),
+            ENV_RANK_SPACING
+        );
+
         read_env!(self.dummy_vertices, parse_bool, ENV_DUMMY_VERTICES);
 
         read_env!(self.dummy_size, (|x| x.parse::<f64>()), ENV_DUMMY_SIZE);
 
         read_env!(self.transpose, parse_bool, ENV_TRANSPOSE);
 
+        read_env!(
+            self.max_sweeps,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_SWEEPS
+        );
+
         self
     }
+
+    /// Updates the config with the values of a configuration file.
+    ///
+    /// The format is determined by the extension of the file, which is either
+    /// `.toml` or `.json`, depending on the enabled features. Keys are named
+    /// like the fields of [Config], values of [RankingType] and
+    /// [CrossingMinimization] are given in the same way as their environment
+    /// variables. Keys which are not present in the file keep their current value.
+    ///
+    /// # Example
+    /// ```toml
+    /// vertex_spacing = 20
+    /// ranking_type = "up"
+    /// c_minimization = "median"
+    /// ```
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn read_file<P: AsRef<Path>>(self, path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        trace!(target: "initializing", "Reading config file {}", path.display());
+        let content = fs::read_to_string(path)?;
+        let partial: PartialConfig = match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&content)?,
+            #[cfg(feature = "json")]
+            Some("json") => serde_json::from_str(&content)?,
+            _ => return Err(ConfigError::UnsupportedFormat(path.to_path_buf())),
+        };
+        Ok(partial.merge(self))
+    }
+}
+
+/// All fields of [Config] as optional values, used to merge configuration files
+/// into an existing config.
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialConfig {
+    minimum_length: Option<u32>,
+    vertex_spacing: Option<f64>,
+    dummy_vertices: Option<bool>,
+    dummy_size: Option<f64>,
+    ranking_type: Option<RankingType>,
+    c_minimization: Option<CrossingMinimization>,
+    transpose: Option<bool>,
+    time_budget: Option<Duration>,
+    max_ranking_iterations: Option<usize>,
+    max_sweeps: Option<usize>,
+    max_transpose_iterations: Option<usize>,
+    seed: Option<u64>,
+    restarts: Option<usize>,
+    multilevel: Option<bool>,
+    edge_routing: Option<EdgeRouting>,
+    back_edge_channels: Option<bool>,
+    transitive_reduction: Option<bool>,
+    condense_components: Option<bool>,
+    contract_chains: Option<bool>,
+    commit_graph: Option<bool>,
+    radial: Option<bool>,
+    aspect_ratio: Option<f64>,
+    max_layer_width: Option<usize>,
+    promote_vertices: Option<bool>,
+    rank_spacing: Option<f64>,
+    padding: Option<usize>,
+    y_down: Option<bool>,
+    mirror: Option<bool>,
+    grid: Option<usize>,
+    snap_y: Option<bool>,
+    subdivisions: Option<usize>,
+    horizontal_compaction: Option<bool>,
+    alignment: Option<Alignment>,
+    center_parents: Option<bool>,
+    minimize_area: Option<bool>,
+    minimize_bends: Option<bool>,
+    patience: Option<usize>,
+    shuffle_transpose: Option<bool>,
+    stable_ties: Option<bool>,
+    community_ordering: Option<bool>,
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl PartialConfig {
+    fn merge(self, config: Config) -> Config {
+        Config {
+            minimum_length: self.minimum_length.unwrap_or(config.minimum_length),
+            vertex_spacing: self.vertex_spacing.unwrap_or(config.vertex_spacing),
+            dummy_vertices: self.dummy_vertices.unwrap_or(config.dummy_vertices),
+            dummy_size: self.dummy_size.unwrap_or(config.dummy_size),
+            ranking_type: self.ranking_type.unwrap_or(config.ranking_type),
+            c_minimization: self.c_minimization.unwrap_or(config.c_minimization),
+            transpose: self.transpose.unwrap_or(config.transpose),
+            time_budget: self.time_budget.or(config.time_budget),
+            max_ranking_iterations: self
+                .max_ranking_iterations
+                .or(config.max_ranking_iterations),
+            max_sweeps: self.max_sweeps.or(config.max_sweeps),
+            max_transpose_iterations: self
+                .max_transpose_iterations
+                .or(config.max_transpose_iterations),
+            seed: self.seed.or(config.seed),
+            restarts: self.restarts.unwrap_or(config.restarts),
+            multilevel: self.multilevel.unwrap_or(config.multilevel),
+            edge_routing: self.edge_routing.unwrap_or(config.edge_routing),
+            back_edge_channels: self.back_edge_channels.unwrap_or(config.back_edge_channels),
+            transitive_reduction: self
+                .transitive_reduction
+                .unwrap_or(config.transitive_reduction),
+            condense_components: self
+                .condense_components
+                .unwrap_or(config.condense_components),
+            contract_chains: self.contract_chains.unwrap_or(config.contract_chains),
+            commit_graph: self.commit_graph.unwrap_or(config.commit_graph),
+            radial: self.radial.unwrap_or(config.radial),
+            aspect_ratio: self.aspect_ratio.or(config.aspect_ratio),
+            max_layer_width: self.max_layer_width.or(config.max_layer_width),
+            promote_vertices: self.promote_vertices.unwrap_or(config.promote_vertices),
+            rank_spacing: self.rank_spacing.or(config.rank_spacing),
+            padding: self.padding.or(config.padding),
+            y_down: self.y_down.unwrap_or(config.y_down),
+            mirror: self.mirror.unwrap_or(config.mirror),
+            grid: self.grid.or(config.grid),
+            snap_y: self.snap_y.unwrap_or(config.snap_y),
+            subdivisions: self.subdivisions.unwrap_or(config.subdivisions),
+            horizontal_compaction: self
+                .horizontal_compaction
+                .unwrap_or(config.horizontal_compaction),
+            alignment: self.alignment.unwrap_or(config.alignment),
+            center_parents: self.center_parents.unwrap_or(config.center_parents),
+            minimize_area: self.minimize_area.unwrap_or(config.minimize_area),
+            minimize_bends: self.minimize_bends.unwrap_or(config.minimize_bends),
+            patience: self.patience.unwrap_or(config.patience),
+            shuffle_transpose: self.shuffle_transpose.unwrap_or(config.shuffle_transpose),
+            stable_ties: self.stable_ties.unwrap_or(config.stable_ties),
+            community_ordering: self.community_ordering.unwrap_or(config.community_ordering),
+        }
+    }
+}
+
+/// Errors which may occur when reading a configuration file.
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file extension is neither `.toml` nor `.json`, or the corresponding
+    /// feature is not enabled.
+    UnsupportedFormat(PathBuf),
+    /// The file is not valid toml, contains unknown keys or invalid values.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The file is not valid json, contains unknown keys or invalid values.
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "unable to read config file: {e}"),
+            Self::UnsupportedFormat(p) => {
+                write!(f, "unsupported config file format: {}", p.display())
+            }
+            #[cfg(feature = "toml")]
+            Self::Toml(e) => write!(f, "invalid toml config: {e}"),
+            #[cfg(feature = "json")]
+            Self::Json(e) => write!(f, "invalid json config: {e}"),
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::UnsupportedFormat(_) => None,
+            #[cfg(feature = "toml")]
+            Self::Toml(e) => Some(e),
+            #[cfg(feature = "json")]
+            Self::Json(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl From<std::io::Error> for ConfigError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for ConfigError {
+    fn from(value: toml::de::Error) -> Self {
+        Self::Toml(value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for ConfigError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             minimum_length: MINIMUM_LENGTH_DEFAULT,
-            vertex_spacing: VERTEX_SPACING_DEFAULT,
+            vertex_spacing: VERTEX_SPACING_DEFAULT as f64,
             dummy_vertices: DUMMY_VERTICES_DEFAULT,
             ranking_type: RANKING_TYPE_DEFAULT,
             c_minimization: C_MINIMIZATION_DEFAULT,
             transpose: TRANSPOSE_DEFAULT,
             dummy_size: DUMMY_SIZE_DEFAULT,
+            time_budget: None,
+            max_ranking_iterations: None,
+            max_sweeps: None,
+            max_transpose_iterations: None,
+            seed: None,
+            restarts: 0,
+            multilevel: false,
+            edge_routing: EdgeRouting::Polyline,
+            back_edge_channels: false,
+            transitive_reduction: false,
+            condense_components: false,
+            contract_chains: false,
+            commit_graph: false,
+            radial: false,
+            aspect_ratio: None,
+            max_layer_width: None,
+            promote_vertices: false,
+            rank_spacing: None,
+            padding: None,
+            y_down: false,
+            mirror: false,
+            grid: None,
+            snap_y: false,
+            subdivisions: 1,
+            horizontal_compaction: false,
+            alignment: Alignment::Balanced,
+            center_parents: false,
+            minimize_area: false,
+            minimize_bends: false,
+            patience: PATIENCE_DEFAULT,
+            shuffle_transpose: false,
+            stable_ties: false,
+            community_ordering: false,
         }
     }
 }
 
 /// Defines the Ranking type, i.e. how vertices are placed on each layer.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "&'static str")
+)]
 pub enum RankingType {
     /// First moves vertices as far up as possible, and then as low as possible
     Original,
@@ -136,6 +698,15 @@ pub enum RankingType {
     Up,
     /// Move vertices as far down as possible
     Down,
+    /// Keeps the widest rank as narrow as possible, counting dummy vertices,
+    /// with the MinWidth heuristic
+    MinWidth,
+    /// Minimizes the weighted sum of the lengths of all edges exactly, by
+    /// solving its linear program. Ignores the time budget and any limit on
+    /// the number of iterations, and may take a long time for large graphs.
+    /// Only available with the `lp` feature.
+    #[cfg(feature = "lp")]
+    Optimal,
 }
 
 impl TryFrom<String> for RankingType {
@@ -147,6 +718,9 @@ impl TryFrom<String> for RankingType {
             "minimize" => Ok(Self::MinimizeEdgeLength),
             "up" => Ok(Self::Up),
             "down" => Ok(Self::Down),
+            "min_width" => Ok(Self::MinWidth),
+            #[cfg(feature = "lp")]
+            "optimal" => Ok(Self::Optimal),
             s => Err(format!("invalid value for ranking type: {s}")),
         }
     }
@@ -157,21 +731,164 @@ impl From<RankingType> for &'static str {
         match value {
             RankingType::Up => "up",
             RankingType::Down => "down",
+            RankingType::MinWidth => "min_width",
+            #[cfg(feature = "lp")]
+            RankingType::Optimal => "optimal",
             RankingType::Original => "original",
             RankingType::MinimizeEdgeLength => "minimize",
         }
     }
 }
 
+/// A custom algorithm which assigns ranks instead of the built-in
+/// [RankingType]s, registered with
+/// [CoordinatesBuilder::ranking_algorithm].
+///
+/// The algorithm is called for each connected component once cycles are
+/// removed, so the graph is acyclic. The weight of each vertex is its index
+/// in the input graph. Vertices which are not part of the input, like the
+/// junctions of hyperedges, have an index past all vertices of the input.
+///
+/// The returned ranks should place the head of each edge at least
+/// `minimum_length` ranks below its tail. Vertices without a rank are put
+/// into the first rank, and vertices are moved down until all edges are long
+/// enough.
+///
+/// Closures taking the graph and the minimum length implement this trait.
+pub trait RankingAlgorithm: Send + Sync {
+    fn rank(
+        &self,
+        graph: &StableDiGraph<usize, ()>,
+        minimum_length: u32,
+    ) -> HashMap<NodeIndex, u32>;
+}
+
+impl<F> RankingAlgorithm for F
+where
+    F: Fn(&StableDiGraph<usize, ()>, u32) -> HashMap<NodeIndex, u32> + Send + Sync,
+{
+    fn rank(
+        &self,
+        graph: &StableDiGraph<usize, ()>,
+        minimum_length: u32,
+    ) -> HashMap<NodeIndex, u32> {
+        self(graph, minimum_length)
+    }
+}
+
+/// A custom algorithm which orders the vertices within each rank instead of
+/// the built-in [CrossingMinimization], registered with
+/// [CoordinatesBuilder::ordering_algorithm].
+///
+/// The algorithm is called for each connected component with its proper
+/// layered graph, in which each edge connects vertices of adjacent ranks. The
+/// weight of each vertex is its index in the input graph, or `None` for the
+/// dummy vertices of edges spanning several ranks. Vertices which are not part
+/// of the input, like the junctions of hyperedges, have an index past all
+/// vertices of the input. `ranks` contains the vertices of each rank, from the
+/// first rank to the last, in an initial order found by a depth first search.
+///
+/// The returned ranks should contain the same vertices as `ranks`, from left
+/// to right. Vertices placed in the wrong rank or more than once are ignored,
+/// and missing vertices are appended in their initial order. Order
+/// constraints and clusters are repaired afterwards.
+///
+/// Closures taking the graph and the ranks implement this trait.
+pub trait OrderingAlgorithm: Send + Sync {
+    fn order(
+        &self,
+        graph: &StableDiGraph<Option<usize>, ()>,
+        ranks: Vec<Vec<NodeIndex>>,
+    ) -> Vec<Vec<NodeIndex>>;
+}
+
+impl<F> OrderingAlgorithm for F
+where
+    F: Fn(&StableDiGraph<Option<usize>, ()>, Vec<Vec<NodeIndex>>) -> Vec<Vec<NodeIndex>>
+        + Send
+        + Sync,
+{
+    fn order(
+        &self,
+        graph: &StableDiGraph<Option<usize>, ()>,
+        ranks: Vec<Vec<NodeIndex>>,
+    ) -> Vec<Vec<NodeIndex>> {
+        self(graph, ranks)
+    }
+}
+
+/// A custom algorithm which calculates the coordinates of the vertices once
+/// they are ordered, instead of the built-in placement, registered with
+/// [CoordinatesBuilder::coordinate_assignment].
+///
+/// The algorithm is called for each connected component with its proper
+/// layered graph, like an [OrderingAlgorithm], and the final order of the
+/// vertices of each rank, from the first rank to the last. It returns the x-
+/// and y-coordinates of each vertex, which are used as they are, with larger
+/// y-coordinates further up. Routes of edges pass through the coordinates of
+/// their dummy vertices. Vertices without coordinates are placed at x = 0,
+/// or their pin, in their rank.
+///
+/// Boxes of clusters and lanes are not part of the geometry of such layouts.
+/// With [subdivisions](CoordinatesBuilder::subdivisions) or a fractional
+/// spacing, the vertex spacing and the coordinates are given in steps of a unit.
+///
+/// Closures taking the graph, the ranks and the vertex spacing implement
+/// this trait.
+pub trait CoordinateAssignment: Send + Sync {
+    fn assign(
+        &self,
+        graph: &StableDiGraph<Option<usize>, ()>,
+        ranks: &[Vec<NodeIndex>],
+        vertex_spacing: usize,
+    ) -> HashMap<NodeIndex, (isize, isize)>;
+}
+
+impl<F> CoordinateAssignment for F
+where
+    F: Fn(
+            &StableDiGraph<Option<usize>, ()>,
+            &[Vec<NodeIndex>],
+            usize,
+        ) -> HashMap<NodeIndex, (isize, isize)>
+        + Send
+        + Sync,
+{
+    fn assign(
+        &self,
+        graph: &StableDiGraph<Option<usize>, ()>,
+        ranks: &[Vec<NodeIndex>],
+        vertex_spacing: usize,
+    ) -> HashMap<NodeIndex, (isize, isize)> {
+        self(graph, ranks, vertex_spacing)
+    }
+}
+
 /// Defines the heuristic used for crossing minimization.
 /// During crossing minimization, the vertices of one layer are
 /// ordered, so they're as close to neighboring vertices as possible.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "&'static str")
+)]
 pub enum CrossingMinimization {
     /// Calculates the average of the positions of adjacent neighbors
     Barycenter,
     /// Calculates the weighted median of the positions of adjacent neighbors
     Median,
+    /// Orders the vertices with both [Barycenter](Self::Barycenter) and
+    /// [Median](Self::Median) and keeps the order with fewer crossings, which
+    /// takes about twice as long
+    Best,
+    /// Evolves a population of orders, starting with the order of
+    /// [Barycenter](Self::Barycenter). Runs until the
+    /// [time budget](Config::time_budget) is used up, or for a fixed number of
+    /// generations without one. Orders with constraints or pinned vertices
+    /// aren't evolved.
+    #[cfg(feature = "evolutionary")]
+    Evolutionary,
 }
 
 impl TryFrom<String> for CrossingMinimization {
@@ -181,6 +898,9 @@ impl TryFrom<String> for CrossingMinimization {
         match value.as_str() {
             "barycenter" => Ok(Self::Barycenter),
             "median" => Ok(Self::Median),
+            "best" => Ok(Self::Best),
+            #[cfg(feature = "evolutionary")]
+            "evolutionary" => Ok(Self::Evolutionary),
             s => Err(format!("invalid value for crossing minimization: {s}")),
         }
     }
@@ -191,10 +911,138 @@ impl From<CrossingMinimization> for &'static str {
         match value {
             CrossingMinimization::Median => "median",
             CrossingMinimization::Barycenter => "barycenter",
+            CrossingMinimization::Best => "best",
+            #[cfg(feature = "evolutionary")]
+            CrossingMinimization::Evolutionary => "evolutionary",
+        }
+    }
+}
+
+/// Defines how the routes of edges are drawn, which are returned by
+/// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "&'static str")
+)]
+pub enum EdgeRouting {
+    /// Straight lines between the endpoints and the dummy vertices of an edge
+    Polyline,
+    /// Vertical and horizontal lines, which bend in the channels between ranks
+    Orthogonal,
+    /// Smooth curves, given by the control points of cubic Bézier curves
+    Spline,
+}
+
+impl TryFrom<String> for EdgeRouting {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "polyline" => Ok(Self::Polyline),
+            "orthogonal" => Ok(Self::Orthogonal),
+            "spline" => Ok(Self::Spline),
+            s => Err(format!("invalid value for edge routing: {s}")),
+        }
+    }
+}
+
+impl From<EdgeRouting> for &'static str {
+    fn from(value: EdgeRouting) -> Self {
+        match value {
+            EdgeRouting::Polyline => "polyline",
+            EdgeRouting::Orthogonal => "orthogonal",
+            EdgeRouting::Spline => "spline",
+        }
+    }
+}
+
+/// Defines which of the four alignments of the Brandes-Köpf coordinate
+/// assignment the x-coordinates are taken from.
+///
+/// Each alignment lines up vertices with the median of their neighbors in the
+/// rank above (up) or below (down), and places the resulting blocks as far to
+/// the left or right as possible. A single alignment keeps more long edges
+/// straight, but leans towards its side, which the balanced layout avoids by
+/// averaging all four.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "&'static str")
+)]
+pub enum Alignment {
+    /// Averages the x-coordinates of all four alignments
+    #[default]
+    Balanced,
+    /// Aligns with upper neighbors and places blocks to the left
+    UpLeft,
+    /// Aligns with upper neighbors and places blocks to the right
+    UpRight,
+    /// Aligns with lower neighbors and places blocks to the left
+    DownLeft,
+    /// Aligns with lower neighbors and places blocks to the right
+    DownRight,
+}
+
+impl TryFrom<String> for Alignment {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "balanced" => Ok(Self::Balanced),
+            "up_left" => Ok(Self::UpLeft),
+            "up_right" => Ok(Self::UpRight),
+            "down_left" => Ok(Self::DownLeft),
+            "down_right" => Ok(Self::DownRight),
+            s => Err(format!("invalid value for alignment: {s}")),
+        }
+    }
+}
+
+impl From<Alignment> for &'static str {
+    fn from(value: Alignment) -> Self {
+        match value {
+            Alignment::Balanced => "balanced",
+            Alignment::UpLeft => "up_left",
+            Alignment::UpRight => "up_right",
+            Alignment::DownLeft => "down_left",
+            Alignment::DownRight => "down_right",
         }
     }
 }
 
+/// Forces a vertex onto the topmost or bottommost rank of its component, like
+/// `rank=min`, `rank=max`, `rank=source` and `rank=sink` in graphviz.
+///
+/// Edges pointing into a vertex which is placed at the top, or out of a vertex
+/// which is placed at the bottom, are reversed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankConstraint {
+    /// Place the vertex in the topmost rank.
+    Min,
+    /// Place the vertex in the bottommost rank.
+    Max,
+    /// Place the vertex in the topmost rank, which only contains vertices
+    /// with this constraint.
+    Source,
+    /// Place the vertex in the bottommost rank, which only contains vertices
+    /// with this constraint.
+    Sink,
+}
+
+/// Shape of the border of a vertex, which the routes of its edges start or
+/// end at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// The box of the vertex, given by its width and height.
+    #[default]
+    Rectangle,
+    /// The ellipse inscribed into the box of the vertex.
+    Ellipse,
+}
+
 /// Can be used to configure the layout of the graph, via the builder pattern.
 ///
 /// # Example
@@ -210,14 +1058,26 @@ impl From<CrossingMinimization> for &'static str {
 pub struct CoordinatesBuilder<Input: IntoCoordinates> {
     config: Config,
     _inner: StableDiGraph<Vertex, Edge>,
+    constraints: Constraints,
+    hooks: Hooks,
+    /// Key of each vertex by its index, for inputs whose vertices aren't
+    /// identified by their index.
+    keys: Vec<Input::Key>,
     pd: PhantomData<Input>,
 }
 
 impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
     pub(super) fn new(graph: StableDiGraph<Vertex, Edge>) -> Self {
+        Self::new_with_keys(graph, Vec::new())
+    }
+
+    pub(super) fn new_with_keys(graph: StableDiGraph<Vertex, Edge>, keys: Vec<Input::Key>) -> Self {
         Self {
             config: Config::default(),
             _inner: graph,
+            constraints: Constraints::default(),
+            hooks: Hooks::default(),
+            keys,
             pd: PhantomData,
         }
     }
@@ -231,13 +1091,180 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
     }
 
     /// Set the spacing between vertices, see [Config] for description
-    pub fn vertex_spacing(mut self, v: usize) -> Self {
+    ///
+    /// Use [vertex_spacing_f64](Self::vertex_spacing_f64) for fractional spacings.
+    pub fn vertex_spacing(self, v: usize) -> Self {
+        self.vertex_spacing_f64(v as f64)
+    }
+
+    /// Set a spacing between vertices which may be fractional, see [Config]
+    ///
+    /// The layout is calculated in fractions of a unit in which the spacing
+    /// is whole, see [subdivisions](Self::subdivisions), so the spacing is
+    /// kept exactly as long as it has a denominator of at most a hundred.
+    pub fn vertex_spacing_f64(mut self, v: f64) -> Self {
         trace!(target: "initializing",
             "Setting vertex spacing to: {v}");
         self.config.vertex_spacing = v;
         self
     }
 
+    /// Set the spacing between adjacent ranks, see [Config] for description
+    ///
+    /// Without it, ranks are [vertex_spacing](Self::vertex_spacing) apart.
+    pub fn rank_spacing(self, v: usize) -> Self {
+        self.rank_spacing_f64(v as f64)
+    }
+
+    /// Set a spacing between adjacent ranks which may be fractional, see
+    /// [rank_spacing](Self::rank_spacing) and [vertex_spacing_f64](Self::vertex_spacing_f64)
+    pub fn rank_spacing_f64(mut self, v: f64) -> Self {
+        trace!(target: "initializing",
+            "Setting rank spacing to: {v}");
+        self.config.rank_spacing = Some(v);
+        self
+    }
+
+    /// Set the padding around the drawing of each component, see [Config]
+    ///
+    /// The bounding box of each component, which is returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry),
+    /// contains the boxes of its vertices, the routes of its edges and all
+    /// other boxes. It is extended by the padding on every side, and the
+    /// component is moved so the box spans `0..=width` horizontally and
+    /// `-height..=0` vertically, with the topmost rank at the top, or
+    /// `0..=height` with [y_down](Self::y_down).
+    pub fn padding(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Setting padding to: {v}");
+        self.config.padding = Some(v);
+        self
+    }
+
+    /// Let the y-axis of the emitted coordinates point down, see [Config]
+    ///
+    /// By default larger y-coordinates are further up, like in OpenGL or PDF.
+    /// With the y-axis pointing down, like in SVG or on a canvas, all
+    /// y-coordinates of the layout and its geometry are negated.
+    pub fn y_down(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Y-axis pointing down: {v}");
+        self.config.y_down = v;
+        self
+    }
+
+    /// Mirror the emitted coordinates horizontally, see [Config]
+    ///
+    /// The layout and its geometry are mirrored within their
+    /// [bounds](crate::Bounds), so the bounds stay the same, and vertices are
+    /// placed from right to left in each rank.
+    pub fn mirror(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Mirror horizontally: {v}");
+        self.config.mirror = v;
+        self
+    }
+
+    /// Snap the x-coordinates of vertices to multiples of `step`, see [Config]
+    ///
+    /// Coordinates are moved to the nearest multiple, or further right if
+    /// that would bring them closer to the next smaller x-coordinate of any
+    /// vertex, so vertices keep their order and spacing, and vertices with the
+    /// same x-coordinate stay aligned. Routes and boxes are stretched along with the vertices. The
+    /// layout may get slightly wider. The grid is applied after the
+    /// [padding](Self::padding), which may grow by less than a step.
+    pub fn grid(mut self, step: usize) -> Self {
+        trace!(target: "initializing",
+            "Snapping to a grid of: {step}");
+        self.config.grid = Some(step);
+        self
+    }
+
+    /// Snap the y-coordinates of ranks to the [grid](Self::grid) as well,
+    /// see [Config]
+    pub fn snap_y(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Snap y-coordinates: {v}");
+        self.config.snap_y = v;
+        self
+    }
+
+    /// Calculate the layout in steps of `1 / n` units, see [Config]
+    ///
+    /// Sizes, margins and all other distances given to the builder are
+    /// multiplied by `n` and rounded to whole steps, so fractional values of
+    /// them take effect. Fractional spacings divide each step further until
+    /// they are whole. Coordinates are divided by `n` again afterwards,
+    /// and rounded to whole units unless the layout is built with floating
+    /// point coordinates, see [build_as](CoordinatesBuilder#method.build_as).
+    /// The default is one step per unit, values of zero are treated as one.
+    pub fn subdivisions(mut self, n: usize) -> Self {
+        trace!(target: "initializing",
+            "Subdividing each unit into {n} steps");
+        self.config.subdivisions = n;
+        self
+    }
+
+    /// Remove horizontal space which no rank needs, see [Config]
+    ///
+    /// The balanced placement of vertices often leaves vertical strips of the
+    /// drawing in which every rank has more space than it needs. These strips
+    /// are narrowed as far as the ranks allow, so vertices keep their order,
+    /// vertices with the same x-coordinate stay aligned and vertices don't get
+    /// closer than their separation. Only applies to the default coordinate
+    /// assignment, not to trees, commit graphs or custom algorithms.
+    pub fn horizontal_compaction(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Compact horizontally: {v}");
+        self.config.horizontal_compaction = v;
+        self
+    }
+
+    /// Set the alignment the x-coordinates are taken from, see [Alignment]
+    ///
+    /// By default, the four alignments of the Brandes-Köpf algorithm are
+    /// balanced, which centers vertices between their neighbors. Choosing a
+    /// single alignment skips the balancing, which often keeps more long
+    /// edges straight. Only applies to the default coordinate assignment,
+    /// which trees are laid out with as well if a single alignment is chosen.
+    pub fn alignment(mut self, v: Alignment) -> Self {
+        trace!(target: "initializing",
+            "Alignment of coordinates: {v:?}");
+        self.config.alignment = v;
+        self
+    }
+
+    /// Move parents towards the center of their children, see [Config]
+    ///
+    /// Starting with the bottommost rank, each vertex is moved as close to the
+    /// average x-coordinate of its children as its neighbors in the rank
+    /// allow, without changing the order or getting closer than the vertex
+    /// spacing. This makes drawings of hierarchies like org charts or syntax
+    /// trees look less lopsided. Only applies to the default coordinate
+    /// assignment, trees are centered already.
+    pub fn center_parents(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Center parents above their children: {v}");
+        self.config.center_parents = v;
+        self
+    }
+
+    /// Remove unnecessary bends of edges spanning several ranks, see [Config]
+    ///
+    /// Each dummy vertex is moved towards the straight line between the
+    /// endpoints of its edge, interpolated by its rank, as far as its
+    /// neighbors allow without changing the order or getting closer than
+    /// their spacing. The bends which remain are counted in the
+    /// [LayoutStats] returned by
+    /// [build_with_stats](CoordinatesBuilder#method.build_with_stats). Only
+    /// applies to the default coordinate assignment.
+    pub fn minimize_bends(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Minimize bends: {v}");
+        self.config.minimize_bends = v;
+        self
+    }
+
     /// Activate/deactivate dummy vertices, see [Config] for description
     pub fn dummy_vertices(mut self, v: bool) -> Self {
         trace!(target: "initializing",
@@ -271,6 +1298,10 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
     }
 
     /// Set the size of the dummy vertices, see [Config]
+    ///
+    /// Each vertex keeps half of the vertex spacing free on either side, and
+    /// dummy vertices keep this share of it, between 0 and 1, so edges
+    /// spanning several ranks are drawn closer to each other and to vertices.
     pub fn dummy_size(mut self, v: f64) -> Self {
         trace!(target: "initializing",
             "Dummy size in regards to vertex size: {v}");
@@ -278,122 +1309,3268 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
         self
     }
 
-    pub fn with_config(mut self, config: Config) -> Self {
+    /// Set the time budget, see [Config] for description
+    pub fn time_budget(mut self, v: Duration) -> Self {
         trace!(target: "initializing",
-            "With config {:?}", config);
-        self.config = config;
+            "Time budget: {v:?}");
+        self.config.time_budget = Some(v);
         self
     }
 
-    /// Read in configuration values from environment variables.
+    /// Set the maximum number of network simplex iterations, see [Config]
+    pub fn max_ranking_iterations(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum number of ranking iterations: {v}");
+        self.config.max_ranking_iterations = Some(v);
+        self
+    }
+
+    /// Set the maximum number of sweeps during crossing minimization, see [Config]
     ///
-    /// Envs that can be set include:
+    /// Each sweep reorders all ranks once, alternating between downward and
+    /// upward sweeps. Fewer sweeps trade crossings for speed on large graphs.
+    /// Without a limit, sweeps continue until the number of crossings stops
+    /// decreasing, see [patience](CoordinatesBuilder#method.patience). Can also be set with the `RUST_GRAPH_MAX_SWEEPS`
+    /// environment variable, see
+    /// [configure_from_env](CoordinatesBuilder#method.configure_from_env).
+    pub fn max_sweeps(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum number of sweeps: {v}");
+        self.config.max_sweeps = Some(v);
+        self
+    }
+
+    /// Set the number of sweeps without improvement after which crossing
+    /// minimization stops, see [Config]
     ///
-    /// | ENV | values | default | description |
-    /// | --- | ------ | ------- | ----------- |
-    /// | RUST_GRAPH_MIN_LEN    | integer, > 0         | 1          | minimum edge length between layers |
-    /// | RUST_GRAPH_V_SPACING  | integer, > 0         | 10         | minimum spacing between vertices on the same layer |
-    /// | RUST_GRAPH_DUMMIES    | y \| n               | y          | if dummy vertices are included in the final layout |
-    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up \| down | minimize   | defines how vertices are places vertically |
-    /// | RUST_GRAPH_CROSS_MIN  | barycenter \| median | barycenter | which heuristic to use for crossing reduction |
-    /// | RUST_GRAPH_TRANSPOSE  | y \| n               | y          | if transpose function is used to further try to reduce crossings (may increase runtime significally for large graphs) |
-    /// | RUST_GRAPH_DUMMY_SIZE | float, 1 >= v > 0    | 1.0        |size of dummy vertices in final layout, if dummy vertices are included. this will squish the graph horizontally |
-    pub fn configure_from_env(mut self) -> Self {
-        self.config = self.config.read_env();
+    /// Sweeps stop once `n` consecutive sweeps didn't reduce the number of
+    /// crossings below the fewest found so far, or once the
+    /// [maximum number of sweeps](CoordinatesBuilder#method.max_sweeps) is
+    /// reached. Graphs whose order converges after a few sweeps are ordered
+    /// faster with a lower patience, while a higher patience gives sweeps
+    /// more chances to escape a local minimum. The default is 4, values of
+    /// zero are treated as one.
+    pub fn patience(mut self, n: usize) -> Self {
+        trace!(target: "initializing",
+            "Stop sweeps after {n} sweeps without improvement");
+        self.config.patience = n;
         self
     }
-}
 
-impl<V, E> CoordinatesBuilder<StableDiGraph<V, E>> {
-    /// Build the layout.
-    pub fn build(self) -> Layouts<NodeIndex> {
-        let Self {
-            config,
-            _inner: graph,
-            ..
-        } = self;
-        algorithm::start(
-            graph.map(|_, _| Vertex::default(), |_, _| Edge::default()),
-            config,
-        )
-        .into_iter()
-        .map(|(l, w, h)| {
-            (
-                l.into_iter()
-                    .map(|(id, coords)| (NodeIndex::from(id as u32), coords))
-                    .collect(),
+    /// Set the maximum number of transpose iterations per sweep, see [Config]
+    pub fn max_transpose_iterations(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum number of transpose iterations: {v}");
+        self.config.max_transpose_iterations = Some(v);
+        self
+    }
+
+    /// Shuffle the order in which transpose visits ranks and pairs of
+    /// vertices in each pass, see [Config]
+    ///
+    /// Visiting the vertices in a different order each pass lets transpose
+    /// escape some local minima of the left to right scan. The order is
+    /// derived from [seed](Self::seed), so layouts stay reproducible.
+    pub fn shuffle_transpose(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Shuffle transpose scan order: {v}");
+        self.config.shuffle_transpose = v;
+        self
+    }
+
+    /// Break ties between vertices with the same barycenter or median by the
+    /// order in which they were added to the graph, see [Config]
+    ///
+    /// Vertices added first are placed further left, so adding vertices to a
+    /// graph changes its layout less. This takes precedence over random ties
+    /// from a [seed](Self::seed).
+    pub fn stable_ties(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Break ties by input order: {v}");
+        self.config.stable_ties = v;
+        self
+    }
+
+    /// Order the initial ranks community by community, see [Config]
+    ///
+    /// Communities are found by label propagation, so vertices which are
+    /// densely connected to each other start out next to each other, instead
+    /// of being interleaved with other parts of the graph.
+    pub fn community_ordering(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Order ranks by communities: {v}");
+        self.config.community_ordering = v;
+        self
+    }
+
+    /// Set the seed for random choices during crossing minimization, see [Config]
+    pub fn seed(mut self, v: u64) -> Self {
+        trace!(target: "initializing",
+            "Seed: {v}");
+        self.config.seed = Some(v);
+        self
+    }
+
+    /// Set the number of additional runs of crossing minimization, see [Config]
+    pub fn restarts(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Restarts of crossing minimization: {v}");
+        self.config.restarts = v;
+        self
+    }
+
+    /// Use multilevel crossing minimization for large graphs, see [Config]
+    pub fn multilevel(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Use multilevel crossing minimization: {v}");
+        self.config.multilevel = v;
+        self
+    }
+
+    /// Set how the routes of edges are drawn, see [EdgeRouting]
+    pub fn edge_routing(mut self, v: EdgeRouting) -> Self {
+        trace!(target: "initializing",
+            "Setting edge routing to: {v:?}");
+        self.config.edge_routing = v;
+        self
+    }
+
+    /// Route back edges in channels beside the vertices they span, see [Config]
+    ///
+    /// Edges which were reversed to remove cycles, like the back edges of
+    /// loops in a control flow graph, are left out of crossing reduction and
+    /// coordinate calculation. Their routes leave the tail sideways, run along
+    /// the left or the right of everything between both endpoints, whichever
+    /// is closer, and enter the head sideways. Routes of nested loops are
+    /// placed outside of the routes of the loops they contain, and ports of
+    /// back edges are ignored.
+    pub fn back_edge_channels(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Route back edges in channels: {v}");
+        self.config.back_edge_channels = v;
+        self
+    }
+
+    /// Leave out redundant edges when calculating the layout, see [Config]
+    ///
+    /// An edge is redundant if its endpoints are also connected by a longer
+    /// path, after edges were reversed to remove cycles. Redundant edges don't
+    /// take part in ranking, crossing reduction and coordinate calculation,
+    /// but are listed in the [Geometry] of their component, with a straight
+    /// route between their endpoints, so they can still be drawn. Vertical
+    /// edges and edges of hyperedges are always kept.
+    pub fn transitive_reduction(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Use transitive reduction: {v}");
+        self.config.transitive_reduction = v;
+        self
+    }
+
+    /// Lay out strongly connected components on their own, see [Config]
+    ///
+    /// Each strongly connected component with more than one vertex is
+    /// replaced by a single vertex with the size of its own layout, which
+    /// keeps the cycles of large components from spreading over the whole
+    /// layout. The vertices of a component are placed inside its box, which
+    /// is listed in the [Geometry] of the layout, and edges from and to the
+    /// component end at their vertex inside the box. Components are laid out
+    /// without the order constraints, clusters, lanes and pins of their
+    /// vertices.
+    pub fn condense_components(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Condense strongly connected components: {v}");
+        self.config.condense_components = v;
+        self
+    }
+
+    /// Contract chains of vertices during crossing reduction, see [Config]
+    ///
+    /// A chain is a path of vertices which have exactly one incoming and one
+    /// outgoing edge. Each chain is ordered like a single edge spanning all of
+    /// its ranks, which saves time for graphs with long chains, like parse
+    /// trees and pipelines, and keeps chains straight. Vertices of a chain are
+    /// spaced like dummy vertices. Vertices with a size, a port, a pin or any
+    /// constraint are never part of a chain.
+    pub fn contract_chains(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Contract chains: {v}");
+        self.config.contract_chains = v;
+        self
+    }
+
+    /// Lay out the graph like a commit graph, see [Config]
+    ///
+    /// Each vertex is placed in a row of its own, in topological order, and
+    /// vertices are assigned to as few columns (lanes) as possible. The first
+    /// outgoing edge of a vertex stays in the lane of the vertex, so chains of
+    /// first parents are drawn straight, while other edges get a lane of their
+    /// own until they reach their head. Replaces ranking, crossing reduction
+    /// and coordinate calculation, so clusters, lanes and order constraints
+    /// have no effect.
+    pub fn commit_graph(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Commit graph: {v}");
+        self.config.commit_graph = v;
+        self
+    }
+
+    /// Lay out the ranks on concentric circles, see [Config]
+    ///
+    /// The finished layout is projected, so the first rank is in the center,
+    /// or on the innermost circle if it has several vertices, and each
+    /// further rank is on a larger circle. The x-coordinates become angles,
+    /// with the width of the layout going once around the circles. Routes
+    /// follow the circles, and the boxes of clusters become the bounding
+    /// boxes of their projection. Lanes are not part of the geometry.
+    pub fn radial(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Radial: {v}");
+        self.config.radial = v;
+        self
+    }
+
+    /// Make the area of the layout as small as possible, see [Config]
+    ///
+    /// After ranking, the ranking is compared to the longest path ranking,
+    /// the MinWidth ranking and rankings in which vertices are pushed into
+    /// lower ranks until no rank is wider than a limit, for a bounded number
+    /// of limits. The one whose widest rank, counting dummy vertices, times
+    /// the number of ranks is smallest is kept. An
+    /// [aspect ratio](CoordinatesBuilder#method.aspect_ratio) and a
+    /// [maximum width](CoordinatesBuilder#method.max_layer_width) are applied
+    /// afterwards.
+    pub fn minimize_area(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Minimize area: {v}");
+        self.config.minimize_area = v;
+        self
+    }
+
+    /// Set the ratio of the width to the height the layout should have, see [Config]
+    ///
+    /// After ranking, vertices are pushed into lower ranks to make ranks
+    /// narrower, or the longest path ranking is used to make the layout
+    /// shorter, whichever gets the ratio of the widest rank, counting dummy
+    /// vertices, to the number of ranks closest to `v`. A ratio of 16:9 is
+    /// given as `16. / 9.`.
+    pub fn aspect_ratio(mut self, v: f64) -> Self {
+        trace!(target: "initializing",
+            "Aspect ratio: {v}");
+        self.config.aspect_ratio = Some(v);
+        self
+    }
+
+    /// Set the maximum number of vertices in each rank, see [Config]
+    ///
+    /// Vertices of ranks which are too wide are pushed into the next rank
+    /// with room for them, which makes edges into them longer and may push
+    /// their successors down as well. Dummy vertices and junctions of
+    /// hyperedges don't count, and rank constraints and frozen vertices are
+    /// applied afterwards, so ranks with such vertices may still be wider.
+    pub fn max_layer_width(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum layer width: {v}");
+        self.config.max_layer_width = Some(v);
+        self
+    }
+
+    /// Promote vertices after ranking, see [Config]
+    ///
+    /// Uses the heuristic by Nikolov and Tarassov, which moves a vertex one
+    /// rank up, together with the predecessors in its way, if this reduces
+    /// the number of dummy vertices. Vertices are never moved above the
+    /// first rank, so the number of ranks stays the same. Fewer dummy
+    /// vertices speed up the later phases.
+    pub fn promote_vertices(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Promote vertices: {v}");
+        self.config.promote_vertices = v;
+        self
+    }
+
+    pub fn with_config(mut self, config: Config) -> Self {
+        trace!(target: "initializing",
+            "With config {:?}", config);
+        self.config = config;
+        self
+    }
+
+    /// Read in configuration values from environment variables.
+    ///
+    /// Envs that can be set include:
+    ///
+    /// | ENV | values | default | description |
+    /// | --- | ------ | ------- | ----------- |
+    /// | RUST_GRAPH_MIN_LEN    | integer, > 0         | 1          | minimum edge length between layers |
+    /// | RUST_GRAPH_V_SPACING  | number, > 0          | 10         | minimum spacing between vertices on the same layer |
+    /// | RUST_GRAPH_R_SPACING  | number, > 0          | unset      | minimum spacing between layers, the vertex spacing if unset |
+    /// | RUST_GRAPH_DUMMIES    | y \| n               | y          | if dummy vertices are included in the final layout |
+    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up \| down \| min_width \| optimal | minimize   | defines how vertices are places vertically, optimal needs the `lp` feature |
+    /// | RUST_GRAPH_CROSS_MIN  | barycenter \| median \| best | barycenter | which heuristic to use for crossing reduction |
+    /// | RUST_GRAPH_TRANSPOSE  | y \| n               | y          | if transpose function is used to further try to reduce crossings (may increase runtime significally for large graphs) |
+    /// | RUST_GRAPH_DUMMY_SIZE | float, 1 >= v > 0    | 1.0        |size of dummy vertices in final layout, if dummy vertices are included. this will squish the graph horizontally |
+    /// | RUST_GRAPH_MAX_SWEEPS | integer, > 0         | unset      | maximum number of sweeps during crossing minimization, unlimited if unset |
+    pub fn configure_from_env(mut self) -> Self {
+        self.config = self.config.read_env();
+        self
+    }
+
+    /// Read in configuration values from a `.toml` or `.json` file.
+    ///
+    /// Values which are present in the file overwrite the current configuration,
+    /// see [Config::read_file] for a description of the format.
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn from_config_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, ConfigError> {
+        self.config = self.config.read_file(path)?;
+        Ok(self)
+    }
+
+    /// Register a callback, which is called with the current phase and the
+    /// estimated progress while the layout is calculated, see [Progress].
+    pub fn on_progress<F: Fn(Progress) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        trace!(target: "initializing", "Registering progress callback");
+        self.hooks.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Assign ranks with a custom algorithm, which takes precedence over
+    /// [Config::ranking_type], see [RankingAlgorithm].
+    ///
+    /// Promotion of vertices, the aspect ratio, the maximum width of ranks and
+    /// rank constraints are applied to the returned ranks like to those of any
+    /// other ranking type. The algorithm is kept on the builder rather than in
+    /// the [Config], so configs stay [Copy] and serializable.
+    pub fn ranking_algorithm<R: RankingAlgorithm + 'static>(mut self, algorithm: R) -> Self {
+        trace!(target: "initializing", "Registering custom ranking algorithm");
+        self.hooks.ranking = Some(Arc::new(algorithm));
+        self
+    }
+
+    /// Order the vertices within each rank with a custom algorithm, which
+    /// takes precedence over [Config::c_minimization], see
+    /// [OrderingAlgorithm].
+    ///
+    /// Trees are ordered by the algorithm as well, instead of being laid out
+    /// without crossings directly.
+    pub fn ordering_algorithm<O: OrderingAlgorithm + 'static>(mut self, algorithm: O) -> Self {
+        trace!(target: "initializing", "Registering custom ordering algorithm");
+        self.hooks.ordering = Some(Box::new(algorithm));
+        self
+    }
+
+    /// Calculate the coordinates of the ordered vertices with a custom
+    /// algorithm, see [CoordinateAssignment].
+    ///
+    /// Trees are placed by the algorithm as well, instead of being laid out
+    /// without crossings directly.
+    pub fn coordinate_assignment<C: CoordinateAssignment + 'static>(
+        mut self,
+        algorithm: C,
+    ) -> Self {
+        trace!(target: "initializing", "Registering custom coordinate assignment");
+        self.hooks.coordinates = Some(Box::new(algorithm));
+        self
+    }
+
+    /// Register an observer, which is called with the intermediate results of
+    /// each phase for each connected component, see [PhaseObserver].
+    pub fn observer<O: PhaseObserver + 'static>(mut self, observer: O) -> Self {
+        trace!(target: "initializing", "Registering phase observer");
+        self.hooks.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Write the graph of each connected component to `dir` after cycle
+    /// removal, after ranking and after each sweep of crossing reduction, as
+    /// dot files named `<component>-<step>.dot`.
+    #[cfg(feature = "debug-dump")]
+    pub fn debug_dump(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        let dir = dir.into();
+        trace!(target: "initializing", "Dumping intermediate graphs to {}", dir.display());
+        self.hooks.dump_dir = Some(dir);
+        self
+    }
+
+    /// Use a token to cancel the layout, see [CancellationToken].
+    ///
+    /// A cancelled layout returns [Cancelled] from `try_build`, while `build`
+    /// returns no layouts at all.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        trace!(target: "initializing", "Registering cancellation token");
+        self.hooks.cancellation_token = Some(token);
+        self
+    }
+
+    /// Nest the cluster `child` within the cluster `parent`.
+    ///
+    /// The box of the parent contains the box of the child, and the child is
+    /// kept next to the other members of the parent. A cluster has at most one
+    /// parent, so nesting it again moves it into the new parent. Nesting a
+    /// cluster within one of its own descendants is ignored and a warning is
+    /// logged.
+    pub fn subcluster(mut self, child: usize, parent: usize) -> Self {
+        trace!(target: "initializing",
+            "Nesting cluster {child} within cluster {parent}");
+        if !self.constraints.set_cluster_parent(child, parent) {
+            warn!(target: "initializing",
+                "Cluster {parent} is contained in cluster {child}, ignoring nesting");
+        }
+        self
+    }
+
+    /// Set the distance between the members of a cluster, including nested
+    /// clusters, and the border of its box. Defaults to a quarter of the
+    /// vertex spacing.
+    pub fn cluster_padding(mut self, id: usize, padding: usize) -> Self {
+        trace!(target: "initializing",
+            "Setting padding of cluster {id} to {padding}");
+        self.constraints.set_cluster_padding(id, padding);
+        self
+    }
+
+    /// Reserve space of the given width and height for the label of a cluster
+    /// at the top of its box.
+    ///
+    /// The box is at least as wide as the label, and the ranks above and
+    /// below the cluster are moved apart if the label doesn't fit between them.
+    pub fn cluster_label(mut self, id: usize, (width, height): (usize, usize)) -> Self {
+        trace!(target: "initializing",
+            "Reserving {width}x{height} for the label of cluster {id}");
+        self.constraints.set_cluster_label(id, (width, height));
+        self
+    }
+
+    fn set_pin(mut self, id: usize, x: isize, y: isize) -> Self {
+        trace!(target: "initializing",
+            "Pinning vertex {id} to: ({x}, {y})");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_pin(x, y);
+        }
+        self
+    }
+
+    fn set_frozen(mut self, id: usize, x: isize, y: isize) -> Self {
+        trace!(target: "initializing",
+            "Freezing vertex {id} at: ({x}, {y})");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.freeze(x, y);
+        }
+        self
+    }
+
+    fn set_rank_constraint(mut self, id: usize, constraint: RankConstraint) -> Self {
+        trace!(target: "initializing",
+            "Constraining rank of vertex {id} to: {constraint:?}");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_rank_constraint(constraint);
+        }
+        self
+    }
+
+    fn set_left_of(mut self, left: usize, right: usize) -> Self {
+        trace!(target: "initializing",
+            "Placing vertex {left} left of vertex {right}");
+        self.constraints
+            .add_left_of(NodeIndex::new(left), NodeIndex::new(right));
+        self
+    }
+
+    fn set_sequence(mut self, sequence: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Keeping the order of vertices {sequence:?} within their ranks");
+        self.constraints
+            .add_sequence(sequence.into_iter().map(NodeIndex::new).collect());
+        self
+    }
+
+    fn set_group(mut self, group: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Keeping vertices {group:?} next to each other within their ranks");
+        self.constraints
+            .add_group(group.into_iter().map(NodeIndex::new).collect());
+        self
+    }
+
+    fn set_non_constraining(mut self, id: usize) -> Self {
+        trace!(target: "initializing",
+            "Marking edge {id} as non-constraining");
+        if let Some(e) = self._inner.edge_weight_mut(EdgeIndex::new(id)) {
+            e.set_non_constraining();
+        }
+        self
+    }
+
+    fn set_alignment(mut self, group: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Aligning vertices {group:?}");
+        self.constraints
+            .add_alignment(group.into_iter().map(NodeIndex::new).collect());
+        self
+    }
+
+    fn set_vertical(mut self, id: usize) -> Self {
+        trace!(target: "initializing",
+            "Drawing edge {id} vertically");
+        if let Some(e) = self._inner.edge_weight_mut(EdgeIndex::new(id)) {
+            e.set_vertical();
+        }
+        self
+    }
+
+    fn set_priority(mut self, id: usize, priority: u32) -> Self {
+        trace!(target: "initializing",
+            "Drawing edge {id} straight with priority {priority}");
+        if let Some(e) = self._inner.edge_weight_mut(EdgeIndex::new(id)) {
+            e.set_priority(priority);
+        }
+        self
+    }
+
+    fn set_cluster(mut self, id: usize, members: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Putting vertices {members:?} into cluster {id}");
+        for v in members {
+            if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(v)) {
+                v.set_cluster(id);
+            }
+        }
+        self
+    }
+
+    fn set_lane(mut self, id: usize, members: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Putting vertices {members:?} into lane {id}");
+        for v in members {
+            if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(v)) {
+                v.set_lane(id);
+            }
+        }
+        self
+    }
+
+    fn set_size(mut self, id: usize, width: f64, height: f64) -> Self {
+        trace!(target: "initializing",
+            "Setting size of vertex {id} to {width}x{height}");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_size(width, height);
+        }
+        self
+    }
+
+    fn set_shape(mut self, id: usize, shape: Shape) -> Self {
+        trace!(target: "initializing",
+            "Setting shape of vertex {id} to {shape:?}");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_shape(shape);
+        }
+        self
+    }
+
+    fn set_margin(mut self, id: usize, margin: (usize, usize)) -> Self {
+        trace!(target: "initializing",
+            "Setting margin of vertex {id} to {margin:?}");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_margin(margin);
+        }
+        self
+    }
+
+    fn set_span(mut self, id: usize, ranks: usize) -> Self {
+        trace!(target: "initializing",
+            "Vertex {id} spans {ranks} ranks");
+        if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+            v.set_span(ranks);
+        }
+        self
+    }
+
+    fn set_ports(mut self, id: usize, offsets: Vec<isize>) -> Self {
+        trace!(target: "initializing",
+            "Setting ports of vertex {id} to offsets {offsets:?}");
+        self.constraints.set_ports(NodeIndex::new(id), offsets);
+        self
+    }
+
+    fn set_edge_ports(mut self, id: usize, tail: Option<usize>, head: Option<usize>) -> Self {
+        trace!(target: "initializing",
+            "Attaching edge {id} to ports {tail:?} and {head:?}");
+        if let Some(e) = self._inner.edge_weight_mut(EdgeIndex::new(id)) {
+            e.set_ports(tail, head);
+        }
+        self
+    }
+
+    fn add_hyperedge(mut self, source: usize, targets: Vec<usize>) -> Self {
+        trace!(target: "initializing",
+            "Adding hyperedge from vertex {source} to vertices {targets:?}");
+        self.constraints.add_hyperedge(
+            NodeIndex::new(source),
+            targets.into_iter().map(NodeIndex::new).collect(),
+        );
+        self
+    }
+
+    fn set_previous_layout(mut self, coordinates: impl Iterator<Item = (usize, isize)>) -> Self {
+        for (id, x) in coordinates {
+            if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+                v.set_prev_x(x);
+            }
+        }
+        self
+    }
+
+    fn set_warm_start(mut self, coordinates: impl Iterator<Item = (usize, isize)>) -> Self {
+        for (id, x) in coordinates {
+            if let Some(v) = self._inner.node_weight_mut(NodeIndex::new(id)) {
+                v.set_start_x(x);
+            }
+        }
+        self
+    }
+
+    fn set_rank(mut self, v: usize, rank: usize) -> Self {
+        trace!(target: "initializing", "Placing vertex {v} in rank {rank}");
+        self.constraints.add_rank(NodeIndex::new(v), rank as u32);
+        self
+    }
+
+    fn set_ranks(mut self, rank: impl Fn(usize) -> u32) -> Self {
+        for v in self._inner.node_indices() {
+            self.constraints.add_rank(v, rank(v.index()));
+        }
+        self
+    }
+
+    /// Components, chains and commit graphs aren't laid out phase by phase,
+    /// since they replace the ranks or the order of their vertices.
+    fn by_phase(mut self) -> Self {
+        self.config = Config {
+            condense_components: false,
+            contract_chains: false,
+            commit_graph: false,
+            ..self.config
+        };
+        self
+    }
+
+    /// Runs the layout until `phase`, and returns the input vertices of each
+    /// rank of each component.
+    fn run_until(mut self, phase: Phase) -> Result<Vec<Vec<Vec<usize>>>, Cancelled> {
+        self.hooks.stop_before = Some(phase);
+        let Self {
+            config,
+            _inner: graph,
+            constraints,
+            hooks,
+            ..
+        } = self.by_phase();
+        algorithm::start(graph, config, &constraints, &hooks)?;
+        Ok(hooks.into_stopped())
+    }
+
+    fn run_ranking(self) -> Rankings<usize> {
+        let stopped = self.run_until(Phase::CrossingReduction).unwrap_or_default();
+        stopped
+            .into_iter()
+            .map(|ranks| {
+                let mut vertices = ranks
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(rank, vertices)| vertices.into_iter().map(move |v| (v, rank)))
+                    .collect::<Vec<_>>();
+                vertices.sort();
+                vertices
+            })
+            .collect()
+    }
+
+    fn run_ordering(mut self, rankings: impl Iterator<Item = (usize, usize)>) -> Orderings<usize> {
+        for (v, rank) in rankings {
+            self = self.set_rank(v, rank);
+        }
+        self.run_until(Phase::CoordinateCalculation)
+            .unwrap_or_default()
+    }
+
+    fn run_coordinates(mut self, orderings: Orderings<usize>) -> Layouts<usize> {
+        for ranks in orderings {
+            for (rank, vertices) in ranks.into_iter().enumerate() {
+                for v in &vertices {
+                    self = self.set_rank(*v, rank);
+                }
+                self = self.set_sequence(vertices);
+            }
+        }
+        self.by_phase()
+            .run()
+            .map(|(layouts, ..)| layouts)
+            .unwrap_or_default()
+    }
+
+    fn run(self) -> Result<algorithm::Results, Cancelled> {
+        let Self {
+            config,
+            _inner: graph,
+            constraints,
+            hooks,
+            ..
+        } = self;
+        algorithm::start(graph, config, &constraints, &hooks)
+    }
+
+    /// Runs the layout and converts the coordinates from layout units.
+    fn run_as<C: Coordinate>(self) -> Result<Layouts<usize, C>, Cancelled> {
+        let Self {
+            config,
+            _inner: graph,
+            constraints,
+            hooks,
+            ..
+        } = self;
+        let scale = config.scale();
+        let (layouts, ..) = algorithm::start_subdivided(graph, config, &constraints, &hooks)?;
+        Ok(layouts
+            .into_iter()
+            .map(|(l, w, h)| {
+                let l = l
+                    .into_iter()
+                    .map(|(v, (x, y))| {
+                        let convert = |units| C::from_layout_units(units, scale);
+                        (v, (convert(x), convert(y)))
+                    })
+                    .collect();
+                (l, w, h)
+            })
+            .collect())
+    }
+
+    /// Build the layout, whose vertices are identified by the
+    /// [Key](IntoCoordinates::Key) of the input.
+    pub fn build(self) -> Layouts<Input::Key> {
+        self.try_build().unwrap_or_default()
+    }
+
+    /// Build the layout, or return [Cancelled] if the layout was cancelled
+    /// via its [CancellationToken].
+    pub fn try_build(self) -> Result<Layouts<Input::Key>, Cancelled> {
+        self.try_build_with_stats().map(|(layouts, _)| layouts)
+    }
+
+    /// Build the layout together with the [LayoutStats] of each connected
+    /// component, which are in the same order as the layouts.
+    pub fn build_with_stats(self) -> (Layouts<Input::Key>, Vec<LayoutStats>) {
+        self.try_build_with_stats().unwrap_or_default()
+    }
+
+    /// Like `build_with_stats`, but returns [Cancelled] if the layout was
+    /// cancelled via its [CancellationToken].
+    pub fn try_build_with_stats(
+        mut self,
+    ) -> Result<(Layouts<Input::Key>, Vec<LayoutStats>), Cancelled> {
+        let keys = std::mem::take(&mut self.keys);
+        let (layouts, stats, _) = self.run()?;
+        Ok((with_keys::<Input, _>(&keys, layouts), stats))
+    }
+
+    /// Build the layout together with the [Geometry] of each connected
+    /// component, which contains the routes of the edges, the boxes of the
+    /// clusters and the extents of the ranks, in the same order as the layouts.
+    pub fn build_with_geometry(self) -> (Layouts<Input::Key>, Vec<Geometry>) {
+        self.try_build_with_geometry().unwrap_or_default()
+    }
+
+    /// Like `build_with_geometry`, but returns [Cancelled] if the layout was
+    /// cancelled via its [CancellationToken].
+    pub fn try_build_with_geometry(
+        mut self,
+    ) -> Result<(Layouts<Input::Key>, Vec<Geometry>), Cancelled> {
+        let keys = std::mem::take(&mut self.keys);
+        let (layouts, _, geometry) = self.run()?;
+        Ok((with_keys::<Input, _>(&keys, layouts), geometry))
+    }
+
+    /// Build the layout with coordinates of the scalar type `C`, like `f32`,
+    /// see [Coordinate].
+    ///
+    /// Floating point coordinates are multiples of one
+    /// [subdivision](Self::subdivisions) of a unit, or of a smaller step in
+    /// which fractional spacings are whole, and equal the ones of
+    /// [build](Self::build) with a single step per unit. Post-processing like
+    /// the [padding](Self::padding) and the [grid](Self::grid) applies in the
+    /// same way.
+    ///
+    /// # Example
+    /// ```
+    /// use petgraph::stable_graph::StableDiGraph;
+    /// use rust_sugiyama::from_graph;
+    ///
+    /// let g = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2)]);
+    /// let layouts = from_graph(&g).build_as::<f32>();
+    /// assert_eq!(layouts[0].0.len(), 3);
+    /// ```
+    pub fn build_as<C: Coordinate>(self) -> Layouts<Input::Key, C> {
+        self.try_build_as().unwrap_or_default()
+    }
+
+    /// Like `build_as`, but returns [Cancelled] if the layout was cancelled
+    /// via its [CancellationToken].
+    pub fn try_build_as<C: Coordinate>(mut self) -> Result<Layouts<Input::Key, C>, Cancelled> {
+        let keys = std::mem::take(&mut self.keys);
+        Ok(with_keys::<Input, _>(&keys, self.run_as()?))
+    }
+
+    /// Build the layout with coordinates which keep fractions of a unit, see
+    /// [build_as](Self::build_as).
+    pub fn build_f64(self) -> Layouts<Input::Key, f64> {
+        self.build_as()
+    }
+
+    /// Like `build_f64`, but returns [Cancelled] if the layout was cancelled
+    /// via its [CancellationToken].
+    pub fn try_build_f64(self) -> Result<Layouts<Input::Key, f64>, Cancelled> {
+        self.try_build_as()
+    }
+}
+
+impl<G: PetgraphInput> CoordinatesBuilder<G> {
+    /// Use the layout of a previous version of the graph, to keep vertices
+    /// close to their previous position.
+    ///
+    /// Vertices of the previous layout keep their relative order in each rank
+    /// where possible and are moved as little as possible, vertices which are
+    /// not contained in `layouts` are placed around them. Since the layout
+    /// stays in the coordinate system of the previous one, the smallest
+    /// x-coordinate is not necessarily 0.
+    pub fn previous_layout(self, layouts: &Layouts<NodeIndex<G::Ix>>) -> Self {
+        trace!(target: "initializing",
+            "Using previous layout with {} components", layouts.len());
+        self.set_previous_layout(
+            layouts
+                .iter()
+                .flat_map(|(l, ..)| l.iter().map(|(v, (x, _))| (v.index(), *x))),
+        )
+    }
+
+    /// Start crossing reduction from the order of the vertices in the layout
+    /// of a previous version of the graph, instead of the order of a depth
+    /// first search.
+    ///
+    /// Unlike [previous_layout](CoordinatesBuilder#method.previous_layout),
+    /// vertices are neither pulled towards their previous position during
+    /// crossing reduction nor kept at their previous coordinates, so the
+    /// layout may move further away from the previous one. Starting from an
+    /// order with few crossings lets crossing reduction finish after fewer
+    /// sweeps.
+    pub fn warm_start(self, layouts: &Layouts<NodeIndex<G::Ix>>) -> Self {
+        trace!(target: "initializing",
+            "Starting from layout with {} components", layouts.len());
+        self.set_warm_start(
+            layouts
+                .iter()
+                .flat_map(|(l, ..)| l.iter().map(|(v, (x, _))| (v.index(), *x))),
+        )
+    }
+
+    /// Pin a vertex to fixed coordinates.
+    ///
+    /// Pinned vertices are placed exactly at `(x, y)` and the remaining vertices
+    /// are laid out around them. The rank of a pinned vertex is still
+    /// determined by the layering, so `y` only changes its final coordinate.
+    /// Since the layout is moved to the pinned coordinates, the smallest
+    /// x-coordinate is not necessarily 0.
+    pub fn pin(self, v: NodeIndex<G::Ix>, (x, y): (isize, isize)) -> Self {
+        self.set_pin(v.index(), x, y)
+    }
+
+    /// Freeze a vertex at the coordinates it has in an existing layout.
+    ///
+    /// Like a pinned vertex, a frozen vertex is placed exactly at `(x, y)`. In
+    /// addition it keeps the rank given by `y` and the order relative to other
+    /// frozen vertices, so freezing all vertices of an already arranged part of
+    /// a layout keeps that part as is, while the remaining vertices are laid
+    /// out around it. If the frozen ranks contradict the edges between them,
+    /// frozen vertices are moved down, but keep their x-coordinate.
+    pub fn freeze(self, v: NodeIndex<G::Ix>, (x, y): (isize, isize)) -> Self {
+        self.set_frozen(v.index(), x, y)
+    }
+
+    /// Mark an edge as non-constraining, like `constraint=false` in graphviz.
+    ///
+    /// A non-constraining edge is ignored when assigning ranks to the
+    /// vertices, but is still taken into account when reducing crossings. If
+    /// it points upwards afterwards, it is reversed, and if both of its
+    /// endpoints end up in the same rank, it becomes a flat edge: its
+    /// endpoints are placed next to each other if this doesn't add crossings,
+    /// and it is routed horizontally, or above the vertices between them.
+    pub fn non_constraining(self, e: EdgeIndex<G::Ix>) -> Self {
+        self.set_non_constraining(e.index())
+    }
+
+    /// Force a vertex onto the topmost or bottommost rank, see [RankConstraint].
+    ///
+    /// If constrained vertices are connected to each other, some of them may
+    /// be moved further down to keep the minimum length of the edges.
+    pub fn rank_constraint(self, v: NodeIndex<G::Ix>, constraint: RankConstraint) -> Self {
+        self.set_rank_constraint(v.index(), constraint)
+    }
+
+    /// Place vertex `left` left of vertex `right`, whenever both are in the
+    /// same rank.
+    ///
+    /// This is a hard constraint for crossing minimization, so it may cause
+    /// additional crossings. Constraints which contradict previously added
+    /// ones can't be satisfied and are ignored.
+    pub fn left_of(self, left: NodeIndex<G::Ix>, right: NodeIndex<G::Ix>) -> Self {
+        self.set_left_of(left.index(), right.index())
+    }
+
+    /// Keep vertices in the given order among the vertices of their rank.
+    ///
+    /// Each vertex is placed left of all vertices after it in `vertices`
+    /// which end up in the same rank, so a sequence can span several ranks.
+    /// Vertices which aren't part of the sequence and dummy vertices are
+    /// still ordered by crossing reduction, around the vertices of the
+    /// sequence. If a sequence contains all vertices of a rank, only dummy
+    /// vertices can move. Contradicting constraints are dropped, like those
+    /// of [left_of](CoordinatesBuilder#method.left_of).
+    pub fn fixed_order(self, vertices: &[NodeIndex<G::Ix>]) -> Self {
+        self.set_sequence(vertices.iter().map(|v| v.index()).collect())
+    }
+
+    /// Keep vertices next to each other within each rank, like the fields of
+    /// a record.
+    ///
+    /// The members of a group which end up in the same rank are ordered as a
+    /// single unit by crossing reduction, so no other vertex is placed
+    /// between them, while the group itself can move freely. A vertex can
+    /// only be part of one group, later groups containing it ignore it.
+    /// Constraints of [left_of](CoordinatesBuilder#method.left_of) which
+    /// would split a group are dropped, and clusters take precedence over
+    /// groups whose members are in different clusters.
+    pub fn keep_together(self, vertices: &[NodeIndex<G::Ix>]) -> Self {
+        self.set_group(vertices.iter().map(|v| v.index()).collect())
+    }
+
+    /// Draw an edge vertically, so both of its endpoints and all dummy
+    /// vertices in between get the same x-coordinate.
+    ///
+    /// Vertical edges between two ranks can't cross each other or share an
+    /// endpoint. An edge which conflicts with a vertical edge of a lower index
+    /// is drawn like all other edges and a warning is logged. If dummy vertices are
+    /// disabled, only edges between adjacent ranks can be drawn vertically.
+    /// Pinned vertices and vertices of a previous layout may still be moved
+    /// away from the other endpoint.
+    pub fn vertical(self, e: EdgeIndex<G::Ix>) -> Self {
+        self.set_vertical(e.index())
+    }
+
+    /// Prefer drawing an edge straight, like the `weight` of an edge affects
+    /// the x-coordinates in graphviz.
+    ///
+    /// The endpoints and dummy vertices of edges with a priority above zero
+    /// are placed in the same column wherever this doesn't conflict with a
+    /// [vertical edge](CoordinatesBuilder#method.vertical), an
+    /// [alignment group](CoordinatesBuilder#method.align) or an edge of a
+    /// higher priority, or of the same priority and a lower index. Unlike
+    /// vertical edges, conflicts are not logged, and the rest of the edge is
+    /// still kept as straight as possible.
+    pub fn straighten(self, e: EdgeIndex<G::Ix>, priority: u32) -> Self {
+        self.set_priority(e.index(), priority)
+    }
+
+    /// Place a group of vertices in a single column, so they all get the same
+    /// x-coordinate.
+    ///
+    /// The vertices need to be in different ranks, and vertices in
+    /// consecutive ranks of the group are placed in the same column, so a rank
+    /// without a vertex of the group splits it into several columns. Like
+    /// [vertical edges](CoordinatesBuilder#method.vertical), columns can't
+    /// cross each other, so vertices which conflict with a vertical edge or a
+    /// previous group are not aligned and a warning is logged.
+    pub fn align(self, group: &[NodeIndex<G::Ix>]) -> Self {
+        self.set_alignment(group.iter().map(|v| v.index()).collect())
+    }
+
+    /// Put vertices into the cluster `id`, like the vertices of a
+    /// `subgraph cluster` in graphviz.
+    ///
+    /// The members of a cluster are placed next to each other in each rank,
+    /// and clusters have the same order in all ranks. All other vertices,
+    /// including the dummy vertices of edges leaving the cluster, are kept out
+    /// of its bounding box, which contains all members and a
+    /// [padding](CoordinatesBuilder#method.cluster_padding). The boxes and the
+    /// routes of the edges are returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry).
+    ///
+    /// A vertex belongs to at most one cluster, so adding it to another
+    /// cluster removes it from the previous one. Clusters can be nested
+    /// within each other with [CoordinatesBuilder::subcluster](CoordinatesBuilder#method.subcluster). Pinned vertices and vertical
+    /// edges may be moved to keep vertices out of the boxes, and if dummy
+    /// vertices are disabled, ranks without a member may overlap the box.
+    pub fn cluster(self, id: usize, members: &[NodeIndex<G::Ix>]) -> Self {
+        self.set_cluster(id, members.iter().map(|v| v.index()).collect())
+    }
+
+    /// Measure the width and height of each vertex from its weight in `graph`,
+    /// which is the graph the layout was created from.
+    ///
+    /// Vertices are placed such that their borders keep the vertex spacing to
+    /// each other, and ranks are moved apart to fit their highest vertex. The
+    /// coordinates of a vertex are the center of its box, and sizes are
+    /// rounded up to whole units. Vertices without a size are points, as are
+    /// dummy vertices.
+    pub fn measure<F: Fn(&G::NodeWeight) -> (f64, f64)>(mut self, graph: &G, measure: F) -> Self {
+        for (v, weight) in graph.node_weights() {
+            let (width, height) = measure(weight);
+            self = self.set_size(v.index(), width, height);
+        }
+        self
+    }
+
+    /// Set the shape of a vertex, which is a rectangle by default.
+    ///
+    /// Routes of edges which aren't attached to a port start and end at the
+    /// border of the shape, in the direction of the next point of the route,
+    /// so arrowheads can be drawn at the end of the route. Vertices without a
+    /// [size](CoordinatesBuilder#method.measure) are points, and their routes
+    /// start and end at their center.
+    pub fn shape(self, v: NodeIndex<G::Ix>, shape: Shape) -> Self {
+        self.set_shape(v.index(), shape)
+    }
+
+    /// Keep extra space free around a vertex, like room for a badge or a
+    /// selection halo, given as the space left and right of its box and the
+    /// space above and below it.
+    ///
+    /// Other vertices, ranks and the boxes of clusters keep their distance to
+    /// the box extended by the margin, while routes of edges still start and
+    /// end at the border of the [size](CoordinatesBuilder#method.measure) of
+    /// the vertex. Setting the margin again replaces the previous one.
+    pub fn margin(self, v: NodeIndex<G::Ix>, margin: (usize, usize)) -> Self {
+        self.set_margin(v.index(), margin)
+    }
+
+    /// Put vertices into the lane `id`, like a swimlane of a process diagram.
+    ///
+    /// Lanes are vertical stripes, which are placed from left to right in the
+    /// order of their ids, and the vertices of a lane are placed inside of it.
+    /// Ranking and crossing minimization still consider the whole graph,
+    /// vertices without a lane and the dummy vertices of edges may be placed in
+    /// any lane. The extents of the lanes are returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry).
+    ///
+    /// A vertex belongs to at most one lane, so adding it to another lane
+    /// removes it from the previous one. Order constraints which contradict
+    /// the order of the lanes are ignored. Clusters whose members are all in
+    /// the same lane are kept inside of it, clusters with members in several
+    /// lanes may cause lanes to overlap.
+    pub fn lane(self, id: usize, members: &[NodeIndex<G::Ix>]) -> Self {
+        self.set_lane(id, members.iter().map(|v| v.index()).collect())
+    }
+
+    /// Give a vertex an ordered list of ports, which edges can be attached
+    /// to with [CoordinatesBuilder::edge_ports](CoordinatesBuilder#method.edge_ports).
+    ///
+    /// Each port is given by its horizontal offset from the center of the
+    /// vertex, and edges attach to the top or bottom side of the vertex,
+    /// depending on the direction they leave it. Setting the ports again
+    /// replaces the previous ones.
+    pub fn ports(self, v: NodeIndex<G::Ix>, offsets: &[isize]) -> Self {
+        self.set_ports(v.index(), offsets.to_vec())
+    }
+
+    /// Attach the tail and the head of an edge to a port of the respective
+    /// vertex, given by its index in the ports of the vertex.
+    ///
+    /// The routes returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry)
+    /// start and end at the ports. Ports which the vertex doesn't have are
+    /// ignored and a warning is logged.
+    pub fn edge_ports(self, e: EdgeIndex<G::Ix>, tail: Option<usize>, head: Option<usize>) -> Self {
+        self.set_edge_ports(e.index(), tail, head)
+    }
+
+    /// Add a hyperedge, which is a single edge from `source` to all `targets`,
+    /// like a bus in a circuit diagram.
+    ///
+    /// The edges of a hyperedge share a trunk, which leaves the source and
+    /// splits into branches to the targets at a junction, so the hyperedge is
+    /// laid out like a star around the junction, which takes up a rank of its
+    /// own. Hyperedges get ids in the order they are added, starting at 0, and
+    /// their routes are returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry).
+    /// Vertices which don't exist are ignored and a warning is logged.
+    pub fn hyperedge(self, source: NodeIndex<G::Ix>, targets: &[NodeIndex<G::Ix>]) -> Self {
+        self.add_hyperedge(source.index(), targets.iter().map(|v| v.index()).collect())
+    }
+
+    /// Let a vertex span `ranks` consecutive ranks, like a lifeline or a tall
+    /// operator.
+    ///
+    /// The vertex takes up a place in each of its ranks during crossing
+    /// reduction and keeps the same x-coordinate in all of them. Its
+    /// coordinates in the layout are those of its topmost rank, where its
+    /// incoming edges end, and its outgoing edges leave its bottommost rank.
+    /// With a minimum length greater than 1, the ranks are that far apart.
+    /// The extents of these vertices are returned by
+    /// [CoordinatesBuilder::build_with_geometry](CoordinatesBuilder#method.build_with_geometry).
+    pub fn rank_span(self, v: NodeIndex<G::Ix>, ranks: usize) -> Self {
+        self.set_span(v.index(), ranks)
+    }
+
+    /// Place each vertex in the rank returned by `rank`, instead of assigning
+    /// ranks, and only order the vertices and calculate coordinates.
+    ///
+    /// The first rank is rank 0. Vertices are moved down if edges don't span
+    /// the minimum length, which can only happen if an edge points upwards or
+    /// stays within its rank. Edges which were reversed to remove cycles
+    /// point upwards. Rank constraints and frozen vertices are ignored.
+    pub fn with_ranks(self, rank: impl Fn(NodeIndex<G::Ix>) -> u32) -> Self {
+        trace!(target: "initializing", "Using given ranks");
+        self.set_ranks(|v| rank(NodeIndex::new(v)))
+    }
+
+    /// Only assign ranks, without ordering the vertices or calculating
+    /// coordinates. Returns the rank of each vertex of each connected
+    /// component, which can be passed to
+    /// [order](CoordinatesBuilder#method.order).
+    ///
+    /// Like [order](CoordinatesBuilder#method.order) and
+    /// [assign_coordinates](CoordinatesBuilder#method.assign_coordinates),
+    /// ranking ignores `condense_components`, `contract_chains` and
+    /// `commit_graph`, since they replace the ranks or the order of vertices.
+    pub fn rank(self) -> Rankings<NodeIndex<G::Ix>> {
+        self.run_ranking()
+            .into_iter()
+            .map(|ranks| {
+                ranks
+                    .into_iter()
+                    .map(|(v, r)| (NodeIndex::new(v), r))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Only order the vertices within each rank, with the ranks of `rankings`
+    /// instead of ranking, without calculating coordinates. Returns the
+    /// vertices of each rank of each connected component, from left to right,
+    /// which can be passed to
+    /// [assign_coordinates](CoordinatesBuilder#method.assign_coordinates).
+    ///
+    /// Vertices without a rank are put into the first rank, and vertices are
+    /// moved down if edges don't span the minimum length.
+    pub fn order(self, rankings: &Rankings<NodeIndex<G::Ix>>) -> Orderings<NodeIndex<G::Ix>> {
+        let rankings = rankings.iter().flatten().map(|(v, r)| (v.index(), *r));
+        self.run_ordering(rankings)
+            .into_iter()
+            .map(|ranks| {
+                ranks
+                    .into_iter()
+                    .map(|vertices| vertices.into_iter().map(NodeIndex::new).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Only calculate the coordinates, with the ranks and the order of
+    /// `orderings` instead of ranking and crossing reduction. Dummy vertices
+    /// are still placed by crossing reduction, without changing the order of
+    /// the other vertices.
+    pub fn assign_coordinates(
+        self,
+        orderings: &Orderings<NodeIndex<G::Ix>>,
+    ) -> Layouts<NodeIndex<G::Ix>> {
+        let orderings = orderings
+            .iter()
+            .map(|ranks| {
+                ranks
+                    .iter()
+                    .map(|vertices| vertices.iter().map(|v| v.index()).collect())
+                    .collect()
+            })
+            .collect();
+        node_indices(self.run_coordinates(orderings))
+    }
+}
+
+impl<K: Copy + PartialEq> CoordinatesBuilder<Keyed<K>> {
+    /// Returns the index of the vertex with key `k`, or logs a warning if
+    /// there is none.
+    fn index_of(&self, k: K) -> Option<usize> {
+        let index = self.keys.iter().position(|key| *key == k);
+        if index.is_none() {
+            warn!(target: "initializing", "Ignoring a vertex which is not part of the graph");
+        }
+        index
+    }
+
+    /// Measure the width and height of each vertex from its key, see
+    /// [CoordinatesBuilder::measure](CoordinatesBuilder#method.measure).
+    pub fn measure<F: Fn(K) -> (f64, f64)>(mut self, measure: F) -> Self {
+        for (v, k) in self.keys.clone().into_iter().enumerate() {
+            let (width, height) = measure(k);
+            self = self.set_size(v, width, height);
+        }
+        self
+    }
+
+    /// Pin the vertex with key `k` to fixed coordinates, see
+    /// [CoordinatesBuilder::pin](CoordinatesBuilder#method.pin).
+    pub fn pin(self, k: K, (x, y): (isize, isize)) -> Self {
+        match self.index_of(k) {
+            Some(v) => self.set_pin(v, x, y),
+            None => self,
+        }
+    }
+
+    /// Place vertex `left` left of vertex `right` if both are in the same rank, see
+    /// [CoordinatesBuilder::left_of](CoordinatesBuilder#method.left_of).
+    pub fn left_of(self, left: K, right: K) -> Self {
+        match (self.index_of(left), self.index_of(right)) {
+            (Some(left), Some(right)) => self.set_left_of(left, right),
+            _ => self,
+        }
+    }
+
+    /// Keep the vertices with the keys of `vertices` in the given order, see
+    /// [CoordinatesBuilder::fixed_order](CoordinatesBuilder#method.fixed_order).
+    pub fn fixed_order(self, vertices: &[K]) -> Self {
+        let sequence = vertices.iter().filter_map(|k| self.index_of(*k)).collect();
+        self.set_sequence(sequence)
+    }
+
+    /// Keep the vertices with the keys of `vertices` next to each other, see
+    /// [CoordinatesBuilder::keep_together](CoordinatesBuilder#method.keep_together).
+    pub fn keep_together(self, vertices: &[K]) -> Self {
+        let group = vertices.iter().filter_map(|k| self.index_of(*k)).collect();
+        self.set_group(group)
+    }
+}
+
+/// Replaces the index of each vertex in `layouts` by its key.
+fn with_keys<Input: IntoCoordinates, C>(
+    keys: &[Input::Key],
+    layouts: Layouts<usize, C>,
+) -> Layouts<Input::Key, C> {
+    layouts
+        .into_iter()
+        .map(|(l, w, h)| {
+            (
+                l.into_iter()
+                    .map(|(id, coords)| (Input::key(keys, id), coords))
+                    .collect(),
+                w,
+                h,
+            )
+        })
+        .collect()
+}
+
+fn node_indices<Ix: IndexType, C>(layouts: Layouts<usize, C>) -> Layouts<NodeIndex<Ix>, C> {
+    layouts
+        .into_iter()
+        .map(|(l, w, h)| {
+            (
+                l.into_iter()
+                    .map(|(id, coords)| (NodeIndex::new(id), coords))
+                    .collect(),
                 w,
                 h,
             )
         })
         .collect()
+}
+
+/// Methods of inputs whose vertices and edges are identified by their index,
+/// see [from_edges](crate::from_edges) and
+/// [from_vertices_and_edges](crate::from_vertices_and_edges).
+macro_rules! index_input {
+    ($input:ty) => {
+        impl CoordinatesBuilder<$input> {
+            /// Use the layout of a previous version of the graph, see
+            /// [CoordinatesBuilder::previous_layout](CoordinatesBuilder#method.previous_layout).
+            pub fn previous_layout(self, layouts: &Layouts<usize>) -> Self {
+                trace!(target: "initializing",
+                    "Using previous layout with {} components", layouts.len());
+                self.set_previous_layout(
+                    layouts
+                        .iter()
+                        .flat_map(|(l, ..)| l.iter().map(|(v, (x, _))| (*v, *x))),
+                )
+            }
+
+            /// Start crossing reduction from the order of a previous layout, see
+            /// [CoordinatesBuilder::warm_start](CoordinatesBuilder#method.warm_start).
+            pub fn warm_start(self, layouts: &Layouts<usize>) -> Self {
+                trace!(target: "initializing",
+                    "Starting from layout with {} components", layouts.len());
+                self.set_warm_start(
+                    layouts
+                        .iter()
+                        .flat_map(|(l, ..)| l.iter().map(|(v, (x, _))| (*v, *x))),
+                )
+            }
+
+            /// Pin a vertex to fixed coordinates, see
+            /// [CoordinatesBuilder::pin](CoordinatesBuilder#method.pin).
+            pub fn pin(self, v: usize, (x, y): (isize, isize)) -> Self {
+                self.set_pin(v, x, y)
+            }
+
+            /// Freeze a vertex at the coordinates it has in an existing layout, see
+            /// [CoordinatesBuilder::freeze](CoordinatesBuilder#method.freeze).
+            pub fn freeze(self, v: usize, (x, y): (isize, isize)) -> Self {
+                self.set_frozen(v, x, y)
+            }
+
+            /// Mark the edge at index `e` of the edges as non-constraining, see
+            /// [CoordinatesBuilder::non_constraining](CoordinatesBuilder#method.non_constraining).
+            pub fn non_constraining(self, e: usize) -> Self {
+                self.set_non_constraining(e)
+            }
+
+            /// Force a vertex onto the topmost or bottommost rank, see
+            /// [CoordinatesBuilder::rank_constraint](CoordinatesBuilder#method.rank_constraint).
+            pub fn rank_constraint(self, v: usize, constraint: RankConstraint) -> Self {
+                self.set_rank_constraint(v, constraint)
+            }
+
+            /// Place vertex `left` left of vertex `right` if both are in the same rank, see
+            /// [CoordinatesBuilder::left_of](CoordinatesBuilder#method.left_of).
+            pub fn left_of(self, left: usize, right: usize) -> Self {
+                self.set_left_of(left, right)
+            }
+
+            /// Keep the vertices at the indices of `vertices` in the given order, see
+            /// [CoordinatesBuilder::fixed_order](CoordinatesBuilder#method.fixed_order).
+            pub fn fixed_order(self, vertices: &[usize]) -> Self {
+                self.set_sequence(vertices.to_vec())
+            }
+
+            /// Keep the vertices at the indices of `vertices` next to each other, see
+            /// [CoordinatesBuilder::keep_together](CoordinatesBuilder#method.keep_together).
+            pub fn keep_together(self, vertices: &[usize]) -> Self {
+                self.set_group(vertices.to_vec())
+            }
+
+            /// Draw the edge at index `e` of the edges vertically, see
+            /// [CoordinatesBuilder::vertical](CoordinatesBuilder#method.vertical).
+            pub fn vertical(self, e: usize) -> Self {
+                self.set_vertical(e)
+            }
+
+            /// Prefer drawing the edge at index `e` of the edges straight, see
+            /// [CoordinatesBuilder::straighten](CoordinatesBuilder#method.straighten).
+            pub fn straighten(self, e: usize, priority: u32) -> Self {
+                self.set_priority(e, priority)
+            }
+
+            /// Place a group of vertices in a single column, see
+            /// [CoordinatesBuilder::align](CoordinatesBuilder#method.align).
+            pub fn align(self, group: &[usize]) -> Self {
+                self.set_alignment(group.to_vec())
+            }
+
+            /// Put vertices into the cluster `id`, see
+            /// [CoordinatesBuilder::cluster](CoordinatesBuilder#method.cluster).
+            pub fn cluster(self, id: usize, members: &[usize]) -> Self {
+                self.set_cluster(id, members.to_vec())
+            }
+
+            /// Put vertices into the lane `id`, see
+            /// [CoordinatesBuilder::lane](CoordinatesBuilder#method.lane).
+            pub fn lane(self, id: usize, members: &[usize]) -> Self {
+                self.set_lane(id, members.to_vec())
+            }
+
+            /// Give a vertex an ordered list of ports, see
+            /// [CoordinatesBuilder::ports](CoordinatesBuilder#method.ports).
+            pub fn ports(self, v: usize, offsets: &[isize]) -> Self {
+                self.set_ports(v, offsets.to_vec())
+            }
+
+            /// Attach the edge at index `e` of the edges to ports of its endpoints, see
+            /// [CoordinatesBuilder::edge_ports](CoordinatesBuilder#method.edge_ports).
+            pub fn edge_ports(self, e: usize, tail: Option<usize>, head: Option<usize>) -> Self {
+                self.set_edge_ports(e, tail, head)
+            }
+
+            /// Add a hyperedge from `source` to all `targets`, see
+            /// [CoordinatesBuilder::hyperedge](CoordinatesBuilder#method.hyperedge).
+            pub fn hyperedge(self, source: usize, targets: &[usize]) -> Self {
+                self.add_hyperedge(source, targets.to_vec())
+            }
+
+            /// Let a vertex span several ranks, see
+            /// [CoordinatesBuilder::rank_span](CoordinatesBuilder#method.rank_span).
+            pub fn rank_span(self, v: usize, ranks: usize) -> Self {
+                self.set_span(v, ranks)
+            }
+
+            /// Place the vertex at index `v` in the rank returned by `rank`, see
+            /// [CoordinatesBuilder::with_ranks](CoordinatesBuilder#method.with_ranks).
+            pub fn with_ranks(self, rank: impl Fn(usize) -> u32) -> Self {
+                trace!(target: "initializing", "Using given ranks");
+                self.set_ranks(rank)
+            }
+
+            /// Only assign ranks, see
+            /// [CoordinatesBuilder::rank](CoordinatesBuilder#method.rank).
+            pub fn rank(self) -> Rankings<usize> {
+                self.run_ranking()
+            }
+
+            /// Only order the vertices within each rank, see
+            /// [CoordinatesBuilder::order](CoordinatesBuilder#method.order).
+            pub fn order(self, rankings: &Rankings<usize>) -> Orderings<usize> {
+                self.run_ordering(rankings.iter().flatten().copied())
+            }
+
+            /// Only calculate the coordinates, see
+            /// [CoordinatesBuilder::assign_coordinates](CoordinatesBuilder#method.assign_coordinates).
+            pub fn assign_coordinates(self, orderings: &Orderings<usize>) -> Layouts<usize> {
+                self.run_coordinates(orderings.clone())
+            }
+        }
+    };
+}
+
+index_input!(&[(u32, u32)]);
+index_input!((&[u32], &[(u32, u32)]));
+
+#[test]
+fn from_env_all_valid() {
+    use super::from_edges;
+    use std::env;
+    let edges = [(1, 2), (2, 3)];
+    env::set_var(ENV_MINIMUM_LENGTH, "5");
+    env::set_var(ENV_DUMMY_VERTICES, "y");
+    env::set_var(ENV_DUMMY_SIZE, "0.1");
+    env::set_var(ENV_RANKING_TYPE, "up");
+    env::set_var(ENV_CROSSING_MINIMIZATION, "median");
+    env::set_var(ENV_TRANSPOSE, "n");
+    env::set_var(ENV_VERTEX_SPACING, "20");
+    env::set_var(ENV_MAX_SWEEPS, "6");
+    let cfg = from_edges(&edges).configure_from_env();
+    assert_eq!(cfg.config.minimum_length, 5);
+    assert!(cfg.config.dummy_vertices);
+    assert_eq!(cfg.config.dummy_size, 0.1);
+    assert_eq!(cfg.config.ranking_type, RankingType::Up);
+    assert_eq!(cfg.config.c_minimization, CrossingMinimization::Median);
+    assert!(!cfg.config.transpose);
+    assert_eq!(cfg.config.vertex_spacing, 20.);
+    assert_eq!(cfg.config.max_sweeps, Some(6));
+}
+
+#[test]
+fn from_env_invalid_value() {
+    use super::from_edges;
+    use std::env;
+
+    let edges = [(1, 2)];
+    env::set_var(ENV_CROSSING_MINIMIZATION, "flubbeldiflap");
+    env::set_var(ENV_VERTEX_SPACING, "1.5");
+    let cfg = from_edges(&edges);
+    let default = Config::default();
+    assert_eq!(default.c_minimization, cfg.config.c_minimization);
+    assert_eq!(default.vertex_spacing, cfg.config.vertex_spacing);
+}
+
+#[test]
+fn run_algo_empty_graph() {
+    use super::from_edges;
+    let edges = [];
+    let g = from_edges(&edges).build();
+    assert!(g.is_empty());
+}
+
+#[test]
+fn layouts_are_deterministic() {
+    use super::from_edges;
+    let edges = graph_generator::GraphLayout::new_from_num_nodes(200, 2).build_edges();
+    let first = from_edges(&edges).build();
+    for _ in 0..3 {
+        assert_eq!(from_edges(&edges).build(), first);
+    }
+}
+
+#[test]
+fn previous_layout_is_kept_for_unchanged_graph() {
+    use super::from_edges;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 5), (5, 6)];
+    let first = from_edges(&edges).build();
+    let second = from_edges(&edges).previous_layout(&first).build();
+    let mut first = first[0].0.clone();
+    let mut second = second[0].0.clone();
+    first.sort();
+    second.sort();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn previous_layout_keeps_order_of_ranks() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 5), (4, 6)];
+    // mirror the layout, so vertices of each rank are in reverse order
+    let mut previous = from_edges(&edges).build();
+    previous[0].0.iter_mut().for_each(|(_, (x, _))| *x = -*x);
+    let layout = from_edges(&edges).previous_layout(&previous).build();
+    let layout = layout[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let previous = previous[0].0.iter().copied().collect::<HashMap<_, _>>();
+    for v in [1, 2, 3] {
+        assert_eq!(
+            layout[&v].0 < layout[&(v + 1)].0,
+            previous[&v].0 < previous[&(v + 1)].0
+        );
+    }
+}
+
+#[test]
+fn pinned_vertices_keep_their_coordinates() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (3, 4)];
+    let layout = from_edges(&edges)
+        .pin(3, (-100, 7))
+        .pin(1, (100, -10))
+        .build();
+    let layout = layout[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(layout[&3], (-100, 7));
+    assert_eq!(layout[&1], (100, -10));
+    // the remaining vertex of the rank is placed between the pinned ones
+    assert!(layout[&2].0 > -100 && layout[&2].0 < 100);
+    assert_eq!(layout[&2].1, -10);
+}
+
+#[test]
+fn frozen_vertices_keep_ranks_and_coordinates() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    let frozen = from_edges(&edges).build()[0].0.clone();
+    // add a vertex above the frozen ones and a few below
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (1, 3),
+        (2, 3),
+        (4, 0),
+        (4, 5),
+        (5, 3),
+        (3, 6),
+    ];
+    let builder = frozen
+        .iter()
+        .fold(from_edges(&edges), |b, (v, coords)| b.freeze(*v, *coords));
+    let layout = builder.build()[0]
+        .0
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+    for (v, coords) in frozen {
+        assert_eq!(layout[&v], coords);
+    }
+    assert!(layout[&4].1 > layout[&0].1);
+    assert!(layout[&6].1 < layout[&3].1);
+}
+
+#[test]
+fn limits_still_produce_valid_layouts() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 5),
+        (3, 4),
+        (4, 6),
+        (5, 6),
+        (0, 6),
+    ];
+    let builders = [
+        from_edges(&edges).time_budget(Duration::ZERO),
+        from_edges(&edges)
+            .max_ranking_iterations(0)
+            .max_sweeps(0)
+            .max_transpose_iterations(0),
+        from_edges(&edges).max_sweeps(1),
+    ];
+    for builder in builders {
+        let layouts = builder.build();
+        assert_eq!(layouts.len(), 1);
+        let layout = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        assert_eq!(layout.len(), 7);
+        for (tail, head) in edges {
+            assert!(layout[&(tail as usize)].1 > layout[&(head as usize)].1);
+        }
+    }
+}
+
+#[test]
+fn layout_stats() {
+    use super::{from_edges, LayoutStats};
+    // complete bipartite graph with 3 + 3 vertices, which always has 9 crossings
+    let edges = [
+        (0, 3),
+        (0, 4),
+        (0, 5),
+        (1, 3),
+        (1, 4),
+        (1, 5),
+        (2, 3),
+        (2, 4),
+        (2, 5),
+    ];
+    let (layouts, stats) = from_edges(&edges).build_with_stats();
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(
+        stats,
+        vec![LayoutStats {
+            crossings: 9,
+            reversed_edges: 0,
+            dummy_vertices: 0,
+            bends: 0,
+        }]
+    );
+
+    // a cycle and an edge spanning two ranks, in a second component
+    let edges = [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (3, 5)];
+    let (_, stats) = from_edges(&edges).build_with_stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats.iter().map(|s| s.reversed_edges).sum::<usize>(), 1);
+    assert_eq!(stats.iter().map(|s| s.dummy_vertices).sum::<usize>(), 2);
+    assert!(stats.iter().all(|s| s.crossings == 0));
+}
+
+#[test]
+fn trees_are_laid_out_without_crossings() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // the edge (0, 6) spans three ranks
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (1, 3),
+        (1, 4),
+        (2, 5),
+        (5, 7),
+        (0, 6),
+        (4, 8),
+    ];
+    let (layouts, stats) = from_edges(&edges).build_with_stats();
+    assert_eq!(stats[0].crossings, 0);
+    let (layout, width, height) = &layouts[0];
+    assert_eq!(layout.len(), 9);
+    assert_eq!(*height, 4);
+    assert!(*width >= 3);
+    let mut by_y = HashMap::<isize, Vec<isize>>::new();
+    for (_, (x, y)) in layout {
+        by_y.entry(*y).or_default().push(*x);
+    }
+    for xs in by_y.values_mut() {
+        xs.sort();
+        assert!(xs.windows(2).all(|w| w[1] - w[0] >= 10));
+    }
+    let coords = layout.iter().copied().collect::<HashMap<_, _>>();
+    for (tail, head) in edges {
+        assert!(coords[&(tail as usize)].1 > coords[&(head as usize)].1);
+    }
+}
+
+#[test]
+fn non_constraining_edges_do_not_change_ranks() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // the last edge closes a cycle, but doesn't constrain the ranks
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    let (layouts, stats) = from_edges(&edges).non_constraining(3).build_with_stats();
+    assert_eq!(stats[0].reversed_edges, 0);
+    let (layout, _, height) = &layouts[0];
+    assert_eq!(*height, 4);
+    let y = layout
+        .iter()
+        .map(|(v, (_, y))| (*v, *y))
+        .collect::<HashMap<_, _>>();
+    assert!(y[&0] > y[&1] && y[&1] > y[&2] && y[&2] > y[&3]);
+}
+
+#[test]
+fn rank_constraints() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (2, 3), (0, 4), (4, 5)];
+    let y = |layouts: Layouts<usize>| {
+        layouts[0]
+            .0
+            .iter()
+            .map(|(v, (_, y))| (*v, *y))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let layout = y(from_edges(&edges)
+        .rank_constraint(5, RankConstraint::Max)
+        .rank_constraint(2, RankConstraint::Min)
+        .build());
+    // 2 is moved to the top, so the edge (1, 2) is reversed
+    assert_eq!(layout[&2], layout[&0]);
+    assert!(layout[&1] < layout[&2]);
+    assert_eq!(layout[&5], layout.values().min().copied().unwrap());
+
+    let layout = y(from_edges(&edges)
+        .rank_constraint(4, RankConstraint::Source)
+        .rank_constraint(3, RankConstraint::Sink)
+        .build());
+    let top = layout.values().max().copied().unwrap();
+    let bottom = layout.values().min().copied().unwrap();
+    assert_eq!(layout.iter().filter(|(_, y)| **y == top).count(), 1);
+    assert_eq!(layout[&4], top);
+    assert_eq!(layout.iter().filter(|(_, y)| **y == bottom).count(), 1);
+    assert_eq!(layout[&3], bottom);
+}
+
+#[test]
+fn left_of_constraints() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 5)];
+    let x = |layouts: Layouts<usize>| {
+        layouts[0]
+            .0
+            .iter()
+            .map(|(v, (x, _))| (*v, *x))
+            .collect::<HashMap<_, _>>()
+    };
+    let layout = x(from_edges(&edges).left_of(3, 1).left_of(2, 3).build());
+    assert!(layout[&3] < layout[&1]);
+    assert!(layout[&2] < layout[&3]);
+    let layout = x(from_edges(&edges).left_of(5, 4).build());
+    assert!(layout[&5] < layout[&4]);
+    // vertices in different ranks are not affected
+    let layout = x(from_edges(&edges).left_of(4, 0).build());
+    assert_eq!(layout.len(), 6);
+}
+
+#[test]
+fn vertical_edges() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 4),
+        (3, 4),
+        (0, 4),
+        (4, 5),
+    ];
+    let x = |layouts: Layouts<usize>| {
+        layouts[0]
+            .0
+            .iter()
+            .map(|(v, (x, _))| (*v, *x))
+            .collect::<HashMap<_, _>>()
+    };
+    let layout = x(from_edges(&edges).vertical(0).vertical(3).build());
+    assert_eq!(layout[&0], layout[&1]);
+    assert_eq!(layout[&1], layout[&4]);
+    // the long edge is drawn vertically via its dummy vertex
+    let layout = x(from_edges(&edges).vertical(6).vertical(7).build());
+    assert_eq!(layout[&0], layout[&4]);
+    assert_eq!(layout[&4], layout[&5]);
+    // (0, 2) shares its tail with (0, 1), so it's drawn like all other edges
+    let layout = x(from_edges(&edges).vertical(0).vertical(1).build());
+    assert_eq!(layout[&0], layout[&1]);
+    assert_ne!(layout[&0], layout[&2]);
+}
+
+#[test]
+fn alignment_groups() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 4), (3, 5), (4, 5), (2, 5)];
+    let x = |layouts: Layouts<usize>| {
+        layouts[0]
+            .0
+            .iter()
+            .map(|(v, (x, _))| (*v, *x))
+            .collect::<HashMap<_, _>>()
+    };
+    let layout = x(from_edges(&edges).align(&[1, 3]).align(&[2, 4, 0]).build());
+    assert_eq!(layout[&1], layout[&3]);
+    assert_eq!(layout[&0], layout[&2]);
+    assert_eq!(layout[&2], layout[&4]);
+    // 1 and 4 are in different ranks, but 4 is already aligned with 2
+    let layout = x(from_edges(&edges).align(&[2, 4]).align(&[1, 4]).build());
+    assert_eq!(layout[&2], layout[&4]);
+    assert_ne!(layout[&1], layout[&4]);
+}
+
+#[test]
+fn clusters_keep_other_vertices_out() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 4),
+        (3, 5),
+        (2, 6),
+        (4, 7),
+        (5, 7),
+        (6, 7),
+    ];
+    let members = [1, 3, 4, 5];
+    let (layouts, geometry) = from_edges(&edges)
+        .cluster(0, &members)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let [cluster] = geometry[0].clusters[..] else {
+        panic!("expected a single cluster box");
+    };
+    assert_eq!(cluster.id, 0);
+    for (v, (x, y)) in &coordinates {
+        let inside = (cluster.min.0..=cluster.max.0).contains(x)
+            && (cluster.min.1..=cluster.max.1).contains(y);
+        assert_eq!(inside, members.contains(v), "vertex {v} at ({x}, {y})");
+    }
+}
+
+#[test]
+fn nested_clusters() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 4),
+        (3, 5),
+        (2, 6),
+        (4, 7),
+        (5, 7),
+        (6, 7),
+    ];
+    let (layouts, geometry) = from_edges(&edges)
+        .cluster(0, &[1, 4])
+        .cluster(1, &[3, 5])
+        .subcluster(0, 1)
+        .cluster_padding(1, 3)
+        .cluster_label(1, (40, 6))
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let [inner, outer] = geometry[0].clusters[..] else {
+        panic!("expected two cluster boxes");
+    };
+    assert_eq!((inner.id, outer.id), (0, 1));
+    let inside = |c: crate::ClusterBox, (x, y): (isize, isize)| {
+        (c.min.0..=c.max.0).contains(&x) && (c.min.1..=c.max.1).contains(&y)
+    };
+    assert!(inside(outer, inner.min) && inside(outer, inner.max));
+    assert!(outer.max.0 - outer.min.0 >= 40);
+    for (v, p) in coordinates {
+        assert_eq!(inside(inner, p), [1, 4].contains(&v), "vertex {v} at {p:?}");
+        assert_eq!(
+            inside(outer, p),
+            [1, 3, 4, 5].contains(&v),
+            "vertex {v} at {p:?}"
+        );
+        if [3, 5].contains(&v) {
+            assert!(p.0 - outer.min.0 >= 3 && outer.max.0 - p.0 >= 3);
+        }
+    }
+    // the label is placed above the topmost member
+    let top = [1, 3].map(|v| layouts[0].0.iter().find(|(id, _)| *id == v).unwrap().1 .1);
+    assert!(outer.max.1 - top.into_iter().max().unwrap() >= 9);
+}
+
+#[test]
+fn lanes_contain_their_vertices() {
+    use super::from_edges;
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (0, 4), (2, 5)];
+    let lanes = [[0, 3].as_slice(), &[1, 4], &[2, 5]];
+    // lanes are ordered by their id, not by the order of the calls
+    let (layouts, geometry) = from_edges(&edges)
+        .lane(2, lanes[0])
+        .lane(0, lanes[1])
+        .lane(1, lanes[2])
+        .build_with_geometry();
+    let lane_ids = geometry[0].lanes.iter().map(|l| l.id).collect::<Vec<_>>();
+    assert_eq!(lane_ids, [0, 1, 2]);
+    for w in geometry[0].lanes.windows(2) {
+        assert_eq!(w[0].max, w[1].min);
+    }
+    for (members, id) in lanes.iter().zip([2, 0, 1]) {
+        let lane = geometry[0].lanes[id];
+        for (v, (x, _)) in &layouts[0].0 {
+            if members.contains(v) {
+                assert!(
+                    lane.min < *x && *x < lane.max,
+                    "vertex {v} at {x}: {lane:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn digraph_layout_matches_stable_graph() {
+    use super::{from_digraph, from_graph};
+    use petgraph::graph::DiGraph;
+    let mut graph = DiGraph::<&str, ()>::new();
+    let v = ["a", "bb", "c", "ddd"].map(|l| graph.add_node(l));
+    for (tail, head) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+        graph.add_edge(v[tail], v[head], ());
+    }
+    let size = |label: &&str| (label.len() as f64 * 7.5, 20.);
+    let layouts = from_digraph(&graph)
+        .left_of(v[2], v[1])
+        .measure(&graph, size)
+        .build();
+    let stable = StableDiGraph::from(graph.clone());
+    let expected = from_graph(&stable)
+        .left_of(v[2], v[1])
+        .measure(&stable, size)
+        .build();
+    assert_eq!(layouts, expected);
+}
+
+#[test]
+fn wide_indices_keep_their_vertices() {
+    use super::from_graph;
+    let mut graph = StableDiGraph::<(), (), usize>::default();
+    let v = [(); 5].map(|_| graph.add_node(()));
+    for (tail, head) in [(0, 1), (0, 2), (1, 4), (2, 4), (3, 4)] {
+        graph.add_edge(v[tail], v[head], ());
+    }
+    graph.remove_node(v[3]);
+    let mut narrow = StableDiGraph::<(), ()>::new();
+    let n = [(); 5].map(|_| narrow.add_node(()));
+    for (tail, head) in [(0, 1), (0, 2), (1, 4), (2, 4), (3, 4)] {
+        narrow.add_edge(n[tail], n[head], ());
+    }
+    narrow.remove_node(n[3]);
+    let layouts = from_graph(&graph).left_of(v[2], v[1]).build();
+    let expected = from_graph(&narrow).left_of(n[2], n[1]).build();
+    let widen = |layouts: Layouts<NodeIndex>| {
+        layouts
+            .into_iter()
+            .map(|(coords, w, h)| {
+                let coords = coords
+                    .into_iter()
+                    .map(|(v, c)| (NodeIndex::<usize>::new(v.index()), c))
+                    .collect::<Vec<_>>();
+                (coords, w, h)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(layouts, widen(expected));
+}
+
+#[test]
+fn graphmap_layout_is_keyed_by_vertices() {
+    use super::from_graphmap;
+    use petgraph::graphmap::DiGraphMap;
+    use std::collections::HashMap;
+    let graph =
+        DiGraphMap::<&str, ()>::from_edges([("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+    let layouts = from_graphmap(&graph)
+        .left_of("c", "b")
+        .measure(|k| (k.len() as f64 * 10., 10.))
+        .build();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(coordinates.len(), 4);
+    assert!(coordinates["c"].0 < coordinates["b"].0);
+    assert!(coordinates["a"].1 > coordinates["b"].1);
+    assert!(coordinates["b"].1 > coordinates["d"].1);
+}
+
+#[test]
+fn measured_vertices_do_not_overlap() {
+    use super::from_graph;
+    use std::collections::HashMap;
+    let mut graph = StableDiGraph::<&str, ()>::new();
+    let labels = ["a", "a long label", "bb", "another long label", "c", "ddd"];
+    let v = labels.map(|l| graph.add_node(l));
+    for (tail, head) in [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 5), (2, 5)] {
+        graph.add_edge(v[tail], v[head], ());
+    }
+    let size = |label: &&str| (label.len() as f64 * 7.5, 20.);
+    let layouts = from_graph(&graph)
+        .vertex_spacing(10)
+        .measure(&graph, size)
+        .build();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    for a in &v {
+        for b in v.iter().filter(|b| *b != a) {
+            let ((xa, ya), (xb, yb)) = (coordinates[a], coordinates[b]);
+            let (wa, wb) = (size(&graph[*a]).0, size(&graph[*b]).0);
+            if ya == yb {
+                assert!((xa - xb).abs() as f64 >= 10. + (wa + wb) / 2.);
+            } else {
+                assert!((ya - yb).abs() >= 30);
+            }
+        }
+    }
+}
+
+#[test]
+fn edges_attach_to_ports() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // the edge from 2 to 0 is reversed to remove the cycle
+    let edges = [(0, 1), (0, 2), (1, 2), (2, 0)];
+    let (layouts, geometry) = from_edges(&edges)
+        .ports(0, &[-3, 3])
+        .ports(2, &[-2, 0, 2])
+        .edge_ports(0, Some(0), None)
+        .edge_ports(1, Some(1), Some(2))
+        .edge_ports(3, Some(0), Some(5))
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let routes = &geometry[0].edges;
+    let (x0, y0) = coordinates[&0];
+    let (x2, y2) = coordinates[&2];
+    assert_eq!(routes[0].points[0], (x0 - 3, y0));
+    assert_eq!(routes[0].points.last(), Some(&coordinates[&1]));
+    assert_eq!(routes[1].points[0], (x0 + 3, y0));
+    assert_eq!(routes[1].points.last(), Some(&(x2 + 2, y2)));
+    // vertex 0 has no port 5
+    assert_eq!(routes[3].points[0], (x2 - 2, y2));
+    assert_eq!(routes[3].points.last(), Some(&coordinates[&0]));
+}
+
+#[test]
+fn hyperedges_share_a_trunk() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (3, 4), (5, 6)];
+    let (layouts, geometry) = from_edges(&edges)
+        .hyperedge(0, &[3, 4, 2])
+        .hyperedge(5, &[6])
+        .build_with_geometry();
+    // vertices 5 and 6 are not connected to the first hyperedge
+    assert_eq!(layouts.len(), 2);
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    // junctions are not part of the layout
+    assert_eq!(coordinates.len(), 5);
+    let routes = &geometry[0];
+    assert_eq!(routes.edges.len(), 3);
+    assert_eq!(routes.hyperedges.len(), 1);
+    let hyperedge = &routes.hyperedges[0];
+    assert_eq!(hyperedge.hyperedge, 0);
+    assert_eq!(hyperedge.trunk.first(), Some(&coordinates[&0]));
+    let junction = *hyperedge.trunk.last().unwrap();
+    assert!(junction.1 < coordinates[&0].1);
+    assert_eq!(hyperedge.branches.len(), 3);
+    for (branch, target) in hyperedge.branches.iter().zip([3, 4, 2]) {
+        assert_eq!(branch.first(), Some(&junction));
+        assert_eq!(branch.last(), Some(&coordinates[&target]));
+    }
+    assert_eq!(geometry[1].hyperedges[0].hyperedge, 1);
+}
+
+#[test]
+fn edges_end_at_borders() {
+    use super::from_graph;
+    use std::collections::HashMap;
+    let mut graph = StableDiGraph::<(f64, f64), ()>::new();
+    let a = graph.add_node((20., 10.));
+    let b = graph.add_node((10., 10.));
+    let c = graph.add_node((0., 0.));
+    graph.add_edge(a, b, ());
+    graph.add_edge(a, c, ());
+    let (layouts, geometry) = from_graph(&graph)
+        .measure(&graph, |size| *size)
+        .shape(b, Shape::Ellipse)
+        .edge_routing(EdgeRouting::Orthogonal)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let ((xa, ya), (xb, yb)) = (coordinates[&a], coordinates[&b]);
+    let routes = &geometry[0].edges;
+    // orthogonal routes leave and enter vertically
+    assert_eq!(routes[0].points.first(), Some(&(xa, ya - 5)));
+    assert_eq!(routes[0].points.last(), Some(&(xb, yb + 5)));
+    assert_eq!(routes[1].points.first(), Some(&(xa, ya - 5)));
+    // vertices without a size are points
+    assert_eq!(routes[1].points.last(), Some(&coordinates[&c]));
+}
+
+#[test]
+fn edge_routes() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (0, 2), (2, 0)];
+    let (layouts, geometry) = from_edges(&edges).build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let routes = &geometry[0].edges;
+    assert_eq!(
+        routes.iter().map(|r| r.edge).collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+    for (route, (tail, head)) in routes.iter().zip(edges) {
+        assert_eq!(route.points.first(), Some(&coordinates[&(tail as usize)]));
+        assert_eq!(route.points.last(), Some(&coordinates[&(head as usize)]));
     }
+    // the edges between 0 and 2 span two ranks and pass through a dummy vertex
+    assert_eq!(routes[2].points.len(), 3);
+    assert_eq!(routes[3].points.len(), 3);
 }
 
-impl CoordinatesBuilder<&[(u32, u32)]> {
-    /// Build the layout.
-    pub fn build(self) -> Layouts<usize> {
-        let Self {
-            config,
-            _inner: graph,
-            ..
-        } = self;
-        algorithm::start(graph, config)
+#[test]
+fn orthogonal_edge_routes() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (3, 4), (0, 4), (4, 0)];
+    let (layouts, geometry) = from_edges(&edges)
+        .edge_routing(EdgeRouting::Orthogonal)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    for (route, (tail, head)) in geometry[0].edges.iter().zip(edges) {
+        assert_eq!(route.points.first(), Some(&coordinates[&(tail as usize)]));
+        assert_eq!(route.points.last(), Some(&coordinates[&(head as usize)]));
+        for w in route.points.windows(2) {
+            assert!(w[0].0 == w[1].0 || w[0].1 == w[1].1);
+        }
     }
 }
 
-impl CoordinatesBuilder<(&[u32], &[(u32, u32)])> {
-    /// Build the layout.
-    pub fn build(self) -> Layouts<usize> {
-        let Self {
-            config,
-            _inner: graph,
-            ..
-        } = self;
-        algorithm::start(graph, config)
+#[test]
+fn spline_edge_routes() {
+    use super::from_edges;
+    let edges = [(0, 1), (0, 2), (1, 2)];
+    let (_, polylines) = from_edges(&edges).build_with_geometry();
+    let (_, splines) = from_edges(&edges)
+        .edge_routing(EdgeRouting::Spline)
+        .build_with_geometry();
+    for (polyline, spline) in polylines[0].edges.iter().zip(&splines[0].edges) {
+        assert_eq!(spline.points.len(), 3 * polyline.points.len() - 2);
+        // every third point is a point of the polyline
+        let points = spline.points.iter().step_by(3).copied().collect::<Vec<_>>();
+        assert_eq!(points, polyline.points);
     }
 }
 
 #[test]
-fn from_env_all_valid() {
+fn back_edges_in_channels() {
     use super::from_edges;
-    use std::env;
-    let edges = [(1, 2), (2, 3)];
-    env::set_var(ENV_MINIMUM_LENGTH, "5");
-    env::set_var(ENV_DUMMY_VERTICES, "y");
-    env::set_var(ENV_DUMMY_SIZE, "0.1");
-    env::set_var(ENV_RANKING_TYPE, "up");
-    env::set_var(ENV_CROSSING_MINIMIZATION, "median");
-    env::set_var(ENV_TRANSPOSE, "n");
-    env::set_var(ENV_VERTEX_SPACING, "20");
-    let cfg = from_edges(&edges).configure_from_env();
-    assert_eq!(cfg.config.minimum_length, 5);
-    assert_eq!(cfg.config.dummy_vertices, true);
-    assert_eq!(cfg.config.dummy_size, 0.1);
-    assert_eq!(cfg.config.ranking_type, RankingType::Up);
-    assert_eq!(cfg.config.c_minimization, CrossingMinimization::Median);
-    assert_eq!(cfg.config.transpose, false);
-    assert_eq!(cfg.config.vertex_spacing, 20);
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 4), (3, 1), (4, 0), (0, 5)];
+    let (layouts, geometry) = from_edges(&edges)
+        .back_edge_channels(true)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let routes = &geometry[0].edges;
+    assert_eq!(routes.len(), edges.len());
+    let mut back_edges = 0;
+    for (route, (tail, head)) in routes.iter().zip(edges) {
+        let (tail, head) = (coordinates[&(tail as usize)], coordinates[&(head as usize)]);
+        if tail.1 < head.1 {
+            back_edges += 1;
+            let x = route.points[1].0;
+            assert_eq!(route.points, [tail, (x, tail.1), (x, head.1), head]);
+            // the channel passes all vertices between both endpoints
+            let spanned = coordinates
+                .values()
+                .filter(|(_, y)| *y >= tail.1 && *y <= head.1);
+            assert!(spanned.clone().all(|v| v.0 < x) || spanned.clone().all(|v| v.0 > x));
+        }
+    }
+    assert!(back_edges > 0);
 }
 
 #[test]
-fn from_env_invalid_value() {
+fn transitive_edges_are_left_out() {
     use super::from_edges;
-    use std::env;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (0, 2), (2, 3), (0, 3), (1, 3)];
+    let (layouts, geometry) = from_edges(&edges)
+        .transitive_reduction(true)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(geometry[0].transitive_edges, [2, 4, 5]);
+    // the remaining edges form a path, so the vertices are placed in a line
+    assert!(coordinates.values().all(|(x, _)| *x == coordinates[&0].0));
+    for e in [2, 4, 5] {
+        let (tail, head) = edges[e];
+        assert_eq!(
+            geometry[0].edges[e].points,
+            [coordinates[&(tail as usize)], coordinates[&(head as usize)]]
+        );
+    }
 
-    let edges = [(1, 2)];
-    env::set_var(ENV_CROSSING_MINIMIZATION, "flubbeldiflap");
-    env::set_var(ENV_VERTEX_SPACING, "1.5");
-    let cfg = from_edges(&edges);
-    let default = Config::default();
-    assert_eq!(default.c_minimization, cfg.config.c_minimization);
-    assert_eq!(default.vertex_spacing, cfg.config.vertex_spacing);
+    let (_, geometry) = from_edges(&edges).build_with_geometry();
+    assert!(geometry[0].transitive_edges.is_empty());
 }
 
 #[test]
-fn run_algo_empty_graph() {
+fn strongly_connected_components_are_condensed() {
     use super::from_edges;
-    let edges = [];
-    let g = from_edges(&edges).build();
-    assert!(g.is_empty());
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (5, 0)];
+    let (layouts, geometry) = from_edges(&edges)
+        .condense_components(true)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let mut ids = coordinates.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, [0, 1, 2, 3, 4, 5]);
+    let boxes = &geometry[0].condensed;
+    assert_eq!(boxes.len(), 2);
+    assert_eq!(boxes[0].vertices, [0, 1, 2]);
+    assert_eq!(boxes[1].vertices, [3, 4]);
+    for b in boxes {
+        for v in &b.vertices {
+            let (x, y) = coordinates[v];
+            assert!(b.min.0 < x && x < b.max.0 && b.min.1 < y && y < b.max.1);
+        }
+    }
+    // the boxes are placed like vertices of an acyclic graph
+    assert!(boxes[0].min.1 > boxes[1].max.1);
+    assert!(coordinates[&5].1 > boxes[0].max.1);
+    // edges between components end at their vertices inside the boxes
+    for (route, (tail, head)) in geometry[0].edges.iter().zip(edges) {
+        assert_eq!(route.points[0], coordinates[&(tail as usize)]);
+        assert_eq!(*route.points.last().unwrap(), coordinates[&(head as usize)]);
+    }
+
+    let (_, geometry) = from_edges(&edges).build_with_geometry();
+    assert!(geometry[0].condensed.is_empty());
+}
+
+#[test]
+fn chains_are_contracted() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 4),
+        (0, 5),
+        (5, 6),
+        (6, 4),
+        (0, 4),
+    ];
+    let (layouts, geometry) = from_edges(&edges)
+        .contract_chains(true)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(coordinates.len(), 7);
+    // vertices of chains keep their ranks
+    let (expected, _) = from_edges(&edges).build_with_geometry();
+    for (v, (_, y)) in &expected[0].0 {
+        assert_eq!(coordinates[v].1, *y);
+    }
+    assert_eq!(geometry[0].edges.len(), edges.len());
+    for (route, (tail, head)) in geometry[0].edges.iter().zip(edges) {
+        assert_eq!(route.points[0], coordinates[&(tail as usize)]);
+        assert_eq!(*route.points.last().unwrap(), coordinates[&(head as usize)]);
+    }
+}
+
+#[test]
+fn flat_edges_are_horizontal() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 3)];
+    let (layouts, geometry) = from_edges(&edges).non_constraining(3).build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let (tail, head) = (coordinates[&1], coordinates[&3]);
+    assert_eq!(tail.1, head.1);
+    // both endpoints are placed next to each other
+    assert!(!(tail.0.min(head.0)..tail.0.max(head.0)).contains(&coordinates[&2].0));
+    assert_eq!(geometry[0].edges[3].points, [tail, head]);
+}
+
+#[test]
+fn vertices_span_several_ranks() {
+    use super::from_edges;
+    use crate::RankSpan;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (0, 3), (3, 2)];
+    let (layouts, geometry) = from_edges(&edges).rank_span(1, 3).build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(coordinates.len(), 4);
+    let (x, y) = coordinates[&1];
+    let spacing = Config::default().spacing() as isize;
+    assert_eq!(
+        geometry[0].spans,
+        [RankSpan {
+            vertex: 1,
+            min: (x, y - 2 * spacing),
+            max: (x, y),
+        }]
+    );
+    // outgoing edges leave the bottommost rank of the vertex
+    assert_eq!(geometry[0].edges[1].points[0], (x, y - 2 * spacing));
+    assert_eq!(coordinates[&2].1, y - 3 * spacing);
+}
+
+#[test]
+fn same_seed_gives_same_layout() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 5),
+        (3, 4),
+        (3, 7),
+        (4, 6),
+        (5, 6),
+        (7, 6),
+        (0, 6),
+    ];
+    let layout = |seed| from_edges(&edges).seed(seed).build();
+    assert_eq!(layout(1), layout(1));
+    for seed in 0..8 {
+        let layouts = layout(seed);
+        assert_eq!(layouts.len(), 1);
+        let layout = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        assert_eq!(layout.len(), 8);
+        for (tail, head) in edges {
+            assert!(layout[&(tail as usize)].1 > layout[&(head as usize)].1);
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "json", feature = "toml")))]
+fn write_config_file(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rust-sugiyama-{}-{name}", std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_config_file_toml_merges() {
+    use super::from_edges;
+    let path = write_config_file(
+        "merge.toml",
+        "vertex_spacing = 42\nranking_type = \"down\"\n",
+    );
+    let cfg = from_edges(&[(0, 1)])
+        .transpose(false)
+        .from_config_file(&path)
+        .unwrap();
+    assert_eq!(cfg.config.vertex_spacing, 42.);
+    assert_eq!(cfg.config.ranking_type, RankingType::Down);
+    // values not in the file are kept
+    assert!(!cfg.config.transpose);
+    assert_eq!(cfg.config.minimum_length, MINIMUM_LENGTH_DEFAULT);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_config_file_unknown_key() {
+    let path = write_config_file("unknown.toml", "vertex_spacin = 42\n");
+    let err = Config::default().read_file(&path).unwrap_err();
+    assert!(matches!(err, ConfigError::Toml(_)));
+    assert!(err.to_string().contains("vertex_spacin"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn from_config_file_json_invalid_value() {
+    let path = write_config_file("invalid.json", r#"{ "c_minimization": "average" }"#);
+    let err = Config::default().read_file(&path).unwrap_err();
+    assert!(matches!(err, ConfigError::Json(_)));
+    assert!(err.to_string().contains("average"));
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+#[test]
+fn from_config_file_unsupported_format() {
+    let path = write_config_file("config.yaml", "vertex_spacing: 42\n");
+    let err = Config::default().read_file(&path).unwrap_err();
+    assert!(matches!(err, ConfigError::UnsupportedFormat(_)));
+}
+
+#[test]
+fn commit_graphs_use_few_lanes() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // a merge commit 1 with the first parent 2 and the second parent 5
+    let edges = [(0, 1), (1, 2), (1, 5), (2, 3), (5, 3), (3, 4)];
+    let (layouts, geometry) = from_edges(&edges).commit_graph(true).build_with_geometry();
+    let (coordinates, width, height) = &layouts[0];
+    let coordinates = coordinates.iter().copied().collect::<HashMap<_, _>>();
+    let s = VERTEX_SPACING_DEFAULT as isize;
+    assert_eq!((*width, *height), (2, 6));
+    // first parents stay in the first lane, one vertex per row
+    for (v, row) in [(0, 0), (1, 1), (2, 2), (3, 4), (4, 5)] {
+        assert_eq!(coordinates[&v], (0, -row * s));
+    }
+    assert_eq!(coordinates[&5], (s, -3 * s));
+    let routes = geometry[0]
+        .edges
+        .iter()
+        .map(|route| (route.edge, route.points.clone()))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(routes[&2], [(0, -s), (s, -s - s / 2), (s, -3 * s)]);
+    // the first parent of 5 is 3, so the edge stays in its lane until it merges
+    assert_eq!(routes[&4], [(s, -3 * s), (s, -4 * s + s / 2), (0, -4 * s)]);
+    assert_eq!(routes[&3], [(0, -2 * s), (0, -4 * s)]);
+}
+
+#[test]
+fn radial_layouts_place_ranks_on_circles() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 5), (3, 6)];
+    let (layouts, geometry) = from_edges(&edges).radial(true).build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let radius = |v: usize| {
+        let (x, y) = coordinates[&v];
+        ((x * x + y * y) as f64).sqrt()
+    };
+    // the root is in the center, its children and grandchildren on two circles
+    assert_eq!(coordinates[&0], (0, 0));
+    for (v, w) in [(1, 2), (2, 3), (4, 5), (5, 6)] {
+        assert!((radius(v) - radius(w)).abs() < 1.5);
+    }
+    assert!(radius(4) >= radius(1) + VERTEX_SPACING_DEFAULT as f64 - 1.5);
+    // vertices on the same circle keep the vertex spacing
+    for (v, w) in [(1, 2), (2, 3), (3, 1)] {
+        let ((x1, y1), (x2, y2)) = (coordinates[&v], coordinates[&w]);
+        let distance = (((x1 - x2).pow(2) + (y1 - y2).pow(2)) as f64).sqrt();
+        assert!(distance >= VERTEX_SPACING_DEFAULT as f64 - 1.5);
+    }
+    for route in &geometry[0].edges {
+        let (tail, head) = edges[route.edge];
+        assert_eq!(route.points[0], coordinates[&(tail as usize)]);
+        assert_eq!(*route.points.last().unwrap(), coordinates[&(head as usize)]);
+    }
+}
+
+#[test]
+fn custom_ranking_algorithm() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // puts each vertex into the rank given by its index, except vertex 2
+    let ranking = |graph: &StableDiGraph<usize, ()>, _| {
+        graph
+            .node_indices()
+            .filter(|v| graph[*v] != 2)
+            .map(|v| (v, graph[v] as u32))
+            .collect::<HashMap<_, _>>()
+    };
+    let edges = [(0, 1), (0, 3), (1, 2), (3, 4)];
+    let layouts = from_edges(&edges).ranking_algorithm(ranking).build();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let s = VERTEX_SPACING_DEFAULT as isize;
+    for (v, rank) in [(0, 0), (1, 1), (3, 3), (4, 4)] {
+        assert_eq!(coordinates[&v].1, -rank * s);
+    }
+    // vertex 2 has no rank, so it is moved below its predecessor
+    assert_eq!(coordinates[&2].1, -2 * s);
+    // the algorithm takes precedence over the ranking type, which is kept
+    let builder = from_edges(&edges)
+        .layering_type(RankingType::Up)
+        .ranking_algorithm(ranking);
+    assert_eq!(builder.config.ranking_type, RankingType::Up);
+    assert_eq!(builder.build(), layouts);
+}
+
+#[test]
+fn custom_ordering_algorithm() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // orders the vertices of each rank by descending index, dummy vertices last
+    let ordering = |graph: &StableDiGraph<Option<usize>, ()>, mut ranks: Vec<Vec<NodeIndex>>| {
+        for rank in &mut ranks {
+            rank.sort_by_key(|v| std::cmp::Reverse(graph[*v]));
+        }
+        ranks
+    };
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (0, 4)];
+    let (layouts, geometry) = from_edges(&edges)
+        .ordering_algorithm(ordering)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert!(coordinates[&3].0 < coordinates[&2].0);
+    assert!(coordinates[&2].0 < coordinates[&1].0);
+    // the dummy vertex of the edge (0, 4) is placed right of vertex 1
+    let route = &geometry[0].edges[4].points;
+    assert!(route.iter().any(|(x, _)| *x > coordinates[&1].0));
+}
+
+#[test]
+fn custom_coordinate_assignment() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // places the vertices of each rank on a diagonal
+    let assignment = |_: &StableDiGraph<Option<usize>, ()>, ranks: &[Vec<NodeIndex>], spacing| {
+        let mut coordinates = HashMap::new();
+        for (rank, vertices) in ranks.iter().enumerate() {
+            for (i, v) in vertices.iter().enumerate() {
+                let x = (rank + i) as isize * spacing as isize;
+                coordinates.insert(*v, (x, -x));
+            }
+        }
+        coordinates
+    };
+    let edges = [(0, 1), (1, 2), (0, 2)];
+    let (layouts, geometry) = from_edges(&edges)
+        .coordinate_assignment(assignment)
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let s = VERTEX_SPACING_DEFAULT as isize;
+    assert_eq!(coordinates[&0], (0, 0));
+    assert_eq!(coordinates[&2], (2 * s, -2 * s));
+    // the route of the long edge passes through its dummy vertex
+    let route = &geometry[0].edges[2].points;
+    assert_eq!(route.len(), 3);
+    assert_eq!(route[1].0, -route[1].1);
+}
+
+#[test]
+fn phases_run_separately() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (4, 5)];
+    let mut rankings = from_edges(&edges).rank();
+    assert_eq!(rankings[0], [(0, 0), (1, 1), (2, 1), (3, 2)]);
+    assert_eq!(rankings[1], [(4, 0), (5, 1)]);
+
+    // vertex 3 moves down, so the rank between has dummy vertices only
+    rankings[0][3].1 = 3;
+    let mut orderings = from_edges(&edges).order(&rankings);
+    assert_eq!(orderings[0].len(), 4);
+    assert!(orderings[0][2].is_empty());
+    // the graph is st-planar, so vertex 1 stays left of vertex 2
+    assert_eq!(orderings[0][1], [1, 2]);
+    orderings[0][1] = vec![2, 1];
+    let layouts = from_edges(&edges).assign_coordinates(&orderings);
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let s = VERTEX_SPACING_DEFAULT as isize;
+    assert!(coordinates[&2].0 < coordinates[&1].0);
+    assert_eq!(coordinates[&3].1, -3 * s);
+    assert_eq!(layouts[1].0.len(), 2);
+}
+
+#[test]
+fn given_ranks_skip_ranking() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (1, 2), (0, 2), (3, 2)];
+    let s = VERTEX_SPACING_DEFAULT as isize;
+    let layouts = from_edges(&edges).with_ranks(|v| 2 * v as u32).build();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(coordinates[&0].1, 0);
+    assert_eq!(coordinates[&1].1, -2 * s);
+    // vertex 3 is below vertex 2, so vertex 2 is moved below it
+    assert_eq!(coordinates[&3].1, -6 * s);
+    assert_eq!(coordinates[&2].1, -7 * s);
+    let layouts = from_edges(&edges).with_ranks(|_| 0).build();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    assert_eq!(coordinates[&2].1, -2 * s);
+    assert_eq!(coordinates[&3].1, 0);
+}
+
+#[test]
+fn fixed_order_keeps_vertices_in_sequence() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (3, 4), (2, 5)];
+    // 1 and 3 share a child, so they'd be placed next to each other
+    let layouts = from_edges(&edges).fixed_order(&[3, 5, 2, 1]).build();
+    let x = layouts[0]
+        .0
+        .iter()
+        .map(|(v, (x, _))| (*v, *x))
+        .collect::<HashMap<_, _>>();
+    assert!(x[&3] < x[&2] && x[&2] < x[&1]);
+}
+
+#[test]
+fn groups_are_kept_together() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (3, 4), (2, 5)];
+    // 1 and 3 share a child, so they'd be placed next to each other
+    let layouts = from_edges(&edges).keep_together(&[1, 2]).build();
+    let x = layouts[0]
+        .0
+        .iter()
+        .map(|(v, (x, _))| (*v, *x))
+        .collect::<HashMap<_, _>>();
+    assert!(x[&3] < x[&1].min(x[&2]) || x[&3] > x[&1].max(x[&2]));
+}
+
+#[test]
+fn warm_start_initializes_order() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3), (4, 1), (4, 2), (4, 3)];
+    let previous = vec![(vec![(1, (10, -10)), (2, (0, -10)), (3, (20, -10))], 3, 2)];
+    // without sweeps, the order is the initial order
+    let layouts = from_edges(&edges)
+        .warm_start(&previous)
+        .max_sweeps(0)
+        .transpose(false)
+        .build();
+    let x = layouts[0]
+        .0
+        .iter()
+        .map(|(v, (x, _))| (*v, *x))
+        .collect::<HashMap<_, _>>();
+    assert!(x[&2] < x[&1] && x[&1] < x[&3]);
+    // vertices aren't kept at their previous coordinates
+    assert_eq!(x.values().min(), Some(&0));
+}
+
+#[test]
+fn presets_lay_out_graphs() {
+    use super::from_graph;
+    use crate::validate::validate;
+    use std::collections::HashMap;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 2), (0, 4), (4, 2)]);
+    for (config, high) in [
+        (Config::fast(), true),
+        (Config::balanced(), false),
+        (Config::quality(), false),
+    ] {
+        let layouts = from_graph(&graph).with_config(config).build();
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+        let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        let (v0, v1, v3) = (0.into(), 1.into(), 3.into());
+        // ranking up moves 3 into the first rank, which lengthens its edge
+        assert_eq!(coordinates[&v3].1 == coordinates[&v0].1, high);
+        assert_eq!(coordinates[&v3].1 == coordinates[&v1].1, !high);
+    }
+}
+
+#[test]
+fn rank_spacing_is_independent() {
+    use super::from_graph;
+    use crate::validate::validate;
+    use std::collections::HashMap;
+    // a tree and a graph which isn't, which are placed by different algorithms
+    for edges in [&[(0, 1), (0, 2)][..], &[(0, 1), (0, 2), (1, 3), (2, 3)]] {
+        let graph = StableDiGraph::<(), ()>::from_edges(edges);
+        let builder = from_graph(&graph).vertex_spacing(10).rank_spacing(30);
+        let config = builder.config;
+        let layouts = builder.build();
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+        let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        let (v0, v1, v2) = (
+            coordinates[&0.into()],
+            coordinates[&1.into()],
+            coordinates[&2.into()],
+        );
+        assert_eq!(v0.1 - v1.1, 30);
+        assert_eq!(v1.1, v2.1);
+        assert_eq!((v1.0 - v2.0).abs(), 10);
+    }
+    assert_eq!(Config::default().rank_distance(), VERTEX_SPACING_DEFAULT);
+}
+
+#[test]
+fn subdivisions_keep_fractional_spacings() {
+    use super::from_graph;
+    use std::collections::HashMap;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
+    let builder = || {
+        from_graph(&graph)
+            .vertex_spacing_f64(2.5)
+            .rank_spacing_f64(1.5)
+            .measure(&graph, |_| (0.5, 0.5))
+    };
+    let layouts = builder().subdivisions(4).build_f64();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let (v0, v1, v2) = (
+        coordinates[&0.into()],
+        coordinates[&1.into()],
+        coordinates[&2.into()],
+    );
+    assert_eq!(v1.1, v2.1);
+    // borders of vertices are the spacing apart
+    assert_eq!(v0.1 - v1.1, 2.);
+    assert_eq!((v1.0 - v2.0).abs(), 3.);
+    // coordinates are rounded to whole units otherwise
+    let rounded = builder().subdivisions(4).build();
+    for ((v, (x, y)), (w, exact)) in rounded[0].0.iter().zip(&layouts[0].0) {
+        assert_eq!(v, w);
+        assert_eq!(
+            (*x, *y),
+            (exact.0.round() as isize, exact.1.round() as isize)
+        );
+    }
+    // with a single step per unit, the spacings are kept and sizes are
+    // rounded to halves, in which the spacings are whole
+    let whole = builder().build_f64();
+    let (v1, v2) = (whole[0].0[1].1, whole[0].0[2].1);
+    assert_eq!((v1.0 - v2.0).abs(), 3.);
+}
+
+#[test]
+fn fractional_spacings_set_the_scale() {
+    use super::from_edges;
+    let config = |vertex_spacing, rank_spacing, subdivisions| Config {
+        vertex_spacing,
+        rank_spacing,
+        subdivisions,
+        ..Default::default()
+    };
+    assert_eq!(config(10., None, 1).scale(), 1);
+    assert_eq!(config(2.5, None, 1).scale(), 2);
+    assert_eq!(config(2.5, Some(0.2), 1).scale(), 10);
+    assert_eq!(config(1. / 3., None, 2).scale(), 6);
+    assert_eq!(config(0.3, None, 4).scale(), 20);
+    // spacings which are not whole in any step are rounded
+    assert_eq!(config(std::f64::consts::PI, None, 3).scale(), 3);
+
+    let builder = || from_edges(&[(0, 1), (0, 2)]).vertex_spacing_f64(2.5);
+    let layouts = builder().build_f64();
+    let (v1, v2) = (layouts[0].0[1].1, layouts[0].0[2].1);
+    assert_eq!((v1.0 - v2.0).abs(), 2.5);
+    // the spacing is a single field, which replaces a fractional one
+    let mut builder = builder();
+    builder.config.vertex_spacing = 20.;
+    let layouts = builder.build_f64();
+    let (v1, v2) = (layouts[0].0[1].1, layouts[0].0[2].1);
+    assert_eq!((v1.0 - v2.0).abs(), 20.);
+}
+
+#[test]
+fn layouts_are_built_with_any_coordinate() {
+    use super::from_edges;
+    use crate::Coordinate;
+    let edges = [(0, 1), (0, 2), (1, 3)];
+    let builder = || from_edges(&edges).vertex_spacing_f64(2.5).subdivisions(2);
+    let exact = builder().build_as::<f64>();
+    let single = builder().build_as::<f32>();
+    let rounded = builder().build_as::<i32>();
+    assert_eq!(builder().build_as::<isize>(), builder().build());
+    for ((v, (x, y)), ((w, s), (u, r))) in
+        exact[0].0.iter().zip(single[0].0.iter().zip(&rounded[0].0))
+    {
+        assert!(v == w && v == u);
+        assert_eq!((*x as f32, *y as f32), *s);
+        let round = |v: f64| (v + 0.5).floor() as i32;
+        assert_eq!((round(*x), round(*y)), *r);
+    }
+    // halves are rounded up, integers saturate
+    assert_eq!(i32::from_layout_units(-3, 2), -1);
+    assert_eq!(i32::from_layout_units(3, 2), 2);
+    assert_eq!(i32::from_layout_units(isize::MAX, 1), i32::MAX);
+    assert_eq!(f32::from_layout_units(3, 4), 0.75);
+}
+
+#[test]
+fn ranks_are_as_high_as_their_vertices() {
+    use super::from_graph;
+    use crate::RankExtent;
+    let mut graph = StableDiGraph::<f64, ()>::new();
+    let v = [10., 40., 20., 0.].map(|height| graph.add_node(height));
+    graph.add_edge(v[0], v[1], ());
+    graph.add_edge(v[0], v[3], ());
+    graph.add_edge(v[1], v[2], ());
+    graph.add_edge(v[3], v[2], ());
+    let (layouts, geometry) = from_graph(&graph)
+        .rank_spacing(10)
+        .measure(&graph, |h| (10., *h))
+        .build_with_geometry();
+    let y = |i: usize| {
+        layouts[0]
+            .0
+            .iter()
+            .find(|(v, _)| v.index() == i)
+            .unwrap()
+            .1
+             .1
+    };
+    let extent = |rank, y, half| RankExtent {
+        rank,
+        y,
+        min: y - half,
+        max: y + half,
+    };
+    assert_eq!(
+        geometry[0].ranks,
+        [extent(0, y(0), 5), extent(1, y(1), 20), extent(2, y(2), 10)]
+    );
+    // the rank spacing is kept between the highest vertices of both ranks
+    assert_eq!(y(0) - y(1), 5 + 10 + 20);
+    assert_eq!(y(1) - y(2), 20 + 10 + 10);
+    assert_eq!(y(1), y(3));
+}
+
+#[test]
+fn margins_keep_vertices_apart() {
+    use super::from_graph;
+    use std::collections::HashMap;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2)]);
+    let size = |_: &()| (10., 10.);
+    let (layouts, geometry) = from_graph(&graph)
+        .vertex_spacing(10)
+        .measure(&graph, size)
+        .margin(1.into(), (5, 8))
+        .build_with_geometry();
+    let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+    let (v0, v1, v2) = (
+        coordinates[&0.into()],
+        coordinates[&1.into()],
+        coordinates[&2.into()],
+    );
+    // the spacing is kept to the margin, 10 / 2 + 5 + 10 + 10 / 2
+    assert_eq!((v1.0 - v2.0).abs(), 25);
+    // 10 / 2 + 10 + 8 + 10 / 2
+    assert_eq!(v0.1 - v1.1, 28);
+    // routes end at the box of the vertex, not at its margin
+    assert_eq!(geometry[0].edges[0].points.last().unwrap().1, v1.1 + 5);
+}
+
+#[test]
+fn padding_moves_components_to_the_origin() {
+    use super::from_graph;
+    use crate::Bounds;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (3, 4)]);
+    let size = |_: &()| (10., 6.);
+    let (layouts, geometry) = from_graph(&graph)
+        .measure(&graph, size)
+        .build_with_geometry();
+    let (padded, padded_geometry) = from_graph(&graph)
+        .measure(&graph, size)
+        .padding(4)
+        .build_with_geometry();
+    for (i, ((layout, ..), (padded, ..))) in layouts.iter().zip(&padded).enumerate() {
+        let bounds = geometry[i].bounds;
+        // the box contains the boxes of the vertices
+        let xs = layout.iter().map(|(_, (x, _))| *x);
+        let ys = layout.iter().map(|(_, (_, y))| *y);
+        assert_eq!(bounds.min.0, xs.clone().min().unwrap() - 5);
+        assert_eq!(bounds.max.0, xs.max().unwrap() + 5);
+        assert_eq!(bounds.min.1, ys.clone().min().unwrap() - 3);
+        assert_eq!(bounds.max.1, ys.max().unwrap() + 3);
+        let (width, height) = (bounds.width() + 8, bounds.height() + 8);
+        assert_eq!(
+            padded_geometry[i].bounds,
+            Bounds {
+                min: (0, -(height as isize)),
+                max: (width as isize, 0),
+            }
+        );
+        // the whole component is moved by the same amount
+        let shift = (
+            padded[0].1 .0 - layout[0].1 .0,
+            padded[0].1 .1 - layout[0].1 .1,
+        );
+        assert_eq!(shift, (4 - bounds.min.0, -4 - bounds.max.1));
+        for ((_, (x, y)), (_, (px, py))) in layout.iter().zip(padded) {
+            assert_eq!((x + shift.0, y + shift.1), (*px, *py));
+        }
+    }
+}
+
+#[test]
+fn coordinates_follow_conventions() {
+    use super::from_graph;
+    use std::collections::HashMap;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3)]);
+    let build = |y_down, mirror| {
+        let (layouts, geometry) = from_graph(&graph)
+            .padding(5)
+            .y_down(y_down)
+            .mirror(mirror)
+            .build_with_geometry();
+        let coordinates = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        (coordinates, geometry.into_iter().next().unwrap())
+    };
+    let (default, geometry) = build(false, false);
+    let width = geometry.bounds.max.0;
+    let (down, down_geometry) = build(true, false);
+    assert_eq!(down_geometry.bounds.min, (0, 0));
+    assert_eq!(
+        down_geometry.bounds.max,
+        (width, geometry.bounds.height() as isize)
+    );
+    let (mirrored, mirrored_geometry) = build(false, true);
+    assert_eq!(mirrored_geometry.bounds, geometry.bounds);
+    for (v, (x, y)) in &default {
+        assert_eq!(down[v], (*x, -y));
+        assert_eq!(mirrored[v], (width - x, *y));
+    }
+    // the topmost rank is at the top in every convention
+    assert!(down[&0.into()].1 < down[&3.into()].1);
+    assert_eq!(
+        down_geometry.edges[0].points,
+        geometry.edges[0]
+            .points
+            .iter()
+            .map(|(x, y)| (*x, -y))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(down_geometry.ranks[0].rank, 0);
+    assert!(down_geometry.ranks[0].min <= down_geometry.ranks[0].max);
+}
+
+#[test]
+fn grid_snaps_vertices() {
+    use super::from_graph;
+    use crate::validate::validate;
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (1, 4), (3, 4)]);
+    let size = |_: &()| (7., 5.);
+    let builder = from_graph(&graph)
+        .measure(&graph, size)
+        .grid(8)
+        .snap_y(true)
+        .padding(3);
+    let config = builder.config;
+    let layouts = builder.build();
+    assert_eq!(validate(&graph, &layouts, &config, size), []);
+    for (_, (x, y)) in &layouts[0].0 {
+        assert_eq!((x % 8, y % 8), (0, 0));
+    }
+}
+
+#[test]
+fn dummy_vertices_take_their_share_of_the_spacing() {
+    use super::from_graph;
+    use std::collections::BTreeMap;
+    // long edges pass the wide vertex 1 on both sides
+    let mut graph = StableDiGraph::<(f64, f64), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (0, 2),
+        (0, 3),
+        (3, 2),
+        (0, 4),
+        (4, 2),
+    ]);
+    graph[NodeIndex::new(1)] = (30., 0.);
+    for dummy_size in [1., 0.5] {
+        let (layouts, geometry) = from_graph(&graph)
+            .measure(&graph, |size| *size)
+            .dummy_size(dummy_size)
+            .build_with_geometry();
+        // left and right border of everything in each rank, and whether it's a dummy
+        let mut ranks = BTreeMap::<isize, Vec<(isize, isize, bool)>>::new();
+        for (v, (x, y)) in &layouts[0].0 {
+            let half = graph[*v].0 as isize / 2;
+            ranks
+                .entry(*y)
+                .or_default()
+                .push((x - half, x + half, false));
+        }
+        for route in &geometry[0].edges {
+            for (x, y) in &route.points[1..route.points.len() - 1] {
+                ranks.entry(*y).or_default().push((*x, *x, true));
+            }
+        }
+        let share = |dummy: bool| if dummy { 10. * dummy_size } else { 10. } as isize;
+        for boxes in ranks.values_mut() {
+            boxes.sort();
+            for w in boxes.windows(2) {
+                let ((_, right, a), (left, _, b)) = (w[0], w[1]);
+                assert!(left - right >= (share(a) + share(b)) / 2, "{boxes:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn compaction_removes_unused_space() {
+    use super::from_edges;
+    use crate::validate::validate;
+    use std::collections::HashMap;
+    let edges = [(0, 4), (1, 5), (1, 4), (3, 4), (0, 1), (1, 6)];
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    let width = |layout: &[(usize, (isize, isize))]| {
+        let xs = layout.iter().map(|(_, (x, _))| *x);
+        xs.clone().max().unwrap() - xs.min().unwrap()
+    };
+    let plain = from_edges(&edges).build();
+    let builder = from_edges(&edges).horizontal_compaction(true);
+    let config = builder.config;
+    let compact = builder.build();
+    assert!(width(&compact[0].0) < width(&plain[0].0));
+    let layouts = node_indices(compact.clone());
+    assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+    // no x-coordinate moves past another one, aligned vertices stay aligned
+    let compact = compact[0].0.iter().copied().collect::<HashMap<_, _>>();
+    for (v, (x, _)) in &plain[0].0 {
+        for (w, (y, _)) in &plain[0].0 {
+            assert!(x > y || compact[v].0 <= compact[w].0);
+            assert!(x != y || compact[v].0 == compact[w].0);
+        }
+    }
+}
+
+#[test]
+fn single_alignments_are_not_balanced() {
+    use super::from_edges;
+    use crate::validate::validate;
+    use std::collections::HashMap;
+    let edges = [(0, 1), (0, 2), (0, 3)];
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    for (alignment, child) in [
+        (Alignment::UpLeft, 0),
+        (Alignment::UpRight, 2),
+        (Alignment::DownLeft, 1),
+        (Alignment::Balanced, 1),
+    ] {
+        let builder = from_edges(&edges).alignment(alignment);
+        let config = builder.config;
+        let layouts = builder.build();
+        let layout = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        let mut children = [layout[&1].0, layout[&2].0, layout[&3].0];
+        children.sort();
+        // the root is above the leftmost, rightmost or middle child
+        assert_eq!(layout[&0].0, children[child], "{alignment:?}");
+        let layouts = node_indices(layouts);
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+    }
+}
+
+#[test]
+fn straightened_edges() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 4),
+        (3, 4),
+        (0, 4),
+        (4, 5),
+        (2, 5),
+        (3, 6),
+        (6, 5),
+    ];
+    let x = |layouts: Layouts<usize>| {
+        layouts[0]
+            .0
+            .iter()
+            .map(|(v, (x, _))| (*v, *x))
+            .collect::<HashMap<_, _>>()
+    };
+    let layout = x(from_edges(&edges).build());
+    assert_ne!(layout[&2], layout[&5]);
+    assert_ne!(layout[&6], layout[&5]);
+    // the long edge is straight via its dummy vertex
+    let layout = x(from_edges(&edges).straighten(8, 1).build());
+    assert_eq!(layout[&2], layout[&5]);
+    let layout = x(from_edges(&edges).straighten(10, 1).build());
+    assert_eq!(layout[&6], layout[&5]);
+    // (0, 1) and (0, 2) share their tail, the higher priority wins
+    let layout = x(from_edges(&edges).straighten(0, 1).straighten(1, 2).build());
+    assert_eq!(layout[&0], layout[&2]);
+    assert_ne!(layout[&0], layout[&1]);
+    let layout = x(from_edges(&edges).straighten(0, 1).straighten(1, 1).build());
+    assert_eq!(layout[&0], layout[&1]);
+}
+
+#[test]
+fn parents_are_centered() {
+    use super::from_edges;
+    use crate::validate::validate;
+    use std::collections::HashMap;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 4),
+        (3, 4),
+        (4, 5),
+        (4, 6),
+    ];
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    for center in [false, true] {
+        // a single alignment places parents above their leftmost child
+        let builder = from_edges(&edges)
+            .alignment(Alignment::UpLeft)
+            .center_parents(center);
+        let config = builder.config;
+        let layouts = builder.build();
+        let layout = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        let x = |v| layout[&v].0;
+        if center {
+            assert_eq!(x(0), (x(1) + x(2) + x(3)) / 3);
+            assert_eq!(x(4), (x(5) + x(6)) / 2);
+        } else {
+            assert_eq!(x(0), x(1).min(x(2)).min(x(3)));
+            assert_eq!(x(4), x(5).min(x(6)));
+        }
+        let layouts = node_indices(layouts);
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+    }
+}
+
+#[test]
+fn bends_are_minimized() {
+    use super::from_edges;
+    use crate::validate::validate;
+    let edges = [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 4),
+        (0, 4),
+        (5, 1),
+        (5, 2),
+        (6, 3),
+        (0, 6),
+    ];
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    let (_, plain) = from_edges(&edges).build_with_stats();
+    let builder = from_edges(&edges).minimize_bends(true);
+    let config = builder.config;
+    let (layouts, stats) = builder.build_with_stats();
+    assert_eq!(plain[0].bends, 4);
+    assert_eq!(stats[0].bends, 2);
+    assert_eq!(stats[0].dummy_vertices, plain[0].dummy_vertices);
+    let layouts = node_indices(layouts);
+    assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+}
+
+#[test]
+fn sweeps_stop_without_improvement() {
+    use super::from_edges;
+    use crate::progress::Phase;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    // complete bipartite graph, whose crossings can't be reduced by any sweep
+    let edges = [
+        (0, 3),
+        (0, 4),
+        (0, 5),
+        (1, 3),
+        (1, 4),
+        (1, 5),
+        (2, 3),
+        (2, 4),
+        (2, 5),
+    ];
+    let sweeps = |patience| {
+        let reports = Arc::new(AtomicUsize::new(0));
+        let counter = reports.clone();
+        from_edges(&edges)
+            .patience(patience)
+            .on_progress(move |p| {
+                if p.phase == Phase::CrossingReduction {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build();
+        reports.load(Ordering::Relaxed)
+    };
+    assert_eq!(sweeps(6) - sweeps(1), 5);
+    assert_eq!(sweeps(0), sweeps(1));
+    assert_eq!(sweeps(PATIENCE_DEFAULT) - sweeps(1), PATIENCE_DEFAULT - 1);
+}
+
+#[test]
+fn shuffled_transpose_is_reproducible() {
+    use super::from_graph;
+    use crate::validate::validate;
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 4),
+        (2, 5),
+        (3, 4),
+        (3, 7),
+        (4, 6),
+        (5, 6),
+        (7, 6),
+        (0, 6),
+    ];
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    let layout = |seed| {
+        let builder = from_graph(&graph).shuffle_transpose(true).seed(seed);
+        let config = builder.config;
+        let layouts = builder.build();
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+        layouts
+    };
+    for seed in 0..8 {
+        assert_eq!(layout(seed), layout(seed));
+    }
+}
+
+#[test]
+fn best_heuristic_keeps_fewer_crossings() {
+    use super::from_graph;
+    use crate::validate::validate;
+    for seed in 1..8 {
+        // two ranks of 6 vertices with edges scattered between them
+        let edges = (0..6)
+            .flat_map(|a| (0..6).map(move |b| (a, 6 + b)))
+            .filter(|(a, b)| (a * seed + b * 3 + seed) % 4 == 0)
+            .collect::<Vec<_>>();
+        let graph = StableDiGraph::<(), ()>::from_edges(edges);
+        let crossings = |heuristic| {
+            let builder = from_graph(&graph).crossing_minimization(heuristic);
+            let config = builder.config;
+            let (layouts, stats) = builder.build_with_stats();
+            assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+            stats.iter().map(|s| s.crossings).sum::<usize>()
+        };
+        let barycenter = crossings(CrossingMinimization::Barycenter);
+        let median = crossings(CrossingMinimization::Median);
+        assert_eq!(
+            crossings(CrossingMinimization::Best),
+            barycenter.min(median)
+        );
+    }
+}
+
+#[test]
+fn ties_are_broken_by_input_order() {
+    use super::from_edges;
+    use std::collections::HashMap;
+    // the children of 0 can't be told apart by crossing minimization
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 5),
+        (2, 5),
+        (3, 5),
+        (4, 5),
+    ];
+    for seed in 0..8 {
+        let layouts = from_edges(&edges).stable_ties(true).seed(seed).build();
+        let layout = layouts[0].0.iter().copied().collect::<HashMap<_, _>>();
+        assert!((1..4).all(|v| layout[&v].0 < layout[&(v + 1)].0));
+    }
+}
+
+#[cfg(feature = "evolutionary")]
+#[test]
+fn evolutionary_ordering_is_not_worse_than_sweeps() {
+    use super::from_graph;
+    use crate::validate::validate;
+    let edges = (0..6)
+        .flat_map(|a| (0..6).map(move |b| (a, 6 + b)))
+        .filter(|(a, b)| (a * 5 + b * 3) % 4 == 0)
+        .collect::<Vec<_>>();
+    let graph = StableDiGraph::<(), ()>::from_edges(edges);
+    let crossings = |heuristic| {
+        let builder = from_graph(&graph).crossing_minimization(heuristic);
+        let config = builder.config;
+        let (layouts, stats) = builder.build_with_stats();
+        assert_eq!(validate(&graph, &layouts, &config, |_| (0., 0.)), []);
+        stats.iter().map(|s| s.crossings).sum::<usize>()
+    };
+    assert!(
+        crossings(CrossingMinimization::Evolutionary)
+            <= crossings(CrossingMinimization::Barycenter)
+    );
 }
@@ -0,0 +1,433 @@
+//! Import and export of graphs in the
+//! [ELK JSON format](https://eclipse.dev/elk/documentation/tooldevelopers/graphdatastructure/jsonformat.html).
+//!
+//! The document is kept as it was read, so [ElkGraph::write] returns the input
+//! with the `x` and `y` coordinates of each node and the sections of each edge
+//! filled in, the same way ELK does.
+//!
+//! The following layout options of the root node are mapped onto [Config],
+//! with or without the `org.eclipse.elk` prefix:
+//!
+//! | option | mapped to |
+//! | ------ | --------- |
+//! | `elk.spacing.nodeNode` | [Config::vertex_spacing] |
+//! | `elk.layered.spacing.nodeNodeBetweenLayers` | [Config::rank_spacing] |
+//! | `elk.layered.layering.strategy` | [Config::ranking_type], `NETWORK_SIMPLEX` or `LONGEST_PATH` |
+//! | `elk.direction` | orientation of the written coordinates, `DOWN`, `UP`, `RIGHT` or `LEFT` |
+//!
+//! Other options are ignored. Edges may connect ports, in which case they are
+//! treated as edges between the nodes owning the ports. Hierarchical graphs,
+//! i.e. nodes which have children themselves, are not supported.
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use serde_json::{Map, Value};
+
+use crate::configure::{Config, CoordinatesBuilder, RankingType};
+use crate::{from_graph, Layouts};
+
+/// Errors which may occur while reading an ELK graph.
+#[derive(Debug)]
+pub enum ElkError {
+    /// The input is not valid json.
+    Parse(serde_json::Error),
+    /// The input does not match the structure of an ELK graph.
+    Format(String),
+    /// A node or port id was declared more than once.
+    DuplicateId(String),
+    /// An edge references a node or port which was never declared.
+    UnknownId(String),
+    /// A layout option has a value which can't be mapped onto [Config].
+    InvalidOption { key: String, value: String },
+    /// The graph is hierarchical.
+    Hierarchical(String),
+}
+
+impl Display for ElkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid json: {e}"),
+            Self::Format(s) => write!(f, "invalid elk graph: {s}"),
+            Self::DuplicateId(id) => write!(f, "id '{id}' is declared more than once"),
+            Self::UnknownId(id) => write!(f, "edge references undeclared node or port '{id}'"),
+            Self::InvalidOption { key, value } => {
+                write!(f, "invalid value for layout option '{key}': {value}")
+            }
+            Self::Hierarchical(id) => {
+                write!(
+                    f,
+                    "node '{id}' has children, hierarchical graphs are not supported"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ElkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ElkError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// The direction edges point to in the written layout, see `elk.direction`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Down,
+    Up,
+    Right,
+    Left,
+}
+
+impl TryFrom<&str> for Direction {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "DOWN" | "UNDEFINED" => Ok(Self::Down),
+            "UP" => Ok(Self::Up),
+            "RIGHT" => Ok(Self::Right),
+            "LEFT" => Ok(Self::Left),
+            s => Err(s.to_string()),
+        }
+    }
+}
+
+/// A graph read from an ELK JSON document.
+#[derive(Clone, Debug)]
+pub struct ElkGraph {
+    document: Value,
+    graph: StableDiGraph<String, String>,
+    sizes: Vec<(f64, f64)>,
+    config: Config,
+    direction: Direction,
+}
+
+/// Reads an ELK JSON document.
+pub fn read_elk(input: &str) -> Result<ElkGraph, ElkError> {
+    info!(target: "elk", "Reading elk graph of {} bytes", input.len());
+    let document: Value = serde_json::from_str(input)?;
+    let root = document
+        .as_object()
+        .ok_or_else(|| ElkError::Format("root is not an object".to_string()))?;
+
+    let mut config = Config::default();
+    let mut direction = Direction::default();
+    if let Some(options) = root.get("layoutOptions").and_then(Value::as_object) {
+        for (key, value) in options {
+            apply_option(&mut config, &mut direction, key, value)?;
+        }
+    }
+
+    let mut graph = StableDiGraph::new();
+    let mut sizes = Vec::new();
+    let mut ids = HashMap::new();
+    for child in array(&document, "children")? {
+        let id = id(child)?;
+        if !array(child, "children")?.is_empty() {
+            return Err(ElkError::Hierarchical(id));
+        }
+        let v = graph.add_node(id.clone());
+        sizes.push((number(child, "width"), number(child, "height")));
+        if ids.insert(id.clone(), v).is_some() {
+            return Err(ElkError::DuplicateId(id));
+        }
+        for port in array(child, "ports")? {
+            let port = self::id(port)?;
+            if ids.insert(port.clone(), v).is_some() {
+                return Err(ElkError::DuplicateId(port));
+            }
+        }
+    }
+
+    for edge in array(&document, "edges")? {
+        let id = id(edge)?;
+        let endpoint = |key| {
+            let endpoints = array(edge, key)?;
+            match endpoints.as_slice() {
+                [Value::String(s)] => ids.get(s).copied().ok_or(ElkError::UnknownId(s.clone())),
+                _ => Err(ElkError::Format(format!(
+                    "edge '{id}' must have exactly one entry in '{key}'"
+                ))),
+            }
+        };
+        let (tail, head) = (endpoint("sources")?, endpoint("targets")?);
+        graph.add_edge(tail, head, id);
+    }
+    debug!(target: "elk",
+        "Read {} nodes and {} edges, {:?}",
+        graph.node_count(),
+        graph.edge_count(),
+        config);
+
+    Ok(ElkGraph {
+        document,
+        graph,
+        sizes,
+        config,
+        direction,
+    })
+}
+
+impl ElkGraph {
+    /// The graph, with the ids of the nodes and edges as weights.
+    pub fn graph(&self) -> &StableDiGraph<String, String> {
+        &self.graph
+    }
+
+    /// The configuration derived from the layout options.
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// The direction derived from the layout options.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns a [CoordinatesBuilder] for the graph, configured with the
+    /// layout options of the document.
+    pub fn builder(&self) -> CoordinatesBuilder<StableDiGraph<String, String>> {
+        from_graph(&self.graph).with_config(self.config)
+    }
+
+    /// Calculates the layout and returns the document with the coordinates.
+    pub fn layout(&self) -> String {
+        self.write(&self.builder().build())
+    }
+
+    /// Writes the layout into the document and returns it as json.
+    ///
+    /// The layout must have been calculated for [ElkGraph::graph]. Connected
+    /// components are placed next to each other, the position of each node is
+    /// the position of its upper left corner, as in ELK.
+    pub fn write(&self, layouts: &Layouts<NodeIndex>) -> String {
+        let spacing = self.config.vertex_spacing;
+        // centers of the nodes, with y increasing downwards
+        let mut centers = HashMap::new();
+        let (mut width, mut height) = (0f64, 0f64);
+        for (layout, ..) in layouts {
+            let Some(min_x) = layout.iter().map(|(_, (x, _))| *x).min() else {
+                continue;
+            };
+            let max_x = layout.iter().map(|(_, (x, _))| *x).max().unwrap();
+            let max_y = layout.iter().map(|(_, (_, y))| *y).max().unwrap();
+            if width > 0. {
+                width += spacing;
+            }
+            for (v, (x, y)) in layout {
+                let (x, y) = ((x - min_x) as f64 + width, (max_y - y) as f64);
+                height = height.max(y);
+                centers.insert(*v, (x, y));
+            }
+            width += (max_x - min_x) as f64;
+        }
+
+        let oriented = |(x, y): (f64, f64)| match self.direction {
+            Direction::Down => (x, y),
+            Direction::Up => (x, height - y),
+            Direction::Right => (y, x),
+            Direction::Left => (height - y, x),
+        };
+        // pad by the largest node, so no node has negative coordinates
+        let pad = self.sizes.iter().fold(0f64, |p, (w, h)| p.max(*w).max(*h)) / 2.;
+        let position = |v: NodeIndex| {
+            centers
+                .get(&v)
+                .map(|c| oriented(*c))
+                .map(|(x, y)| (x + pad, y + pad))
+        };
+
+        let mut document = self.document.clone();
+        let root = document.as_object_mut().unwrap();
+        if let Some(Value::Array(children)) = root.get_mut("children") {
+            for (v, child) in self.graph.node_indices().zip(children.iter_mut()) {
+                let (Some((x, y)), Some(child)) = (position(v), child.as_object_mut()) else {
+                    continue;
+                };
+                let (w, h) = self.sizes[v.index()];
+                child.insert("x".to_string(), Value::from(x - w / 2.));
+                child.insert("y".to_string(), Value::from(y - h / 2.));
+            }
+        }
+        if let Some(Value::Array(edges)) = root.get_mut("edges") {
+            for (e, edge) in self.graph.edge_indices().zip(edges.iter_mut()) {
+                let (tail, head) = self.graph.edge_endpoints(e).unwrap();
+                let (Some(start), Some(end), Some(edge)) =
+                    (position(tail), position(head), edge.as_object_mut())
+                else {
+                    continue;
+                };
+                let mut section = Map::new();
+                section.insert(
+                    "id".to_string(),
+                    Value::from(format!("{}_s0", self.graph[e])),
+                );
+                section.insert("startPoint".to_string(), point(start));
+                section.insert("endPoint".to_string(), point(end));
+                edge.insert(
+                    "sections".to_string(),
+                    Value::Array(vec![Value::Object(section)]),
+                );
+            }
+        }
+        let (width, height) = match self.direction {
+            Direction::Down | Direction::Up => (width, height),
+            Direction::Right | Direction::Left => (height, width),
+        };
+        root.insert("width".to_string(), Value::from(width + 2. * pad));
+        root.insert("height".to_string(), Value::from(height + 2. * pad));
+
+        document.to_string()
+    }
+}
+
+fn apply_option(
+    config: &mut Config,
+    direction: &mut Direction,
+    key: &str,
+    value: &Value,
+) -> Result<(), ElkError> {
+    let invalid = || ElkError::InvalidOption {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    let name = key.strip_prefix("org.eclipse.").unwrap_or(key);
+    let name = name.strip_prefix("elk.").unwrap_or(name);
+    let text = match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    };
+
+    match name {
+        "spacing.nodeNode" => config.vertex_spacing = text.parse::<f64>().map_err(|_| invalid())?,
+        "layered.spacing.nodeNodeBetweenLayers" => {
+            config.rank_spacing = Some(text.parse::<f64>().map_err(|_| invalid())?)
+        }
+        "layered.layering.strategy" => {
+            config.ranking_type = match text.as_str() {
+                "NETWORK_SIMPLEX" => RankingType::MinimizeEdgeLength,
+                "LONGEST_PATH" => RankingType::Down,
+                "MIN_WIDTH" => RankingType::MinWidth,
+                _ => return Err(invalid()),
+            }
+        }
+        "direction" => *direction = Direction::try_from(text.as_str()).map_err(|_| invalid())?,
+        _ => (),
+    }
+    Ok(())
+}
+
+fn array<'a>(object: &'a Value, key: &str) -> Result<&'a Vec<Value>, ElkError> {
+    static EMPTY: Vec<Value> = Vec::new();
+    match object.get(key) {
+        None => Ok(&EMPTY),
+        Some(Value::Array(a)) => Ok(a),
+        Some(_) => Err(ElkError::Format(format!("'{key}' is not an array"))),
+    }
+}
+
+fn id(object: &Value) -> Result<String, ElkError> {
+    match object.get("id") {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Number(n)) => Ok(n.to_string()),
+        _ => Err(ElkError::Format(format!("missing id in {object}"))),
+    }
+}
+
+fn number(object: &Value, key: &str) -> f64 {
+    object.get(key).and_then(Value::as_f64).unwrap_or(0.)
+}
+
+fn point((x, y): (f64, f64)) -> Value {
+    let mut point = Map::new();
+    point.insert("x".to_string(), Value::from(x));
+    point.insert("y".to_string(), Value::from(y));
+    Value::Object(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{read_elk, Direction, ElkError};
+    use crate::configure::RankingType;
+
+    static GRAPH: &str = r#"{
+        "id": "root",
+        "layoutOptions": {
+            "elk.algorithm": "layered",
+            "elk.direction": "RIGHT",
+            "org.eclipse.elk.spacing.nodeNode": 20,
+            "elk.layered.spacing.nodeNodeBetweenLayers": 40,
+            "elk.layered.layering.strategy": "LONGEST_PATH"
+        },
+        "children": [
+            { "id": "n1", "width": 30, "height": 30, "ports": [{ "id": "p1" }] },
+            { "id": "n2", "width": 30, "height": 30 },
+            { "id": "n3", "width": 30, "height": 30 }
+        ],
+        "edges": [
+            { "id": "e1", "sources": ["p1"], "targets": ["n2"] },
+            { "id": "e2", "sources": ["n1"], "targets": ["n3"] }
+        ]
+    }"#;
+
+    #[test]
+    fn read_options() {
+        let elk = read_elk(GRAPH).unwrap();
+        assert_eq!(elk.config().vertex_spacing, 20.);
+        assert_eq!(elk.config().rank_spacing, Some(40.));
+        assert_eq!(elk.config().ranking_type, RankingType::Down);
+        assert_eq!(elk.direction(), Direction::Right);
+        assert_eq!(elk.graph().node_count(), 3);
+        assert!(elk.graph().contains_edge(0.into(), 1.into()));
+        assert!(elk.graph().contains_edge(0.into(), 2.into()));
+    }
+
+    #[test]
+    fn write_coordinates() {
+        let elk = read_elk(GRAPH).unwrap();
+        let output: Value = serde_json::from_str(&elk.layout()).unwrap();
+        let children = output["children"].as_array().unwrap();
+        // direction is right, so n1 is left of its successors
+        let x = |i: usize| children[i]["x"].as_f64().unwrap();
+        assert!(x(0) < x(1));
+        assert_eq!(x(1), x(2));
+        assert!(children.iter().all(|c| c["y"].as_f64().unwrap() >= 0.));
+        assert!(output["edges"][0]["sections"][0]["startPoint"].is_object());
+        assert_eq!(output["layoutOptions"]["elk.algorithm"], "layered");
+    }
+
+    #[test]
+    fn unknown_port() {
+        let input = r#"{ "children": [{ "id": "a" }], "edges": [{ "id": "e", "sources": ["a"], "targets": ["b"] }] }"#;
+        assert!(matches!(read_elk(input), Err(ElkError::UnknownId(id)) if id == "b"));
+    }
+
+    #[test]
+    fn invalid_option() {
+        let input = r#"{ "layoutOptions": { "elk.direction": "SIDEWAYS" } }"#;
+        assert!(matches!(
+            read_elk(input),
+            Err(ElkError::InvalidOption { .. })
+        ));
+    }
+
+    #[test]
+    fn hierarchical() {
+        let input = r#"{ "children": [{ "id": "a", "children": [{ "id": "b" }] }] }"#;
+        assert!(matches!(read_elk(input), Err(ElkError::Hierarchical(_))));
+    }
+}
@@ -0,0 +1,574 @@
+//! C interface for embedding the layout engine into other languages.
+//!
+//! The declarations are available in `include/rust_sugiyama.h`. To build a
+//! shared or static library, run
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+//!
+//! Graphs and layouts are opaque handles which have to be released with
+//! [sugiyama_graph_free] and [sugiyama_layout_free]. Functions which can fail
+//! return one of the `SUGIYAMA_*` status codes.
+//!
+//! # Example
+//! ```c
+//! SugiyamaGraph *graph = sugiyama_graph_new();
+//! uint32_t a = sugiyama_graph_add_vertex(graph);
+//! uint32_t b = sugiyama_graph_add_vertex(graph);
+//! sugiyama_graph_add_edge(graph, a, b);
+//!
+//! SugiyamaConfig config;
+//! sugiyama_config_default(&config);
+//! config.vertex_spacing = 20;
+//! sugiyama_graph_set_config(graph, &config);
+//!
+//! SugiyamaLayout *layout = sugiyama_layout(graph);
+//! int64_t x, y;
+//! sugiyama_layout_coordinates(layout, b, &x, &y, NULL);
+//! sugiyama_layout_free(layout);
+//! sugiyama_graph_free(graph);
+//! ```
+use std::time::Duration;
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::configure::{Alignment, Config, CrossingMinimization, RankingType};
+use crate::from_graph;
+
+/// The call succeeded.
+pub const SUGIYAMA_OK: i32 = 0;
+/// A pointer argument was null.
+pub const SUGIYAMA_NULL_POINTER: i32 = -1;
+/// A vertex id does not belong to the graph or layout.
+pub const SUGIYAMA_UNKNOWN_VERTEX: i32 = -2;
+/// A configuration value is out of range.
+pub const SUGIYAMA_INVALID_CONFIG: i32 = -3;
+/// A component index is out of range.
+pub const SUGIYAMA_UNKNOWN_COMPONENT: i32 = -4;
+
+/// Values of [SugiyamaConfig::ranking_type].
+pub const SUGIYAMA_RANKING_ORIGINAL: u32 = 0;
+pub const SUGIYAMA_RANKING_MINIMIZE: u32 = 1;
+pub const SUGIYAMA_RANKING_UP: u32 = 2;
+pub const SUGIYAMA_RANKING_DOWN: u32 = 3;
+pub const SUGIYAMA_RANKING_MIN_WIDTH: u32 = 4;
+/// Only valid if the library is built with the `lp` feature.
+pub const SUGIYAMA_RANKING_OPTIMAL: u32 = 5;
+
+/// Values of [SugiyamaConfig::c_minimization].
+pub const SUGIYAMA_CROSSING_BARYCENTER: u32 = 0;
+pub const SUGIYAMA_CROSSING_MEDIAN: u32 = 1;
+pub const SUGIYAMA_CROSSING_BEST: u32 = 2;
+/// Only valid if the library is built with the `evolutionary` feature.
+pub const SUGIYAMA_CROSSING_EVOLUTIONARY: u32 = 3;
+
+/// Values of [SugiyamaConfig::alignment].
+pub const SUGIYAMA_ALIGNMENT_BALANCED: u32 = 0;
+pub const SUGIYAMA_ALIGNMENT_UP_LEFT: u32 = 1;
+pub const SUGIYAMA_ALIGNMENT_UP_RIGHT: u32 = 2;
+pub const SUGIYAMA_ALIGNMENT_DOWN_LEFT: u32 = 3;
+pub const SUGIYAMA_ALIGNMENT_DOWN_RIGHT: u32 = 4;
+
+/// C representation of [Config], see there for a description of the fields.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SugiyamaConfig {
+    pub minimum_length: u32,
+    pub vertex_spacing: f64,
+    pub dummy_vertices: bool,
+    pub dummy_size: f64,
+    /// One of the `SUGIYAMA_RANKING_*` constants.
+    pub ranking_type: u32,
+    /// One of the `SUGIYAMA_CROSSING_*` constants.
+    pub c_minimization: u32,
+    pub transpose: bool,
+    /// Time budget in milliseconds, 0 means no time budget.
+    pub time_budget_ms: u64,
+    /// 0 means no limit.
+    pub max_ranking_iterations: usize,
+    /// 0 means no limit.
+    pub max_sweeps: usize,
+    /// 0 means no limit.
+    pub max_transpose_iterations: usize,
+    /// Seed for random choices during crossing minimization, 0 means no seed.
+    pub seed: u64,
+    /// Additional runs of crossing minimization, 0 means a single run.
+    pub restarts: usize,
+    pub multilevel: bool,
+    pub transitive_reduction: bool,
+    pub condense_components: bool,
+    pub contract_chains: bool,
+    pub commit_graph: bool,
+    pub radial: bool,
+    /// Ratio of the width to the height of the layout, 0 means no ratio.
+    pub aspect_ratio: f64,
+    /// 0 means no limit.
+    pub max_layer_width: usize,
+    pub promote_vertices: bool,
+    /// 0 means the vertex spacing.
+    pub rank_spacing: f64,
+    /// 0 means no padding, components keep their coordinates.
+    pub padding: usize,
+    pub y_down: bool,
+    pub mirror: bool,
+    /// 0 means no grid.
+    pub grid: usize,
+    pub snap_y: bool,
+    /// 0 means a single step per unit.
+    pub subdivisions: usize,
+    pub horizontal_compaction: bool,
+    /// One of the `SUGIYAMA_ALIGNMENT_*` constants.
+    pub alignment: u32,
+    pub center_parents: bool,
+    pub minimize_area: bool,
+    pub minimize_bends: bool,
+    /// 0 is treated as 1.
+    pub patience: usize,
+    pub shuffle_transpose: bool,
+    pub stable_ties: bool,
+    pub community_ordering: bool,
+}
+
+impl From<Config> for SugiyamaConfig {
+    fn from(config: Config) -> Self {
+        Self {
+            minimum_length: config.minimum_length,
+            vertex_spacing: config.vertex_spacing,
+            dummy_vertices: config.dummy_vertices,
+            dummy_size: config.dummy_size,
+            ranking_type: match config.ranking_type {
+                RankingType::Original => SUGIYAMA_RANKING_ORIGINAL,
+                RankingType::MinimizeEdgeLength => SUGIYAMA_RANKING_MINIMIZE,
+                RankingType::Up => SUGIYAMA_RANKING_UP,
+                RankingType::Down => SUGIYAMA_RANKING_DOWN,
+                RankingType::MinWidth => SUGIYAMA_RANKING_MIN_WIDTH,
+                #[cfg(feature = "lp")]
+                RankingType::Optimal => SUGIYAMA_RANKING_OPTIMAL,
+            },
+            c_minimization: match config.c_minimization {
+                CrossingMinimization::Barycenter => SUGIYAMA_CROSSING_BARYCENTER,
+                CrossingMinimization::Median => SUGIYAMA_CROSSING_MEDIAN,
+                CrossingMinimization::Best => SUGIYAMA_CROSSING_BEST,
+                #[cfg(feature = "evolutionary")]
+                CrossingMinimization::Evolutionary => SUGIYAMA_CROSSING_EVOLUTIONARY,
+            },
+            transpose: config.transpose,
+            time_budget_ms: config
+                .time_budget
+                .map_or(0, |budget| budget.as_millis().max(1) as u64),
+            max_ranking_iterations: config.max_ranking_iterations.unwrap_or(0),
+            max_sweeps: config.max_sweeps.unwrap_or(0),
+            max_transpose_iterations: config.max_transpose_iterations.unwrap_or(0),
+            seed: config.seed.unwrap_or(0),
+            restarts: config.restarts,
+            multilevel: config.multilevel,
+            transitive_reduction: config.transitive_reduction,
+            condense_components: config.condense_components,
+            contract_chains: config.contract_chains,
+            commit_graph: config.commit_graph,
+            radial: config.radial,
+            aspect_ratio: config.aspect_ratio.unwrap_or(0.),
+            max_layer_width: config.max_layer_width.unwrap_or(0),
+            promote_vertices: config.promote_vertices,
+            rank_spacing: config.rank_spacing.unwrap_or(0.),
+            padding: config.padding.unwrap_or(0),
+            y_down: config.y_down,
+            mirror: config.mirror,
+            grid: config.grid.unwrap_or(0),
+            snap_y: config.snap_y,
+            subdivisions: config.subdivisions,
+            horizontal_compaction: config.horizontal_compaction,
+            alignment: match config.alignment {
+                Alignment::Balanced => SUGIYAMA_ALIGNMENT_BALANCED,
+                Alignment::UpLeft => SUGIYAMA_ALIGNMENT_UP_LEFT,
+                Alignment::UpRight => SUGIYAMA_ALIGNMENT_UP_RIGHT,
+                Alignment::DownLeft => SUGIYAMA_ALIGNMENT_DOWN_LEFT,
+                Alignment::DownRight => SUGIYAMA_ALIGNMENT_DOWN_RIGHT,
+            },
+            center_parents: config.center_parents,
+            minimize_area: config.minimize_area,
+            minimize_bends: config.minimize_bends,
+            patience: config.patience,
+            shuffle_transpose: config.shuffle_transpose,
+            stable_ties: config.stable_ties,
+            community_ordering: config.community_ordering,
+        }
+    }
+}
+
+impl TryFrom<SugiyamaConfig> for Config {
+    type Error = i32;
+
+    fn try_from(config: SugiyamaConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            minimum_length: config.minimum_length,
+            vertex_spacing: config.vertex_spacing,
+            dummy_vertices: config.dummy_vertices,
+            dummy_size: config.dummy_size,
+            ranking_type: match config.ranking_type {
+                SUGIYAMA_RANKING_ORIGINAL => RankingType::Original,
+                SUGIYAMA_RANKING_MINIMIZE => RankingType::MinimizeEdgeLength,
+                SUGIYAMA_RANKING_UP => RankingType::Up,
+                SUGIYAMA_RANKING_DOWN => RankingType::Down,
+                SUGIYAMA_RANKING_MIN_WIDTH => RankingType::MinWidth,
+                #[cfg(feature = "lp")]
+                SUGIYAMA_RANKING_OPTIMAL => RankingType::Optimal,
+                _ => return Err(SUGIYAMA_INVALID_CONFIG),
+            },
+            c_minimization: match config.c_minimization {
+                SUGIYAMA_CROSSING_BARYCENTER => CrossingMinimization::Barycenter,
+                SUGIYAMA_CROSSING_MEDIAN => CrossingMinimization::Median,
+                SUGIYAMA_CROSSING_BEST => CrossingMinimization::Best,
+                #[cfg(feature = "evolutionary")]
+                SUGIYAMA_CROSSING_EVOLUTIONARY => CrossingMinimization::Evolutionary,
+                _ => return Err(SUGIYAMA_INVALID_CONFIG),
+            },
+            transpose: config.transpose,
+            time_budget: (config.time_budget_ms > 0)
+                .then(|| Duration::from_millis(config.time_budget_ms)),
+            max_ranking_iterations: (config.max_ranking_iterations > 0)
+                .then_some(config.max_ranking_iterations),
+            max_sweeps: (config.max_sweeps > 0).then_some(config.max_sweeps),
+            max_transpose_iterations: (config.max_transpose_iterations > 0)
+                .then_some(config.max_transpose_iterations),
+            seed: (config.seed > 0).then_some(config.seed),
+            restarts: config.restarts,
+            multilevel: config.multilevel,
+            transitive_reduction: config.transitive_reduction,
+            condense_components: config.condense_components,
+            contract_chains: config.contract_chains,
+            commit_graph: config.commit_graph,
+            radial: config.radial,
+            aspect_ratio: (config.aspect_ratio > 0.).then_some(config.aspect_ratio),
+            max_layer_width: (config.max_layer_width > 0).then_some(config.max_layer_width),
+            promote_vertices: config.promote_vertices,
+            rank_spacing: (config.rank_spacing > 0.).then_some(config.rank_spacing),
+            padding: (config.padding > 0).then_some(config.padding),
+            y_down: config.y_down,
+            mirror: config.mirror,
+            grid: (config.grid > 0).then_some(config.grid),
+            snap_y: config.snap_y,
+            subdivisions: config.subdivisions,
+            horizontal_compaction: config.horizontal_compaction,
+            alignment: match config.alignment {
+                SUGIYAMA_ALIGNMENT_BALANCED => Alignment::Balanced,
+                SUGIYAMA_ALIGNMENT_UP_LEFT => Alignment::UpLeft,
+                SUGIYAMA_ALIGNMENT_UP_RIGHT => Alignment::UpRight,
+                SUGIYAMA_ALIGNMENT_DOWN_LEFT => Alignment::DownLeft,
+                SUGIYAMA_ALIGNMENT_DOWN_RIGHT => Alignment::DownRight,
+                _ => return Err(SUGIYAMA_INVALID_CONFIG),
+            },
+            center_parents: config.center_parents,
+            minimize_area: config.minimize_area,
+            minimize_bends: config.minimize_bends,
+            patience: config.patience,
+            shuffle_transpose: config.shuffle_transpose,
+            stable_ties: config.stable_ties,
+            community_ordering: config.community_ordering,
+            // routes of edges are not part of the C interface
+            ..Config::default()
+        })
+    }
+}
+
+/// A graph which is built up through the C interface.
+pub struct SugiyamaGraph {
+    graph: StableDiGraph<(), ()>,
+    config: Config,
+}
+
+/// The calculated layout of a [SugiyamaGraph].
+pub struct SugiyamaLayout {
+    /// x, y and component of each vertex, indexed by vertex id
+    coordinates: Vec<Option<(isize, isize, usize)>>,
+    /// width and height of each component
+    components: Vec<(usize, usize)>,
+}
+
+/// Writes the default configuration into `config`.
+///
+/// # Safety
+///
+/// `config` must be null or point to a writable [SugiyamaConfig].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_config_default(config: *mut SugiyamaConfig) -> i32 {
+    match config.as_mut() {
+        Some(config) => {
+            *config = Config::default().into();
+            SUGIYAMA_OK
+        }
+        None => SUGIYAMA_NULL_POINTER,
+    }
+}
+
+/// Creates a new, empty graph with the default configuration.
+#[no_mangle]
+pub extern "C" fn sugiyama_graph_new() -> *mut SugiyamaGraph {
+    Box::into_raw(Box::new(SugiyamaGraph {
+        graph: StableDiGraph::new(),
+        config: Config::default(),
+    }))
+}
+
+/// Releases a graph.
+///
+/// # Safety
+///
+/// `graph` must be null or a pointer returned by [sugiyama_graph_new], which
+/// was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_graph_free(graph: *mut SugiyamaGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// Adds a vertex to the graph and returns its id. Ids are assigned
+/// consecutively, starting at 0. Returns `UINT32_MAX` if `graph` is null.
+///
+/// # Safety
+///
+/// `graph` must be null or a valid pointer returned by [sugiyama_graph_new].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_graph_add_vertex(graph: *mut SugiyamaGraph) -> u32 {
+    match graph.as_mut() {
+        Some(g) => g.graph.add_node(()).index() as u32,
+        None => u32::MAX,
+    }
+}
+
+/// Adds an edge from `tail` to `head` to the graph.
+///
+/// # Safety
+///
+/// `graph` must be null or a valid pointer returned by [sugiyama_graph_new].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_graph_add_edge(
+    graph: *mut SugiyamaGraph,
+    tail: u32,
+    head: u32,
+) -> i32 {
+    let Some(g) = graph.as_mut() else {
+        return SUGIYAMA_NULL_POINTER;
+    };
+    let (tail, head) = (NodeIndex::from(tail), NodeIndex::from(head));
+    if !g.graph.contains_node(tail) || !g.graph.contains_node(head) {
+        return SUGIYAMA_UNKNOWN_VERTEX;
+    }
+    g.graph.add_edge(tail, head, ());
+    SUGIYAMA_OK
+}
+
+/// Sets the configuration used to calculate the layout of the graph.
+///
+/// # Safety
+///
+/// `graph` must be null or a valid pointer returned by [sugiyama_graph_new],
+/// `config` must be null or point to a [SugiyamaConfig].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_graph_set_config(
+    graph: *mut SugiyamaGraph,
+    config: *const SugiyamaConfig,
+) -> i32 {
+    let (Some(g), Some(config)) = (graph.as_mut(), config.as_ref()) else {
+        return SUGIYAMA_NULL_POINTER;
+    };
+    match Config::try_from(*config) {
+        Ok(config) => {
+            g.config = config;
+            SUGIYAMA_OK
+        }
+        Err(e) => e,
+    }
+}
+
+/// Calculates the layout of the graph. Returns null if `graph` is null.
+///
+/// # Safety
+///
+/// `graph` must be null or a valid pointer returned by [sugiyama_graph_new].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_layout(graph: *const SugiyamaGraph) -> *mut SugiyamaLayout {
+    let Some(g) = graph.as_ref() else {
+        return std::ptr::null_mut();
+    };
+    let layouts = from_graph(&g.graph).with_config(g.config).build();
+    let mut coordinates = vec![None; g.graph.node_count()];
+    let mut components = Vec::new();
+    for (component, (layout, width, height)) in layouts.into_iter().enumerate() {
+        for (v, (x, y)) in layout {
+            coordinates[v.index()] = Some((x, y, component));
+        }
+        components.push((width, height));
+    }
+    Box::into_raw(Box::new(SugiyamaLayout {
+        coordinates,
+        components,
+    }))
+}
+
+/// Releases a layout.
+///
+/// # Safety
+///
+/// `layout` must be null or a pointer returned by [sugiyama_layout], which
+/// was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_layout_free(layout: *mut SugiyamaLayout) {
+    if !layout.is_null() {
+        drop(Box::from_raw(layout));
+    }
+}
+
+/// Returns the number of connected components of the layout, each of them
+/// laid out separately. Returns 0 if `layout` is null.
+///
+/// # Safety
+///
+/// `layout` must be null or a valid pointer returned by [sugiyama_layout].
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_layout_component_count(layout: *const SugiyamaLayout) -> usize {
+    layout.as_ref().map_or(0, |l| l.components.len())
+}
+
+/// Reads the width and height of a component, as returned by
+/// [CoordinatesBuilder::build](crate::configure::CoordinatesBuilder).
+/// `width` and `height` may be null.
+///
+/// # Safety
+///
+/// `layout` must be null or a valid pointer returned by [sugiyama_layout],
+/// `width` and `height` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_layout_component_size(
+    layout: *const SugiyamaLayout,
+    component: usize,
+    width: *mut usize,
+    height: *mut usize,
+) -> i32 {
+    let Some(l) = layout.as_ref() else {
+        return SUGIYAMA_NULL_POINTER;
+    };
+    let Some((w, h)) = l.components.get(component) else {
+        return SUGIYAMA_UNKNOWN_COMPONENT;
+    };
+    if let Some(width) = width.as_mut() {
+        *width = *w;
+    }
+    if let Some(height) = height.as_mut() {
+        *height = *h;
+    }
+    SUGIYAMA_OK
+}
+
+/// Reads the coordinates of a vertex and the component it belongs to.
+/// `x`, `y` and `component` may be null.
+///
+/// # Safety
+///
+/// `layout` must be null or a valid pointer returned by [sugiyama_layout],
+/// `x`, `y` and `component` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn sugiyama_layout_coordinates(
+    layout: *const SugiyamaLayout,
+    vertex: u32,
+    x: *mut i64,
+    y: *mut i64,
+    component: *mut usize,
+) -> i32 {
+    let Some(l) = layout.as_ref() else {
+        return SUGIYAMA_NULL_POINTER;
+    };
+    let Some(Some((vx, vy, c))) = l.coordinates.get(vertex as usize) else {
+        return SUGIYAMA_UNKNOWN_VERTEX;
+    };
+    if let Some(x) = x.as_mut() {
+        *x = *vx as i64;
+    }
+    if let Some(y) = y.as_mut() {
+        *y = *vy as i64;
+    }
+    if let Some(component) = component.as_mut() {
+        *component = *c;
+    }
+    SUGIYAMA_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::{null, null_mut};
+
+    use super::*;
+
+    #[test]
+    fn layout_through_ffi() {
+        unsafe {
+            let graph = sugiyama_graph_new();
+            let a = sugiyama_graph_add_vertex(graph);
+            let b = sugiyama_graph_add_vertex(graph);
+            let c = sugiyama_graph_add_vertex(graph);
+            assert_eq!(sugiyama_graph_add_edge(graph, a, b), SUGIYAMA_OK);
+            assert_eq!(
+                sugiyama_graph_add_edge(graph, a, 7),
+                SUGIYAMA_UNKNOWN_VERTEX
+            );
+
+            let mut config = std::mem::zeroed();
+            assert_eq!(sugiyama_config_default(&mut config), SUGIYAMA_OK);
+            config.vertex_spacing = 20.;
+            assert_eq!(sugiyama_graph_set_config(graph, &config), SUGIYAMA_OK);
+
+            let layout = sugiyama_layout(graph);
+            assert_eq!(sugiyama_layout_component_count(layout), 2);
+
+            let (mut x, mut y, mut component) = (0, 0, 0);
+            assert_eq!(
+                sugiyama_layout_coordinates(layout, b, &mut x, &mut y, &mut component),
+                SUGIYAMA_OK
+            );
+            assert_eq!((x, y, component), (0, -20, 0));
+            assert_eq!(
+                sugiyama_layout_coordinates(layout, c, null_mut(), null_mut(), &mut component),
+                SUGIYAMA_OK
+            );
+            assert_eq!(component, 1);
+            assert_eq!(
+                sugiyama_layout_coordinates(layout, 3, null_mut(), null_mut(), null_mut()),
+                SUGIYAMA_UNKNOWN_VERTEX
+            );
+
+            let (mut width, mut height) = (0, 0);
+            assert_eq!(
+                sugiyama_layout_component_size(layout, 0, &mut width, &mut height),
+                SUGIYAMA_OK
+            );
+            assert_eq!((width, height), (1, 2));
+            assert_eq!(
+                sugiyama_layout_component_size(layout, 2, null_mut(), null_mut()),
+                SUGIYAMA_UNKNOWN_COMPONENT
+            );
+
+            sugiyama_layout_free(layout);
+            sugiyama_graph_free(graph);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        unsafe {
+            assert_eq!(sugiyama_graph_add_vertex(null_mut()), u32::MAX);
+            assert_eq!(sugiyama_layout(null()), null_mut());
+
+            let graph = sugiyama_graph_new();
+            let mut config = SugiyamaConfig::from(Config::default());
+            config.ranking_type = 42;
+            assert_eq!(
+                sugiyama_graph_set_config(graph, &config),
+                SUGIYAMA_INVALID_CONFIG
+            );
+            assert_eq!(
+                sugiyama_graph_set_config(graph, null()),
+                SUGIYAMA_NULL_POINTER
+            );
+            sugiyama_graph_free(graph);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Scaling layouts into a viewport of a given size.
+//!
+//! [FitInto::fit_into] maps the coordinates of the layout of a component into
+//! the rectangle from `(0, 0)` to `(width, height)`, and returns the applied
+//! [Transform], so routes of edges and sizes of vertices can be mapped the
+//! same way. The axes keep their direction, so the topmost rank gets the
+//! largest y-coordinate unless the layout was built with
+//! [y_down](crate::configure::CoordinatesBuilder::y_down).
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{fit::FitInto, from_edges};
+//!
+//! let (layouts, geometry) = from_edges(&[(0, 1), (0, 2)]).build_with_geometry();
+//! let (coordinates, transform) = layouts[0].fit_into(200., 100., true);
+//! assert!(coordinates
+//!     .iter()
+//!     .all(|(_, (x, y))| (0. ..=200.).contains(x) && (0. ..=100.).contains(y)));
+//! let route = geometry[0].edges[0].points.iter().map(|p| transform.apply(*p));
+//! ```
+use crate::Bounds;
+
+type Coordinates = (isize, isize);
+/// Coordinates of the vertices in the viewport and the applied transform.
+type Fitted<T> = (Vec<(T, (f64, f64))>, Transform);
+
+/// Scaling followed by a translation, which maps coordinates of a layout
+/// into a viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// Factors the x- and y-coordinates are multiplied with.
+    pub scale: (f64, f64),
+    /// Offsets added to the scaled x- and y-coordinates.
+    pub translate: (f64, f64),
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            scale: (1., 1.),
+            translate: (0., 0.),
+        }
+    }
+}
+
+impl Transform {
+    /// Transform which maps the box `bounds` into the rectangle from `(0, 0)`
+    /// to `(width, height)`.
+    ///
+    /// With `preserve_aspect`, both axes are scaled by the same factor, which
+    /// is the largest one that fits, and the box is centered along the other
+    /// axis. Axes along which the box has no extent aren't scaled, unless the
+    /// aspect is preserved, and the box is centered along them.
+    pub fn fit(bounds: &Bounds, width: f64, height: f64, preserve_aspect: bool) -> Self {
+        let extent = (bounds.width() as f64, bounds.height() as f64);
+        let factor = |extent: f64, size: f64| (extent > 0.).then(|| size / extent);
+        let (sx, sy) = (factor(extent.0, width), factor(extent.1, height));
+        let scale = if preserve_aspect {
+            let s = match (sx, sy) {
+                (Some(sx), Some(sy)) => sx.min(sy),
+                (s, None) | (None, s) => s.unwrap_or(1.),
+            };
+            (s, s)
+        } else {
+            (sx.unwrap_or(1.), sy.unwrap_or(1.))
+        };
+        // center the scaled box within the viewport
+        let offset = |min: isize, extent: f64, scale: f64, size: f64| {
+            (size - extent * scale) / 2. - min as f64 * scale
+        };
+        Self {
+            scale,
+            translate: (
+                offset(bounds.min.0, extent.0, scale.0, width),
+                offset(bounds.min.1, extent.1, scale.1, height),
+            ),
+        }
+    }
+
+    /// Maps a point, like a vertex or a point of a route.
+    pub fn apply(&self, (x, y): Coordinates) -> (f64, f64) {
+        (
+            x as f64 * self.scale.0 + self.translate.0,
+            y as f64 * self.scale.1 + self.translate.1,
+        )
+    }
+
+    /// Maps the width and height of a vertex or a box, which are only scaled.
+    pub fn size(&self, (width, height): (f64, f64)) -> (f64, f64) {
+        (width * self.scale.0, height * self.scale.1)
+    }
+}
+
+/// Layouts of single components which can be fit into a viewport.
+pub trait FitInto<T> {
+    /// Returns the coordinates of the vertices mapped into the rectangle from
+    /// `(0, 0)` to `(width, height)`, and the transform which was applied.
+    ///
+    /// Only the centers of the vertices are fit into the rectangle. To keep
+    /// the boxes of vertices and the routes of edges inside as well, use
+    /// [Transform::fit] with the [bounds](crate::Geometry::bounds) of the
+    /// component instead.
+    fn fit_into(&self, width: f64, height: f64, preserve_aspect: bool) -> Fitted<T>;
+}
+
+impl<T: Copy> FitInto<T> for (Vec<(T, Coordinates)>, usize, usize) {
+    fn fit_into(&self, width: f64, height: f64, preserve_aspect: bool) -> Fitted<T> {
+        let Some(bounds) = self
+            .0
+            .iter()
+            .map(|(_, (x, y))| Bounds {
+                min: (*x, *y),
+                max: (*x, *y),
+            })
+            .reduce(|a, b| Bounds {
+                min: (a.min.0.min(b.min.0), a.min.1.min(b.min.1)),
+                max: (a.max.0.max(b.max.0), a.max.1.max(b.max.1)),
+            })
+        else {
+            return (Vec::new(), Transform::default());
+        };
+        let transform = Transform::fit(&bounds, width, height, preserve_aspect);
+        let coordinates = self
+            .0
+            .iter()
+            .map(|(v, p)| (*v, transform.apply(*p)))
+            .collect();
+        (coordinates, transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FitInto, Transform};
+    use crate::Bounds;
+
+    #[test]
+    fn fit_into_viewport() {
+        let layout = (vec![(0, (0, 0)), (1, (-10, -20)), (2, (30, -40))], 2, 3);
+        let (coordinates, transform) = layout.fit_into(80., 80., false);
+        assert_eq!(
+            coordinates,
+            [(0, (20., 80.)), (1, (0., 40.)), (2, (80., 0.))]
+        );
+        assert_eq!(transform.size((10., 10.)), (20., 20.));
+        // the viewport is twice as wide as high, so the layout is centered horizontally
+        let (coordinates, transform) = layout.fit_into(80., 40., true);
+        assert_eq!(
+            coordinates,
+            [(0, (30., 40.)), (1, (20., 20.)), (2, (60., 0.))]
+        );
+        assert_eq!(transform.scale, (1., 1.));
+        let (coordinates, _) = layout.fit_into(80., 40., false);
+        assert_eq!(coordinates[0].1, (20., 40.));
+    }
+
+    #[test]
+    fn flat_layouts_are_centered() {
+        let bounds = Bounds {
+            min: (0, 5),
+            max: (10, 5),
+        };
+        let transform = Transform::fit(&bounds, 20., 10., false);
+        assert_eq!(transform.apply((10, 5)), (20., 5.));
+        let transform = Transform::fit(&bounds, 20., 10., true);
+        assert_eq!(transform.apply((0, 5)), (0., 5.));
+        assert_eq!(transform.apply((10, 5)), (20., 5.));
+        let single = (vec![(0, (3, 4))], 1, 1);
+        assert_eq!(single.fit_into(20., 10., true).0, [(0, (10., 5.))]);
+    }
+}
@@ -0,0 +1,140 @@
+//! Random graphs, to benchmark and test layouts.
+//!
+//! All generators are deterministic for a given seed. The weight of each
+//! vertex is its index, so the graphs can be drawn and laid out right away.
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{from_graph, generators};
+//!
+//! let graph = generators::layered(&[3, 5, 4], 0.3, 42);
+//! let layouts = from_graph(&graph).build();
+//! assert_eq!(layouts.iter().map(|(l, ..)| l.len()).sum::<usize>(), 12);
+//! ```
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::util::Rng;
+
+/// Returns `true` with probability `p`.
+fn chance(rng: &mut Rng, p: f64) -> bool {
+    ((rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+}
+
+fn with_vertices(n: usize) -> (StableDiGraph<usize, ()>, Vec<NodeIndex>) {
+    let mut graph = StableDiGraph::with_capacity(n, 0);
+    let vertices = (0..n).map(|i| graph.add_node(i)).collect();
+    (graph, vertices)
+}
+
+/// Random directed acyclic graph with `vertices` vertices, in which each
+/// pair of vertices is connected with probability `density`.
+///
+/// Edges point along a random order of the vertices, which is unrelated to
+/// their indices.
+pub fn random_dag(vertices: usize, density: f64, seed: u64) -> StableDiGraph<usize, ()> {
+    info!(target: "generators", "Generating random dag with {vertices} vertices");
+    let mut rng = Rng::new(seed);
+    let (mut graph, mut order) = with_vertices(vertices);
+    rng.shuffle(&mut order);
+    for (i, tail) in order.iter().enumerate() {
+        for head in &order[i + 1..] {
+            if chance(&mut rng, density) {
+                graph.add_edge(*tail, *head, ());
+            }
+        }
+    }
+    graph
+}
+
+/// Random layered graph with the given number of vertices in each layer, in
+/// which each pair of vertices of adjacent layers is connected with
+/// probability `density`, from the upper to the lower layer.
+///
+/// Vertices are numbered layer by layer, so every edge has the length one in
+/// the layering given by the layers.
+pub fn layered(layers: &[usize], density: f64, seed: u64) -> StableDiGraph<usize, ()> {
+    info!(target: "generators", "Generating random layered graph with {} layers", layers.len());
+    let mut rng = Rng::new(seed);
+    let (mut graph, vertices) = with_vertices(layers.iter().sum());
+    let mut start = 0;
+    for pair in layers.windows(2) {
+        let upper = &vertices[start..start + pair[0]];
+        let lower = &vertices[start + pair[0]..start + pair[0] + pair[1]];
+        for tail in upper {
+            for head in lower {
+                if chance(&mut rng, density) {
+                    graph.add_edge(*tail, *head, ());
+                }
+            }
+        }
+        start += pair[0];
+    }
+    graph
+}
+
+/// Random tree with `vertices` vertices rooted at vertex 0, to which
+/// `extra_edges` random edges are added which keep the graph acyclic.
+///
+/// Each vertex is the child of a random vertex with a smaller index, so the
+/// tree tends to be bushy near the root. Extra edges may duplicate edges of
+/// the tree.
+pub fn tree(vertices: usize, extra_edges: usize, seed: u64) -> StableDiGraph<usize, ()> {
+    info!(target: "generators", "Generating random tree with {vertices} vertices");
+    let mut rng = Rng::new(seed);
+    let (mut graph, vertices) = with_vertices(vertices);
+    for (i, child) in vertices.iter().enumerate().skip(1) {
+        graph.add_edge(vertices[rng.below(i)], *child, ());
+    }
+    if vertices.len() > 1 {
+        for _ in 0..extra_edges {
+            let (a, b) = (rng.below(vertices.len()), rng.below(vertices.len() - 1));
+            // pick two different vertices, the edge points to the larger index
+            let b = if b >= a { b + 1 } else { b };
+            graph.add_edge(vertices[a.min(b)], vertices[a.max(b)], ());
+        }
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::algo::toposort;
+
+    use super::{layered, random_dag, tree};
+
+    #[test]
+    fn graphs_are_acyclic() {
+        for seed in 0..10 {
+            let dag = random_dag(30, 0.2, seed);
+            assert_eq!(dag.node_count(), 30);
+            assert!(toposort(&dag, None).is_ok());
+            let tree = tree(30, 10, seed);
+            assert_eq!(tree.edge_count(), 29 + 10);
+            assert!(toposort(&tree, None).is_ok());
+        }
+        assert_eq!(random_dag(10, 1., 0).edge_count(), 45);
+        assert_eq!(random_dag(10, 0., 0).edge_count(), 0);
+    }
+
+    #[test]
+    fn layered_edges_connect_adjacent_layers() {
+        let graph = layered(&[2, 3, 1], 1., 7);
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 2 * 3 + 3);
+        let layer = |v: usize| [0, 0, 1, 1, 1, 2][v];
+        for e in graph.edge_indices() {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            assert_eq!(layer(graph[tail]) + 1, layer(graph[head]));
+        }
+        let edges = |seed| {
+            let graph = layered(&[4, 4, 4], 0.5, seed);
+            graph
+                .edge_indices()
+                .map(|e| graph.edge_endpoints(e).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(edges(3), edges(3));
+        assert_ne!(edges(3), edges(4));
+    }
+}
@@ -0,0 +1,373 @@
+//! Import and export of graphs in the [GraphML](http://graphml.graphdrawing.org/)
+//! format.
+//!
+//! Only the structural part of GraphML is read, i.e. `<node>` and `<edge>`
+//! elements. Node ids are kept as the node weights of the returned graph, so
+//! the coordinates calculated for a [NodeIndex] can be mapped back to the
+//! id used in the document.
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{from_graph, graphml};
+//!
+//! let input = r#"<graphml>
+//!   <graph edgedefault="directed">
+//!     <node id="a"/> <node id="b"/> <node id="c"/>
+//!     <edge source="a" target="b"/> <edge source="a" target="c"/>
+//!   </graph>
+//! </graphml>"#;
+//!
+//! let graph = graphml::read_graphml(input).unwrap();
+//! let layouts = from_graph(&graph).build();
+//! let output = graphml::write_graphml(&graph, &layouts);
+//! assert!(output.contains(r#"<data key="x">"#));
+//! ```
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::configure::CoordinatesBuilder;
+use crate::{from_graph, Layouts};
+
+/// Errors which may occur while reading a GraphML document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphMLError {
+    /// A tag or attribute was not terminated properly.
+    Malformed(String),
+    /// A required attribute is missing on an element.
+    MissingAttribute {
+        element: &'static str,
+        attribute: &'static str,
+    },
+    /// A node id was declared more than once.
+    DuplicateNode(String),
+    /// An edge references a node which was never declared.
+    UnknownNode(String),
+}
+
+impl Display for GraphMLError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(s) => write!(f, "malformed graphml: {s}"),
+            Self::MissingAttribute { element, attribute } => {
+                write!(f, "element <{element}> is missing attribute '{attribute}'")
+            }
+            Self::DuplicateNode(id) => write!(f, "node '{id}' is declared more than once"),
+            Self::UnknownNode(id) => write!(f, "edge references undeclared node '{id}'"),
+        }
+    }
+}
+
+impl std::error::Error for GraphMLError {}
+
+/// Creates a graph layout from a GraphML document.
+///
+/// It returns a [CoordinatesBuilder] which can be used to configure the
+/// layout. Use [read_graphml] instead, if the ids of the nodes are needed
+/// to interpret the result.
+pub fn from_graphml(
+    input: &str,
+) -> Result<CoordinatesBuilder<StableDiGraph<String, ()>>, GraphMLError> {
+    Ok(from_graph(&read_graphml(input)?))
+}
+
+/// Reads a GraphML document into a graph, whose node weights are the ids of
+/// the nodes in the document.
+///
+/// Nodes are added in the order they are declared in, edges are always
+/// treated as directed from `source` to `target`.
+pub fn read_graphml(input: &str) -> Result<StableDiGraph<String, ()>, GraphMLError> {
+    info!(target: "graphml", "Reading graphml document of {} bytes", input.len());
+    let mut graph = StableDiGraph::new();
+    let mut ids = HashMap::new();
+    let mut edges = Vec::new();
+
+    for tag in Tags::new(input) {
+        let tag = tag?;
+        match tag.name {
+            "node" => {
+                let id = tag.attribute("node", "id")?;
+                if ids.contains_key(&id) {
+                    return Err(GraphMLError::DuplicateNode(id));
+                }
+                let v = graph.add_node(id.clone());
+                ids.insert(id, v);
+            }
+            // edges may reference nodes which are declared later on
+            "edge" => edges.push((
+                tag.attribute("edge", "source")?,
+                tag.attribute("edge", "target")?,
+            )),
+            _ => (),
+        }
+    }
+
+    for (source, target) in edges {
+        let tail = *ids.get(&source).ok_or(GraphMLError::UnknownNode(source))?;
+        let head = *ids.get(&target).ok_or(GraphMLError::UnknownNode(target))?;
+        graph.add_edge(tail, head, ());
+    }
+    debug!(target: "graphml",
+        "Read {} nodes and {} edges",
+        graph.node_count(),
+        graph.edge_count());
+
+    Ok(graph)
+}
+
+/// Writes the graph and its layout as a GraphML document.
+///
+/// The coordinates are stored in the node attributes `x` and `y`. Since
+/// every connected component is laid out seperately, the attribute
+/// `component` holds the index of the layout in `layouts` the node belongs to.
+pub fn write_graphml<V: Display, E>(
+    graph: &StableDiGraph<V, E>,
+    layouts: &Layouts<NodeIndex>,
+) -> String {
+    let mut coordinates = HashMap::new();
+    for (component, (layout, ..)) in layouts.iter().enumerate() {
+        for (v, coords) in layout {
+            coordinates.insert(*v, (component, *coords));
+        }
+    }
+
+    let mut s = String::new();
+    s.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    s.push('\n');
+    s.push_str(r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#);
+    s.push('\n');
+    s.push_str(r#"  <key id="x" for="node" attr.name="x" attr.type="long"/>"#);
+    s.push('\n');
+    s.push_str(r#"  <key id="y" for="node" attr.name="y" attr.type="long"/>"#);
+    s.push('\n');
+    s.push_str(r#"  <key id="component" for="node" attr.name="component" attr.type="int"/>"#);
+    s.push('\n');
+    s.push_str(r#"  <graph edgedefault="directed">"#);
+    s.push('\n');
+
+    for v in graph.node_indices() {
+        let id = escape(&graph[v].to_string());
+        match coordinates.get(&v) {
+            Some((component, (x, y))) => {
+                let _ = writeln!(s, r#"    <node id="{id}">"#);
+                let _ = writeln!(s, r#"      <data key="x">{x}</data>"#);
+                let _ = writeln!(s, r#"      <data key="y">{y}</data>"#);
+                let _ = writeln!(s, r#"      <data key="component">{component}</data>"#);
+                s.push_str("    </node>\n");
+            }
+            None => {
+                let _ = writeln!(s, r#"    <node id="{id}"/>"#);
+            }
+        }
+    }
+
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let _ = writeln!(
+            s,
+            r#"    <edge source="{}" target="{}"/>"#,
+            escape(&graph[tail].to_string()),
+            escape(&graph[head].to_string())
+        );
+    }
+
+    s.push_str("  </graph>\n</graphml>\n");
+    s
+}
+
+/// A start (or empty element) tag of the document.
+struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, String)>,
+}
+
+impl Tag<'_> {
+    fn attribute(
+        &self,
+        element: &'static str,
+        attribute: &'static str,
+    ) -> Result<String, GraphMLError> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == attribute)
+            .map(|(_, value)| value.clone())
+            .ok_or(GraphMLError::MissingAttribute { element, attribute })
+    }
+}
+
+/// Iterates over the start tags of an xml document, skipping end tags,
+/// comments, processing instructions and declarations.
+struct Tags<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tags<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { rest: input }
+    }
+
+    fn skip_past(&mut self, pattern: &str) -> Result<(), GraphMLError> {
+        match self.rest.find(pattern) {
+            Some(i) => {
+                self.rest = &self.rest[i + pattern.len()..];
+                Ok(())
+            }
+            None => Err(GraphMLError::Malformed(format!("missing '{pattern}'"))),
+        }
+    }
+
+    fn parse_tag(&mut self) -> Result<Tag<'a>, GraphMLError> {
+        let body = self.rest;
+        let name_end = body
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .ok_or(GraphMLError::Malformed("unterminated tag".to_string()))?;
+        // strip namespace prefixes, like in <graphml:node>
+        let name = body[..name_end].rsplit(':').next().unwrap_or_default();
+        let mut attributes = Vec::new();
+        let mut rest = &body[name_end..];
+
+        loop {
+            rest = rest.trim_start();
+            if let Some(r) = rest.strip_prefix("/>").or_else(|| rest.strip_prefix('>')) {
+                self.rest = r;
+                return Ok(Tag { name, attributes });
+            }
+            let eq = rest.find('=').ok_or(GraphMLError::Malformed(format!(
+                "expected attribute in <{name}>"
+            )))?;
+            let key = rest[..eq].trim();
+            let value = rest[eq + 1..].trim_start();
+            let quote = value
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or(GraphMLError::Malformed(format!(
+                    "unquoted value for '{key}' in <{name}>"
+                )))?;
+            let end = value[1..]
+                .find(quote)
+                .ok_or(GraphMLError::Malformed(format!(
+                    "unterminated value for '{key}' in <{name}>"
+                )))?;
+            attributes.push((key, unescape(&value[1..end + 1])));
+            rest = &value[end + 2..];
+        }
+    }
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Result<Tag<'a>, GraphMLError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.rest.find('<')?;
+            self.rest = &self.rest[start + 1..];
+            let skipped = if self.rest.starts_with("!--") {
+                self.skip_past("-->")
+            } else if self.rest.starts_with("![CDATA[") {
+                self.skip_past("]]>")
+            } else if self.rest.starts_with('?') {
+                self.skip_past("?>")
+            } else if self.rest.starts_with('!') || self.rest.starts_with('/') {
+                self.skip_past(">")
+            } else {
+                return Some(self.parse_tag());
+            };
+            if let Err(e) = skipped {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_graphml, write_graphml, GraphMLError};
+    use crate::from_graph;
+
+    static SIMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- a comment containing <node id="ignored"/> -->
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="label" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+    <node id="n0"><data key="d0">root</data></node>
+    <node id='n1'/>
+    <node id="n&amp;2"/>
+    <edge source="n0" target="n1"/>
+    <edge id="e1" source="n0" target="n&amp;2"></edge>
+  </graph>
+</graphml>"#;
+
+    #[test]
+    fn read_simple_document() {
+        let g = read_graphml(SIMPLE).unwrap();
+        assert_eq!(g.node_count(), 3);
+        assert_eq!(g.edge_count(), 2);
+        let ids = g.node_weights().cloned().collect::<Vec<_>>();
+        assert_eq!(ids, ["n0", "n1", "n&2"]);
+        assert!(g.contains_edge(0.into(), 1.into()));
+        assert!(g.contains_edge(0.into(), 2.into()));
+    }
+
+    #[test]
+    fn edge_to_unknown_node() {
+        let input =
+            r#"<graphml><graph><node id="a"/><edge source="a" target="b"/></graph></graphml>"#;
+        assert_eq!(
+            read_graphml(input).unwrap_err(),
+            GraphMLError::UnknownNode("b".to_string())
+        );
+    }
+
+    #[test]
+    fn duplicate_node() {
+        let input = r#"<graphml><graph><node id="a"/><node id="a"/></graph></graphml>"#;
+        assert_eq!(
+            read_graphml(input).unwrap_err(),
+            GraphMLError::DuplicateNode("a".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let input = r#"<graphml><graph><node id="a"/><edge source="a"/></graph></graphml>"#;
+        assert_eq!(
+            read_graphml(input).unwrap_err(),
+            GraphMLError::MissingAttribute {
+                element: "edge",
+                attribute: "target"
+            }
+        );
+    }
+
+    #[test]
+    fn write_roundtrip() {
+        let g = read_graphml(SIMPLE).unwrap();
+        let layouts = from_graph(&g).build();
+        let output = write_graphml(&g, &layouts);
+        assert!(output.contains(r#"<node id="n&amp;2">"#));
+        assert_eq!(output.matches(r#"<data key="x">"#).count(), 3);
+
+        let g2 = read_graphml(&output).unwrap();
+        assert_eq!(g2.node_count(), g.node_count());
+        assert_eq!(g2.edge_count(), g.edge_count());
+    }
+}
@@ -0,0 +1,160 @@
+//! Output of layouts in the formats used by [Graphviz](https://graphviz.org/).
+//!
+//! [write_plain] emits the same format as `dot -Tplain`, so tools which
+//! consume the output of dot can use the layouts of this crate instead.
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::Layouts;
+
+/// Writes the graph and its layout in the `plain` format of graphviz.
+///
+/// The output consists of a `graph` line containing the scale and the size of
+/// the drawing, followed by a `node` line for each vertex, an `edge` line for
+/// each edge and a terminating `stop` line.
+///
+/// Coordinates are given in the same units as the layout, i.e. the scale is
+/// always 1. Vertices are drawn with a width and height of half the
+/// `vertex_spacing` the layout was calculated with. Contrary to the layout,
+/// the origin is in the lower left corner and y increases upwards.
+/// Connected components are placed next to each other from left to right.
+/// Edges are given as straight b-splines with four control points.
+pub fn write_plain<V: Display, E>(
+    graph: &StableDiGraph<V, E>,
+    layouts: &Layouts<NodeIndex>,
+    vertex_spacing: usize,
+) -> String {
+    let size = vertex_spacing as f64 / 2.;
+    let mut coordinates = HashMap::new();
+    let (mut width, mut height) = (0., 0f64);
+
+    for (layout, ..) in layouts {
+        if layout.is_empty() {
+            continue;
+        }
+        let min_x = layout.iter().map(|(_, (x, _))| *x).min().unwrap();
+        let max_x = layout.iter().map(|(_, (x, _))| *x).max().unwrap();
+        let min_y = layout.iter().map(|(_, (_, y))| *y).min().unwrap();
+        let max_y = layout.iter().map(|(_, (_, y))| *y).max().unwrap();
+        // leave space between components
+        if width > 0. {
+            width += size;
+        }
+        for (v, (x, y)) in layout {
+            coordinates.insert(
+                *v,
+                (
+                    width + (x - min_x) as f64 + size / 2.,
+                    (y - min_y) as f64 + size / 2.,
+                ),
+            );
+        }
+        width += (max_x - min_x) as f64 + size;
+        height = height.max((max_y - min_y) as f64 + size);
+    }
+
+    let mut s = String::new();
+    let _ = writeln!(s, "graph 1 {width} {height}");
+    for v in graph.node_indices() {
+        let Some((x, y)) = coordinates.get(&v) else {
+            continue;
+        };
+        let name = quote(&graph[v].to_string());
+        let _ = writeln!(
+            s,
+            "node {name} {x} {y} {size} {size} {name} solid ellipse black lightgrey"
+        );
+    }
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let (Some(&(x1, y1)), Some(&(x2, y2))) = (coordinates.get(&tail), coordinates.get(&head))
+        else {
+            continue;
+        };
+        let _ = write!(
+            s,
+            "edge {} {} 4",
+            quote(&graph[tail].to_string()),
+            quote(&graph[head].to_string())
+        );
+        for t in [0., 1. / 3., 2. / 3., 1.] {
+            let _ = write!(s, " {} {}", x1 + t * (x2 - x1), y1 + t * (y2 - y1));
+        }
+        s.push_str(" solid black\n");
+    }
+    s.push_str("stop\n");
+    s
+}
+
+/// Quotes a name, if it contains characters other than letters, digits
+/// and underscores.
+fn quote(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::{quote, write_plain};
+    use crate::from_graph;
+
+    #[test]
+    fn plain_output() {
+        let mut g = StableDiGraph::<&str, ()>::new();
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let c = g.add_node("c d");
+        g.add_edge(a, b, ());
+        g.add_edge(a, c, ());
+        let layouts = from_graph(&g).vertex_spacing(10).build();
+        let plain = write_plain(&g, &layouts, 10);
+        let lines = plain.lines().collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("graph 1 "));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("node ")).count(), 3);
+        assert_eq!(lines.iter().filter(|l| l.starts_with("edge ")).count(), 2);
+        let c_line = lines
+            .iter()
+            .find(|l| l.starts_with("node \"c d\" "))
+            .unwrap();
+        assert!(c_line.ends_with(" 2.5 5 5 \"c d\" solid ellipse black lightgrey"));
+        assert_eq!(lines.last(), Some(&"stop"));
+
+        // a is on the top layer, y increases upwards
+        let a_line = lines.iter().find(|l| l.starts_with("node a ")).unwrap();
+        assert_eq!(a_line.split(' ').nth(3), Some("12.5"));
+    }
+
+    #[test]
+    fn components_side_by_side() {
+        let mut g = StableDiGraph::<u32, ()>::new();
+        let a = g.add_node(0);
+        let b = g.add_node(1);
+        g.add_node(2);
+        g.add_edge(a, b, ());
+        let layouts = from_graph(&g).vertex_spacing(10).build();
+        let plain = write_plain(&g, &layouts, 10);
+        let xs = plain
+            .lines()
+            .filter(|l| l.starts_with("node "))
+            .map(|l| l.split(' ').nth(2).unwrap().parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(xs, [2.5, 2.5, 12.5]);
+        assert!(plain.starts_with("graph 1 15 15\n"));
+    }
+
+    #[test]
+    fn quote_names() {
+        assert_eq!(quote("a_1"), "a_1");
+        assert_eq!(quote("a b"), "\"a b\"");
+        assert_eq!(quote("a\"b"), "\"a\\\"b\"");
+        assert_eq!(quote(""), "\"\"");
+    }
+}
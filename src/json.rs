@@ -0,0 +1,176 @@
+//! Creating layouts from graphs given as a json adjacency list.
+//!
+//! The expected format is an object containing the vertices and the edges
+//! of the graph:
+//! ```json
+//! { "nodes": [0, 1, 2], "edges": [[0, 1], [0, 2]] }
+//! ```
+//! If `nodes` is omitted, the vertices are derived from the edges, in the
+//! same way as for [from_edges](crate::from_edges).
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use log::info;
+use petgraph::stable_graph::StableDiGraph;
+
+use crate::algorithm::{Edge, Vertex};
+use crate::configure::CoordinatesBuilder;
+use crate::RawGraph;
+
+/// Errors which may occur while reading a json adjacency list.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The input is not valid json or does not match the expected format.
+    Parse(serde_json::Error),
+    /// A vertex is listed more than once in `nodes`.
+    DuplicateNode(u32),
+    /// An edge references a vertex which is not listed in `nodes`.
+    UnknownNode(u32),
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid adjacency list: {e}"),
+            Self::DuplicateNode(v) => write!(f, "vertex {v} is listed more than once"),
+            Self::UnknownNode(v) => write!(f, "edge references unlisted vertex {v}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdjacencyList {
+    nodes: Option<Vec<u32>>,
+    edges: Vec<(u32, u32)>,
+}
+
+/// Creates a graph layout from a json adjacency list.
+///
+/// It returns a [CoordinatesBuilder] which can be used to configure the
+/// layout. Like with [from_vertices_and_edges](crate::from_vertices_and_edges),
+/// vertices in the layout are identified by their position in `nodes`. The
+/// values in `nodes` only name the vertices for `edges` and don't need to be
+/// contiguous.
+///
+/// # Example
+/// ```
+/// let layouts = rust_sugiyama::json::from_json(r#"{ "edges": [[0, 1], [0, 2]] }"#)
+///     .unwrap()
+///     .vertex_spacing(20)
+///     .build();
+/// assert_eq!(layouts[0].0.len(), 3);
+/// ```
+pub fn from_json(input: &str) -> Result<CoordinatesBuilder<RawGraph<'static>>, JsonError> {
+    let AdjacencyList { nodes, edges } = serde_json::from_str(input)?;
+    info!(target: "initializing",
+        "Creating new layout from json, containing {} edges",
+        edges.len());
+
+    let Some(nodes) = nodes else {
+        return Ok(CoordinatesBuilder::new(StableDiGraph::from_edges(edges)));
+    };
+
+    let mut graph = StableDiGraph::new();
+    let mut id_map = HashMap::new();
+    for (position, v) in nodes.into_iter().enumerate() {
+        if id_map.contains_key(&v) {
+            return Err(JsonError::DuplicateNode(v));
+        }
+        id_map.insert(v, graph.add_node(Vertex::new(position)));
+    }
+
+    for (tail, head) in edges {
+        let tail = *id_map.get(&tail).ok_or(JsonError::UnknownNode(tail))?;
+        let head = *id_map.get(&head).ok_or(JsonError::UnknownNode(head))?;
+        graph.add_edge(tail, head, Edge::default());
+    }
+
+    Ok(CoordinatesBuilder::new(graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_json, JsonError};
+
+    #[test]
+    fn nodes_and_edges() {
+        let layouts = from_json(r#"{ "nodes": [5, 3, 7], "edges": [[5, 3], [3, 7]] }"#)
+            .unwrap()
+            .build();
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].0.len(), 3);
+        assert_eq!(layouts[0].2, 3);
+    }
+
+    #[test]
+    fn vertices_are_identified_by_their_position() {
+        let layouts = from_json(r#"{ "nodes": [10, 42, 7], "edges": [[10, 42]] }"#)
+            .unwrap()
+            .build();
+        let mut coordinates = layouts
+            .into_iter()
+            .flat_map(|(l, ..)| l)
+            .collect::<Vec<_>>();
+        coordinates.sort();
+        let ids = coordinates.iter().map(|(v, _)| *v).collect::<Vec<_>>();
+        assert_eq!(ids, [0, 1, 2]);
+        // 10 is the tail of the edge, so it is above 42
+        assert!(coordinates[0].1 .1 > coordinates[1].1 .1);
+    }
+
+    #[test]
+    fn isolated_node() {
+        let layouts = from_json(r#"{ "nodes": [0, 1, 2], "edges": [[0, 1]] }"#)
+            .unwrap()
+            .build();
+        assert_eq!(layouts.len(), 2);
+    }
+
+    #[test]
+    fn edges_only() {
+        let layouts = from_json(r#"{ "edges": [[0, 1], [1, 2]] }"#)
+            .unwrap()
+            .build();
+        assert_eq!(layouts[0].0.len(), 3);
+    }
+
+    #[test]
+    fn unknown_node() {
+        assert!(matches!(
+            from_json(r#"{ "nodes": [0], "edges": [[0, 1]] }"#),
+            Err(JsonError::UnknownNode(1))
+        ));
+    }
+
+    #[test]
+    fn duplicate_node() {
+        assert!(matches!(
+            from_json(r#"{ "nodes": [0, 0], "edges": [] }"#),
+            Err(JsonError::DuplicateNode(0))
+        ));
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(matches!(
+            from_json(r#"{ "vertices": [0], "edges": [] }"#),
+            Err(JsonError::Parse(_))
+        ));
+    }
+}
@@ -1,17 +1,283 @@
 use std::collections::HashMap;
 
 use algorithm::{Edge, Vertex};
-use configure::CoordinatesBuilder;
+use configure::{CoordinatesBuilder, Keyed};
 
 use log::info;
-use petgraph::stable_graph::StableDiGraph;
+use petgraph::graph::DiGraph;
+use petgraph::graphmap::{DiGraphMap, NodeTrait};
+use petgraph::stable_graph::{EdgeIndex, IndexType, NodeIndex, StableDiGraph};
 
 mod algorithm;
+pub mod bundling;
 pub mod configure;
+#[cfg(feature = "elk")]
+pub mod elk;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fit;
+#[cfg(feature = "generators")]
+pub mod generators;
+#[cfg(feature = "graphml")]
+pub mod graphml;
+pub mod graphviz;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod metrics;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod render;
+pub mod transition;
 mod util;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// The layout of a single connected component.
+///
+/// Contains the coordinates of each vertex, as well as the width and the height
+/// of the layout.
+pub type Layout = (Vec<(usize, (isize, isize))>, usize, usize);
+/// The layouts of all connected components of a graph, as returned by
+/// [CoordinatesBuilder::build].
+///
+/// With the `serde` feature enabled, layouts implement `Serialize` and
+/// `Deserialize`. This includes layouts of a `StableDiGraph`, whose vertices
+/// are identified by [NodeIndex](petgraph::stable_graph::NodeIndex).
+///
+/// Coordinates are whole units by default, layouts built with
+/// [build_as](configure::CoordinatesBuilder#method.build_as) use any
+/// [Coordinate] type instead.
+pub type Layouts<T, C = isize> = Vec<(Vec<(T, (C, C))>, usize, usize)>;
+
+/// Scalar type of the coordinates of [Layouts], like `f32` for game engines.
+///
+/// The layout is calculated in layout units, of which there are
+/// [subdivisions](configure::CoordinatesBuilder#method.subdivisions) per unit
+/// of the coordinates. Floating point types keep fractions of a unit, integer
+/// types are rounded to the nearest unit and saturate at their bounds.
+pub trait Coordinate: Copy {
+    /// Converts `units` layout units, of which there are `scale` per unit.
+    fn from_layout_units(units: isize, scale: isize) -> Self;
+}
+
+macro_rules! float_coordinate {
+    ($($t:ty),*) => {$(
+        impl Coordinate for $t {
+            fn from_layout_units(units: isize, scale: isize) -> Self {
+                (units as f64 / scale as f64) as $t
+            }
+        }
+    )*};
+}
+
+macro_rules! integer_coordinate {
+    ($($t:ty),*) => {$(
+        impl Coordinate for $t {
+            fn from_layout_units(units: isize, scale: isize) -> Self {
+                let rounded = (2 * units as i128 + scale as i128).div_euclid(2 * scale as i128);
+                rounded.clamp(<$t>::MIN as i128, <$t>::MAX as i128) as $t
+            }
+        }
+    )*};
+}
+
+float_coordinate!(f32, f64);
+integer_coordinate!(i32, i64, isize);
+
+/// The rank of each vertex of all connected components of a graph, ordered by
+/// vertex, as returned by
+/// [CoordinatesBuilder::rank](configure::CoordinatesBuilder#method.rank).
+pub type Rankings<T> = Vec<Vec<(T, usize)>>;
+
+/// The vertices of each rank of all connected components of a graph, from
+/// left to right, as returned by
+/// [CoordinatesBuilder::order](configure::CoordinatesBuilder#method.order).
+/// Dummy vertices are left out, so ranks may be empty.
+pub type Orderings<T> = Vec<Vec<Vec<T>>>;
+
+/// Statistics about the layout of a single connected component, as returned by
+/// [CoordinatesBuilder::build_with_stats](configure::CoordinatesBuilder#method.build_with_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutStats {
+    /// Number of edge crossings between adjacent ranks, where edges spanning
+    /// multiple ranks are routed through their dummy vertices.
+    pub crossings: usize,
+    /// Number of edges which were reversed to remove cycles.
+    pub reversed_edges: usize,
+    /// Number of dummy vertices inserted for edges spanning multiple ranks.
+    pub dummy_vertices: usize,
+    /// Number of bends of the polyline routes of the edges, which are the
+    /// points at which a route changes its direction, besides its ends.
+    pub bends: usize,
+}
+
+/// Geometry of the layout of a single connected component, besides the
+/// coordinates of its vertices, as returned by
+/// [CoordinatesBuilder::build_with_geometry](configure::CoordinatesBuilder#method.build_with_geometry).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geometry {
+    /// Routes of the edges of the component, ordered by the index of the edge.
+    pub edges: Vec<EdgeRoute>,
+    /// Bounding boxes of the clusters with members in the component, ordered
+    /// by the id of the cluster.
+    pub clusters: Vec<ClusterBox>,
+    /// Horizontal extents of the lanes with vertices in the component,
+    /// ordered from left to right, which is the order of their ids.
+    pub lanes: Vec<Lane>,
+    /// Routes of the hyperedges of the component, ordered by their id.
+    pub hyperedges: Vec<HyperedgeRoute>,
+    /// Indices of the edges which were left out of the layout by
+    /// [transitive reduction](configure::CoordinatesBuilder#method.transitive_reduction),
+    /// in ascending order. Their routes are straight lines.
+    pub transitive_edges: Vec<usize>,
+    /// Boxes of the strongly connected components which were laid out on
+    /// their own by
+    /// [condense_components](configure::CoordinatesBuilder#method.condense_components),
+    /// ordered by their smallest vertex.
+    pub condensed: Vec<CondensedBox>,
+    /// Extents of the vertices which span several ranks, see
+    /// [CoordinatesBuilder::rank_span](configure::CoordinatesBuilder#method.rank_span),
+    /// ordered by the id of the vertex.
+    pub spans: Vec<RankSpan>,
+    /// Vertical extents of the ranks, ordered from top to bottom. Each rank is
+    /// as high as its highest vertex, and adjacent ranks keep the
+    /// [rank spacing](configure::Config) between their extents.
+    pub ranks: Vec<RankExtent>,
+    /// Bounding box of everything above, the vertices and their margins,
+    /// extended by the [padding](configure::Config).
+    pub bounds: Bounds,
+}
+
+/// The points an edge passes through.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeRoute {
+    /// Index of the edge, in the order in which edges were given, or its
+    /// [EdgeIndex](petgraph::stable_graph::EdgeIndex) for a `StableDiGraph`.
+    pub edge: usize,
+    /// Coordinates of the tail of the edge, its dummy vertices, and its head.
+    /// Edges which were reversed to remove cycles are still routed from tail
+    /// to head. An edge attached to a port starts or ends at the port, on the
+    /// side of the vertex which faces the rest of the route, other edges of a
+    /// vertex with a size start or end at the border of its
+    /// [shape](configure::CoordinatesBuilder#method.shape).
+    ///
+    /// Depending on [Config::edge_routing](configure::Config), the points are
+    /// the bends of an orthogonal route, or the first point followed by the
+    /// two control points and the end point of each cubic Bézier curve.
+    pub points: Vec<(isize, isize)>,
+}
+
+/// The points a hyperedge passes through, which are a trunk shared by all
+/// targets and a branch to each target.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HyperedgeRoute {
+    /// Id of the hyperedge, which is the number of hyperedges added before it
+    /// with [CoordinatesBuilder::hyperedge](configure::CoordinatesBuilder#method.hyperedge).
+    pub hyperedge: usize,
+    /// Coordinates of the source, the dummy vertices of the trunk, and the
+    /// junction where the branches split.
+    pub trunk: Vec<(isize, isize)>,
+    /// Coordinates of the junction, the dummy vertices of a branch, and its
+    /// target, in the order in which the targets were given.
+    pub branches: Vec<Vec<(isize, isize)>>,
+}
+
+/// Bounding box of a cluster, which contains all of its members and nested
+/// clusters extended by the padding of the cluster and the space for its label.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusterBox {
+    /// Id of the cluster, as given to
+    /// [CoordinatesBuilder::cluster](configure::CoordinatesBuilder#method.cluster).
+    pub id: usize,
+    /// Smallest x- and y-coordinate of the box.
+    pub min: (isize, isize),
+    /// Largest x- and y-coordinate of the box.
+    pub max: (isize, isize),
+}
+
+/// Box of a strongly connected component, which contains its vertices and
+/// the routes of the edges between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CondensedBox {
+    /// Ids of the vertices of the component, in ascending order.
+    pub vertices: Vec<usize>,
+    /// Smallest x- and y-coordinate of the box.
+    pub min: (isize, isize),
+    /// Largest x- and y-coordinate of the box.
+    pub max: (isize, isize),
+}
+
+/// Box of a vertex which spans several ranks. Its coordinates in the layout
+/// are those of its topmost rank.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankSpan {
+    /// Id of the vertex.
+    pub vertex: usize,
+    /// Smallest x- and y-coordinate of the box.
+    pub min: (isize, isize),
+    /// Largest x- and y-coordinate of the box.
+    pub max: (isize, isize),
+}
+
+/// Vertical extent of a rank, which contains all vertices of the rank.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankExtent {
+    /// Index of the rank, counted from the topmost rank of the component.
+    pub rank: usize,
+    /// y-coordinate of the centers of the vertices of the rank.
+    pub y: isize,
+    /// Smallest y-coordinate of the vertices of the rank.
+    pub min: isize,
+    /// Largest y-coordinate of the vertices of the rank.
+    pub max: isize,
+}
+
+/// Bounding box of the drawing of a single connected component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    /// Smallest x- and y-coordinate of the box.
+    pub min: (isize, isize),
+    /// Largest x- and y-coordinate of the box.
+    pub max: (isize, isize),
+}
+
+impl Bounds {
+    /// Width of the box.
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0) as usize
+    }
+
+    /// Height of the box.
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1) as usize
+    }
+}
+
+/// Horizontal extent of a lane, which contains all vertices of the lane.
+/// Adjacent lanes share their border.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lane {
+    /// Id of the lane, as given to
+    /// [CoordinatesBuilder::lane](configure::CoordinatesBuilder#method.lane).
+    pub id: usize,
+    /// Smallest x-coordinate of the lane.
+    pub min: isize,
+    /// Largest x-coordinate of the lane.
+    pub max: isize,
+}
 
-type Layout = (Vec<(usize, (isize, isize))>, usize, usize);
-type Layouts<T> = Vec<(Vec<(T, (isize, isize))>, usize, usize)>;
 type RawGraph<'a> = (&'a [u32], &'a [(u32, u32)]);
 
 /// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`.
@@ -24,18 +290,103 @@ pub fn from_edges(edges: &[(u32, u32)]) -> CoordinatesBuilder<&[(u32, u32)]> {
     CoordinatesBuilder::new(graph)
 }
 
-/// Creates a graph layout from a preexisting `StableDiGraph<V, E>`.
+/// Creates a graph layout from a preexisting `StableDiGraph<V, E, Ix>`, with
+/// any index type `Ix`.
 ///
 /// It returns a [CoordinatesBuilder] which can be used to configure the
 /// layout.
-pub fn from_graph<V, E>(graph: &StableDiGraph<V, E>) -> CoordinatesBuilder<StableDiGraph<V, E>> {
+pub fn from_graph<V, E, Ix: IndexType>(
+    graph: &StableDiGraph<V, E, Ix>,
+) -> CoordinatesBuilder<StableDiGraph<V, E, Ix>> {
     info!(target: "initializing", 
         "Creating new layout from existing graph, containing {} vertices and {} edges.", 
         graph.node_count(), 
         graph.edge_count());
 
-    let graph = graph.map(|id, _| Vertex::new(id.index()), |_, _| Edge::default());
-    CoordinatesBuilder::new(graph)
+    // vertices and edges keep their indices, removed ones are added and
+    // removed again to leave the same holes
+    let node_bound = graph.node_indices().map(|v| v.index() + 1).max();
+    let edge_bound = graph.edge_indices().map(|e| e.index() + 1).max();
+    let mut layout_graph = StableDiGraph::new();
+    for id in 0..node_bound.unwrap_or(0) {
+        layout_graph.add_node(Vertex::new(id));
+    }
+    for id in 0..edge_bound.unwrap_or(0) {
+        let (tail, head) = graph
+            .edge_endpoints(EdgeIndex::new(id))
+            .map_or((0, 0), |(tail, head)| (tail.index(), head.index()));
+        layout_graph.add_edge(NodeIndex::new(tail), NodeIndex::new(head), Edge::default());
+    }
+    for id in 0..edge_bound.unwrap_or(0) {
+        if graph.edge_weight(EdgeIndex::<Ix>::new(id)).is_none() {
+            layout_graph.remove_edge(EdgeIndex::new(id));
+        }
+    }
+    for id in 0..node_bound.unwrap_or(0) {
+        if !graph.contains_node(NodeIndex::<Ix>::new(id)) {
+            layout_graph.remove_node(NodeIndex::new(id));
+        }
+    }
+    CoordinatesBuilder::new(layout_graph)
+}
+
+/// Creates a graph layout from a preexisting `Graph<V, E, Directed, Ix>`,
+/// with any index type `Ix`.
+///
+/// The graph is converted into a `StableDiGraph` internally, which keeps the
+/// indices of its vertices and edges. It returns a [CoordinatesBuilder] which
+/// can be used to configure the layout.
+pub fn from_digraph<V, E, Ix: IndexType>(
+    graph: &DiGraph<V, E, Ix>,
+) -> CoordinatesBuilder<DiGraph<V, E, Ix>> {
+    info!(target: "initializing",
+        "Creating new layout from existing graph, containing {} vertices and {} edges.",
+        graph.node_count(),
+        graph.edge_count());
+
+    let mut layout_graph = StableDiGraph::with_capacity(graph.node_count(), graph.edge_count());
+    for v in graph.node_indices() {
+        layout_graph.add_node(Vertex::new(v.index()));
+    }
+    for edge in graph.raw_edges() {
+        layout_graph.add_edge(
+            NodeIndex::new(edge.source().index()),
+            NodeIndex::new(edge.target().index()),
+            Edge::default(),
+        );
+    }
+    CoordinatesBuilder::new(layout_graph)
+}
+
+/// Creates a graph layout from a preexisting `DiGraphMap<K, E>`.
+///
+/// The vertices are identified by their keys `K` in the resulting layouts.
+/// It returns a [CoordinatesBuilder] which can be used to configure the
+/// layout.
+pub fn from_graphmap<K: NodeTrait, E>(graph: &DiGraphMap<K, E>) -> CoordinatesBuilder<Keyed<K>> {
+    info!(target: "initializing",
+        "Creating new layout from existing graph, containing {} vertices and {} edges.",
+        graph.node_count(),
+        graph.edge_count());
+
+    let keys = graph.nodes().collect::<Vec<_>>();
+    let index = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (*k, i))
+        .collect::<HashMap<_, _>>();
+    let mut g = StableDiGraph::with_capacity(graph.node_count(), graph.edge_count());
+    for i in 0..keys.len() {
+        g.add_node(Vertex::new(i));
+    }
+    for (tail, head, _) in graph.all_edges() {
+        g.add_edge(
+            NodeIndex::new(index[&tail]),
+            NodeIndex::new(index[&head]),
+            Edge::default(),
+        );
+    }
+    CoordinatesBuilder::new_with_keys(g, keys)
 }
 
 /// Creates a graph layot from `&[u32]` (vertices)
@@ -74,17 +425,32 @@ pub fn from_vertices_and_edges<'a>(
     CoordinatesBuilder::new(graph)
 }
 
+#[cfg(all(test, feature = "serde"))]
+mod serde_support {
+    use petgraph::stable_graph::NodeIndex;
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use super::{Geometry, Layout, LayoutStats, Layouts};
+
+    fn is_serde<T: Serialize + DeserializeOwned>() {}
+
+    #[test]
+    fn layouts_are_serde() {
+        is_serde::<Layout>();
+        is_serde::<Layouts<usize>>();
+        is_serde::<Layouts<NodeIndex>>();
+        is_serde::<LayoutStats>();
+        is_serde::<Geometry>();
+    }
+}
+
 #[cfg(test)]
 mod benchmark {
     use super::from_edges;
 
     #[test]
     fn r_100() {
-        let edges = graph_generator::RandomLayout::new(100)
-            .build_edges()
-            .into_iter()
-            .map(|(r, l)| (r as u32, l as u32))
-            .collect::<Vec<(u32, u32)>>();
+        let edges = graph_generator::RandomLayout::new(100).build_edges();
         let start = std::time::Instant::now();
         let _ = from_edges(&edges).build();
         println!("Random 100 edges: {}ms", start.elapsed().as_millis());
@@ -92,11 +458,7 @@ mod benchmark {
 
     #[test]
     fn r_1000() {
-        let edges = graph_generator::RandomLayout::new(1000)
-            .build_edges()
-            .into_iter()
-            .map(|(r, l)| (r as u32, l as u32))
-            .collect::<Vec<(u32, u32)>>();
+        let edges = graph_generator::RandomLayout::new(1000).build_edges();
         let start = std::time::Instant::now();
         let _ = from_edges(&edges).build();
         println!("Random 1000 edges: {}ms", start.elapsed().as_millis());
@@ -237,7 +599,7 @@ mod check_visuals {
             (8, 9),
         ];
         let (layout, width, height) = &mut from_edges(&edges).build()[0];
-        layout.sort_by(|a, b| a.0.cmp(&b.0));
+        layout.sort_by_key(|a| a.0);
 
         assert_eq!(*width, 4);
         assert_eq!(*height, 6);
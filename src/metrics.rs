@@ -0,0 +1,177 @@
+//! Measures of the quality of layouts, to compare them with each other.
+//!
+//! # Example
+//! ```
+//! use petgraph::stable_graph::StableDiGraph;
+//! use rust_sugiyama::{from_graph, metrics::count_crossings};
+//!
+//! let g = StableDiGraph::<(), ()>::from_edges([(0, 2), (0, 3), (1, 2), (1, 3)]);
+//! let layouts = from_graph(&g).build();
+//! assert_eq!(count_crossings(&g, &layouts), 1);
+//! let orderings = from_graph(&g).order(&from_graph(&g).rank());
+//! assert_eq!(count_crossings(&g, &orderings), 1);
+//! ```
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::{Layouts, Orderings};
+
+/// Vertices of a graph arranged in ranks, like [Orderings] and [Layouts].
+pub trait Arrangement {
+    /// Returns the rank of each vertex and its position within the rank,
+    /// for each connected component.
+    fn positions(&self) -> Vec<HashMap<NodeIndex, (usize, f64)>>;
+}
+
+impl Arrangement for Orderings<NodeIndex> {
+    fn positions(&self) -> Vec<HashMap<NodeIndex, (usize, f64)>> {
+        self.iter()
+            .map(|ranks| {
+                ranks
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(rank, vertices)| {
+                        vertices
+                            .iter()
+                            .enumerate()
+                            .map(move |(pos, v)| (*v, (rank, pos as f64)))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Arrangement for Layouts<NodeIndex> {
+    /// Each distinct y-coordinate of a component is a rank, from top to
+    /// bottom, positions are the x-coordinates.
+    fn positions(&self) -> Vec<HashMap<NodeIndex, (usize, f64)>> {
+        self.iter()
+            .map(|(layout, ..)| {
+                let mut ys = layout.iter().map(|(_, (_, y))| -*y).collect::<Vec<_>>();
+                ys.sort();
+                ys.dedup();
+                layout
+                    .iter()
+                    .map(|(v, (x, y))| (*v, (ys.binary_search(&-y).unwrap(), *x as f64)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Counts the crossings between the edges of `graph` in an ordering or a
+/// layout of it.
+///
+/// Crossings are counted between adjacent ranks. Edges spanning several
+/// ranks are straight lines, which differs from the number of crossings in
+/// [LayoutStats](crate::LayoutStats), where they are routed through their
+/// dummy vertices. Edges within a rank, loops and edges with an endpoint
+/// which isn't arranged are left out. Edges sharing an endpoint don't cross.
+pub fn count_crossings<V, E, A: Arrangement + ?Sized>(
+    graph: &StableDiGraph<V, E>,
+    arrangement: &A,
+) -> usize {
+    let components = arrangement.positions();
+    info!(target: "metrics", "Counting crossings of {} components", components.len());
+    let mut crossings = 0;
+    for positions in components {
+        // segments between each rank and the rank below it
+        let mut segments = Vec::<Vec<(f64, f64)>>::new();
+        for e in graph.edge_indices() {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            let (Some(t), Some(h)) = (positions.get(&tail), positions.get(&head)) else {
+                continue;
+            };
+            let ((upper, from), (lower, to)) = if t.0 < h.0 { (*t, *h) } else { (*h, *t) };
+            if upper == lower {
+                continue;
+            }
+            segments.resize_with(segments.len().max(lower), Vec::new);
+            let at =
+                |rank: usize| from + (to - from) * (rank - upper) as f64 / (lower - upper) as f64;
+            for (rank, segments) in (upper..).zip(&mut segments[upper..lower]) {
+                segments.push((at(rank), at(rank + 1)));
+            }
+        }
+        for mut segments in segments {
+            segments.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let mut lower = segments.into_iter().map(|(_, b)| b).collect::<Vec<_>>();
+            crossings += inversions(&mut lower);
+        }
+    }
+    crossings
+}
+
+/// Sorts `values` and returns the number of pairs which were in the wrong
+/// order, not counting equal values.
+fn inversions(values: &mut [f64]) -> usize {
+    if values.len() < 2 {
+        return 0;
+    }
+    let middle = values.len() / 2;
+    let (left, right) = values.split_at_mut(middle);
+    let mut count = inversions(left) + inversions(right);
+    let mut merged = Vec::with_capacity(values.len());
+    let (mut i, mut j) = (0, 0);
+    while i < middle && j < values.len() - middle {
+        if values[middle + j] < values[i] {
+            // all remaining values of the left half are greater
+            count += middle - i;
+            merged.push(values[middle + j]);
+            j += 1;
+        } else {
+            merged.push(values[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&values[i..middle]);
+    merged.extend_from_slice(&values[middle + j..]);
+    values.copy_from_slice(&merged);
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{count_crossings, inversions};
+    use crate::from_graph;
+
+    #[test]
+    fn inversions_leave_out_ties() {
+        assert_eq!(inversions(&mut [3., 1., 2.]), 2);
+        assert_eq!(inversions(&mut [1., 1., 1.]), 0);
+        assert_eq!(inversions(&mut [4., 3., 2., 1.]), 6);
+    }
+
+    #[test]
+    fn long_edges_are_straight() {
+        let g = StableDiGraph::<(), ()>::from_edges([(0, 3), (4, 1), (5, 2)]);
+        let v = NodeIndex::new;
+        // 0 -> 3 passes between 1 and 5, crossing 4 -> 1 and 5 -> 2
+        let orderings = vec![vec![vec![v(0), v(4)], vec![v(1), v(5)], vec![v(2), v(3)]]];
+        assert_eq!(count_crossings(&g, &orderings), 2);
+        // 0 -> 3 runs straight down, touching 4 -> 1 only at 1
+        let orderings = vec![vec![vec![v(0), v(4)], vec![v(1), v(5)], vec![v(3), v(2)]]];
+        assert_eq!(count_crossings(&g, &orderings), 0);
+    }
+
+    #[test]
+    fn layouts_match_stats() {
+        let g = StableDiGraph::<(), ()>::from_edges([
+            (0, 3),
+            (0, 4),
+            (1, 3),
+            (1, 5),
+            (2, 3),
+            (2, 4),
+            (2, 5),
+        ]);
+        let (layouts, stats) = from_graph(&g).build_with_stats();
+        assert_eq!(count_crossings(&g, &layouts), stats[0].crossings);
+    }
+}
@@ -0,0 +1,381 @@
+//! Progress reporting, cancellation and observation of a running layout.
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{from_edges, progress::CancellationToken};
+//!
+//! let token = CancellationToken::new();
+//! let layouts = from_edges(&[(0, 1), (0, 2)])
+//!     .on_progress(|p| println!("{:?}: {:.0}%", p.phase, p.percent))
+//!     .cancellation_token(token.clone())
+//!     .try_build();
+//! assert!(layouts.is_ok());
+//! ```
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::configure::{CoordinateAssignment, OrderingAlgorithm, RankingAlgorithm};
+use crate::{Geometry, Layout};
+
+/// The phases of the algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    CycleRemoval,
+    Ranking,
+    CrossingReduction,
+    CoordinateCalculation,
+}
+
+impl Phase {
+    /// Estimated share of the total runtime of each phase, in percent.
+    fn range(self) -> (f64, f64) {
+        match self {
+            Self::CycleRemoval => (0., 5.),
+            Self::Ranking => (5., 35.),
+            Self::CrossingReduction => (35., 85.),
+            Self::CoordinateCalculation => (85., 100.),
+        }
+    }
+}
+
+/// Reported to the callback registered with
+/// [CoordinatesBuilder::on_progress](crate::configure::CoordinatesBuilder::on_progress).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// The phase currently executed.
+    pub phase: Phase,
+    /// The estimated progress of the whole layout, from 0 to 100.
+    ///
+    /// Connected components are weighted by their number of vertices. Since
+    /// the number of sweeps in crossing reduction is not known in advance, the
+    /// progress during that phase is only a rough estimate. If components are
+    /// laid out in parallel, `phase` is the phase of the component which
+    /// reported last.
+    pub percent: f64,
+}
+
+/// Can be used to cancel a running layout from another thread.
+///
+/// The token is checked between the phases of the algorithm and between the
+/// sweeps of crossing reduction.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all layouts using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Returned if a layout was cancelled via its [CancellationToken].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("layout was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Observes the intermediate results of the layout of each connected
+/// component, registered with
+/// [CoordinatesBuilder::observer](crate::configure::CoordinatesBuilder::observer).
+///
+/// Graphs are given like to the custom algorithms of
+/// [configure](crate::configure): the weight of each vertex is its index in
+/// the input graph, or `None` for dummy vertices. All methods do nothing by
+/// default.
+pub trait PhaseObserver: Send + Sync {
+    /// Called once each vertex is assigned a rank, with the acyclic graph of
+    /// the component, like a [RankingAlgorithm].
+    fn on_ranked(&self, _graph: &StableDiGraph<usize, ()>, _ranks: &HashMap<NodeIndex, u32>) {}
+
+    /// Called once the vertices of each rank are ordered, with the proper
+    /// layered graph and the vertices of each rank, like a
+    /// [CoordinateAssignment]. Trees and commit graphs aren't ordered.
+    fn on_ordered(&self, _graph: &StableDiGraph<Option<usize>, ()>, _ranks: &[Vec<NodeIndex>]) {}
+
+    /// Called once the layout of the component is finished, with the same
+    /// layout and geometry as returned by the builder.
+    fn on_positioned(&self, _layout: &Layout, _geometry: &Geometry) {}
+}
+
+pub(crate) type ProgressCallback = Box<dyn Fn(Progress) + Send + Sync>;
+
+/// Callbacks and tokens registered on the builder.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub(crate) on_progress: Option<ProgressCallback>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    pub(crate) ranking: Option<Arc<dyn RankingAlgorithm>>,
+    pub(crate) ordering: Option<Box<dyn OrderingAlgorithm>>,
+    pub(crate) coordinates: Option<Box<dyn CoordinateAssignment>>,
+    pub(crate) observer: Option<Box<dyn PhaseObserver>>,
+    /// Directory the intermediate graphs are written to.
+    #[cfg(feature = "debug-dump")]
+    pub(crate) dump_dir: Option<std::path::PathBuf>,
+    /// Phase before which the layout of each component stops, to return the
+    /// ranks or the order of its vertices instead.
+    pub(crate) stop_before: Option<Phase>,
+    /// Input vertices of each rank of the components which stopped, by the
+    /// index of the component.
+    stopped: Mutex<Vec<(usize, Vec<Vec<usize>>)>>,
+    /// Progress of each component in percent of the whole layout
+    progress: Mutex<Vec<f64>>,
+}
+
+impl Hooks {
+    /// Creates a monitor for the next component, which makes up `share`
+    /// percent of the whole layout.
+    pub(crate) fn monitor(&self, share: f64) -> Monitor<'_> {
+        let mut progress = self.progress.lock().unwrap();
+        progress.push(0.);
+        Monitor {
+            hooks: self,
+            component: progress.len() - 1,
+            share,
+            silent: false,
+        }
+    }
+
+    /// Returns the input vertices of each rank of each component which
+    /// stopped, in the order of the components.
+    pub(crate) fn into_stopped(self) -> Vec<Vec<Vec<usize>>> {
+        let mut stopped = self.stopped.into_inner().unwrap();
+        stopped.sort_by_key(|(component, _)| *component);
+        stopped.into_iter().map(|(_, ranks)| ranks).collect()
+    }
+}
+
+/// Reports progress and checks for cancellation during the layout of a single
+/// connected component.
+#[derive(Clone, Copy)]
+pub(crate) struct Monitor<'a> {
+    hooks: &'a Hooks,
+    component: usize,
+    share: f64,
+    /// Only checks for cancellation, without reporting progress.
+    silent: bool,
+}
+
+impl<'a> Monitor<'a> {
+    /// Report that `fraction` of `phase` is done.
+    pub(crate) fn report(&self, phase: Phase, fraction: f64) {
+        if self.silent {
+            return;
+        }
+        if let Some(on_progress) = &self.hooks.on_progress {
+            let (from, to) = phase.range();
+            let percent = from + (to - from) * fraction.clamp(0., 1.);
+            // keep the lock while calling back, so reports don't overtake each other
+            let mut progress = self.hooks.progress.lock().unwrap();
+            progress[self.component] = self.share * percent / 100.;
+            on_progress(Progress {
+                phase,
+                percent: progress.iter().sum(),
+            });
+        }
+    }
+
+    /// Returns the hooks registered on the builder.
+    pub(crate) fn hooks(&self) -> &'a Hooks {
+        self.hooks
+    }
+
+    /// Returns the observer of the layout, unless this is the layout of a
+    /// part of the component.
+    pub(crate) fn observer(&self) -> Option<&'a dyn PhaseObserver> {
+        self.hooks.observer.as_deref().filter(|_| !self.silent)
+    }
+
+    /// Returns whether the layout of the component stops before `phase`, and
+    /// records the input vertices of each rank returned by `ranks` if it does.
+    /// Layouts of parts of the component never stop.
+    pub(crate) fn stop_before(
+        &self,
+        phase: Phase,
+        ranks: impl FnOnce() -> Vec<Vec<usize>>,
+    ) -> bool {
+        if self.silent || self.hooks.stop_before != Some(phase) {
+            return false;
+        }
+        info!(target: "layouting", "Stopping layout before {phase:?}");
+        let ranks = ranks();
+        self.hooks
+            .stopped
+            .lock()
+            .unwrap()
+            .push((self.component, ranks));
+        true
+    }
+
+    /// Writes the graph in the dot format returned by `dot` to the dump
+    /// directory, as `<component>-<step>.dot`. Layouts of parts of the
+    /// component aren't written.
+    #[cfg(feature = "debug-dump")]
+    pub(crate) fn dump(&self, step: &str, dot: impl FnOnce() -> String) {
+        let Some(dir) = self.hooks.dump_dir.as_ref().filter(|_| !self.silent) else {
+            return;
+        };
+        let path = dir.join(format!("{}-{step}.dot", self.component));
+        let result = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, dot()));
+        if let Err(error) = result {
+            log::warn!(target: "layouting", "Couldn't write {}: {error}", path.display());
+        }
+    }
+
+    /// Returns a monitor which only checks for cancellation, for the layout
+    /// of a part of the component.
+    pub(crate) fn silent(&self) -> Self {
+        Self {
+            silent: true,
+            ..*self
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Cancelled> {
+        match &self.hooks.cancellation_token {
+            Some(token) if token.is_cancelled() => {
+                info!(target: "layouting", "Layout was cancelled");
+                Err(Cancelled)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use std::collections::HashMap;
+
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use super::{CancellationToken, Cancelled, Phase, PhaseObserver};
+    use crate::{from_edges, Geometry, Layout};
+
+    #[test]
+    fn progress_is_reported_in_order() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let r = reported.clone();
+        let edges = [(0, 1), (0, 2), (1, 3), (4, 5)];
+        let layouts = from_edges(&edges)
+            .on_progress(move |p| r.lock().unwrap().push(p))
+            .build();
+        assert_eq!(layouts.len(), 2);
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.first().unwrap().phase, Phase::CycleRemoval);
+        assert_eq!(reported.first().unwrap().percent, 0.);
+        assert_eq!(reported.last().unwrap().phase, Phase::CoordinateCalculation);
+        assert!((reported.last().unwrap().percent - 100.).abs() < 1e-9);
+        assert!(reported.windows(2).all(|p| p[0].percent <= p[1].percent));
+    }
+
+    #[test]
+    fn cancelled_layout() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let edges = [(0, 1), (0, 2), (1, 3)];
+        let result = from_edges(&edges).cancellation_token(token).try_build();
+        assert_eq!(result, Err(Cancelled));
+    }
+
+    #[test]
+    fn cancel_while_running() {
+        let token = CancellationToken::new();
+        let t = token.clone();
+        let edges = [(0, 1), (0, 2), (1, 3)];
+        let result = from_edges(&edges)
+            .cancellation_token(token)
+            .on_progress(move |p| {
+                if p.phase == Phase::CrossingReduction {
+                    t.cancel()
+                }
+            })
+            .try_build();
+        assert_eq!(result, Err(Cancelled));
+    }
+
+    /// Records the calls of each phase.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl PhaseObserver for Recorder {
+        fn on_ranked(&self, graph: &StableDiGraph<usize, ()>, ranks: &HashMap<NodeIndex, u32>) {
+            let mut ranks = ranks
+                .iter()
+                .map(|(v, r)| (graph[*v], *r))
+                .collect::<Vec<_>>();
+            ranks.sort();
+            self.0.lock().unwrap().push(format!("ranked {ranks:?}"));
+        }
+
+        fn on_ordered(&self, graph: &StableDiGraph<Option<usize>, ()>, ranks: &[Vec<NodeIndex>]) {
+            let dummies = graph.node_weights().filter(|v| v.is_none()).count();
+            let sizes = ranks.iter().map(|r| r.len()).collect::<Vec<_>>();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("ordered {sizes:?} {dummies}"));
+        }
+
+        fn on_positioned(&self, layout: &Layout, _: &Geometry) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("positioned {}", layout.0.len()));
+        }
+    }
+
+    #[test]
+    fn observer_sees_each_phase() {
+        let recorder = Recorder::default();
+        let edges = [(0, 1), (0, 2), (1, 2)];
+        from_edges(&edges).observer(recorder.clone()).build();
+        let calls = recorder.0.lock().unwrap();
+        assert_eq!(
+            *calls,
+            [
+                "ranked [(0, 0), (1, 1), (2, 2)]",
+                "ordered [1, 2, 1] 1",
+                "positioned 3"
+            ]
+        );
+    }
+
+    #[cfg(feature = "debug-dump")]
+    #[test]
+    fn dumps_each_step() {
+        let dir = std::env::temp_dir().join(format!("sugiyama-dump-{}", std::process::id()));
+        let edges = [(0, 2), (0, 3), (1, 2), (1, 3), (2, 4), (3, 4), (4, 0)];
+        from_edges(&edges).debug_dump(&dir).build();
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files[..2], ["0-0-cycle-removal.dot", "0-1-ranking.dot"]);
+        assert!(files[2..].iter().all(|f| f.starts_with("0-2-sweep-0-")));
+        assert!(files.len() > 2);
+        let ranking = std::fs::read_to_string(dir.join("0-1-ranking.dot")).unwrap();
+        assert!(ranking.contains("rank=same"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
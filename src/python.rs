@@ -0,0 +1,204 @@
+//! Python bindings, built with [pyo3](https://pyo3.rs).
+//!
+//! The module can be built with [maturin](https://www.maturin.rs), e.g. by
+//! running `maturin develop --features python,pyo3/extension-module`.
+//!
+//! # Example
+//! ```python
+//! import rust_sugiyama
+//!
+//! coordinates = rust_sugiyama.layout(
+//!     [("a", "b"), ("a", "c"), ("b", "d")],
+//!     {"vertex_spacing": 20, "c_minimization": "median"},
+//! )
+//! x, y = coordinates["d"]
+//! ```
+use std::collections::HashMap;
+use std::time::Duration;
+
+use petgraph::stable_graph::StableDiGraph;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::configure::{Alignment, Config, CrossingMinimization, EdgeRouting, RankingType};
+use crate::from_graph;
+
+/// A vertex of a graph passed in from python, either an integer or a string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, FromPyObject)]
+pub enum Node {
+    Int(i64),
+    Str(String),
+}
+
+impl IntoPy<PyObject> for Node {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Self::Int(i) => i.into_py(py),
+            Self::Str(s) => s.into_py(py),
+        }
+    }
+}
+
+/// Calculates the layout of the graph given by `edges`.
+///
+/// `config` is an optional dict, whose keys are the fields of [Config].
+/// Values of `ranking_type`, `c_minimization`, `edge_routing` and `alignment` are given as strings, like
+/// for the environment variables, `time_budget` is given in seconds. Returns a dict mapping each vertex to its
+/// coordinates, with connected components placed next to each other.
+#[pyfunction]
+#[pyo3(signature = (edges, config = None))]
+fn layout(
+    edges: Vec<(Node, Node)>,
+    config: Option<&PyDict>,
+) -> PyResult<HashMap<Node, (isize, isize)>> {
+    let config = match config {
+        Some(config) => read_config(config)?,
+        None => Config::default(),
+    };
+    Ok(layout_nodes(edges, config))
+}
+
+fn read_config(dict: &PyDict) -> PyResult<Config> {
+    let mut config = Config::default();
+    for (key, value) in dict.iter() {
+        match key.extract::<&str>()? {
+            "minimum_length" => config.minimum_length = value.extract()?,
+            "vertex_spacing" => config.vertex_spacing = value.extract()?,
+            "dummy_vertices" => config.dummy_vertices = value.extract()?,
+            "dummy_size" => config.dummy_size = value.extract()?,
+            "ranking_type" => {
+                config.ranking_type = RankingType::try_from(value.extract::<String>()?)
+                    .map_err(PyValueError::new_err)?
+            }
+            "c_minimization" => {
+                config.c_minimization = CrossingMinimization::try_from(value.extract::<String>()?)
+                    .map_err(PyValueError::new_err)?
+            }
+            "transpose" => config.transpose = value.extract()?,
+            "time_budget" => {
+                config.time_budget = Some(Duration::from_secs_f64(value.extract::<f64>()?))
+            }
+            "max_ranking_iterations" => config.max_ranking_iterations = value.extract()?,
+            "max_sweeps" => config.max_sweeps = value.extract()?,
+            "patience" => config.patience = value.extract()?,
+            "max_transpose_iterations" => config.max_transpose_iterations = value.extract()?,
+            "shuffle_transpose" => config.shuffle_transpose = value.extract()?,
+            "stable_ties" => config.stable_ties = value.extract()?,
+            "community_ordering" => config.community_ordering = value.extract()?,
+            "seed" => config.seed = value.extract()?,
+            "restarts" => config.restarts = value.extract()?,
+            "multilevel" => config.multilevel = value.extract()?,
+            "edge_routing" => {
+                config.edge_routing = EdgeRouting::try_from(value.extract::<String>()?)
+                    .map_err(PyValueError::new_err)?
+            }
+            "back_edge_channels" => config.back_edge_channels = value.extract()?,
+            "transitive_reduction" => config.transitive_reduction = value.extract()?,
+            "condense_components" => config.condense_components = value.extract()?,
+            "contract_chains" => config.contract_chains = value.extract()?,
+            "commit_graph" => config.commit_graph = value.extract()?,
+            "radial" => config.radial = value.extract()?,
+            "aspect_ratio" => config.aspect_ratio = value.extract()?,
+            "max_layer_width" => config.max_layer_width = value.extract()?,
+            "promote_vertices" => config.promote_vertices = value.extract()?,
+            "rank_spacing" => config.rank_spacing = value.extract()?,
+            "padding" => config.padding = value.extract()?,
+            "y_down" => config.y_down = value.extract()?,
+            "mirror" => config.mirror = value.extract()?,
+            "grid" => config.grid = value.extract()?,
+            "snap_y" => config.snap_y = value.extract()?,
+            "subdivisions" => config.subdivisions = value.extract()?,
+            "horizontal_compaction" => config.horizontal_compaction = value.extract()?,
+            "center_parents" => config.center_parents = value.extract()?,
+            "minimize_area" => config.minimize_area = value.extract()?,
+            "minimize_bends" => config.minimize_bends = value.extract()?,
+            "alignment" => {
+                config.alignment = Alignment::try_from(value.extract::<String>()?)
+                    .map_err(PyValueError::new_err)?
+            }
+            k => return Err(PyValueError::new_err(format!("unknown config key: {k}"))),
+        }
+    }
+    Ok(config)
+}
+
+fn layout_nodes(edges: Vec<(Node, Node)>, config: Config) -> HashMap<Node, (isize, isize)> {
+    let mut graph = StableDiGraph::new();
+    let mut ids = HashMap::new();
+    for (tail, head) in edges {
+        let tail = *ids
+            .entry(tail.clone())
+            .or_insert_with(|| graph.add_node(tail));
+        let head = *ids
+            .entry(head.clone())
+            .or_insert_with(|| graph.add_node(head));
+        graph.add_edge(tail, head, ());
+    }
+
+    let mut coordinates = HashMap::new();
+    let mut offset = 0;
+    for (layout, ..) in from_graph(&graph).with_config(config).build() {
+        let Some(min_x) = layout.iter().map(|(_, (x, _))| *x).min() else {
+            continue;
+        };
+        let max_x = layout.iter().map(|(_, (x, _))| *x).max().unwrap();
+        for (v, (x, y)) in layout {
+            coordinates.insert(graph[v].clone(), (x - min_x + offset, y));
+        }
+        offset += max_x - min_x + config.spacing() as isize;
+    }
+    coordinates
+}
+
+#[pymodule]
+fn rust_sugiyama(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(layout, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::PyDict;
+    use pyo3::Python;
+
+    use super::{layout_nodes, read_config, Node};
+    use crate::configure::{Config, EdgeRouting};
+
+    #[test]
+    fn layout_mixed_nodes() {
+        let edges = vec![
+            (Node::Str("a".to_string()), Node::Int(1)),
+            (Node::Str("a".to_string()), Node::Int(2)),
+            (Node::Int(3), Node::Int(4)),
+        ];
+        let coordinates = layout_nodes(edges, Config::default());
+        assert_eq!(coordinates.len(), 5);
+        assert_eq!(coordinates[&Node::Str("a".to_string())].1, 0);
+        assert_eq!(coordinates[&Node::Int(1)].1, -10);
+
+        // the second component is placed to the right of the first one
+        let max_x = [Node::Int(1), Node::Int(2)]
+            .iter()
+            .map(|v| coordinates[v].0)
+            .max()
+            .unwrap();
+        assert!(coordinates[&Node::Int(3)].0 > max_x);
+    }
+
+    #[test]
+    fn read_edge_routing_keys() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("edge_routing", "orthogonal").unwrap();
+            dict.set_item("back_edge_channels", true).unwrap();
+            let config = read_config(dict).unwrap();
+            assert_eq!(config.edge_routing, EdgeRouting::Orthogonal);
+            assert!(config.back_edge_channels);
+
+            dict.set_item("edge_routing", "curved").unwrap();
+            assert!(read_config(dict).is_err());
+        });
+    }
+}
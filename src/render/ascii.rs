@@ -0,0 +1,285 @@
+//! Draws layouts with box-drawing characters, for the terminal.
+//!
+//! Vertices are drawn as boxes containing their label. Coordinates are only
+//! used to find the order of the vertices: each distinct x-coordinate becomes
+//! a column as wide as its widest box, each distinct y-coordinate a row.
+//! Edges leave their tail at the side facing their head, turn in the gap next
+//! to their head and end in an arrow. Edges spanning several rows are drawn
+//! behind the boxes in between, edges between vertices of the same row run
+//! below the row.
+//!
+//! # Example
+//! ```
+//! use petgraph::stable_graph::StableDiGraph;
+//! use rust_sugiyama::{from_graph, render::ascii};
+//!
+//! let mut g = StableDiGraph::<&str, ()>::new();
+//! let a = g.add_node("a");
+//! let b = g.add_node("b");
+//! g.add_edge(a, b, ());
+//! let layouts = from_graph(&g).build();
+//! assert_eq!(
+//!     ascii::draw(&g, &layouts),
+//!     "┌───┐\n│ a │\n└─┬─┘\n  │\n  │\n  │\n  ▼\n┌───┐\n│ b │\n└───┘\n"
+//! );
+//! ```
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::Layouts;
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+/// Height of a box, which is the height of a row.
+const BOX_HEIGHT: usize = 3;
+/// Lines between two rows, where edges turn.
+const GAP: usize = 4;
+
+/// Where a vertex is drawn on the canvas.
+struct Placement {
+    /// Line of the top border of the box.
+    top: usize,
+    /// Column of the left border of the box.
+    left: usize,
+    /// Column of the center of the box, where edges pointing down are
+    /// attached, edges pointing up are attached right of it.
+    center: usize,
+    label: Vec<char>,
+}
+
+impl Placement {
+    /// Width of the box of `label`, which leaves room for edges in both
+    /// directions.
+    fn width(label: &[char]) -> usize {
+        label.len().max(1) + 4
+    }
+
+    fn right(&self) -> usize {
+        self.left + Self::width(&self.label) - 1
+    }
+
+    fn bottom(&self) -> usize {
+        self.top + BOX_HEIGHT - 1
+    }
+}
+
+/// Lines of the canvas, each cell holds the directions it connects to.
+struct Canvas(Vec<Vec<u8>>);
+
+impl Canvas {
+    fn connect(&mut self, (row, col): (usize, usize), direction: u8) {
+        if self.0.len() <= row {
+            self.0.resize_with(row + 1, Vec::new);
+        }
+        let line = &mut self.0[row];
+        if line.len() <= col {
+            line.resize(col + 1, 0);
+        }
+        line[col] |= direction;
+    }
+
+    /// Draws an orthogonal line through `points`.
+    fn path(&mut self, points: &[(usize, usize)]) {
+        for segment in points.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            let (forward, backward, cells) = if from.1 == to.1 {
+                let (forward, backward) = if from.0 <= to.0 {
+                    (DOWN, UP)
+                } else {
+                    (UP, DOWN)
+                };
+                let cells = range(from.0, to.0)
+                    .map(|row| (row, from.1))
+                    .collect::<Vec<_>>();
+                (forward, backward, cells)
+            } else {
+                let (forward, backward) = if from.1 <= to.1 {
+                    (RIGHT, LEFT)
+                } else {
+                    (LEFT, RIGHT)
+                };
+                let cells = range(from.1, to.1)
+                    .map(|col| (from.0, col))
+                    .collect::<Vec<_>>();
+                (forward, backward, cells)
+            };
+            for (i, cell) in cells.iter().enumerate() {
+                if i + 1 < cells.len() {
+                    self.connect(*cell, forward);
+                }
+                if i > 0 {
+                    self.connect(*cell, backward);
+                }
+            }
+        }
+    }
+}
+
+/// Cells from `from` to `to`, including both, in either direction.
+fn range(from: usize, to: usize) -> Box<dyn Iterator<Item = usize>> {
+    if from <= to {
+        Box::new(from..=to)
+    } else {
+        Box::new((to..=from).rev())
+    }
+}
+
+/// Characters of the cells, indexed by the directions they connect to.
+const SYMBOLS: [char; 16] = [
+    ' ', '│', '│', '│', '─', '┘', '┐', '┤', '─', '└', '┌', '├', '─', '┴', '┬', '┼',
+];
+
+/// Draws the layouts of `graph` as lines of box-drawing characters, each
+/// vertex labeled with its weight. Connected components are drawn side by
+/// side, vertices which aren't part of the layouts are left out.
+pub fn draw<V: Display, E>(graph: &StableDiGraph<V, E>, layouts: &Layouts<NodeIndex>) -> String {
+    info!(target: "render", "Drawing {} layouts as text", layouts.len());
+    let mut placements = HashMap::new();
+    let mut offset = 0;
+    for (layout, ..) in layouts {
+        if layout.is_empty() {
+            continue;
+        }
+        let mut xs = layout.iter().map(|(_, (x, _))| *x).collect::<Vec<_>>();
+        xs.sort();
+        xs.dedup();
+        // the topmost rank has the largest y-coordinate
+        let mut ys = layout.iter().map(|(_, (_, y))| -*y).collect::<Vec<_>>();
+        ys.sort();
+        ys.dedup();
+        let labels = layout
+            .iter()
+            .map(|(v, _)| (*v, graph[*v].to_string().chars().collect::<Vec<_>>()))
+            .collect::<HashMap<_, _>>();
+        let mut widths = vec![0; xs.len()];
+        for (v, (x, _)) in layout {
+            let column = xs.binary_search(x).unwrap();
+            widths[column] = widths[column].max(Placement::width(&labels[v]));
+        }
+        let mut lefts = Vec::with_capacity(xs.len());
+        let mut left = offset;
+        for width in &widths {
+            lefts.push(left);
+            left += width + 1;
+        }
+        for (v, (x, y)) in layout {
+            let column = xs.binary_search(x).unwrap();
+            let row = ys.binary_search(&-y).unwrap();
+            let center = lefts[column] + widths[column] / 2;
+            let label = labels[v].clone();
+            placements.insert(
+                *v,
+                Placement {
+                    top: row * (BOX_HEIGHT + GAP),
+                    left: center - Placement::width(&label) / 2,
+                    center,
+                    label,
+                },
+            );
+        }
+        // leave space between components
+        offset = left + 1;
+    }
+
+    let mut canvas = Canvas(Vec::new());
+    let mut arrows = Vec::new();
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let (Some(t), Some(h)) = (placements.get(&tail), placements.get(&head)) else {
+            continue;
+        };
+        if tail == head {
+            continue;
+        }
+        if h.top > t.top {
+            let turn = h.top - 2;
+            canvas.path(&[
+                (t.bottom(), t.center),
+                (turn, t.center),
+                (turn, h.center),
+                (h.top - 1, h.center),
+            ]);
+            arrows.push(((h.top - 1, h.center), '▼'));
+        } else {
+            let start = if h.top == t.top { t.bottom() } else { t.top };
+            // turn above the edges pointing down, so they don't overlap
+            let turn = h.bottom() + 2;
+            canvas.path(&[
+                (start, t.center + 1),
+                (turn, t.center + 1),
+                (turn, h.center + 1),
+                (h.bottom() + 1, h.center + 1),
+            ]);
+            arrows.push(((h.bottom() + 1, h.center + 1), '▲'));
+        }
+    }
+    for p in placements.values() {
+        canvas.path(&[
+            (p.top, p.left),
+            (p.top, p.right()),
+            (p.bottom(), p.right()),
+            (p.bottom(), p.left),
+            (p.top, p.left),
+        ]);
+    }
+
+    let mut lines = canvas
+        .0
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|d| SYMBOLS[*d as usize])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for p in placements.values() {
+        let line = &mut lines[p.top + 1];
+        line[p.left + 1..p.right()].fill(' ');
+        line[p.left + 2..p.left + 2 + p.label.len()].copy_from_slice(&p.label);
+    }
+    for ((row, col), arrow) in arrows {
+        lines[row][col] = arrow;
+    }
+    let mut s = String::new();
+    for line in lines {
+        s.extend(line);
+        s.truncate(s.trim_end().len());
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use super::draw;
+    use crate::from_graph;
+
+    #[test]
+    fn edges_join_at_boxes() {
+        let mut g = StableDiGraph::<&str, ()>::new();
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let c = g.add_node("long");
+        g.add_edge(a, b, ());
+        g.add_edge(a, c, ());
+        g.add_edge(c, a, ());
+        let layouts = from_graph(&g).build();
+        let text = draw(&g, &layouts);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 10);
+        assert!(lines[1].contains("│ a │"));
+        assert!(lines[8].contains("│ b │") && lines[8].contains("│ long │"));
+        // both edges between a and long are drawn
+        assert_eq!(lines[6].matches('▼').count(), 2);
+        assert_eq!(lines[3].matches('▲').count(), 1);
+        assert!(lines[7].contains('┴'));
+    }
+}
@@ -0,0 +1,5 @@
+//! Drawing layouts without an external viewer.
+//!
+//! [ascii] draws a layout with box-drawing characters, to look at layouts in
+//! the terminal.
+pub mod ascii;
@@ -0,0 +1,153 @@
+//! Helpers for animating the transition between two layouts of a graph.
+//!
+//! # Example
+//! ```
+//! use rust_sugiyama::{from_edges, transition::Transition};
+//!
+//! let before = from_edges(&[(0, 1), (0, 2)]).build();
+//! let after = from_edges(&[(0, 1), (1, 3)]).previous_layout(&before).build();
+//! let transition = Transition::new(&before, &after);
+//! assert_eq!(transition.entering.len(), 1);
+//! assert_eq!(transition.exiting.len(), 1);
+//! // halfway through the animation
+//! let frame = transition.at(0.5);
+//! ```
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::Layouts;
+
+type Coordinates = (isize, isize);
+
+/// Describes how vertices move from one layout to the next one.
+///
+/// Coordinates are taken as is from both layouts, so the layouts need to share
+/// one coordinate system, e.g. by calculating the second layout with
+/// [CoordinatesBuilder::previous_layout](crate::configure::CoordinatesBuilder#method.previous_layout).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transition<T> {
+    /// Vertices contained in both layouts, with their old and new coordinates.
+    pub matched: Vec<(T, Coordinates, Coordinates)>,
+    /// Vertices which are only contained in the new layout.
+    pub entering: Vec<(T, Coordinates)>,
+    /// Vertices which are only contained in the old layout.
+    pub exiting: Vec<(T, Coordinates)>,
+}
+
+impl<T: Copy + Eq + Hash> Transition<T> {
+    /// Matches the vertices of layout `from` with the vertices of layout `to`.
+    ///
+    /// Vertices are listed in the order of the components and vertices of `to`,
+    /// exiting vertices in the order of `from`.
+    pub fn new(from: &Layouts<T>, to: &Layouts<T>) -> Self {
+        let old = from
+            .iter()
+            .flat_map(|(l, ..)| l.iter().copied())
+            .collect::<HashMap<_, _>>();
+        let new = to
+            .iter()
+            .flat_map(|(l, ..)| l.iter().copied())
+            .collect::<HashMap<_, _>>();
+
+        let mut transition = Self {
+            matched: Vec::new(),
+            entering: Vec::new(),
+            exiting: Vec::new(),
+        };
+        for (v, coords) in to.iter().flat_map(|(l, ..)| l.iter().copied()) {
+            match old.get(&v) {
+                Some(old_coords) => transition.matched.push((v, *old_coords, coords)),
+                None => transition.entering.push((v, coords)),
+            }
+        }
+        transition.exiting = from
+            .iter()
+            .flat_map(|(l, ..)| l.iter().copied())
+            .filter(|(v, _)| !new.contains_key(v))
+            .collect();
+        transition
+    }
+
+    /// Linearly interpolates the coordinates of matched vertices, where `t`
+    /// is in the range `[0, 1]`.
+    ///
+    /// Entering vertices are returned at their new coordinates and exiting
+    /// vertices at their old ones, so they can be faded in and out.
+    pub fn at(&self, t: f64) -> Vec<(T, (f64, f64))> {
+        let t = t.clamp(0., 1.);
+        let lerp = |a: isize, b: isize| a as f64 + (b - a) as f64 * t;
+        self.matched
+            .iter()
+            .map(|(v, (x0, y0), (x1, y1))| (*v, (lerp(*x0, *x1), lerp(*y0, *y1))))
+            .chain(
+                self.entering
+                    .iter()
+                    .chain(self.exiting.iter())
+                    .map(|(v, (x, y))| (*v, (*x as f64, *y as f64))),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transition;
+    use crate::from_edges;
+
+    #[test]
+    fn match_vertices() {
+        let from = vec![
+            (vec![(0, (0, 0)), (1, (10, -10))], 1, 2),
+            (vec![(2, (20, 0))], 1, 1),
+        ];
+        let to = vec![(vec![(0, (10, 0)), (3, (0, -10)), (1, (20, -10))], 2, 2)];
+        let transition = Transition::new(&from, &to);
+        assert_eq!(
+            transition.matched,
+            vec![(0, (0, 0), (10, 0)), (1, (10, -10), (20, -10))]
+        );
+        assert_eq!(transition.entering, vec![(3, (0, -10))]);
+        assert_eq!(transition.exiting, vec![(2, (20, 0))]);
+    }
+
+    #[test]
+    fn interpolate_matched_vertices() {
+        let from = vec![(vec![(0, (0, 0)), (1, (10, -10))], 1, 2)];
+        let to = vec![(vec![(0, (10, 0)), (1, (10, -20))], 1, 2)];
+        let transition = Transition::new(&from, &to);
+        assert_eq!(transition.at(0.5), vec![(0, (5., 0.)), (1, (10., -15.))]);
+        assert_eq!(transition.at(2.), vec![(0, (10., 0.)), (1, (10., -20.))]);
+    }
+
+    #[test]
+    fn vertices_entering_and_exiting_built_layouts() {
+        let before = from_edges(&[(0, 1), (0, 2)]).build();
+        let after = from_edges(&[(0, 1), (1, 3)])
+            .previous_layout(&before)
+            .build();
+        let transition = Transition::new(&before, &after);
+        let mut matched = transition
+            .matched
+            .iter()
+            .map(|(v, ..)| *v)
+            .collect::<Vec<_>>();
+        matched.sort();
+        assert_eq!(matched, [0, 1]);
+        assert_eq!(
+            transition
+                .entering
+                .iter()
+                .map(|(v, _)| *v)
+                .collect::<Vec<_>>(),
+            [3]
+        );
+        assert_eq!(
+            transition
+                .exiting
+                .iter()
+                .map(|(v, _)| *v)
+                .collect::<Vec<_>>(),
+            [2]
+        );
+    }
+}
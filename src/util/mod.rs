@@ -131,6 +131,36 @@ fn key(key: usize, n: usize) -> usize {
     (n / key) % 10
 }
 
+/// Small pseudo random number generator (SplitMix64), used by heuristics
+/// to make arbitrary choices reproducible for a given seed.
+#[derive(Clone, Debug)]
+pub(super) struct Rng(u64);
+
+impl Rng {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(super) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in `0..n`.
+    pub(super) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(super) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
 #[test]
 fn test_counting_sort_first_digit() {
     let mut input = [10, 0, 1, 5, 4, 22, 12];
@@ -153,3 +183,22 @@ fn test_radix_sort() {
     let output = radix_sort(input.to_vec(), 2);
     assert_eq!(output, [0, 1, 4, 5, 10, 12, 22]);
 }
+
+#[test]
+fn rng_is_reproducible() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let mut c = Rng::new(43);
+    let a = (0..4).map(|_| a.next_u64()).collect::<Vec<_>>();
+    assert_eq!(a, (0..4).map(|_| b.next_u64()).collect::<Vec<_>>());
+    assert_ne!(a, (0..4).map(|_| c.next_u64()).collect::<Vec<_>>());
+}
+
+#[test]
+fn rng_shuffle_is_permutation() {
+    let mut items = (0..20).collect::<Vec<_>>();
+    Rng::new(7).shuffle(&mut items);
+    assert_ne!(items, (0..20).collect::<Vec<_>>());
+    items.sort();
+    assert_eq!(items, (0..20).collect::<Vec<_>>());
+}
@@ -0,0 +1,275 @@
+//! Checks layouts for the invariants promised by their [Config].
+//!
+//! [validate] is meant for tests of code using this crate, e.g. property
+//! tests of layouts of random graphs. It checks each connected component on
+//! its own, since their coordinates are independent of each other.
+//!
+//! # Example
+//! ```
+//! use petgraph::stable_graph::StableDiGraph;
+//! use rust_sugiyama::{configure::Config, from_graph, validate::validate};
+//!
+//! let g = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 2)]);
+//! let config = Config::default();
+//! let layouts = from_graph(&g).with_config(config).build();
+//! assert!(validate(&g, &layouts, &config, |_| (0., 0.)).is_empty());
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use log::{debug, info};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+
+use crate::configure::Config;
+use crate::Layouts;
+
+/// An invariant which doesn't hold for a layout, as returned by [validate].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The vertex isn't part of any layout, or part of several.
+    Missing(NodeIndex),
+    /// The boxes of two vertices overlap, or two vertices without a size
+    /// have the same coordinates.
+    Overlap(NodeIndex, NodeIndex),
+    /// Two neighboring vertices of the same rank are closer to each other
+    /// than the vertex spacing.
+    Spacing(NodeIndex, NodeIndex),
+    /// The endpoints of the edge are less than `minimum_length` ranks apart.
+    ShortEdge(EdgeIndex),
+    /// The rank at the given y-coordinate contains more vertices than
+    /// `max_layer_width`, contains the number of vertices.
+    WideRank(isize, usize),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(v) => write!(f, "vertex {} is not laid out once", v.index()),
+            Self::Overlap(a, b) => {
+                write!(f, "vertices {} and {} overlap", a.index(), b.index())
+            }
+            Self::Spacing(a, b) => write!(
+                f,
+                "vertices {} and {} are closer than the vertex spacing",
+                a.index(),
+                b.index()
+            ),
+            Self::ShortEdge(e) => {
+                write!(f, "edge {} is shorter than its minimum length", e.index())
+            }
+            Self::WideRank(y, width) => {
+                write!(f, "rank at y = {y} contains {width} vertices, too many")
+            }
+        }
+    }
+}
+
+/// Checks the layouts of `graph` built with `config`, with `node_sizes`
+/// returning the width and height of each vertex like given to
+/// [CoordinatesBuilder::measure](crate::configure::CoordinatesBuilder::measure).
+/// Returns the violations found, in the order of the variants of [Violation].
+///
+/// Sizes are rounded up to whole units, like the layout does. Vertices keep
+/// the vertex spacing to their neighbors in the same rank, ranks are at least
+/// the rank spacing apart, plus the half heights of their highest vertices.
+/// Edges which were deliberately placed within a rank are reported as
+/// [Violation::ShortEdge]. Constraints given to the builder are not checked,
+/// and only [Violation::Missing] is checked for [radial](Config::radial)
+/// layouts.
+pub fn validate<V, E, F: Fn(&V) -> (f64, f64)>(
+    graph: &StableDiGraph<V, E>,
+    layouts: &Layouts<NodeIndex>,
+    config: &Config,
+    node_sizes: F,
+) -> Vec<Violation> {
+    info!(target: "validate", "Validating {} layouts", layouts.len());
+    let mut violations = Vec::new();
+    let mut components = HashMap::new();
+    let mut coordinates = HashMap::new();
+    let mut duplicates = HashSet::new();
+    for (component, (layout, ..)) in layouts.iter().enumerate() {
+        for (v, coordinate) in layout {
+            if components.insert(*v, component).is_some() {
+                duplicates.insert(*v);
+            }
+            coordinates.insert(*v, *coordinate);
+        }
+    }
+    violations.extend(
+        graph
+            .node_indices()
+            .filter(|v| !components.contains_key(v) || duplicates.contains(v))
+            .map(Violation::Missing),
+    );
+    if config.radial {
+        return violations;
+    }
+
+    let sizes = graph
+        .node_indices()
+        .map(|v| {
+            let (width, height) = node_sizes(&graph[v]);
+            let size = (
+                width.max(0.).ceil() as isize,
+                height.max(0.).ceil() as isize,
+            );
+            (v, size)
+        })
+        .collect::<HashMap<_, _>>();
+    let spacing = config.spacing() as isize;
+
+    for (layout, ..) in layouts {
+        for (i, (a, (xa, ya))) in layout.iter().enumerate() {
+            for (b, (xb, yb)) in &layout[i + 1..] {
+                let (sa, sb) = (sizes[a], sizes[b]);
+                let width = ((sa.0 + sb.0 + 1) / 2).max(1);
+                let height = ((sa.1 + sb.1 + 1) / 2).max(1);
+                if (xa - xb).abs() < width && (ya - yb).abs() < height {
+                    violations.push(Violation::Overlap(*a, *b));
+                }
+            }
+        }
+    }
+
+    let mut wide_ranks = Vec::new();
+    for (layout, ..) in layouts {
+        let mut ranks = HashMap::<isize, Vec<(isize, NodeIndex)>>::new();
+        for (v, (x, y)) in layout {
+            ranks.entry(*y).or_default().push((*x, *v));
+        }
+        let mut ranks = ranks.into_iter().collect::<Vec<_>>();
+        ranks.sort_by_key(|(y, _)| -y);
+        for (_, rank) in &mut ranks {
+            rank.sort();
+            for pair in rank.windows(2) {
+                let ((xa, a), (xb, b)) = (pair[0], pair[1]);
+                let distance = spacing + (sizes[&a].0 + sizes[&b].0 + 1) / 2;
+                // overlapping vertices are already reported
+                let overlap = [Violation::Overlap(a, b), Violation::Overlap(b, a)];
+                if xb - xa < distance && !overlap.iter().any(|o| violations.contains(o)) {
+                    violations.push(Violation::Spacing(a, b));
+                }
+            }
+        }
+        if let Some(limit) = config.max_layer_width {
+            wide_ranks.extend(
+                ranks
+                    .iter()
+                    .filter(|(_, rank)| rank.len() > limit)
+                    .map(|(y, rank)| Violation::WideRank(*y, rank.len())),
+            );
+        }
+    }
+    violations.append(&mut wide_ranks);
+
+    for e in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(e).unwrap();
+        let (Some((_, yt)), Some((_, yh))) = (coordinates.get(&tail), coordinates.get(&head))
+        else {
+            continue;
+        };
+        if tail == head {
+            continue;
+        }
+        let length = config.minimum_length as isize * config.rank_distance() as isize
+            + (sizes[&tail].1 + 1) / 2
+            + (sizes[&head].1 + 1) / 2;
+        if (yt - yh).abs() < length {
+            violations.push(Violation::ShortEdge(e));
+        }
+    }
+    debug!(target: "validate", "Found {} violations", violations.len());
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+
+    use super::{validate, Violation};
+    use crate::configure::Config;
+    use crate::from_graph;
+
+    #[test]
+    fn layouts_are_valid() {
+        let mut g = StableDiGraph::<&str, ()>::new();
+        let v = ["a", "a long label", "b", "c"].map(|l| g.add_node(l));
+        for (tail, head) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 0)] {
+            g.add_edge(v[tail], v[head], ());
+        }
+        let size = |label: &&str| (label.len() as f64 * 7.5, 20.);
+        let config = Config {
+            minimum_length: 2,
+            ..Default::default()
+        };
+        let layouts = from_graph(&g).with_config(config).measure(&g, size).build();
+        assert_eq!(validate(&g, &layouts, &config, size), []);
+        let layouts = from_graph(&g).with_config(config).build();
+        assert_eq!(validate(&g, &layouts, &config, |_| (0., 0.)), []);
+    }
+
+    #[test]
+    fn negative_coordinates_keep_spacing() {
+        // averaging the four alignments used to round towards zero
+        let edges = [
+            (6, 1),
+            (10, 0),
+            (1, 4),
+            (11, 0),
+            (4, 0),
+            (10, 3),
+            (6, 7),
+            (2, 6),
+            (4, 9),
+            (5, 4),
+            (9, 6),
+            (3, 8),
+            (10, 1),
+            (2, 6),
+            (0, 5),
+            (11, 4),
+            (5, 9),
+        ];
+        let g = StableDiGraph::<(), ()>::from_edges(edges);
+        let size = |_: &()| (13., 7.);
+        let layouts = from_graph(&g).measure(&g, size).build();
+        assert_eq!(validate(&g, &layouts, &Config::default(), size), []);
+    }
+
+    #[test]
+    fn violations_are_found() {
+        let mut g = StableDiGraph::<(), ()>::new();
+        let v = [(); 5].map(|_| g.add_node(()));
+        g.add_edge(v[0], v[1], ());
+        g.add_edge(v[1], v[2], ());
+        let layouts = vec![(
+            vec![
+                (v[0], (0, 0)),
+                (v[1], (0, -10)),
+                (v[2], (5, -10)),
+                (v[3], (5, -10)),
+            ],
+            5,
+            10,
+        )];
+        let config = Config {
+            vertex_spacing: 10.,
+            max_layer_width: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate(&g, &layouts, &config, |_| (0., 0.)),
+            [
+                Violation::Missing(v[4]),
+                Violation::Overlap(v[2], v[3]),
+                Violation::Spacing(v[1], v[2]),
+                Violation::WideRank(-10, 3),
+                Violation::ShortEdge(EdgeIndex::new(1)),
+            ]
+        );
+        assert_eq!(
+            Violation::Overlap(NodeIndex::new(2), NodeIndex::new(3)).to_string(),
+            "vertices 2 and 3 overlap"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Bindings for javascript, generated with
+//! [wasm-bindgen](https://rustwasm.github.io/docs/wasm-bindgen/).
+//!
+//! Graphs are passed in as flat arrays of edges, layouts are returned as flat
+//! arrays, which are converted to typed arrays on the javascript side.
+//!
+//! # Example
+//! ```js
+//! import { LayoutBuilder } from "rust_sugiyama";
+//!
+//! const builder = new LayoutBuilder(new Uint32Array([0, 1, 0, 2, 1, 3]));
+//! builder.vertex_spacing(20);
+//! builder.crossing_minimization("median");
+//! const layout = builder.build();
+//! const vertices = layout.vertices(); // Uint32Array [0, 1, 2, 3]
+//! const coordinates = layout.coordinates(); // Int32Array [x0, y0, x1, y1, ...]
+//! ```
+use std::time::Duration;
+
+use log::info;
+use wasm_bindgen::prelude::*;
+
+use crate::configure::{Alignment, Config, CrossingMinimization, RankingType};
+use crate::from_edges;
+
+/// Configures and calculates a layout, see [CoordinatesBuilder](crate::configure::CoordinatesBuilder).
+#[wasm_bindgen]
+pub struct LayoutBuilder {
+    edges: Vec<(u32, u32)>,
+    config: Config,
+}
+
+#[wasm_bindgen]
+impl LayoutBuilder {
+    /// Creates a builder from a flat array of edges, containing the tail and
+    /// the head of each edge after another, like `[tail0, head0, tail1, head1]`.
+    ///
+    /// Like with [from_edges], the graph contains all vertices from 0 up to the
+    /// largest vertex of the edges.
+    #[wasm_bindgen(constructor)]
+    pub fn new(edges: &[u32]) -> Result<LayoutBuilder, JsError> {
+        if !edges.len().is_multiple_of(2) {
+            return Err(JsError::new(
+                "edges must contain an even number of vertices",
+            ));
+        }
+        info!(target: "initializing", "Creating new layout from wasm, containing {} edges", edges.len() / 2);
+        Ok(Self {
+            edges: edges.chunks_exact(2).map(|e| (e[0], e[1])).collect(),
+            config: Config::default(),
+        })
+    }
+
+    /// Set the minimimum length, see [Config] for description
+    pub fn minimum_length(&mut self, v: u32) {
+        self.config.minimum_length = v;
+    }
+
+    /// Set the spacing between vertices, see [Config] for description
+    pub fn vertex_spacing(&mut self, v: f64) {
+        self.config.vertex_spacing = v;
+    }
+
+    /// Set the spacing between adjacent ranks, see [Config] for description
+    pub fn rank_spacing(&mut self, v: f64) {
+        self.config.rank_spacing = Some(v);
+    }
+
+    /// Set the padding around each component, see [Config] for description
+    pub fn padding(&mut self, v: usize) {
+        self.config.padding = Some(v);
+    }
+
+    /// Let the y-axis point down, see [Config] for description
+    pub fn y_down(&mut self, v: bool) {
+        self.config.y_down = v;
+    }
+
+    /// Mirror the layout horizontally, see [Config] for description
+    pub fn mirror(&mut self, v: bool) {
+        self.config.mirror = v;
+    }
+
+    /// Snap x-coordinates to a grid, see [Config] for description
+    pub fn grid(&mut self, step: usize) {
+        self.config.grid = Some(step);
+    }
+
+    /// Snap y-coordinates to the grid as well, see [Config] for description
+    pub fn snap_y(&mut self, v: bool) {
+        self.config.snap_y = v;
+    }
+
+    /// Set the number of steps each unit is divided into, see [Config] for description
+    pub fn subdivisions(&mut self, v: usize) {
+        self.config.subdivisions = v;
+    }
+
+    /// Remove horizontal space which no rank needs, see [Config] for description
+    pub fn horizontal_compaction(&mut self, v: bool) {
+        self.config.horizontal_compaction = v;
+    }
+
+    /// Set the alignment of coordinates, one of `balanced`, `up_left`, `up_right`, `down_left` or `down_right`
+    pub fn alignment(&mut self, v: String) -> Result<(), JsError> {
+        self.config.alignment = Alignment::try_from(v).map_err(|e| JsError::new(&e))?;
+        Ok(())
+    }
+
+    /// Move parents towards the center of their children, see [Config] for description
+    pub fn center_parents(&mut self, v: bool) {
+        self.config.center_parents = v;
+    }
+
+    /// Make the area of the layout as small as possible, see [Config] for description
+    pub fn minimize_area(&mut self, v: bool) {
+        self.config.minimize_area = v;
+    }
+
+    /// Remove unnecessary bends of long edges, see [Config] for description
+    pub fn minimize_bends(&mut self, v: bool) {
+        self.config.minimize_bends = v;
+    }
+
+    /// Activate/deactivate dummy vertices, see [Config] for description
+    pub fn dummy_vertices(&mut self, v: bool) {
+        self.config.dummy_vertices = v;
+    }
+
+    /// Set the size of the dummy vertices, see [Config]
+    pub fn dummy_size(&mut self, v: f64) {
+        self.config.dummy_size = v;
+    }
+
+    /// Set the layering type, one of `original`, `minimize`, `up`, `down`, `min_width` or `optimal`,
+    /// if built with the `lp` feature
+    pub fn layering_type(&mut self, v: String) -> Result<(), JsError> {
+        self.config.ranking_type = RankingType::try_from(v).map_err(|e| JsError::new(&e))?;
+        Ok(())
+    }
+
+    /// Set the crossing minimization heuristic, one of `barycenter` or `median`
+    pub fn crossing_minimization(&mut self, v: String) -> Result<(), JsError> {
+        self.config.c_minimization =
+            CrossingMinimization::try_from(v).map_err(|e| JsError::new(&e))?;
+        Ok(())
+    }
+
+    /// Use transpose function during crossing minimization, see [Config]
+    pub fn transpose(&mut self, v: bool) {
+        self.config.transpose = v;
+    }
+
+    /// Set the time budget in milliseconds, see [Config]
+    pub fn time_budget(&mut self, ms: u32) {
+        self.config.time_budget = Some(Duration::from_millis(ms as u64));
+    }
+
+    /// Set the maximum number of network simplex iterations, see [Config]
+    pub fn max_ranking_iterations(&mut self, v: usize) {
+        self.config.max_ranking_iterations = Some(v);
+    }
+
+    /// Set the maximum number of sweeps during crossing minimization, see [Config]
+    pub fn max_sweeps(&mut self, v: usize) {
+        self.config.max_sweeps = Some(v);
+    }
+
+    /// Set the number of sweeps without improvement after which crossing minimization stops, see [Config]
+    pub fn patience(&mut self, v: usize) {
+        self.config.patience = v;
+    }
+
+    /// Set the maximum number of transpose iterations per sweep, see [Config]
+    pub fn max_transpose_iterations(&mut self, v: usize) {
+        self.config.max_transpose_iterations = Some(v);
+    }
+
+    /// Shuffle the order in which transpose visits vertices, see [Config]
+    pub fn shuffle_transpose(&mut self, v: bool) {
+        self.config.shuffle_transpose = v;
+    }
+
+    /// Break ties of crossing minimization by input order, see [Config]
+    pub fn stable_ties(&mut self, v: bool) {
+        self.config.stable_ties = v;
+    }
+
+    /// Order the initial ranks community by community, see [Config]
+    pub fn community_ordering(&mut self, v: bool) {
+        self.config.community_ordering = v;
+    }
+
+    /// Set the seed for random choices during crossing minimization, see [Config]
+    pub fn seed(&mut self, v: u32) {
+        self.config.seed = Some(v as u64);
+    }
+
+    /// Set the number of additional runs of crossing minimization, see [Config]
+    pub fn restarts(&mut self, v: usize) {
+        self.config.restarts = v;
+    }
+
+    /// Use multilevel crossing minimization for large graphs, see [Config]
+    pub fn multilevel(&mut self, v: bool) {
+        self.config.multilevel = v;
+    }
+
+    /// Leave out redundant edges when calculating the layout, see [Config]
+    pub fn transitive_reduction(&mut self, v: bool) {
+        self.config.transitive_reduction = v;
+    }
+
+    /// Lay out strongly connected components on their own, see [Config]
+    pub fn condense_components(&mut self, v: bool) {
+        self.config.condense_components = v;
+    }
+
+    /// Contract chains of vertices during crossing reduction, see [Config]
+    pub fn contract_chains(&mut self, v: bool) {
+        self.config.contract_chains = v;
+    }
+
+    /// Lay out the graph like a commit graph, see [Config]
+    pub fn commit_graph(&mut self, v: bool) {
+        self.config.commit_graph = v;
+    }
+
+    /// Lay out the ranks on concentric circles, see [Config]
+    pub fn radial(&mut self, v: bool) {
+        self.config.radial = v;
+    }
+
+    /// Set the ratio of the width to the height of the layout, see [Config]
+    pub fn aspect_ratio(&mut self, v: f64) {
+        self.config.aspect_ratio = Some(v);
+    }
+
+    /// Set the maximum number of vertices in each rank, see [Config]
+    pub fn max_layer_width(&mut self, v: usize) {
+        self.config.max_layer_width = Some(v);
+    }
+
+    /// Promote vertices after ranking, see [Config]
+    pub fn promote_vertices(&mut self, v: bool) {
+        self.config.promote_vertices = v;
+    }
+
+    /// Build the layout.
+    pub fn build(&self) -> Layout {
+        let layouts = from_edges(&self.edges).with_config(self.config).build();
+        let mut layout = Layout {
+            vertices: Vec::new(),
+            coordinates: Vec::new(),
+            components: Vec::new(),
+            sizes: Vec::new(),
+        };
+        for (component, (coordinates, width, height)) in layouts.into_iter().enumerate() {
+            for (v, (x, y)) in coordinates {
+                layout.vertices.push(v as u32);
+                layout.coordinates.extend([x as i32, y as i32]);
+                layout.components.push(component as u32);
+            }
+            layout.sizes.extend([width as u32, height as u32]);
+        }
+        layout
+    }
+}
+
+/// The calculated layout, given as flat arrays.
+#[wasm_bindgen]
+pub struct Layout {
+    vertices: Vec<u32>,
+    coordinates: Vec<i32>,
+    components: Vec<u32>,
+    sizes: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl Layout {
+    /// The vertices of the layout.
+    pub fn vertices(&self) -> Vec<u32> {
+        self.vertices.clone()
+    }
+
+    /// The x and y coordinates of each vertex, in the same order as
+    /// [Layout::vertices], like `[x0, y0, x1, y1]`.
+    pub fn coordinates(&self) -> Vec<i32> {
+        self.coordinates.clone()
+    }
+
+    /// The connected component each vertex belongs to. Each component is laid
+    /// out separately.
+    pub fn components(&self) -> Vec<u32> {
+        self.components.clone()
+    }
+
+    /// The width and height of each component, like `[width0, height0, width1, height1]`.
+    pub fn sizes(&self) -> Vec<u32> {
+        self.sizes.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayoutBuilder;
+
+    #[test]
+    fn build_flat_layout() {
+        let Ok(mut builder) = LayoutBuilder::new(&[0, 1, 0, 2, 3, 4]) else {
+            panic!("valid edges");
+        };
+        builder.vertex_spacing(20.);
+        let layout = builder.build();
+        assert_eq!(layout.vertices().len(), 5);
+        assert_eq!(layout.coordinates().len(), 10);
+        assert_eq!(layout.components(), [0, 0, 0, 1, 1]);
+        assert_eq!(layout.sizes(), [2, 2, 1, 2]);
+
+        let i = layout.vertices().iter().position(|v| *v == 1).unwrap();
+        assert_eq!(layout.coordinates()[2 * i + 1], -20);
+    }
+}